@@ -3,6 +3,7 @@ use gw_store::Store;
 
 pub fn bench(c: &mut Criterion) {
     c.bench_function("db init", |b| b.iter(|| Store::open_tmp().unwrap()));
+    c.bench_function("memory db init", |b| b.iter(Store::open_memory));
 }
 
 criterion_group! {
@@ -131,12 +131,14 @@ impl BenchExecutionEnvironment {
                     validator_path: META_VALIDATOR_PATH.into(),
                     generator_path: META_GENERATOR_PATH.into(),
                     validator_script_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+                    activation: None,
                 },
                 BackendConfig {
                     backend_type: BackendType::Sudt,
                     validator_path: SUDT_VALIDATOR_PATH.into(),
                     generator_path: SUDT_GENERATOR_PATH.into(),
                     validator_script_type_hash: SUDT_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+                    activation: None,
                 },
             ];
             BackendManage::from_config(configs).expect("bench backend")
@@ -155,6 +157,8 @@ impl BenchExecutionEnvironment {
             rollup_context,
             Default::default(),
             L2TX_MAX_CYCLES,
+            Default::default(),
+            Default::default(),
         );
 
         Self::init_genesis(&store, &genesis_config, accounts);
@@ -3,7 +3,7 @@ use gw_common::{
     state::{to_short_address, State},
     H256,
 };
-use gw_config::BackendConfig;
+use gw_config::{BackendConfig, BackendType};
 use gw_generator::{
     account_lock_manage::AccountLockManage, backend_manage::BackendManage, dummy_state::DummyState,
     error::TransactionError, traits::StateExt, Generator,
@@ -38,12 +38,14 @@ fn build_backend_manage(rollup_config: &RollupConfig) -> BackendManage {
             validator_path: META_VALIDATOR_PATH.into(),
             generator_path: META_GENERATOR_PATH.into(),
             validator_script_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+            activation: None,
         },
         BackendConfig {
             backend_type: BackendType::Sudt,
             validator_path: SUDT_VALIDATOR_PATH.into(),
             generator_path: SUDT_GENERATOR_PATH.into(),
             validator_script_type_hash: sudt_validator_script_type_hash.into(),
+            activation: None,
         },
     ];
     BackendManage::from_config(configs).expect("default backend")
@@ -73,24 +75,41 @@ fn run_contract_get_result<S: State + CodeStore>(
     args: Bytes,
     block_info: &BlockInfo,
 ) -> Result<RunResult, TransactionError> {
-    let raw_tx = RawL2Transaction::new_builder()
-        .from_id(from_id.pack())
-        .to_id(to_id.pack())
-        .args(args.pack())
-        .build();
+    let generator = build_generator(rollup_config);
+    execute_contract(&generator, tree, from_id, to_id, args, block_info)
+}
+
+fn build_generator(rollup_config: &RollupConfig) -> Generator {
     let backend_manage = build_backend_manage(rollup_config);
     let account_lock_manage = AccountLockManage::default();
     let rollup_ctx = RollupContext {
         rollup_config: rollup_config.clone(),
         rollup_script_hash: [42u8; 32].into(),
     };
-    let generator = Generator::new(
+    Generator::new(
         backend_manage,
         account_lock_manage,
         rollup_ctx,
         Default::default(),
         7000_0000,
-    );
+        Default::default(),
+        Default::default(),
+    )
+}
+
+fn execute_contract<S: State + CodeStore>(
+    generator: &Generator,
+    tree: &mut S,
+    from_id: u32,
+    to_id: u32,
+    args: Bytes,
+    block_info: &BlockInfo,
+) -> Result<RunResult, TransactionError> {
+    let raw_tx = RawL2Transaction::new_builder()
+        .from_id(from_id.pack())
+        .to_id(to_id.pack())
+        .args(args.pack())
+        .build();
     let chain_view = DummyChainStore;
     let run_result = generator.execute_transaction_with_default_max_cycles(
         &chain_view,
@@ -115,76 +134,116 @@ fn run_contract<S: State + CodeStore>(
     Ok(run_result.return_data)
 }
 
+type TransferState = (DummyState, RollupConfig, u32, u32, H256, BlockInfo);
+
+fn init_transfer_state() -> TransferState {
+    let mut tree = DummyState::default();
+
+    let rollup_config = RollupConfig::new_builder()
+        .l2_sudt_validator_script_type_hash(DUMMY_SUDT_VALIDATOR_SCRIPT_TYPE_HASH.pack())
+        .build();
+
+    let init_a_balance: u128 = 10000;
+
+    // init accounts
+    let sudt_id = tree
+        .create_account_from_script(
+            Script::new_builder()
+                .code_hash(DUMMY_SUDT_VALIDATOR_SCRIPT_TYPE_HASH.clone().pack())
+                .args([0u8; 64].to_vec().pack())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+        )
+        .expect("create account");
+    let a_id = tree
+        .create_account_from_script(
+            Script::new_builder()
+                .code_hash([0u8; 32].pack())
+                .args([0u8; 20].to_vec().pack())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+        )
+        .expect("create account");
+    let b_id = tree
+        .create_account_from_script(
+            Script::new_builder()
+                .code_hash([0u8; 32].pack())
+                .args([1u8; 20].to_vec().pack())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+        )
+        .expect("create account");
+    let a_script_hash = tree.get_script_hash(a_id).expect("get script hash");
+    let b_script_hash = tree.get_script_hash(b_id).expect("get script hash");
+    let block_producer_id = tree
+        .create_account_from_script(
+            Script::new_builder()
+                .code_hash([0u8; 32].pack())
+                .args([3u8; 20].to_vec().pack())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+        )
+        .expect("create account");
+    let block_info = new_block_info(block_producer_id, 1, 0);
+
+    // init balance for a
+    tree.mint_sudt(sudt_id, to_short_address(&a_script_hash), init_a_balance)
+        .expect("init balance");
+    (
+        tree,
+        rollup_config,
+        sudt_id,
+        a_id,
+        b_script_hash,
+        block_info,
+    )
+}
+
 pub fn bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("throughput");
     group.throughput(Throughput::Elements(1u64));
     group.bench_function("sudt", move |b| {
         b.iter_batched(
-            || {
-                let mut tree = DummyState::default();
-
-                let rollup_config = RollupConfig::new_builder()
-                    .l2_sudt_validator_script_type_hash(
-                        DUMMY_SUDT_VALIDATOR_SCRIPT_TYPE_HASH.pack(),
-                    )
-                    .build();
-
-                let init_a_balance: u128 = 10000;
-
-                // init accounts
-                let sudt_id = tree
-                    .create_account_from_script(
-                        Script::new_builder()
-                            .code_hash(DUMMY_SUDT_VALIDATOR_SCRIPT_TYPE_HASH.clone().pack())
-                            .args([0u8; 64].to_vec().pack())
-                            .hash_type(ScriptHashType::Type.into())
-                            .build(),
-                    )
-                    .expect("create account");
-                let a_id = tree
-                    .create_account_from_script(
-                        Script::new_builder()
-                            .code_hash([0u8; 32].pack())
-                            .args([0u8; 20].to_vec().pack())
-                            .hash_type(ScriptHashType::Type.into())
-                            .build(),
-                    )
-                    .expect("create account");
-                let b_id = tree
-                    .create_account_from_script(
-                        Script::new_builder()
-                            .code_hash([0u8; 32].pack())
-                            .args([1u8; 20].to_vec().pack())
-                            .hash_type(ScriptHashType::Type.into())
-                            .build(),
-                    )
-                    .expect("create account");
-                let a_script_hash = tree.get_script_hash(a_id).expect("get script hash");
-                let b_script_hash = tree.get_script_hash(b_id).expect("get script hash");
-                let block_producer_id = tree
-                    .create_account_from_script(
-                        Script::new_builder()
-                            .code_hash([0u8; 32].pack())
-                            .args([3u8; 20].to_vec().pack())
-                            .hash_type(ScriptHashType::Type.into())
+            init_transfer_state,
+            |(mut tree, rollup_config, sudt_id, a_id, b_script_hash, block_info)| {
+                // transfer from A to B
+                let value = 4000u128;
+                let fee = 42u128;
+                let b_address = to_short_address(&b_script_hash).to_vec();
+                let args = SUDTArgs::new_builder()
+                    .set(
+                        SUDTTransfer::new_builder()
+                            .to(b_address.pack())
+                            .amount(value.pack())
+                            .fee(fee.pack())
                             .build(),
                     )
-                    .expect("create account");
-                let block_info = new_block_info(block_producer_id, 1, 0);
-
-                // init balance for a
-                tree.mint_sudt(sudt_id, to_short_address(&a_script_hash), init_a_balance)
-                    .expect("init balance");
-                (
-                    tree,
-                    rollup_config,
-                    sudt_id,
+                    .build();
+                run_contract(
+                    &rollup_config,
+                    &mut tree,
                     a_id,
-                    b_script_hash,
-                    block_info,
+                    sudt_id,
+                    args.as_bytes(),
+                    &block_info,
                 )
+                .expect("execute");
             },
-            |(mut tree, rollup_config, sudt_id, a_id, b_script_hash, block_info)| {
+            BatchSize::SmallInput,
+        );
+    });
+}
+
+/// Execute with a generator shared by iterations, the compiled generator
+/// program is served from the program cache
+pub fn bench_cached(c: &mut Criterion) {
+    let generator = build_generator(&init_transfer_state().1);
+    let mut group = c.benchmark_group("throughput");
+    group.throughput(Throughput::Elements(1u64));
+    group.bench_function("sudt_cached", move |b| {
+        b.iter_batched(
+            init_transfer_state,
+            |(mut tree, _rollup_config, sudt_id, a_id, b_script_hash, block_info)| {
                 // transfer from A to B
                 let value = 4000u128;
                 let fee = 42u128;
@@ -198,8 +257,8 @@ pub fn bench(c: &mut Criterion) {
                             .build(),
                     )
                     .build();
-                run_contract(
-                    &rollup_config,
+                execute_contract(
+                    &generator,
                     &mut tree,
                     a_id,
                     sudt_id,
@@ -216,5 +275,5 @@ pub fn bench(c: &mut Criterion) {
 criterion_group! {
     name = sudt;
     config = Criterion::default().sample_size(10);
-    targets = bench
+    targets = bench, bench_cached
 }
@@ -0,0 +1,130 @@
+//! Database backup and restore
+//!
+//! Backups are RocksDB checkpoints, a running node can create one through the
+//! admin RPC `gw_create_db_checkpoint`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use gw_common::{state::State, H256};
+use gw_config::Config;
+use gw_db::{schema::COLUMNS, RocksDB};
+use gw_store::{integrity, state::state_db::StateContext, Store};
+use gw_types::prelude::*;
+use std::{fs, path::Path};
+
+/// Create a checkpoint of the store in `to`.
+///
+/// RocksDB can't be opened by two processes, the node must be stopped.
+pub fn backup(config: &Config, to: &Path) -> Result<()> {
+    if config.store.path.as_os_str().is_empty() {
+        bail!("empty store path, no db to backup");
+    }
+    if to.exists() {
+        bail!("backup dir {} already exists", to.display());
+    }
+
+    let store = Store::new(RocksDB::open(&config.store, COLUMNS));
+    let (tip_number, tip_hash) = check_tip(&store).with_context(|| "check store")?;
+    store
+        .create_checkpoint(to)
+        .with_context(|| format!("create checkpoint in {}", to.display()))?;
+
+    log::info!(
+        "Backup db at tip #{} 0x{} to {}",
+        tip_number,
+        hex::encode(tip_hash.as_slice()),
+        to.display()
+    );
+    Ok(())
+}
+
+/// Restore the store from a backup. The backup is copied to the store path
+/// and the copy is validated, the backup itself is never opened because
+/// opening RocksDB may write to its files.
+pub fn restore(config: &Config, from: &Path) -> Result<()> {
+    let store_path = &config.store.path;
+    if store_path.as_os_str().is_empty() {
+        bail!("empty store path, no place to restore db");
+    }
+    if store_path.exists() && fs::read_dir(store_path)?.next().is_some() {
+        bail!("store path {} is not empty", store_path.display());
+    }
+
+    copy_dir(from, store_path).with_context(|| "copy backup")?;
+    let store = Store::new(RocksDB::open(&config.store, COLUMNS));
+    let (tip_number, tip_hash) = match check_tip(&store) {
+        Ok(tip) => tip,
+        Err(err) => {
+            // don't leave an invalid db in the store path
+            drop(store);
+            fs::remove_dir_all(store_path)?;
+            return Err(err.context("check backup"));
+        }
+    };
+
+    log::info!(
+        "Restore db at tip #{} 0x{} to {}",
+        tip_number,
+        hex::encode(tip_hash.as_slice()),
+        store_path.display()
+    );
+    Ok(())
+}
+
+/// Check that the last valid tip block matches its post global state and the
+/// account and block SMTs rebuilt from the stored leaves.
+pub fn check_tip(store: &Store) -> Result<(u64, H256)> {
+    let db = store.begin_transaction();
+    let tip_block = db.get_last_valid_tip_block()?;
+    let tip_number: u64 = tip_block.raw().number().unpack();
+    let tip_hash: H256 = tip_block.hash().into();
+
+    let global_state = store
+        .get_block_post_global_state(&tip_hash)?
+        .ok_or_else(|| anyhow!("tip block #{} post global state not found", tip_number))?;
+    let global_state_tip_hash: H256 = global_state.tip_block_hash().unpack();
+    if global_state_tip_hash != tip_hash {
+        bail!("tip block #{} mismatch global state tip", tip_number);
+    }
+
+    let post_account = tip_block.raw().post_account();
+    if global_state.account().as_slice() != post_account.as_slice() {
+        bail!(
+            "tip block #{} post account mismatch global state",
+            tip_number
+        );
+    }
+
+    // roots in meta don't cover corrupted leaves, rebuild them from the leaves
+    let account_root: H256 = post_account.merkle_root().unpack();
+    if integrity::rebuild_account_smt_root(&db)? != account_root {
+        bail!("account smt leaves mismatch tip block #{}", tip_number);
+    }
+    let tree = db.state_tree(StateContext::ReadOnly)?;
+    let account_count: u32 = post_account.count().unpack();
+    if tree.get_account_count()? != account_count {
+        bail!("account count mismatch tip block #{}", tip_number);
+    }
+
+    let block_root: H256 = global_state.block().merkle_root().unpack();
+    if integrity::rebuild_block_smt_root(&db)? != block_root {
+        bail!("block smt leaves mismatch tip block #{}", tip_number);
+    }
+    if db.get_block_smt_root()? != block_root {
+        bail!("block smt root mismatch tip block #{}", tip_number);
+    }
+
+    Ok((tip_number, tip_hash))
+}
+
+// checkpoint dir is flat, it only contains files
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            bail!("unexpected entry {} in backup", entry.path().display());
+        }
+        fs::copy(entry.path(), to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
@@ -283,12 +283,13 @@ impl BlockProducer {
                 // fast sync new tx to local chain
                 let mut chain = self.chain.lock().await;
 
-                let (deposit_requests, deposit_asset_scripts) = extract_deposit_requests(
-                    &self.rpc_client,
-                    self.generator.rollup_context(),
-                    &tx,
-                )
-                .await?;
+                let (deposit_requests, deposit_out_points, deposit_asset_scripts) =
+                    extract_deposit_requests(
+                        &self.rpc_client,
+                        self.generator.rollup_context(),
+                        &tx,
+                    )
+                    .await?;
                 log::debug!("[block producer] fast path sync");
                 let t = Instant::now();
                 chain.sync(SyncParam::Update(UpdateAction::Local(LocalAction {
@@ -297,6 +298,7 @@ impl BlockProducer {
                         /// deposit requests
                         l2block: block,
                         deposit_requests,
+                        deposit_out_points,
                         deposit_asset_scripts,
                     },
                 })))?;
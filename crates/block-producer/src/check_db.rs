@@ -0,0 +1,22 @@
+//! Offline store integrity check
+
+use anyhow::{bail, Result};
+use gw_config::Config;
+use gw_db::{schema::COLUMNS, RocksDB};
+use gw_store::{integrity::check_integrity, Store};
+
+/// Check the store and print the report, returns error if the store is
+/// corrupted. The node must be stopped.
+pub fn check(config: &Config) -> Result<()> {
+    if config.store.path.as_os_str().is_empty() {
+        bail!("empty store path, no db to check");
+    }
+
+    let store = Store::new(RocksDB::open(&config.store, COLUMNS));
+    let report = check_integrity(&store)?;
+    println!("{}", report);
+    if !report.is_ok() {
+        bail!("db {} is corrupted", config.store.path.display());
+    }
+    Ok(())
+}
@@ -1,5 +1,7 @@
+pub mod backup;
 pub mod block_producer;
 pub mod challenger;
+pub mod check_db;
 pub mod cleaner;
 pub mod db_block_validator;
 pub mod debugger;
@@ -7,6 +9,7 @@ pub mod deposit;
 pub mod poller;
 pub mod produce_block;
 pub mod runner;
+pub mod snapshot;
 pub mod stake;
 pub mod test_mode_control;
 pub mod types;
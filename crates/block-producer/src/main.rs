@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
 use clap::{App, Arg, SubCommand};
-use gw_block_producer::{db_block_validator, runner};
+use gw_block_producer::{backup, check_db, db_block_validator, runner, snapshot};
 use gw_config::Config;
 use gw_version::Version;
 use std::{fs, path::Path};
@@ -8,11 +8,19 @@ use std::{fs, path::Path};
 const COMMAND_RUN: &str = "run";
 const COMMAND_EXAMPLE_CONFIG: &str = "generate-example-config";
 const COMMAND_VERIFY_DB_BLOCK: &str = "verify-db-block";
+const COMMAND_BACKUP: &str = "backup";
+const COMMAND_RESTORE: &str = "restore";
+const COMMAND_SNAPSHOT: &str = "snapshot";
+const COMMAND_EXPORT: &str = "export";
+const COMMAND_IMPORT: &str = "import";
+const COMMAND_CHECK_DB: &str = "check-db";
 const ARG_OUTPUT_PATH: &str = "output-path";
 const ARG_CONFIG: &str = "config";
 const ARG_SKIP_CONFIG_CHECK: &str = "skip-config-check";
 const ARG_FROM_BLOCK: &str = "from-block";
 const ARG_TO_BLOCK: &str = "to-block";
+const ARG_TO: &str = "to";
+const ARG_FROM: &str = "from";
 
 fn read_config<P: AsRef<Path>>(path: P) -> Result<Config> {
     let content = fs::read(&path)
@@ -90,6 +98,102 @@ fn run_cli() -> Result<()> {
                         .help("To block number"),
                 )
                 .display_order(2),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_BACKUP)
+                .about("Backup db to a checkpoint, the node must be stopped")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./config.toml")
+                        .help("The config file path"),
+                )
+                .arg(
+                    Arg::with_name(ARG_TO)
+                        .long(ARG_TO)
+                        .takes_value(true)
+                        .required(true)
+                        .help("The backup dir, must not exist"),
+                )
+                .display_order(3),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_RESTORE)
+                .about("Restore db from a backup to the store path")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./config.toml")
+                        .help("The config file path"),
+                )
+                .arg(
+                    Arg::with_name(ARG_FROM)
+                        .long(ARG_FROM)
+                        .takes_value(true)
+                        .required(true)
+                        .help("The backup dir"),
+                )
+                .display_order(4),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_SNAPSHOT)
+                .about("Export or import state snapshot")
+                .subcommand(
+                    SubCommand::with_name(COMMAND_EXPORT)
+                        .about("Export state at the db tip, the node must be stopped")
+                        .arg(
+                            Arg::with_name(ARG_CONFIG)
+                                .short("c")
+                                .takes_value(true)
+                                .required(true)
+                                .default_value("./config.toml")
+                                .help("The config file path"),
+                        )
+                        .arg(
+                            Arg::with_name(ARG_TO)
+                                .long(ARG_TO)
+                                .takes_value(true)
+                                .required(true)
+                                .help("The snapshot file, must not exist"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name(COMMAND_IMPORT)
+                        .about("Import state snapshot into an empty store path and verify it on layer1")
+                        .arg(
+                            Arg::with_name(ARG_CONFIG)
+                                .short("c")
+                                .takes_value(true)
+                                .required(true)
+                                .default_value("./config.toml")
+                                .help("The config file path"),
+                        )
+                        .arg(
+                            Arg::with_name(ARG_FROM)
+                                .long(ARG_FROM)
+                                .takes_value(true)
+                                .required(true)
+                                .help("The snapshot file"),
+                        ),
+                )
+                .display_order(5),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_CHECK_DB)
+                .about("Check the integrity of the db, exit with error if it is corrupted")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./config.toml")
+                        .help("The config file path"),
+                )
+                .display_order(6),
         );
 
     // handle subcommands
@@ -111,6 +215,40 @@ fn run_cli() -> Result<()> {
             let to_block: Option<u64> = m.value_of(ARG_TO_BLOCK).map(str::parse).transpose()?;
             db_block_validator::verify(config, from_block, to_block)?;
         }
+        (COMMAND_BACKUP, Some(m)) => {
+            let config_path = m.value_of(ARG_CONFIG).unwrap();
+            let config = read_config(&config_path)?;
+            let to = m.value_of(ARG_TO).unwrap();
+            backup::backup(&config, Path::new(to))?;
+        }
+        (COMMAND_RESTORE, Some(m)) => {
+            let config_path = m.value_of(ARG_CONFIG).unwrap();
+            let config = read_config(&config_path)?;
+            let from = m.value_of(ARG_FROM).unwrap();
+            backup::restore(&config, Path::new(from))?;
+        }
+        (COMMAND_SNAPSHOT, Some(m)) => match m.subcommand() {
+            (COMMAND_EXPORT, Some(m)) => {
+                let config_path = m.value_of(ARG_CONFIG).unwrap();
+                let config = read_config(&config_path)?;
+                let to = m.value_of(ARG_TO).unwrap();
+                snapshot::export(&config, Path::new(to))?;
+            }
+            (COMMAND_IMPORT, Some(m)) => {
+                let config_path = m.value_of(ARG_CONFIG).unwrap();
+                let config = read_config(&config_path)?;
+                let from = m.value_of(ARG_FROM).unwrap();
+                snapshot::import(&config, Path::new(from))?;
+            }
+            _ => {
+                println!("{}", m.usage());
+            }
+        },
+        (COMMAND_CHECK_DB, Some(m)) => {
+            let config_path = m.value_of(ARG_CONFIG).unwrap();
+            let config = read_config(&config_path)?;
+            check_db::check(&config)?;
+        }
         _ => {
             // default command: start a Godwoken node
             let config_path = "./config.toml";
@@ -7,6 +7,7 @@ use crate::{
 use anyhow::{anyhow, Result};
 use async_jsonrpc_client::{Params as ClientParams, Transport};
 use ckb_fixed_hash::H256;
+use ckb_types::core::EpochNumberWithFraction;
 use gw_chain::chain::{
     Chain, ChallengeCell, L1Action, L1ActionContext, LocalSyncStatus, RevertL1ActionContext,
     RevertedAction, RevertedL1Action, RevertedLocalAction, SyncParam, UpdateAction,
@@ -303,12 +304,24 @@ impl ChainUpdater {
         let rollup_action = self.extract_rollup_action(&tx)?;
         let context = match rollup_action.to_enum() {
             RollupActionUnion::RollupSubmitBlock(submitted) => {
-                let (requests, asset_type_scripts) =
+                // VM version switches apply from the first block submitted in
+                // their L1 epochs
+                let block_number = submitted.block().raw().number().unpack();
+                let l1_epoch =
+                    EpochNumberWithFraction::from_full_value(header_view.inner.epoch.value())
+                        .number();
+                self.chain
+                    .lock()
+                    .await
+                    .activate_vm_versions(l1_epoch, block_number)?;
+
+                let (requests, out_points, asset_type_scripts) =
                     extract_deposit_requests(&self.rpc_client, &self.rollup_context, &tx).await?;
 
                 L1ActionContext::SubmitBlock {
                     l2block: submitted.block(),
                     deposit_requests: requests,
+                    deposit_out_points: out_points,
                     deposit_asset_scripts: asset_type_scripts,
                 }
             }
@@ -7,27 +7,21 @@ use async_jsonrpc_client::HttpClient;
 use ckb_types::core::hardfork::HardForkSwitch;
 use gw_chain::chain::Chain;
 use gw_challenge::offchain::{OffChainMockContext, OffChainValidatorContext};
-use gw_ckb_hardfork::{GLOBAL_CURRENT_EPOCH_NUMBER, GLOBAL_HARDFORK_SWITCH, GLOBAL_VM_VERSION};
+use gw_ckb_hardfork::{GLOBAL_CURRENT_EPOCH_NUMBER, GLOBAL_HARDFORK_SWITCH};
 use gw_common::{blake2b::new_blake2b, H256};
 use gw_config::{BlockProducerConfig, Config, NodeMode};
-use gw_db::{schema::COLUMNS, RocksDB};
-use gw_generator::{
-    account_lock_manage::{
-        secp256k1::{Secp256k1Eth, Secp256k1Tron},
-        AccountLockManage,
-    },
-    backend_manage::BackendManage,
-    genesis::init_genesis,
-    Generator,
-};
+use gw_db::{schema::COLUMNS, RocksDB, RocksDBSecondary};
+use gw_generator::{genesis::init_genesis, Generator};
 use gw_mem_pool::{
     batch::MemPoolBatch, default_provider::DefaultMemPoolProvider, pool::MemPool,
     traits::MemPoolErrorTxHandler,
 };
 use gw_poa::PoA;
 use gw_rpc_client::rpc_client::RPCClient;
-use gw_rpc_server::{registry::Registry, server::start_jsonrpc_server};
-use gw_store::Store;
+use gw_rpc_server::{
+    binary::start_binary_server, registry::Registry, server::start_jsonrpc_server,
+};
+use gw_store::{prune::HistoryPruner, Store};
 use gw_types::{
     bytes::Bytes,
     offchain::RollupContext,
@@ -52,6 +46,7 @@ use std::{
 const MIN_CKB_VERSION: &str = "0.40.0";
 const SMOL_THREADS_ENV_VAR: &str = "SMOL_THREADS";
 const DEFAULT_RUNTIME_THREADS: usize = 4;
+const SECONDARY_CATCH_UP_INTERVAL: Duration = Duration::from_secs(1);
 
 async fn poll_loop(
     rpc_client: RPCClient,
@@ -167,7 +162,6 @@ async fn poll_loop(
 
             // update global hardfork info
             let hardfork_switch = rpc_client.get_hardfork_switch().await?;
-            let rpc32_epoch_number = hardfork_switch.rfc_0032();
             let mut global_hardfork_switch = GLOBAL_HARDFORK_SWITCH.lock().await;
             if !is_hardfork_switch_eq(&*global_hardfork_switch, &hardfork_switch) {
                 *global_hardfork_switch = hardfork_switch
@@ -179,17 +173,6 @@ async fn poll_loop(
             if *global_epoch_number != current_epoch_number {
                 *global_epoch_number = current_epoch_number;
             }
-
-            // update global vm version
-            let vm_version: u32 = if current_epoch_number >= rpc32_epoch_number {
-                1
-            } else {
-                0
-            };
-            let mut global_vm_version = GLOBAL_VM_VERSION.lock().await;
-            if *global_vm_version != vm_version {
-                *global_vm_version = vm_version;
-            }
         } else {
             log::debug!(
                 "Not found layer1 block #{} sleep {}s then retry",
@@ -252,14 +235,32 @@ impl BaseInitComponents {
 
         // Open store
         let timer = Instant::now();
-        let store = if config.store.path.as_os_str().is_empty() {
+        let mut store = if let Some(ref secondary_path) = config.store.secondary_path {
+            if NodeMode::ReadOnly != config.node_mode {
+                return Err(anyhow!("secondary store requires readonly mode"));
+            }
+            if config.web3_indexer.is_some() {
+                return Err(anyhow!("web3 indexer isn't supported with secondary store"));
+            }
+            Store::new_secondary(RocksDBSecondary::open(
+                &config.store,
+                secondary_path,
+                COLUMNS,
+            ))
+        } else if config.store.path.as_os_str().is_empty() {
             log::warn!("config.store.path is blank, using temporary store");
             Store::open_tmp().with_context(|| "init store")?
         } else {
             Store::new(RocksDB::open(&config.store, COLUMNS))
         };
+        if config.store.prune.is_some() {
+            store.enable_history_pruner();
+        }
         let elapsed_ms = timer.elapsed().as_millis();
         log::debug!("Open rocksdb costs: {}ms.", elapsed_ms);
+        if store.is_secondary() && !store.has_genesis()? {
+            return Err(anyhow!("primary db isn't initialized"));
+        }
 
         let secp_data: Bytes = {
             let out_point = config.genesis.secp_data_dep.out_point.clone();
@@ -279,33 +280,27 @@ impl BaseInitComponents {
             secp_data.clone(),
         )
         .with_context(|| "init genesis")?;
+        if !store.is_secondary() {
+            let indexed = store
+                .backfill_account_transaction_index()
+                .with_context(|| "backfill account transaction index")?;
+            if indexed > 0 {
+                log::info!("Backfilled account transaction index of {} blocks", indexed);
+            }
+        }
 
         let rollup_config_hash: H256 = rollup_config.hash().into();
-        let generator = {
-            let backend_manage = BackendManage::from_config(config.backends.clone())
-                .with_context(|| "config backends")?;
-            let mut account_lock_manage = AccountLockManage::default();
-            let eth_lock_script_type_hash = rollup_config
-                .allowed_eoa_type_hashes()
-                .get(0)
-                .ok_or_else(|| anyhow!("Eth: No allowed EoA type hashes in the rollup config"))?;
-            account_lock_manage.register_lock_algorithm(
-                eth_lock_script_type_hash.unpack(),
-                Box::new(Secp256k1Eth::default()),
-            );
-            let tron_lock_script_type_hash = rollup_config.allowed_eoa_type_hashes().get(1);
-            if let Some(code_hash) = tron_lock_script_type_hash {
-                account_lock_manage
-                    .register_lock_algorithm(code_hash.unpack(), Box::new(Secp256k1Tron::default()))
-            }
-            Arc::new(Generator::new(
-                backend_manage,
-                account_lock_manage,
-                rollup_context.clone(),
-                config.rpc.clone(),
-                config.mem_pool.submit_l2tx_max_cycles,
-            ))
-        };
+        let generator = Arc::new(
+            Generator::from_config(config, rollup_context.clone())
+                .with_context(|| "config generator")?,
+        );
+        {
+            let hardfork_switch = smol::block_on(rpc_client.get_hardfork_switch())?;
+            generator
+                .vm_version_schedule()
+                .check_rfc_0032(hardfork_switch.rfc_0032())
+                .with_context(|| "check [[chain.vm_version_schedule]]")?;
+        }
 
         let ckb_genesis_info = {
             let ckb_genesis = smol::block_on(async { rpc_client.get_block_by_number(0).await })?
@@ -398,9 +393,11 @@ pub fn run(config: Config, skip_config_check: bool) -> Result<()> {
     );
 
     let base = BaseInitComponents::init(&config, skip_config_check)?;
+    // secondary store is read-only, mem-pool isn't available
     let (mem_pool, wallet, poa, offchain_mock_context, pg_pool) = match config
         .block_producer
         .clone()
+        .filter(|_| !base.store.is_secondary())
     {
         Some(block_producer_config) => {
             let wallet = Wallet::from_config(&block_producer_config.wallet_config)
@@ -530,6 +527,17 @@ pub fn run(config: Config, skip_config_check: bool) -> Result<()> {
         web3_indexer,
     );
 
+    // History pruner background service
+    let pruner_task = config
+        .store
+        .prune
+        .clone()
+        .filter(|_| !store.is_secondary())
+        .map(|prune_config| {
+            let pruner = HistoryPruner::new(store.clone(), rollup_context.clone(), prune_config);
+            smol::spawn(prune_loop(pruner))
+        });
+
     let (block_producer, challenger, test_mode_control, cleaner) = match config.node_mode {
         NodeMode::ReadOnly => (None, None, None, None),
         mode => {
@@ -618,7 +626,7 @@ pub fn run(config: Config, skip_config_check: bool) -> Result<()> {
 
     // RPC registry
     let rpc_registry = Registry::new(
-        store,
+        store.clone(),
         generator,
         test_mode_control.map(Box::new),
         rollup_config,
@@ -629,6 +637,8 @@ pub fn run(config: Config, skip_config_check: bool) -> Result<()> {
         config.node_mode,
         mem_pool_batch,
         config.rpc_server.clone(),
+        config.rpc.clone(),
+        rpc_client.clone(),
     );
 
     let (exit_sender, exit_recv) = async_channel::bounded(100);
@@ -640,15 +650,21 @@ pub fn run(config: Config, skip_config_check: bool) -> Result<()> {
     };
     ctrlc::set_handler(handle).unwrap();
 
-    let rpc_address: SocketAddr = {
-        let mut addrs: Vec<_> = config.rpc_server.listen.to_socket_addrs()?.collect();
-        if addrs.len() != 1 {
-            return Err(anyhow!(
-                "Invalid RPC listen address `{}`",
-                &config.rpc_server.listen
-            ));
+    let rpc_address = parse_listen_address(&config.rpc_server.listen)?;
+    let binary_rpc = match config.rpc_server.binary_listen {
+        Some(ref listen) => {
+            let address = parse_listen_address(listen)?;
+            let handler = rpc_registry.build_binary_handler();
+            // the binary server has no other access control
+            if !address.ip().is_loopback() && !handler.has_auth_token() {
+                return Err(anyhow!(
+                    "Binary RPC listens on non-loopback address `{}`, set `rpc_server.binary_auth_token`",
+                    listen
+                ));
+            }
+            Some((address, handler))
         }
-        addrs.remove(0)
+        None => None,
     };
 
     {
@@ -666,17 +682,23 @@ pub fn run(config: Config, skip_config_check: bool) -> Result<()> {
 
     let chain_task = smol::spawn({
         let exit_sender = exit_sender.clone();
+        let store = store.clone();
         async move {
-            if let Err(err) = poll_loop(
-                rpc_client,
-                chain_updater,
-                block_producer,
-                challenger,
-                cleaner,
-                Duration::from_secs(3),
-            )
-            .await
-            {
+            // secondary store follows the primary node instead of layer1
+            let result = if store.is_secondary() {
+                catch_up_loop(store, SECONDARY_CATCH_UP_INTERVAL).await
+            } else {
+                poll_loop(
+                    rpc_client,
+                    chain_updater,
+                    block_producer,
+                    challenger,
+                    cleaner,
+                    Duration::from_secs(3),
+                )
+                .await
+            };
+            if let Err(err) = result {
                 log::error!("chain polling loop exit unexpected, error: {}", err);
             }
             if let Err(err) = exit_sender.send(()).await {
@@ -684,6 +706,13 @@ pub fn run(config: Config, skip_config_check: bool) -> Result<()> {
             }
         }
     });
+    let binary_rpc_task = binary_rpc.map(|(address, handler)| {
+        smol::spawn(async move {
+            if let Err(err) = start_binary_server(address, handler).await {
+                log::error!("Error running binary RPC server: {:?}", err);
+            }
+        })
+    });
     let rpc_task = smol::spawn(async move {
         if let Err(err) = start_jsonrpc_server(rpc_address, rpc_registry).await {
             log::error!("Error running JSONRPC server: {:?}", err);
@@ -698,7 +727,13 @@ pub fn run(config: Config, skip_config_check: bool) -> Result<()> {
         log::info!("Exiting...");
 
         rpc_task.cancel().await;
+        if let Some(binary_rpc_task) = binary_rpc_task {
+            binary_rpc_task.cancel().await;
+        }
         chain_task.cancel().await;
+        if let Some(pruner_task) = pruner_task {
+            pruner_task.cancel().await;
+        }
     });
 
     if let Some(mem_pool) = mem_pool.as_ref() {
@@ -708,13 +743,45 @@ pub fn run(config: Config, skip_config_check: bool) -> Result<()> {
             if let Err(err) = mem_pool.save_mem_block() {
                 log::error!("save mem block error {}", err);
             }
-            mem_pool.restore_manager().delete_before_one_hour();
+            mem_pool.restore_manager().apply_retention();
         });
     }
 
     Ok(())
 }
 
+async fn prune_loop(pruner: HistoryPruner) {
+    let interval = Duration::from_secs(pruner.config().interval_secs);
+    loop {
+        let round = pruner.clone();
+        match smol::unblock(move || round.prune()).await {
+            // keep pruning until history out of the retention window is pruned
+            Ok(Some(_)) => continue,
+            Ok(None) => (),
+            Err(err) => log::error!("prune history error: {}", err),
+        }
+        async_std::task::sleep(interval).await;
+    }
+}
+
+async fn catch_up_loop(store: Store, interval: Duration) -> Result<()> {
+    loop {
+        let secondary = store.clone();
+        smol::unblock(move || secondary.try_catch_up_with_primary())
+            .await
+            .with_context(|| "catch up with primary")?;
+        async_std::task::sleep(interval).await;
+    }
+}
+
+fn parse_listen_address(listen: &str) -> Result<SocketAddr> {
+    let mut addrs: Vec<_> = listen.to_socket_addrs()?.collect();
+    if addrs.len() != 1 {
+        return Err(anyhow!("Invalid RPC listen address `{}`", listen));
+    }
+    Ok(addrs.remove(0))
+}
+
 fn check_ckb_version(rpc_client: &RPCClient) -> Result<()> {
     let ckb_version = smol::block_on(rpc_client.get_ckb_version())?;
     let ckb_version = ckb_version.split('(').collect::<Vec<&str>>()[0].trim_end();
@@ -0,0 +1,143 @@
+//! State snapshot export and import for fast node bootstrap
+//!
+//! An imported snapshot is verified against the rollup cell global state on
+//! layer1, the node continues syncing from the snapshot tip after start.
+
+use crate::backup::check_tip;
+use anyhow::{anyhow, bail, Context, Result};
+use async_jsonrpc_client::HttpClient;
+use gw_chain::chain::parse_global_state;
+use gw_common::H256;
+use gw_config::Config;
+use gw_db::{schema::COLUMNS, RocksDB};
+use gw_rpc_client::rpc_client::RPCClient;
+use gw_store::{state_snapshot, Store};
+use gw_types::{
+    offchain::{RollupContext, TxStatus},
+    packed::Script,
+    prelude::*,
+};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+/// Export the state at the store tip to a snapshot file, the node must be stopped.
+pub fn export(config: &Config, to: &Path) -> Result<()> {
+    if config.store.path.as_os_str().is_empty() {
+        bail!("empty store path, no state to export");
+    }
+    if to.exists() {
+        bail!("snapshot file {} already exists", to.display());
+    }
+
+    let store = Store::new(RocksDB::open(&config.store, COLUMNS));
+    check_tip(&store).with_context(|| "check store")?;
+    let writer = BufWriter::new(File::create(to)?);
+    let tip = state_snapshot::export_state_snapshot(&store, writer)
+        .with_context(|| format!("export state snapshot to {}", to.display()))?;
+
+    let tip_number: u64 = tip.block.raw().number().unpack();
+    log::info!(
+        "Export state snapshot at tip #{} 0x{} to {}",
+        tip_number,
+        hex::encode(tip.block.hash()),
+        to.display()
+    );
+    Ok(())
+}
+
+/// Import a snapshot file into an empty store. The store is removed if the
+/// snapshot doesn't match layer1.
+pub fn import(config: &Config, from: &Path) -> Result<()> {
+    let store_path = &config.store.path;
+    if store_path.as_os_str().is_empty() {
+        bail!("empty store path, no place to import state");
+    }
+    if store_path.exists() && fs::read_dir(store_path)?.next().is_some() {
+        bail!("store path {} is not empty", store_path.display());
+    }
+
+    let tip_hash = state_snapshot::verify_state_snapshot(BufReader::new(File::open(from)?))
+        .with_context(|| format!("verify state snapshot {}", from.display()))?;
+    log::info!(
+        "Verified state snapshot at tip 0x{}",
+        hex::encode(tip_hash.as_slice())
+    );
+
+    let result = {
+        let store = Store::new(RocksDB::open(&config.store, COLUMNS));
+        import_and_verify(config, &store, from)
+    };
+    if result.is_err() {
+        fs::remove_dir_all(store_path)
+            .with_context(|| format!("remove store path {}", store_path.display()))?;
+    }
+    let tip_number = result?;
+
+    log::info!(
+        "Import state snapshot at tip #{} to {}",
+        tip_number,
+        store_path.display()
+    );
+    Ok(())
+}
+
+fn import_and_verify(config: &Config, store: &Store, from: &Path) -> Result<u64> {
+    let tip = state_snapshot::import_state_snapshot(store, BufReader::new(File::open(from)?))
+        .with_context(|| "import state snapshot")?;
+    let rollup_type_hash: [u8; 32] = config.genesis.rollup_type_hash.clone().into();
+    if store.get_chain_id()? != H256::from(rollup_type_hash) {
+        bail!("snapshot doesn't belong to the rollup");
+    }
+    let tip_number: u64 = tip.block.raw().number().unpack();
+
+    // the import has verified the state roots rebuilt from the leaves against
+    // the snapshot global state, verify it against the rollup cell on layer1
+    let rpc_client = build_rpc_client(config)?;
+    let tx_hash: H256 = tip.committed_info.transaction_hash().unpack();
+    let tx_status = smol::block_on(rpc_client.get_transaction_status(tx_hash))?;
+    if !matches!(tx_status, Some(TxStatus::Committed)) {
+        bail!(
+            "tip block #{} submission tx 0x{} isn't committed on layer1",
+            tip_number,
+            hex::encode(tx_hash.as_slice())
+        );
+    }
+    let tx = smol::block_on(rpc_client.get_transaction(tx_hash))?
+        .ok_or_else(|| anyhow!("tip block #{} submission tx not found", tip_number))?;
+    let global_state = parse_global_state(&tx, &rollup_type_hash)?;
+    if global_state.account().merkle_root() != tip.global_state.account().merkle_root() {
+        bail!("imported account smt root mismatch layer1 rollup cell");
+    }
+    if global_state.block().merkle_root() != tip.global_state.block().merkle_root() {
+        bail!("imported block smt root mismatch layer1 rollup cell");
+    }
+    if global_state.as_slice() != tip.global_state.as_slice() {
+        bail!("snapshot global state mismatch layer1 rollup cell");
+    }
+
+    Ok(tip_number)
+}
+
+fn build_rpc_client(config: &Config) -> Result<RPCClient> {
+    let rollup_context = RollupContext {
+        rollup_config: config.genesis.rollup_config.clone().into(),
+        rollup_script_hash: {
+            let rollup_script_hash: [u8; 32] = config.genesis.rollup_type_hash.clone().into();
+            rollup_script_hash.into()
+        },
+    };
+    let rollup_type_script: Script = config.chain.rollup_type_script.clone().into();
+    let indexer_client = HttpClient::new(config.rpc_client.indexer_url.to_owned())?;
+    let ckb_client = HttpClient::new(config.rpc_client.ckb_url.to_owned())?;
+    let rollup_type_script =
+        ckb_types::packed::Script::new_unchecked(rollup_type_script.as_bytes());
+    Ok(RPCClient::new(
+        rollup_type_script,
+        rollup_context,
+        ckb_client,
+        indexer_client,
+    ))
+}
@@ -8,7 +8,7 @@ use ckb_fixed_hash::H256;
 use gw_config::DebugConfig;
 use gw_jsonrpc_types::ckb_jsonrpc_types::TransactionWithStatus;
 use gw_rpc_client::rpc_client::RPCClient;
-use gw_types::packed::{CellOutput, DepositRequest, Script, Transaction};
+use gw_types::packed::{CellOutput, DepositRequest, OutPoint, Script, Transaction};
 use gw_types::{
     bytes::Bytes, core::ScriptHashType, offchain::RollupContext, packed::DepositLockArgs,
     prelude::*,
@@ -69,8 +69,9 @@ pub async fn extract_deposit_requests(
     rpc_client: &RPCClient,
     rollup_context: &RollupContext,
     tx: &Transaction,
-) -> Result<(Vec<DepositRequest>, HashSet<Script>)> {
+) -> Result<(Vec<DepositRequest>, Vec<OutPoint>, HashSet<Script>)> {
     let mut results = vec![];
+    let mut out_points = vec![];
     let mut asset_type_scripts = HashSet::new();
     for input in tx.raw().inputs().into_iter() {
         // Load cell denoted by the transaction input
@@ -111,12 +112,13 @@ pub async fn extract_deposit_requests(
             try_parse_deposit_request(&cell_output, &cell_data.unpack(), rollup_context)
         {
             results.push(deposit_request);
+            out_points.push(input.previous_output());
             if let Some(type_) = &cell_output.type_().to_opt() {
                 asset_type_scripts.insert(type_.clone());
             }
         }
     }
-    Ok((results, asset_type_scripts))
+    Ok((results, out_points, asset_type_scripts))
 }
 
 fn try_parse_deposit_request(
@@ -19,7 +19,8 @@ use gw_types::{
     offchain::global_state_from_slice,
     packed::{
         BlockMerkleState, CellInput, CellOutput, ChallengeTarget, ChallengeWitness, DepositRequest,
-        GlobalState, L2Block, L2BlockCommittedInfo, RawL2Block, RollupConfig, Script, Transaction,
+        GlobalState, L2Block, L2BlockCommittedInfo, OutPoint, RawL2Block, RollupConfig, Script,
+        Transaction,
     },
     prelude::{Builder as GWBuilder, Entity as GWEntity, Pack as GWPack, Unpack as GWUnpack},
 };
@@ -48,6 +49,8 @@ pub enum L1ActionContext {
         /// deposit requests
         l2block: L2Block,
         deposit_requests: Vec<DepositRequest>,
+        /// out points of the deposit cells, in the same order as deposit requests
+        deposit_out_points: Vec<OutPoint>,
         deposit_asset_scripts: HashSet<Script>,
     },
     Challenge {
@@ -203,6 +206,7 @@ pub struct Chain {
     mem_pool: Option<Arc<Mutex<MemPool>>>,
     complete_initial_syncing: bool,
     skipped_invalid_block_list: HashSet<H256>,
+    tx_access_list: bool,
 }
 
 impl Chain {
@@ -244,6 +248,9 @@ impl Chain {
             last_synced,
             last_global_state,
         };
+        generator
+            .vm_version_schedule()
+            .restore_activations(store.begin_transaction().get_vm_version_activations()?)?;
         let rollup_config_hash = rollup_config.hash();
         let skipped_invalid_block_list = config
             .skipped_invalid_block_list
@@ -265,6 +272,7 @@ impl Chain {
             rollup_config_hash,
             complete_initial_syncing: false,
             skipped_invalid_block_list,
+            tx_access_list: config.tx_access_list,
         })
     }
 
@@ -273,6 +281,25 @@ impl Chain {
         &self.local_state
     }
 
+    /// Activate VM version switches scheduled in or before the L1 epoch a
+    /// block is submitted in, activations are recorded in the db
+    pub fn activate_vm_versions(&self, l1_epoch: u64, block_number: u64) -> Result<()> {
+        let schedule = self.generator.vm_version_schedule();
+        if schedule.activate(l1_epoch, block_number) {
+            let activations = schedule.activations();
+            let db = self.store.begin_transaction();
+            db.set_vm_version_activations(&activations)?;
+            db.commit()?;
+            log::info!(
+                "VM {:?} is activated from block #{} submitted in L1 epoch {}",
+                schedule.vm_version(u64::MAX),
+                activations.last().expect("activated"),
+                l1_epoch
+            );
+        }
+        Ok(())
+    }
+
     pub fn store(&self) -> &Store {
         &self.store
     }
@@ -398,6 +425,7 @@ impl Chain {
                     L1ActionContext::SubmitBlock {
                         l2block,
                         deposit_requests,
+                        deposit_out_points,
                         deposit_asset_scripts,
                     },
                 ) => {
@@ -460,6 +488,7 @@ impl Chain {
                         l2block.clone(),
                         global_state.clone(),
                         deposit_requests.to_owned(),
+                        deposit_out_points,
                         deposit_asset_scripts.to_owned(),
                     )? {
                         Some(challenge_target) => {
@@ -1074,6 +1103,7 @@ impl Chain {
         l2block: L2Block,
         global_state: GlobalState,
         deposit_requests: Vec<DepositRequest>,
+        deposit_out_points: &[OutPoint],
         deposit_asset_scripts: HashSet<Script>,
     ) -> Result<Option<ChallengeTarget>> {
         let tip_number: u64 = self.local_state.tip.raw().number().unpack();
@@ -1110,13 +1140,14 @@ impl Chain {
         // process transactions
         // TODO: run offchain validator before send challenge, to make sure the block is bad
         let generator = &self.generator;
-        let (withdrawal_receipts, prev_txs_state, tx_receipts) = match generator
+        let (withdrawal_receipts, prev_txs_state, tx_receipts, tx_access_lists) = match generator
             .verify_and_apply_block(db, &chain_view, args, &self.skipped_invalid_block_list)
         {
             ApplyBlockResult::Success {
                 tx_receipts,
                 prev_txs_state,
                 withdrawal_receipts,
+                tx_access_lists,
                 offchain_used_cycles,
             } => {
                 log::debug!(
@@ -1125,7 +1156,12 @@ impl Chain {
                     tx_receipts.len(),
                     offchain_used_cycles
                 );
-                (withdrawal_receipts, prev_txs_state, tx_receipts)
+                (
+                    withdrawal_receipts,
+                    prev_txs_state,
+                    tx_receipts,
+                    tx_access_lists,
+                )
             }
             ApplyBlockResult::Challenge { target, error } => {
                 log::warn!("verify #{} state transition error {}", block_number, error);
@@ -1143,6 +1179,10 @@ impl Chain {
             tx_receipts,
             deposit_requests,
         )?;
+        if self.tx_access_list {
+            db.insert_block_tx_access_lists(&l2block, &tx_access_lists)?;
+        }
+        db.insert_block_deposit_info(&l2block, deposit_out_points)?;
         db.insert_asset_scripts(deposit_asset_scripts)?;
 
         db.attach_block(l2block.clone())?;
@@ -1161,7 +1201,8 @@ impl Chain {
     }
 }
 
-fn parse_global_state(tx: &Transaction, rollup_id: &[u8; 32]) -> Result<GlobalState> {
+/// Parse the global state from the rollup cell in the layer1 transaction
+pub fn parse_global_state(tx: &Transaction, rollup_id: &[u8; 32]) -> Result<GlobalState> {
     // find rollup state cell from outputs
     let (i, _) = tx
         .raw()
@@ -32,6 +32,8 @@ pub struct Config {
     pub db_block_validator: Option<DBBlockValidatorConfig>,
     #[serde(default)]
     pub store: StoreConfig,
+    #[serde(default)]
+    pub parallel_execution: ParallelExecutionConfig,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
@@ -40,6 +42,7 @@ pub enum RPCMethods {
     #[serde(rename = "pprof")]
     PProf,
     Test,
+    Admin,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
@@ -47,6 +50,13 @@ pub struct RPCServerConfig {
     pub listen: String,
     #[serde(default)]
     pub enable_methods: HashSet<RPCMethods>,
+    /// Listen address of the binary (molecule over TCP) RPC server, disabled if unset
+    #[serde(default)]
+    pub binary_listen: Option<String>,
+    /// Token clients of the binary RPC server must send before requests,
+    /// required if the server listens on a non-loopback address
+    #[serde(default)]
+    pub binary_auth_token: Option<String>,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
@@ -61,6 +71,27 @@ pub struct RPCConfig {
     pub sudt_proxy_code_hashes: Vec<H256>,
     pub allowed_polyjuice_contract_creator_address: Option<HashSet<H160>>,
     pub polyjuice_script_code_hash: Option<H256>,
+    /// Max entries of the RPC response cache, the cache is disabled if unset or zero
+    #[serde(default)]
+    pub cache_size: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneConfig {
+    /// Number of blocks before the last finalized block whose history is kept
+    pub retained_blocks: u64,
+    pub interval_secs: u64,
+    pub max_blocks_per_round: u64,
+}
+
+impl Default for PruneConfig {
+    fn default() -> Self {
+        Self {
+            retained_blocks: 10000,
+            interval_secs: 60,
+            max_blocks_per_round: 100,
+        }
+    }
 }
 
 /// Onchain rollup cell config
@@ -71,6 +102,43 @@ pub struct ChainConfig {
     pub skipped_invalid_block_list: Vec<H256>,
     pub genesis_committed_info: L2BlockCommittedInfo,
     pub rollup_type_script: Script,
+    /// Record state keys touched by each tx of blocks, they are returned by
+    /// `gw_get_transaction_receipt` with the access list flag
+    #[serde(default)]
+    pub tx_access_list: bool,
+    /// CKB VM versions of L2 blocks, blocks before the first switch use VM 0.
+    /// L1 epochs and VM versions of switches must be strictly increasing.
+    #[serde(default)]
+    pub vm_version_schedule: Vec<VMVersionSwitch>,
+}
+
+/// Switch the CKB VM version of L2 blocks at an L1 hardfork. The switch
+/// applies from the first L2 block submitted in or after `l1_epoch`, the
+/// block is recorded when it's synced from L1.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VMVersionSwitch {
+    pub l1_epoch: u64,
+    pub vm_version: u32,
+}
+
+/// Optimistic parallel execution of transactions in block verification.
+/// Transactions are executed speculatively on worker threads, then committed
+/// in order, conflicting transactions are re-executed serially.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParallelExecutionConfig {
+    /// Execute transactions serially if disabled
+    pub enabled: bool,
+    /// Number of worker threads, the number of CPUs is used if zero
+    pub threads: usize,
+}
+
+impl Default for ParallelExecutionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threads: 0,
+        }
+    }
 }
 
 /// Genesis config
@@ -141,6 +209,21 @@ pub struct BackendConfig {
     pub validator_path: PathBuf,
     pub generator_path: PathBuf,
     pub validator_script_type_hash: H256,
+    /// Activate the backend from a block, it replaces backends listed before
+    /// it with the same validator script type hash. Active from genesis if
+    /// unset.
+    #[serde(default)]
+    pub activation: Option<BackendActivation>,
+}
+
+/// Activation point of a backend upgrade, e.g. `activation = { block_number = 1000 }`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendActivation {
+    /// L2 block number
+    BlockNumber(u64),
+    /// L2 block timestamp in milliseconds
+    Timestamp(u64),
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -286,6 +369,8 @@ pub struct MemPoolConfig {
     #[serde(default = "default_restore_path")]
     pub restore_path: PathBuf,
     #[serde(default)]
+    pub restore_retention: RestoreRetentionConfig,
+    #[serde(default)]
     pub fee_config: FeeConfig,
 }
 
@@ -304,11 +389,33 @@ impl Default for MemPoolConfig {
             max_batch_channel_buffer_size: 2000,
             max_batch_tx_withdrawal_size: 200,
             restore_path: default_restore_path(),
+            restore_retention: Default::default(),
             fee_config: Default::default(),
         }
     }
 }
 
+/// Retention of the mem block files saved in `restore_path`. Files out of any
+/// limit are deleted, the latest file is always kept.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RestoreRetentionConfig {
+    pub max_files: Option<usize>,
+    pub max_age_secs: Option<u64>,
+    /// Limit of the total size of files
+    pub max_total_bytes: Option<u64>,
+}
+
+impl Default for RestoreRetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_files: None,
+            max_age_secs: None,
+            max_total_bytes: None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum NodeMode {
@@ -352,4 +459,35 @@ pub struct StoreConfig {
     pub options_file: Option<PathBuf>,
     #[serde(default)]
     pub cache_size: Option<usize>,
+    /// Prune history out of the retention window. If unset, only state records
+    /// of blocks 3600 blocks below the tip are removed on attaching blocks,
+    /// history queries of them fail as pruned
+    #[serde(default)]
+    pub prune: Option<PruneConfig>,
+    /// Open `path` as a read-only secondary instance of a running node's db,
+    /// the secondary instance keeps its info logs in this path.
+    #[serde(default)]
+    pub secondary_path: Option<PathBuf>,
+    /// Column family name => built-in tuning profile, e.g.
+    /// `COLUMN_ACCOUNT_SMT_LEAF = "smt"`, names are the `COLUMN_*` constants
+    /// of the db schema.
+    /// Unlisted columns use RocksDB's default options. Can't be used with
+    /// `options_file`.
+    #[serde(default)]
+    pub column_profiles: HashMap<String, ColumnProfile>,
+}
+
+/// Built-in RocksDB column family options
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnProfile {
+    /// Random point lookups, e.g. SMT branches and leaves: bloom filters and
+    /// a large block cache shared by the columns of this profile
+    Smt,
+    /// Written once and rarely read, e.g. blocks and transactions: compressed,
+    /// no bloom filters
+    AppendOnly,
+    /// Frequently overwritten and deleted: large write buffers and bloom
+    /// filters, no compression
+    MemPool,
 }
@@ -0,0 +1,101 @@
+//! Built-in column family tuning profiles, see `gw_config::ColumnProfile`
+use crate::schema::{column_by_name, Col};
+use crate::{internal_error, Result};
+use gw_config::{ColumnProfile, StoreConfig};
+use rocksdb::{BlockBasedOptions, Cache, DBCompressionType, Options};
+use std::collections::BTreeMap;
+
+/// Block cache size of the smt profile if `cache_size` isn't set
+const DEFAULT_SMT_CACHE_SIZE: usize = 256 << 20;
+const BLOOM_FILTER_BITS_PER_KEY: i32 = 10;
+const APPEND_ONLY_BLOCK_SIZE: usize = 16 << 10;
+const MEM_POOL_WRITE_BUFFER_SIZE: usize = 64 << 20;
+const MEM_POOL_MAX_WRITE_BUFFER_NUMBER: i32 = 4;
+
+/// Parse column names of the config, they must be `COLUMN_*` names in
+/// `schema` and less than `columns`
+pub(crate) fn parse_column_profiles(
+    config: &StoreConfig,
+    columns: u32,
+) -> Result<BTreeMap<Col, ColumnProfile>> {
+    config
+        .column_profiles
+        .iter()
+        .map(|(name, profile)| match column_by_name(name) {
+            Some(col) if u32::from(col) < columns => Ok((col, *profile)),
+            _ => Err(internal_error(format!(
+                "unknown column {} in column profiles",
+                name
+            ))),
+        })
+        .collect()
+}
+
+/// Build column family options of the profiles, columns of the smt profile
+/// share one block cache.
+pub(crate) struct ProfileOptions {
+    smt_cache_size: usize,
+    smt_cache: Option<Cache>,
+}
+
+impl ProfileOptions {
+    pub fn new(config: &StoreConfig) -> Self {
+        ProfileOptions {
+            smt_cache_size: config.cache_size.unwrap_or(DEFAULT_SMT_CACHE_SIZE),
+            smt_cache: None,
+        }
+    }
+
+    pub fn build(&mut self, profile: ColumnProfile) -> Options {
+        let mut opts = Options::default();
+        let mut block_opts = BlockBasedOptions::default();
+        match profile {
+            ColumnProfile::Smt => {
+                let cache_size = self.smt_cache_size;
+                let cache = self
+                    .smt_cache
+                    .get_or_insert_with(|| Cache::new_lru_cache(cache_size));
+                block_opts.set_block_cache(cache);
+                block_opts.set_bloom_filter(BLOOM_FILTER_BITS_PER_KEY, false);
+                block_opts.set_cache_index_and_filter_blocks(true);
+                block_opts.set_pin_l0_filter_and_index_blocks_in_cache(true);
+                // keys and values are hashes, they can't be compressed
+                opts.set_compression_type(DBCompressionType::None);
+            }
+            ColumnProfile::AppendOnly => {
+                block_opts.set_block_size(APPEND_ONLY_BLOCK_SIZE);
+                opts.set_compression_type(DBCompressionType::Snappy);
+                opts.set_level_compaction_dynamic_level_bytes(true);
+            }
+            ColumnProfile::MemPool => {
+                block_opts.set_bloom_filter(BLOOM_FILTER_BITS_PER_KEY, false);
+                opts.set_write_buffer_size(MEM_POOL_WRITE_BUFFER_SIZE);
+                opts.set_max_write_buffer_number(MEM_POOL_MAX_WRITE_BUFFER_NUMBER);
+                opts.set_compression_type(DBCompressionType::None);
+            }
+        }
+        opts.set_block_based_table_factory(&block_opts);
+        opts
+    }
+
+    /// Describe settings of the profile for logs
+    pub fn describe(&self, profile: ColumnProfile) -> String {
+        match profile {
+            ColumnProfile::Smt => format!(
+                "smt (bloom filter {} bits/key, shared block cache {} MB, no compression)",
+                BLOOM_FILTER_BITS_PER_KEY,
+                self.smt_cache_size >> 20
+            ),
+            ColumnProfile::AppendOnly => format!(
+                "append_only (snappy compression, block size {} KB, no bloom filter)",
+                APPEND_ONLY_BLOCK_SIZE >> 10
+            ),
+            ColumnProfile::MemPool => format!(
+                "mem_pool (bloom filter {} bits/key, write buffer {} MB x {}, no compression)",
+                BLOOM_FILTER_BITS_PER_KEY,
+                MEM_POOL_WRITE_BUFFER_SIZE >> 20,
+                MEM_POOL_MAX_WRITE_BUFFER_NUMBER
+            ),
+        }
+    }
+}
@@ -1,18 +1,20 @@
+use crate::column_profile::{parse_column_profiles, ProfileOptions};
 use crate::memory_stats::TrackRocksDBMemory;
 use crate::schema::Col;
 use crate::snapshot::RocksDBSnapshot;
-use crate::transaction::RocksDBTransaction;
+use crate::transaction::{RocksDBTransaction, TransactionInner};
 use crate::write_batch::RocksDBWriteBatch;
 use crate::{internal_error, CfMemStat, Result};
 use gw_config::StoreConfig;
 use rocksdb::ops::{
-    CreateCF, DropCF, GetColumnFamilys, GetPinned, GetPinnedCF, IterateCF, OpenCF, Put, SetOptions,
-    WriteOps,
+    CreateCF, CreateCheckpointObject, DropCF, GetColumnFamilys, GetPinned, GetPinnedCF, IterateCF,
+    OpenCF, Put, SetOptions, WriteOps,
 };
 use rocksdb::{
     ffi, ColumnFamily, ColumnFamilyDescriptor, DBPinnableSlice, FullOptions, IteratorMode,
     OptimisticTransactionDB, OptimisticTransactionOptions, Options, WriteBatch, WriteOptions,
 };
+use std::path::Path;
 use std::sync::Arc;
 
 /// RocksDB wrapper base on OptimisticTransactionDB
@@ -25,32 +27,7 @@ pub struct RocksDB {
 
 impl RocksDB {
     pub(crate) fn open_with_check(config: &StoreConfig, columns: u32) -> Result<Self> {
-        let cf_names: Vec<_> = (0..columns).map(|c| c.to_string()).collect();
-
-        let (mut opts, cf_descriptors) = if let Some(ref file) = config.options_file {
-            let mut full_opts = FullOptions::load_from_file(file, config.cache_size, false)
-                .map_err(|err| {
-                    internal_error(format!("failed to load the options file: {}", err))
-                })?;
-            let cf_names_str: Vec<&str> = cf_names.iter().map(|s| s.as_str()).collect();
-            full_opts
-                .complete_column_families(&cf_names_str, false)
-                .map_err(|err| {
-                    internal_error(format!("failed to check all column families: {}", err))
-                })?;
-            let FullOptions {
-                db_opts,
-                cf_descriptors,
-            } = full_opts;
-            (db_opts, cf_descriptors)
-        } else {
-            let opts = Options::default();
-            let cf_descriptors: Vec<_> = cf_names
-                .iter()
-                .map(|c| ColumnFamilyDescriptor::new(c, Options::default()))
-                .collect();
-            (opts, cf_descriptors)
-        };
+        let (mut opts, cf_descriptors) = load_options(config, columns)?;
 
         opts.create_if_missing(false);
         opts.create_missing_column_families(true);
@@ -167,8 +144,10 @@ impl RocksDB {
         transaction_options.set_snapshot(true);
 
         RocksDBTransaction {
-            db: Arc::clone(&self.inner),
-            inner: self.inner.transaction(&write_options, &transaction_options),
+            inner: TransactionInner::Optimistic {
+                db: Arc::clone(&self.inner),
+                inner: self.inner.transaction(&write_options, &transaction_options),
+            },
         }
     }
 
@@ -212,6 +191,69 @@ impl RocksDB {
     pub fn gather_mem_stats(&self) -> Vec<CfMemStat> {
         self.inner.gather_memory_stats()
     }
+
+    /// Create a consistent checkpoint of the database in `path`, which must
+    /// not exist. SST files are hard linked if `path` is on the same filesystem.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let checkpoint = self
+            .inner
+            .create_checkpoint_object()
+            .map_err(internal_error)?;
+        checkpoint
+            .create_checkpoint(path.as_ref())
+            .map_err(internal_error)
+    }
+}
+
+/// Load db options and column family descriptors from the config
+pub(crate) fn load_options(
+    config: &StoreConfig,
+    columns: u32,
+) -> Result<(Options, Vec<ColumnFamilyDescriptor>)> {
+    let cf_names: Vec<_> = (0..columns).map(|c| c.to_string()).collect();
+
+    let column_profiles = parse_column_profiles(config, columns)?;
+
+    if let Some(ref file) = config.options_file {
+        if !column_profiles.is_empty() {
+            return Err(internal_error(
+                "column profiles can't be used with the options file",
+            ));
+        }
+        let mut full_opts = FullOptions::load_from_file(file, config.cache_size, false)
+            .map_err(|err| internal_error(format!("failed to load the options file: {}", err)))?;
+        let cf_names_str: Vec<&str> = cf_names.iter().map(|s| s.as_str()).collect();
+        full_opts
+            .complete_column_families(&cf_names_str, false)
+            .map_err(|err| {
+                internal_error(format!("failed to check all column families: {}", err))
+            })?;
+        let FullOptions {
+            db_opts,
+            cf_descriptors,
+        } = full_opts;
+        Ok((db_opts, cf_descriptors))
+    } else {
+        let opts = Options::default();
+        let mut profile_options = ProfileOptions::new(config);
+        let cf_descriptors: Vec<_> = (0..columns)
+            .map(|col| {
+                let cf_opts = match column_profiles.get(&(col as Col)) {
+                    Some(profile) => {
+                        log::info!(
+                            "column {} profile: {}",
+                            col,
+                            profile_options.describe(*profile)
+                        );
+                        profile_options.build(*profile)
+                    }
+                    None => Options::default(),
+                };
+                ColumnFamilyDescriptor::new(col.to_string(), cf_opts)
+            })
+            .collect();
+        Ok((opts, cf_descriptors))
+    }
 }
 
 #[inline]
@@ -223,6 +265,8 @@ pub(crate) fn cf_handle(db: &OptimisticTransactionDB, col: Col) -> Result<&Colum
 #[cfg(test)]
 mod tests {
     use super::{Result, RocksDB, StoreConfig};
+    use crate::{MemoryDB, RocksDBSecondary};
+    use gw_config::ColumnProfile;
     use std::collections::HashMap;
 
     fn setup_db(prefix: &str, columns: u32) -> RocksDB {
@@ -254,6 +298,9 @@ mod tests {
             },
             options_file: None,
             cache_size: None,
+            prune: None,
+            secondary_path: None,
+            column_profiles: HashMap::new(),
         };
         RocksDB::open(&config, 2); // no panic
     }
@@ -269,10 +316,51 @@ mod tests {
             options: HashMap::new(),
             options_file: None,
             cache_size: None,
+            prune: None,
+            secondary_path: None,
+            column_profiles: HashMap::new(),
         };
         RocksDB::open(&config, 2); // no panic
     }
 
+    #[test]
+    fn test_set_column_profiles() {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("test_set_column_profiles")
+            .tempdir()
+            .unwrap();
+        let mut config = StoreConfig {
+            path: tmp_dir.as_ref().to_path_buf(),
+            ..Default::default()
+        };
+        config
+            .column_profiles
+            .insert("COLUMN_META".to_owned(), ColumnProfile::Smt);
+        config
+            .column_profiles
+            .insert("COLUMN_INDEX".to_owned(), ColumnProfile::AppendOnly);
+        config
+            .column_profiles
+            .insert("COLUMN_BLOCK".to_owned(), ColumnProfile::MemPool);
+        RocksDB::open(&config, 3); // no panic
+
+        // column out of range
+        let mut out_of_range = config.clone();
+        out_of_range
+            .column_profiles
+            .insert("COLUMN_BLOCK_HEADER_INFO".to_owned(), ColumnProfile::Smt);
+        assert!(RocksDB::open_with_check(&out_of_range, 3).is_err());
+
+        // unknown column names, including numeric ids
+        for name in ["COLUMN_UNKNOWN", "2", "column_block"].iter() {
+            let mut unknown = config.clone();
+            unknown
+                .column_profiles
+                .insert(name.to_string(), ColumnProfile::Smt);
+            assert!(RocksDB::open_with_check(&unknown, 3).is_err());
+        }
+    }
+
     #[test]
     #[should_panic]
     fn test_panic_on_invalid_rocksdb_options() {
@@ -289,6 +377,9 @@ mod tests {
             },
             options_file: None,
             cache_size: None,
+            prune: None,
+            secondary_path: None,
+            column_profiles: HashMap::new(),
         };
         RocksDB::open(&config, 2); // panic
     }
@@ -322,6 +413,128 @@ mod tests {
         assert_eq!(r.get(&vec![1, 1]), Some(&vec![1, 1, 1]));
     }
 
+    #[test]
+    fn secondary_is_read_only() {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("secondary_is_read_only")
+            .tempdir()
+            .unwrap();
+        let config = StoreConfig {
+            path: tmp_dir.path().join("primary"),
+            ..Default::default()
+        };
+        let primary = RocksDB::open(&config, 2);
+        let txn = primary.transaction();
+        txn.put(1, &[1], &[1]).unwrap();
+        txn.commit().unwrap();
+
+        let secondary = RocksDBSecondary::open(&config, tmp_dir.path().join("secondary"), 2);
+        let txn = secondary.transaction();
+        assert_eq!(txn.get(1, &[1]).unwrap().unwrap().as_ref(), &[1]);
+        assert!(txn.put(1, &[2], &[2]).is_err());
+        assert!(txn.delete(1, &[1]).is_err());
+        assert!(txn.get_snapshot().is_err());
+    }
+
+    #[test]
+    fn secondary_catch_up_waits_for_transactions() {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("secondary_catch_up_waits_for_transactions")
+            .tempdir()
+            .unwrap();
+        let config = StoreConfig {
+            path: tmp_dir.path().join("primary"),
+            ..Default::default()
+        };
+        let primary = RocksDB::open(&config, 2);
+        let secondary = RocksDBSecondary::open(&config, tmp_dir.path().join("secondary"), 2);
+
+        let txn = primary.transaction();
+        txn.put(1, &[1], &[1]).unwrap();
+        txn.commit().unwrap();
+
+        let reading = secondary.transaction();
+        let catch_up = {
+            let secondary = secondary.clone();
+            std::thread::spawn(move || secondary.try_catch_up_with_primary())
+        };
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        // the running transaction doesn't see the primary's new write
+        assert!(reading.get(1, &[1]).unwrap().is_none());
+        drop(reading);
+        catch_up.join().unwrap().unwrap();
+
+        let txn = secondary.transaction();
+        assert_eq!(txn.get(1, &[1]).unwrap().unwrap().as_ref(), &[1]);
+    }
+
+    #[test]
+    fn secondary_catch_up_under_continuous_reads() {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("secondary_catch_up_under_continuous_reads")
+            .tempdir()
+            .unwrap();
+        let config = StoreConfig {
+            path: tmp_dir.path().join("primary"),
+            ..Default::default()
+        };
+        let primary = RocksDB::open(&config, 2);
+        let secondary = RocksDBSecondary::open(&config, tmp_dir.path().join("secondary"), 2);
+
+        let txn = primary.transaction();
+        txn.put(1, &[1], &[1]).unwrap();
+        txn.commit().unwrap();
+
+        fn caught_up(secondary: &RocksDBSecondary) -> bool {
+            let txn = secondary.transaction();
+            txn.get(1, &[1]).unwrap().is_some()
+        }
+
+        // a long read is running while catching up is tried
+        let reading = secondary.transaction();
+        let catch_up = {
+            let secondary = secondary.clone();
+            std::thread::spawn(move || {
+                for _ in 0..3 {
+                    secondary.try_catch_up_with_primary().unwrap();
+                    assert!(!caught_up(&secondary));
+                }
+                assert!(secondary.catch_up_lag() >= std::time::Duration::from_secs(3));
+
+                // skipped too many times, wait for the long read
+                secondary.try_catch_up_with_primary().unwrap();
+                assert!(secondary.catch_up_lag() < std::time::Duration::from_secs(1));
+                caught_up(&secondary)
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_secs(5));
+        drop(reading);
+        assert!(catch_up.join().unwrap());
+    }
+
+    #[test]
+    fn memory_db_trims_versions() {
+        let db = MemoryDB::open(2);
+        let txn = db.transaction();
+        txn.put(1, &[1], &[1]).unwrap();
+        txn.commit().unwrap();
+        drop(txn);
+        // no open transaction can conflict with the commit
+        assert_eq!(db.versions_len(), 0);
+
+        let reading = db.transaction();
+        reading.get_for_update(1, &[1]).unwrap();
+        let txn = db.transaction();
+        txn.put(1, &[1], &[2]).unwrap();
+        txn.commit().unwrap();
+        drop(txn);
+        // kept for the conflict check of the open transaction
+        assert_eq!(db.versions_len(), 1);
+        assert!(reading.commit().is_err());
+        drop(reading);
+        assert_eq!(db.versions_len(), 0);
+    }
+
     #[test]
     fn snapshot_isolation() {
         let db = setup_db("snapshot_isolation", 2);
@@ -344,6 +557,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn create_checkpoint() {
+        let db = setup_db("create_checkpoint", 2);
+        let txn = db.transaction();
+        txn.put(1, &[1, 1], &[1, 2, 3]).unwrap();
+        txn.commit().unwrap();
+
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("create_checkpoint_dst")
+            .tempdir()
+            .unwrap();
+        let checkpoint_path = tmp_dir.path().join("checkpoint");
+        db.create_checkpoint(&checkpoint_path).unwrap();
+
+        // writes after checkpoint are not included
+        let txn = db.transaction();
+        txn.put(1, &[2, 2], &[4, 5, 6]).unwrap();
+        txn.commit().unwrap();
+
+        let config = StoreConfig {
+            path: checkpoint_path,
+            ..Default::default()
+        };
+        let checkpoint_db = RocksDB::open(&config, 2);
+        assert_eq!(
+            checkpoint_db
+                .get_pinned(1, &[1, 1])
+                .unwrap()
+                .unwrap()
+                .as_ref(),
+            &[1, 2, 3]
+        );
+        assert!(checkpoint_db.get_pinned(1, &[2, 2]).unwrap().is_none());
+    }
+
     #[test]
     fn write_and_partial_read() {
         let db = setup_db("write_and_partial_read", 2);
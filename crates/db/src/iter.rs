@@ -1,16 +1,117 @@
 //! TODO(doc): @quake
 use crate::db::cf_handle;
+use crate::memory::{MemoryIterator, MemoryRawIterator};
 use crate::schema::Col;
+use crate::secondary::secondary_cf_handle;
+use crate::transaction::TransactionInner;
 use crate::{
-    internal_error, Result, RocksDB, RocksDBSnapshot, RocksDBTransaction,
+    internal_error, Result, RocksDB, RocksDBSecondary, RocksDBSnapshot, RocksDBTransaction,
     RocksDBTransactionSnapshot,
 };
 use rocksdb::{ops::IterateCF, ReadOptions};
-pub use rocksdb::{DBIterator as DBIter, Direction, IteratorMode};
+pub use rocksdb::{Direction, IteratorMode};
 
 /// TODO(doc): @quake
 pub type DBIterItem = (Box<[u8]>, Box<[u8]>);
 
+/// Iterator of RocksDB or the in-memory db
+pub enum DBIter<'a> {
+    RocksDB(rocksdb::DBIterator<'a>),
+    Memory(MemoryIterator),
+}
+
+impl<'a> Iterator for DBIter<'a> {
+    type Item = DBIterItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            DBIter::RocksDB(iter) => iter.next(),
+            DBIter::Memory(iter) => iter.next(),
+        }
+    }
+}
+
+/// Raw iterator of RocksDB or the in-memory db, converted from `DBIter`
+pub enum DBRawIter<'a> {
+    RocksDB(rocksdb::DBRawIterator<'a>),
+    Memory(MemoryRawIterator),
+}
+
+impl<'a> From<DBIter<'a>> for DBRawIter<'a> {
+    fn from(iter: DBIter<'a>) -> Self {
+        match iter {
+            DBIter::RocksDB(iter) => DBRawIter::RocksDB(iter.into()),
+            DBIter::Memory(iter) => DBRawIter::Memory(iter.into_raw()),
+        }
+    }
+}
+
+impl<'a> DBRawIter<'a> {
+    pub fn valid(&self) -> bool {
+        match self {
+            DBRawIter::RocksDB(iter) => iter.valid(),
+            DBRawIter::Memory(iter) => iter.valid(),
+        }
+    }
+
+    pub fn key(&self) -> Option<&[u8]> {
+        match self {
+            DBRawIter::RocksDB(iter) => iter.key(),
+            DBRawIter::Memory(iter) => iter.key(),
+        }
+    }
+
+    pub fn value(&self) -> Option<&[u8]> {
+        match self {
+            DBRawIter::RocksDB(iter) => iter.value(),
+            DBRawIter::Memory(iter) => iter.value(),
+        }
+    }
+
+    pub fn seek_to_first(&mut self) {
+        match self {
+            DBRawIter::RocksDB(iter) => iter.seek_to_first(),
+            DBRawIter::Memory(iter) => iter.seek_to_first(),
+        }
+    }
+
+    pub fn seek_to_last(&mut self) {
+        match self {
+            DBRawIter::RocksDB(iter) => iter.seek_to_last(),
+            DBRawIter::Memory(iter) => iter.seek_to_last(),
+        }
+    }
+
+    pub fn seek<K: AsRef<[u8]>>(&mut self, key: K) {
+        match self {
+            DBRawIter::RocksDB(iter) => iter.seek(key),
+            DBRawIter::Memory(iter) => iter.seek(key),
+        }
+    }
+
+    pub fn seek_for_prev<K: AsRef<[u8]>>(&mut self, key: K) {
+        match self {
+            DBRawIter::RocksDB(iter) => iter.seek_for_prev(key),
+            DBRawIter::Memory(iter) => iter.seek_for_prev(key),
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) {
+        match self {
+            DBRawIter::RocksDB(iter) => iter.next(),
+            DBRawIter::Memory(iter) => iter.next(),
+        }
+    }
+
+    pub fn prev(&mut self) {
+        match self {
+            DBRawIter::RocksDB(iter) => iter.prev(),
+            DBRawIter::Memory(iter) => iter.prev(),
+        }
+    }
+}
+
 /// TODO(doc): @quake
 pub trait DBIterator {
     /// TODO(doc): @quake
@@ -28,15 +129,37 @@ impl DBIterator for RocksDB {
         let cf = cf_handle(&self.inner, col)?;
         self.inner
             .iterator_cf_opt(cf, mode, readopts)
+            .map(DBIter::RocksDB)
             .map_err(internal_error)
     }
 }
 
 impl DBIterator for RocksDBTransaction {
     fn iter_opt(&self, col: Col, mode: IteratorMode, readopts: &ReadOptions) -> Result<DBIter> {
-        let cf = cf_handle(&self.db, col)?;
+        match &self.inner {
+            TransactionInner::Optimistic { db, inner } => {
+                let cf = cf_handle(db, col)?;
+                inner
+                    .iterator_cf_opt(cf, mode, readopts)
+                    .map(DBIter::RocksDB)
+                    .map_err(internal_error)
+            }
+            TransactionInner::Secondary { db, .. } => {
+                let cf = secondary_cf_handle(db, col)?;
+                db.iterator_cf_opt(cf, mode, readopts)
+                    .map(DBIter::RocksDB)
+                    .map_err(internal_error)
+            }
+        }
+    }
+}
+
+impl DBIterator for RocksDBSecondary {
+    fn iter_opt(&self, col: Col, mode: IteratorMode, readopts: &ReadOptions) -> Result<DBIter> {
+        let cf = secondary_cf_handle(&self.inner, col)?;
         self.inner
             .iterator_cf_opt(cf, mode, readopts)
+            .map(DBIter::RocksDB)
             .map_err(internal_error)
     }
 }
@@ -46,6 +169,7 @@ impl<'a> DBIterator for RocksDBTransactionSnapshot<'a> {
         let cf = cf_handle(&self.db, col)?;
         self.inner
             .iterator_cf_opt(cf, mode, readopts)
+            .map(DBIter::RocksDB)
             .map_err(internal_error)
     }
 }
@@ -54,6 +178,7 @@ impl DBIterator for RocksDBSnapshot {
     fn iter_opt(&self, col: Col, mode: IteratorMode, readopts: &ReadOptions) -> Result<DBIter> {
         let cf = cf_handle(&self.db, col)?;
         self.iterator_cf_opt(cf, mode, readopts)
+            .map(DBIter::RocksDB)
             .map_err(internal_error)
     }
 }
@@ -1,8 +1,11 @@
+mod column_profile;
 pub mod db;
 pub mod error;
 pub mod iter;
+pub mod memory;
 pub mod memory_stats;
 pub mod schema;
+pub mod secondary;
 pub mod snapshot;
 pub mod transaction;
 pub mod write_batch;
@@ -10,7 +13,9 @@ pub mod write_batch;
 // re-exports
 pub use crate::db::RocksDB;
 pub use crate::iter::DBIterator;
+pub use crate::memory::{MemoryDB, MemoryTransaction, MemoryWriteBatch};
 pub use crate::memory_stats::CfMemStat;
+pub use crate::secondary::RocksDBSecondary;
 pub use crate::snapshot::RocksDBSnapshot;
 pub use crate::transaction::{RocksDBTransaction, RocksDBTransactionSnapshot};
 pub use crate::write_batch::RocksDBWriteBatch;
@@ -0,0 +1,549 @@
+//! In-memory database
+//!
+//! `MemoryDB` keeps each column in an ordered map and supports optimistic
+//! transactions like `OptimisticTransactionDB`: a transaction reads the
+//! committed data and its own writes, and its commit fails if another commit
+//! wrote the same keys after it began. Data is dropped with the db, it is
+//! meant for tests and benchmarks.
+use crate::iter::{DBIter, DBIterator};
+use crate::schema::Col;
+use crate::{internal_error, Result};
+use rocksdb::{Direction, IteratorMode, ReadOptions};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::ops::Bound;
+use std::sync::{Arc, Mutex, RwLock};
+
+type Column = BTreeMap<Vec<u8>, Vec<u8>>;
+/// Pending writes of a column, `None` is a deletion
+type PendingColumn = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+struct MemoryState {
+    columns: Vec<Column>,
+    /// Sequence number of the last commit which wrote the key, only commits
+    /// after the oldest open transaction began are kept
+    versions: Vec<HashMap<Vec<u8>, u64>>,
+    /// Sequence number and written keys of the commits in `versions`
+    commits: VecDeque<(u64, Vec<(Col, Vec<u8>)>)>,
+    /// Start sequence number => count of open transactions
+    open_transactions: BTreeMap<u64, usize>,
+    sequence: u64,
+}
+
+impl MemoryState {
+    fn column(&self, col: Col) -> Result<&Column> {
+        self.columns
+            .get(col as usize)
+            .ok_or_else(|| internal_error(format!("column {} not found", col)))
+    }
+
+    fn check_column(&self, col: Col) -> Result<()> {
+        self.column(col).map(|_| ())
+    }
+
+    fn begin_commit(&mut self) {
+        self.sequence += 1;
+        self.commits.push_back((self.sequence, Vec::new()));
+    }
+
+    fn apply(&mut self, col: Col, key: Vec<u8>, value: Option<Vec<u8>>) {
+        if let Some((_, keys)) = self.commits.back_mut() {
+            keys.push((col, key.clone()));
+        }
+        let col = col as usize;
+        self.versions[col].insert(key.clone(), self.sequence);
+        match value {
+            Some(value) => {
+                self.columns[col].insert(key, value);
+            }
+            None => {
+                self.columns[col].remove(&key);
+            }
+        }
+    }
+
+    fn open_transaction(&mut self) -> u64 {
+        *self.open_transactions.entry(self.sequence).or_default() += 1;
+        self.sequence
+    }
+
+    fn close_transaction(&mut self, start_sequence: u64) {
+        if let Some(count) = self.open_transactions.get_mut(&start_sequence) {
+            *count -= 1;
+            if *count == 0 {
+                self.open_transactions.remove(&start_sequence);
+            }
+        }
+        self.trim_versions();
+    }
+
+    /// Drop versions of the commits before all open transactions began, they
+    /// can't conflict with open or new transactions
+    fn trim_versions(&mut self) {
+        let oldest = match self.open_transactions.keys().next() {
+            Some(sequence) => *sequence,
+            None => self.sequence,
+        };
+        while let Some((sequence, _)) = self.commits.front() {
+            if *sequence > oldest {
+                break;
+            }
+            let (sequence, keys) = self.commits.pop_front().expect("front");
+            for (col, key) in keys {
+                let versions = &mut self.versions[col as usize];
+                if versions.get(&key) == Some(&sequence) {
+                    versions.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MemoryDB {
+    inner: Arc<RwLock<MemoryState>>,
+}
+
+impl MemoryDB {
+    pub fn open(columns: u32) -> Self {
+        let state = MemoryState {
+            columns: (0..columns).map(|_| Default::default()).collect(),
+            versions: (0..columns).map(|_| Default::default()).collect(),
+            commits: VecDeque::new(),
+            open_transactions: BTreeMap::new(),
+            sequence: 0,
+        };
+        MemoryDB {
+            inner: Arc::new(RwLock::new(state)),
+        }
+    }
+
+    pub fn get(&self, col: Col, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let state = self.inner.read().expect("memory db lock");
+        Ok(state.column(col)?.get(key).cloned())
+    }
+
+    pub fn transaction(&self) -> MemoryTransaction {
+        let start_sequence = self
+            .inner
+            .write()
+            .expect("memory db lock")
+            .open_transaction();
+        MemoryTransaction {
+            db: Arc::clone(&self.inner),
+            state: Arc::new(Mutex::new(TransactionState::new(start_sequence))),
+        }
+    }
+
+    /// Count of the kept commit versions
+    #[cfg(test)]
+    pub(crate) fn versions_len(&self) -> usize {
+        let state = self.inner.read().expect("memory db lock");
+        state.versions.iter().map(HashMap::len).sum()
+    }
+
+    pub fn new_write_batch(&self) -> MemoryWriteBatch {
+        MemoryWriteBatch::default()
+    }
+
+    pub fn write(&self, batch: &MemoryWriteBatch) -> Result<()> {
+        let mut state = self.inner.write().expect("memory db lock");
+        for (col, _) in batch.ops.iter() {
+            state.check_column(*col)?;
+        }
+        state.begin_commit();
+        for (col, op) in batch.ops.iter() {
+            match op {
+                BatchOp::Put(key, value) => state.apply(*col, key.clone(), Some(value.clone())),
+                BatchOp::Delete(key) => state.apply(*col, key.clone(), None),
+                BatchOp::DeleteRange(from, to) if from < to => {
+                    let keys: Vec<Vec<u8>> = state.columns[*col as usize]
+                        .range::<[u8], _>((Bound::Included(&from[..]), Bound::Excluded(&to[..])))
+                        .map(|(key, _)| key.clone())
+                        .collect();
+                    for key in keys {
+                        state.apply(*col, key, None);
+                    }
+                }
+                BatchOp::DeleteRange(..) => (),
+            }
+        }
+        state.trim_versions();
+        Ok(())
+    }
+}
+
+impl DBIterator for MemoryDB {
+    /// Read options are ignored
+    fn iter_opt(&self, col: Col, mode: IteratorMode, _readopts: &ReadOptions) -> Result<DBIter> {
+        self.inner
+            .read()
+            .expect("memory db lock")
+            .check_column(col)?;
+        let raw = MemoryRawIterator::new(Arc::clone(&self.inner), None, col);
+        Ok(DBIter::Memory(MemoryIterator::new(raw, mode)))
+    }
+}
+
+struct TransactionState {
+    start_sequence: u64,
+    writes: HashMap<Col, PendingColumn>,
+    /// Keys read for update, they are checked for conflicts on commit
+    tracked: HashSet<(Col, Vec<u8>)>,
+    savepoints: Vec<HashMap<Col, PendingColumn>>,
+}
+
+impl TransactionState {
+    fn new(start_sequence: u64) -> Self {
+        TransactionState {
+            start_sequence,
+            writes: HashMap::new(),
+            tracked: HashSet::new(),
+            savepoints: Vec::new(),
+        }
+    }
+}
+
+pub struct MemoryTransaction {
+    db: Arc<RwLock<MemoryState>>,
+    state: Arc<Mutex<TransactionState>>,
+}
+
+impl MemoryTransaction {
+    pub fn get(&self, col: Col, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let db = self.db.read().expect("memory db lock");
+        let committed = db.column(col)?;
+        let state = self.state.lock().expect("memory transaction lock");
+        match state.writes.get(&col).and_then(|writes| writes.get(key)) {
+            Some(value) => Ok(value.clone()),
+            None => Ok(committed.get(key).cloned()),
+        }
+    }
+
+    pub fn get_for_update(&self, col: Col, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let value = self.get(col, key)?;
+        let mut state = self.state.lock().expect("memory transaction lock");
+        state.tracked.insert((col, key.to_vec()));
+        Ok(value)
+    }
+
+    pub fn put(&self, col: Col, key: &[u8], value: &[u8]) -> Result<()> {
+        self.write(col, key, Some(value.to_vec()))
+    }
+
+    pub fn delete(&self, col: Col, key: &[u8]) -> Result<()> {
+        self.write(col, key, None)
+    }
+
+    fn write(&self, col: Col, key: &[u8], value: Option<Vec<u8>>) -> Result<()> {
+        self.db.read().expect("memory db lock").check_column(col)?;
+        let mut state = self.state.lock().expect("memory transaction lock");
+        state
+            .writes
+            .entry(col)
+            .or_default()
+            .insert(key.to_vec(), value);
+        Ok(())
+    }
+
+    /// Fail if a written or tracked key was committed by others after the
+    /// transaction began. The transaction can be reused after commit.
+    pub fn commit(&self) -> Result<()> {
+        let mut db = self.db.write().expect("memory db lock");
+        let mut state = self.state.lock().expect("memory transaction lock");
+
+        let start_sequence = state.start_sequence;
+        let is_conflict = |col: Col, key: &[u8]| -> bool {
+            db.versions[col as usize]
+                .get(key)
+                .map(|sequence| *sequence > start_sequence)
+                .unwrap_or(false)
+        };
+        let written = state
+            .writes
+            .iter()
+            .flat_map(|(col, writes)| writes.keys().map(move |key| (*col, key)));
+        let tracked = state.tracked.iter().map(|(col, key)| (*col, key));
+        if written
+            .chain(tracked)
+            .any(|(col, key)| is_conflict(col, key.as_slice()))
+        {
+            return Err(internal_error("Resource busy: "));
+        }
+
+        db.begin_commit();
+        for (col, writes) in std::mem::take(&mut state.writes) {
+            for (key, value) in writes {
+                db.apply(col, key, value);
+            }
+        }
+        let start_sequence = db.open_transaction();
+        db.close_transaction(state.start_sequence);
+        *state = TransactionState::new(start_sequence);
+        Ok(())
+    }
+
+    pub fn rollback(&self) -> Result<()> {
+        let mut db = self.db.write().expect("memory db lock");
+        let mut state = self.state.lock().expect("memory transaction lock");
+        let start_sequence = db.open_transaction();
+        db.close_transaction(state.start_sequence);
+        *state = TransactionState::new(start_sequence);
+        Ok(())
+    }
+
+    pub fn set_savepoint(&self) {
+        let mut state = self.state.lock().expect("memory transaction lock");
+        let writes = state.writes.clone();
+        state.savepoints.push(writes);
+    }
+
+    pub fn rollback_to_savepoint(&self) -> Result<()> {
+        let mut state = self.state.lock().expect("memory transaction lock");
+        match state.savepoints.pop() {
+            Some(writes) => {
+                state.writes = writes;
+                Ok(())
+            }
+            None => Err(internal_error("NotFound: ")),
+        }
+    }
+
+    pub fn iter(&self, col: Col, mode: IteratorMode) -> Result<DBIter> {
+        self.db.read().expect("memory db lock").check_column(col)?;
+        let raw = MemoryRawIterator::new(Arc::clone(&self.db), Some(Arc::clone(&self.state)), col);
+        Ok(DBIter::Memory(MemoryIterator::new(raw, mode)))
+    }
+}
+
+impl Drop for MemoryTransaction {
+    fn drop(&mut self) {
+        let start_sequence = self
+            .state
+            .lock()
+            .expect("memory transaction lock")
+            .start_sequence;
+        self.db
+            .write()
+            .expect("memory db lock")
+            .close_transaction(start_sequence);
+    }
+}
+
+enum BatchOp {
+    Put(Vec<u8>, Vec<u8>),
+    Delete(Vec<u8>),
+    DeleteRange(Vec<u8>, Vec<u8>),
+}
+
+#[derive(Default)]
+pub struct MemoryWriteBatch {
+    ops: Vec<(Col, BatchOp)>,
+    size: usize,
+}
+
+impl MemoryWriteBatch {
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Size of the keys and values in the batch
+    pub fn size_in_bytes(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    pub fn put(&mut self, col: Col, key: &[u8], value: &[u8]) -> Result<()> {
+        self.size += key.len() + value.len();
+        self.ops
+            .push((col, BatchOp::Put(key.to_vec(), value.to_vec())));
+        Ok(())
+    }
+
+    pub fn delete(&mut self, col: Col, key: &[u8]) -> Result<()> {
+        self.size += key.len();
+        self.ops.push((col, BatchOp::Delete(key.to_vec())));
+        Ok(())
+    }
+
+    /// Remove entries in the range ["from", "to")
+    pub fn delete_range(&mut self, col: Col, from: &[u8], to: &[u8]) -> Result<()> {
+        self.size += from.len() + to.len();
+        self.ops
+            .push((col, BatchOp::DeleteRange(from.to_vec(), to.to_vec())));
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        self.ops.clear();
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Raw iterator over the committed data and the pending writes of a
+/// transaction. Every move looks up the maps, so it sees later writes.
+pub struct MemoryRawIterator {
+    db: Arc<RwLock<MemoryState>>,
+    transaction: Option<Arc<Mutex<TransactionState>>>,
+    col: Col,
+    current: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl MemoryRawIterator {
+    fn new(
+        db: Arc<RwLock<MemoryState>>,
+        transaction: Option<Arc<Mutex<TransactionState>>>,
+        col: Col,
+    ) -> Self {
+        MemoryRawIterator {
+            db,
+            transaction,
+            col,
+            current: None,
+        }
+    }
+
+    pub fn valid(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn key(&self) -> Option<&[u8]> {
+        self.current.as_ref().map(|(key, _)| key.as_slice())
+    }
+
+    pub fn value(&self) -> Option<&[u8]> {
+        self.current.as_ref().map(|(_, value)| value.as_slice())
+    }
+
+    pub fn seek_to_first(&mut self) {
+        self.current = self.find(Bound::Unbounded, true);
+    }
+
+    pub fn seek_to_last(&mut self) {
+        self.current = self.find(Bound::Unbounded, false);
+    }
+
+    pub fn seek<K: AsRef<[u8]>>(&mut self, key: K) {
+        self.current = self.find(Bound::Included(key.as_ref().to_vec()), true);
+    }
+
+    pub fn seek_for_prev<K: AsRef<[u8]>>(&mut self, key: K) {
+        self.current = self.find(Bound::Included(key.as_ref().to_vec()), false);
+    }
+
+    pub fn next(&mut self) {
+        if let Some((key, _)) = self.current.take() {
+            self.current = self.find(Bound::Excluded(key), true);
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if let Some((key, _)) = self.current.take() {
+            self.current = self.find(Bound::Excluded(key), false);
+        }
+    }
+
+    /// Find the first entry after `from` in the direction, pending writes
+    /// shadow the committed data.
+    fn find(&self, mut from: Bound<Vec<u8>>, forward: bool) -> Option<(Vec<u8>, Vec<u8>)> {
+        let db = self.db.read().expect("memory db lock");
+        let transaction = self
+            .transaction
+            .as_ref()
+            .map(|state| state.lock().expect("memory transaction lock"));
+        let committed = &db.columns[self.col as usize];
+        let pending = transaction
+            .as_ref()
+            .and_then(|state| state.writes.get(&self.col));
+
+        loop {
+            let committed_entry =
+                first_entry(committed, &from, forward).map(|(key, value)| (key, Some(value)));
+            let pending_entry = pending
+                .and_then(|pending| first_entry(pending, &from, forward))
+                .map(|(key, value)| (key, value.as_ref()));
+            let (key, value) = match (committed_entry, pending_entry) {
+                (None, None) => return None,
+                (Some(entry), None) | (None, Some(entry)) => entry,
+                (Some(committed_entry), Some(pending_entry)) => {
+                    let pending_first = if forward {
+                        pending_entry.0 <= committed_entry.0
+                    } else {
+                        pending_entry.0 >= committed_entry.0
+                    };
+                    if pending_first {
+                        pending_entry
+                    } else {
+                        committed_entry
+                    }
+                }
+            };
+            match value {
+                Some(value) => return Some((key.clone(), value.clone())),
+                // skip deleted
+                None => from = Bound::Excluded(key.clone()),
+            }
+        }
+    }
+}
+
+fn first_entry<'a, V>(
+    map: &'a BTreeMap<Vec<u8>, V>,
+    from: &Bound<Vec<u8>>,
+    forward: bool,
+) -> Option<(&'a Vec<u8>, &'a V)> {
+    if forward {
+        map.range((from.clone(), Bound::Unbounded)).next()
+    } else {
+        map.range((Bound::Unbounded, from.clone())).next_back()
+    }
+}
+
+pub struct MemoryIterator {
+    raw: MemoryRawIterator,
+    direction: Direction,
+}
+
+impl MemoryIterator {
+    fn new(mut raw: MemoryRawIterator, mode: IteratorMode) -> Self {
+        let direction = match mode {
+            IteratorMode::Start => {
+                raw.seek_to_first();
+                Direction::Forward
+            }
+            IteratorMode::End => {
+                raw.seek_to_last();
+                Direction::Reverse
+            }
+            IteratorMode::From(key, Direction::Forward) => {
+                raw.seek(key);
+                Direction::Forward
+            }
+            IteratorMode::From(key, Direction::Reverse) => {
+                raw.seek_for_prev(key);
+                Direction::Reverse
+            }
+        };
+        MemoryIterator { raw, direction }
+    }
+
+    pub(crate) fn into_raw(self) -> MemoryRawIterator {
+        self.raw
+    }
+}
+
+impl Iterator for MemoryIterator {
+    type Item = (Box<[u8]>, Box<[u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.raw.current.clone()?;
+        match self.direction {
+            Direction::Forward => self.raw.next(),
+            Direction::Reverse => self.raw.prev(),
+        }
+        Some((key.into_boxed_slice(), value.into_boxed_slice()))
+    }
+}
@@ -3,7 +3,7 @@
 /// Column families alias type
 pub type Col = u8;
 /// Total column number
-pub const COLUMNS: u32 = 33;
+pub const COLUMNS: u32 = 40;
 /// Column store meta data
 pub const COLUMN_META: Col = 0;
 /// Column store chain index
@@ -70,6 +70,68 @@ pub const COLUMN_MEM_POOL_SCRIPT: Col = 30;
 pub const COLUMN_MEM_POOL_DATA: Col = 31;
 /// Column script prefix
 pub const COLUMN_MEM_POOL_SCRIPT_PREFIX: Col = 32;
+/// Column account id => transaction keys of the account's sent and received transactions
+pub const COLUMN_ACCOUNT_TRANSACTION: Col = 33;
+/// Column withdrawal hash => withdrawal info (block number and key of block hash | index)
+pub const COLUMN_WITHDRAWAL_INFO: Col = 34;
+/// Column deposit cell out point => deposit info (block number and key of block hash | index)
+pub const COLUMN_DEPOSIT_INFO: Col = 35;
+/// Column mem block tx index => tx hash, txs of the mem block in order
+pub const COLUMN_MEM_POOL_BLOCK_TX: Col = 36;
+/// Column account id | kind => hashes of the account's pending txs (kind 0) or withdrawals (kind 1)
+pub const COLUMN_MEM_POOL_PENDING: Col = 37;
+/// Column tx key | kind => state keys read (kind 0) or written (kind 1) by the tx
+pub const COLUMN_TRANSACTION_ACCESS_LIST: Col = 38;
+/// Column block hash => out points of the deposit cells consumed by the block
+pub const COLUMN_BLOCK_DEPOSIT_OUT_POINTS: Col = 39;
+
+/// Column of the constant name, e.g. `COLUMN_ACCOUNT_SMT_LEAF`
+pub fn column_by_name(name: &str) -> Option<Col> {
+    let col = match name {
+        "COLUMN_META" => COLUMN_META,
+        "COLUMN_INDEX" => COLUMN_INDEX,
+        "COLUMN_BLOCK" => COLUMN_BLOCK,
+        "COLUMN_BLOCK_HEADER_INFO" => COLUMN_BLOCK_HEADER_INFO,
+        "COLUMN_BLOCK_GLOBAL_STATE" => COLUMN_BLOCK_GLOBAL_STATE,
+        "COLUMN_TRANSACTION" => COLUMN_TRANSACTION,
+        "COLUMN_TRANSACTION_RECEIPT" => COLUMN_TRANSACTION_RECEIPT,
+        "COLUMN_L2BLOCK_COMMITTED_INFO" => COLUMN_L2BLOCK_COMMITTED_INFO,
+        "COLUMN_TRANSACTION_INFO" => COLUMN_TRANSACTION_INFO,
+        "COLUMN_ACCOUNT_SMT_BRANCH" => COLUMN_ACCOUNT_SMT_BRANCH,
+        "COLUMN_ACCOUNT_SMT_LEAF" => COLUMN_ACCOUNT_SMT_LEAF,
+        "COLUMN_BLOCK_SMT_BRANCH" => COLUMN_BLOCK_SMT_BRANCH,
+        "COLUMN_BLOCK_SMT_LEAF" => COLUMN_BLOCK_SMT_LEAF,
+        "COLUMN_NUMBER_HASH" => COLUMN_NUMBER_HASH,
+        "COLUMN_SCRIPT" => COLUMN_SCRIPT,
+        "COLUMN_DATA" => COLUMN_DATA,
+        "COLUMN_BLOCK_DEPOSIT_REQUESTS" => COLUMN_BLOCK_DEPOSIT_REQUESTS,
+        "COLUMN_MEM_POOL_TRANSACTION_RECEIPT" => COLUMN_MEM_POOL_TRANSACTION_RECEIPT,
+        "COLUMN_BLOCK_STATE_RECORD" => COLUMN_BLOCK_STATE_RECORD,
+        "COLUMN_SCRIPT_PREFIX" => COLUMN_SCRIPT_PREFIX,
+        "COLUMN_BLOCK_STATE_REVERSE_RECORD" => COLUMN_BLOCK_STATE_REVERSE_RECORD,
+        "COLUMN_REVERTED_BLOCK_SMT_BRANCH" => COLUMN_REVERTED_BLOCK_SMT_BRANCH,
+        "COLUMN_REVERTED_BLOCK_SMT_LEAF" => COLUMN_REVERTED_BLOCK_SMT_LEAF,
+        "COLUMN_BAD_BLOCK_CHALLENGE_TARGET" => COLUMN_BAD_BLOCK_CHALLENGE_TARGET,
+        "COLUMN_REVERTED_BLOCK_SMT_ROOT" => COLUMN_REVERTED_BLOCK_SMT_ROOT,
+        "COLUMN_ASSET_SCRIPT" => COLUMN_ASSET_SCRIPT,
+        "COLUMN_MEM_POOL_TRANSACTION" => COLUMN_MEM_POOL_TRANSACTION,
+        "COLUMN_MEM_POOL_WITHDRAWAL" => COLUMN_MEM_POOL_WITHDRAWAL,
+        "COLUMN_MEM_POOL_ACCOUNT_SMT_BRANCH" => COLUMN_MEM_POOL_ACCOUNT_SMT_BRANCH,
+        "COLUMN_MEM_POOL_ACCOUNT_SMT_LEAF" => COLUMN_MEM_POOL_ACCOUNT_SMT_LEAF,
+        "COLUMN_MEM_POOL_SCRIPT" => COLUMN_MEM_POOL_SCRIPT,
+        "COLUMN_MEM_POOL_DATA" => COLUMN_MEM_POOL_DATA,
+        "COLUMN_MEM_POOL_SCRIPT_PREFIX" => COLUMN_MEM_POOL_SCRIPT_PREFIX,
+        "COLUMN_ACCOUNT_TRANSACTION" => COLUMN_ACCOUNT_TRANSACTION,
+        "COLUMN_WITHDRAWAL_INFO" => COLUMN_WITHDRAWAL_INFO,
+        "COLUMN_DEPOSIT_INFO" => COLUMN_DEPOSIT_INFO,
+        "COLUMN_MEM_POOL_BLOCK_TX" => COLUMN_MEM_POOL_BLOCK_TX,
+        "COLUMN_MEM_POOL_PENDING" => COLUMN_MEM_POOL_PENDING,
+        "COLUMN_TRANSACTION_ACCESS_LIST" => COLUMN_TRANSACTION_ACCESS_LIST,
+        "COLUMN_BLOCK_DEPOSIT_OUT_POINTS" => COLUMN_BLOCK_DEPOSIT_OUT_POINTS,
+        _ => return None,
+    };
+    Some(col)
+}
 
 /// chain id
 pub const META_CHAIN_ID_KEY: &[u8] = b"CHAIN_ID";
@@ -85,8 +147,16 @@ pub const META_LAST_VALID_TIP_BLOCK_HASH_KEY: &[u8] = b"LAST_VALID_TIP_BLOCK_HAS
 pub const META_MEM_BLOCK_ACCOUNT_SMT_ROOT_KEY: &[u8] = b"MEM_BLOCK_ACCOUNT_SMT_ROOT_KEY";
 /// account SMT count
 pub const META_MEM_BLOCK_ACCOUNT_SMT_COUNT_KEY: &[u8] = b"MEM_BLOCK_ACCOUNT_SMT_COUNT_KEY";
+/// history of blocks at or below the number is pruned
+pub const META_PRUNED_BLOCK_NUMBER_KEY: &[u8] = b"PRUNED_BLOCK_NUMBER";
+/// account transaction index of blocks attached before the index existed is built
+pub const META_ACCOUNT_TRANSACTION_INDEXED_KEY: &[u8] = b"ACCOUNT_TRANSACTION_INDEXED";
+/// first blocks of activated VM version switches, concatenated Uint64s
+pub const META_VM_VERSION_ACTIVATIONS_KEY: &[u8] = b"VM_VERSION_ACTIVATIONS";
 /// mem pool block info
 pub const META_MEM_POOL_BLOCK_INFO: &[u8] = b"MEM_POOL_BLOCK_INFO";
+/// mem block before txs, txs are in COLUMN_MEM_POOL_BLOCK_TX
+pub const META_MEM_POOL_BLOCK: &[u8] = b"MEM_POOL_BLOCK";
 
 /// CHAIN_SPEC_HASH_KEY tracks the hash of chain spec which created current database
 pub const CHAIN_SPEC_HASH_KEY: &[u8] = b"chain-spec-hash";
@@ -0,0 +1,193 @@
+//! RocksDB secondary instance
+//!
+//! A secondary instance opens the db of a running primary process in
+//! read-only mode. It sees the primary's writes after
+//! `try_catch_up_with_primary`, and keeps its own info logs in `secondary_path`.
+//!
+//! Secondary instances don't support snapshots, so catching up waits for the
+//! running transactions to finish and new transactions wait for the catch-up,
+//! a transaction never reads across a catch-up.
+//!
+//! https://github.com/facebook/rocksdb/wiki/Read-only-and-Secondary-instances
+use crate::db::load_options;
+use crate::schema::Col;
+use crate::transaction::{RocksDBTransaction, TransactionInner};
+use crate::{internal_error, Result};
+use gw_config::StoreConfig;
+use rocksdb::ops::{GetColumnFamilys, GetPinnedCF, OpenCF};
+use rocksdb::{ColumnFamily, DBPinnableSlice, SecondaryDB, SecondaryOpenDescriptor};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Catching up is skipped if running transactions don't finish in time, new
+/// transactions are blocked while waiting
+const CATCH_UP_WAIT_TIMEOUT: Duration = Duration::from_secs(1);
+/// After this many catch-ups are skipped in a row, catching up waits for long
+/// running transactions with `CATCH_UP_FORCE_WAIT_TIMEOUT`
+const MAX_SKIPPED_CATCH_UPS: u32 = 3;
+/// Bounded so a thread opening a transaction while holding another one can't
+/// block catching up forever
+const CATCH_UP_FORCE_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct RocksDBSecondary {
+    pub(crate) inner: Arc<SecondaryDB>,
+    gate: Arc<CatchUpGate>,
+}
+
+struct CatchUpGate {
+    state: Mutex<CatchUpState>,
+    cond: Condvar,
+}
+
+struct CatchUpState {
+    transactions: usize,
+    catching_up: bool,
+    /// Catch-ups skipped since the last one
+    skipped: u32,
+    caught_up_at: Instant,
+}
+
+impl CatchUpGate {
+    fn new() -> Self {
+        CatchUpGate {
+            state: Mutex::new(CatchUpState {
+                transactions: 0,
+                catching_up: false,
+                skipped: 0,
+                caught_up_at: Instant::now(),
+            }),
+            cond: Condvar::new(),
+        }
+    }
+
+    fn enter(self: &Arc<Self>) -> SecondaryReadGuard {
+        let mut state = self.state.lock().expect("lock catch up state");
+        while state.catching_up {
+            state = self.cond.wait(state).expect("lock catch up state");
+        }
+        state.transactions += 1;
+        SecondaryReadGuard(Arc::clone(self))
+    }
+}
+
+/// Held by a transaction of the secondary instance, catching up waits for it
+pub(crate) struct SecondaryReadGuard(Arc<CatchUpGate>);
+
+impl Drop for SecondaryReadGuard {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().expect("lock catch up state");
+        state.transactions -= 1;
+        if state.transactions == 0 {
+            self.0.cond.notify_all();
+        }
+    }
+}
+
+impl RocksDBSecondary {
+    /// Open the primary db in `config.path` as a secondary instance
+    pub fn open<P: AsRef<Path>>(config: &StoreConfig, secondary_path: P, columns: u32) -> Self {
+        Self::open_with_check(config, secondary_path.as_ref(), columns)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn open_with_check(config: &StoreConfig, secondary_path: &Path, columns: u32) -> Result<Self> {
+        let (mut opts, cf_descriptors) = load_options(config, columns)?;
+        opts.create_if_missing(false);
+        // secondary instance requires max_open_files = -1
+        opts.set_max_open_files(-1);
+
+        let descriptor = SecondaryOpenDescriptor::new(secondary_path.to_string_lossy().into());
+        let db = SecondaryDB::open_cf_descriptors_with_descriptor(
+            &opts,
+            &config.path,
+            cf_descriptors,
+            descriptor,
+        )
+        .map_err(|err| internal_error(format!("failed to open the secondary db: {}", err)))?;
+
+        Ok(RocksDBSecondary {
+            inner: Arc::new(db),
+            gate: Arc::new(CatchUpGate::new()),
+        })
+    }
+
+    /// Replay the primary's new MANIFEST and WAL entries. It waits for running
+    /// transactions to finish, and is skipped if they don't finish in time.
+    /// After `MAX_SKIPPED_CATCH_UPS` skips in a row it waits longer, so long
+    /// running transactions can't keep the instance behind the primary.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        let gate = &self.gate;
+        let mut state = gate.state.lock().expect("lock catch up state");
+        if state.catching_up {
+            return Ok(());
+        }
+        state.catching_up = true;
+        let timeout = if state.skipped >= MAX_SKIPPED_CATCH_UPS {
+            CATCH_UP_FORCE_WAIT_TIMEOUT
+        } else {
+            CATCH_UP_WAIT_TIMEOUT
+        };
+        let (mut state, wait) = gate
+            .cond
+            .wait_timeout_while(state, timeout, |state| state.transactions > 0)
+            .expect("lock catch up state");
+        if wait.timed_out() {
+            state.skipped += 1;
+            log::warn!(
+                "skip catching up with primary, {} transactions are running, {} catch-ups skipped, {}s behind",
+                state.transactions,
+                state.skipped,
+                state.caught_up_at.elapsed().as_secs()
+            );
+            state.catching_up = false;
+            gate.cond.notify_all();
+            return Ok(());
+        }
+        drop(state);
+
+        let result = self
+            .inner
+            .try_catch_up_with_primary()
+            .map_err(internal_error);
+
+        let mut state = gate.state.lock().expect("lock catch up state");
+        state.catching_up = false;
+        if result.is_ok() {
+            state.skipped = 0;
+            state.caught_up_at = Instant::now();
+        }
+        gate.cond.notify_all();
+        result
+    }
+
+    /// Time since the last catch-up, or since the instance is opened
+    pub fn catch_up_lag(&self) -> Duration {
+        let state = self.gate.state.lock().expect("lock catch up state");
+        state.caught_up_at.elapsed()
+    }
+
+    pub fn get_pinned(&self, col: Col, key: &[u8]) -> Result<Option<DBPinnableSlice>> {
+        let cf = secondary_cf_handle(&self.inner, col)?;
+        self.inner.get_pinned_cf(cf, &key).map_err(internal_error)
+    }
+
+    /// A read-only transaction, writes return error. The transaction blocks
+    /// catching up until it's dropped, don't hold it longer than needed.
+    pub fn transaction(&self) -> RocksDBTransaction {
+        let guard = self.gate.enter();
+        RocksDBTransaction {
+            inner: TransactionInner::Secondary {
+                db: Arc::clone(&self.inner),
+                _guard: guard,
+            },
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn secondary_cf_handle(db: &SecondaryDB, col: Col) -> Result<&ColumnFamily> {
+    db.cf_handle(&col.to_string())
+        .ok_or_else(|| internal_error(format!("column {} not found", col)))
+}
@@ -1,32 +1,68 @@
 use crate::db::cf_handle;
 use crate::schema::Col;
+use crate::secondary::{secondary_cf_handle, SecondaryReadGuard};
 use crate::{internal_error, Result};
 use rocksdb::ops::{DeleteCF, GetCF, PutCF};
 pub use rocksdb::{DBPinnableSlice, DBVector};
 use rocksdb::{
     OptimisticTransaction, OptimisticTransactionDB, OptimisticTransactionSnapshot, ReadOptions,
+    SecondaryDB,
 };
 use std::sync::Arc;
 
 pub struct RocksDBTransaction {
-    pub(crate) db: Arc<OptimisticTransactionDB>,
-    pub(crate) inner: OptimisticTransaction,
+    pub(crate) inner: TransactionInner,
+}
+
+pub(crate) enum TransactionInner {
+    Optimistic {
+        db: Arc<OptimisticTransactionDB>,
+        inner: OptimisticTransaction,
+    },
+    /// Reads from a secondary instance, catching up waits for the transaction
+    /// to be dropped. Writes are rejected
+    Secondary {
+        db: Arc<SecondaryDB>,
+        _guard: SecondaryReadGuard,
+    },
+}
+
+fn read_only_error() -> crate::error::Error {
+    internal_error("secondary db is read-only")
 }
 
 impl RocksDBTransaction {
     pub fn get(&self, col: Col, key: &[u8]) -> Result<Option<DBVector>> {
-        let cf = cf_handle(&self.db, col)?;
-        self.inner.get_cf(cf, key).map_err(internal_error)
+        match &self.inner {
+            TransactionInner::Optimistic { db, inner } => {
+                let cf = cf_handle(db, col)?;
+                inner.get_cf(cf, key).map_err(internal_error)
+            }
+            TransactionInner::Secondary { db, .. } => {
+                let cf = secondary_cf_handle(db, col)?;
+                db.get_cf(cf, key).map_err(internal_error)
+            }
+        }
     }
 
     pub fn put(&self, col: Col, key: &[u8], value: &[u8]) -> Result<()> {
-        let cf = cf_handle(&self.db, col)?;
-        self.inner.put_cf(cf, key, value).map_err(internal_error)
+        match &self.inner {
+            TransactionInner::Optimistic { db, inner } => {
+                let cf = cf_handle(db, col)?;
+                inner.put_cf(cf, key, value).map_err(internal_error)
+            }
+            TransactionInner::Secondary { .. } => Err(read_only_error()),
+        }
     }
 
     pub fn delete(&self, col: Col, key: &[u8]) -> Result<()> {
-        let cf = cf_handle(&self.db, col)?;
-        self.inner.delete_cf(cf, key).map_err(internal_error)
+        match &self.inner {
+            TransactionInner::Optimistic { db, inner } => {
+                let cf = cf_handle(db, col)?;
+                inner.delete_cf(cf, key).map_err(internal_error)
+            }
+            TransactionInner::Secondary { .. } => Err(read_only_error()),
+        }
     }
 
     pub fn get_for_update<'a>(
@@ -35,35 +71,60 @@ impl RocksDBTransaction {
         key: &[u8],
         snapshot: &RocksDBTransactionSnapshot<'a>,
     ) -> Result<Option<DBVector>> {
-        let cf = cf_handle(&self.db, col)?;
-        let mut opts = ReadOptions::default();
-        opts.set_snapshot(&snapshot.inner);
-        self.inner
-            .get_for_update_cf_opt(cf, key, &opts, true)
-            .map_err(internal_error)
+        match &self.inner {
+            TransactionInner::Optimistic { db, inner } => {
+                let cf = cf_handle(db, col)?;
+                let mut opts = ReadOptions::default();
+                opts.set_snapshot(&snapshot.inner);
+                inner
+                    .get_for_update_cf_opt(cf, key, &opts, true)
+                    .map_err(internal_error)
+            }
+            TransactionInner::Secondary { .. } => Err(read_only_error()),
+        }
     }
 
+    /// Commit is a no-op on a secondary instance, nothing can be written
     pub fn commit(&self) -> Result<()> {
-        self.inner.commit().map_err(internal_error)
+        match &self.inner {
+            TransactionInner::Optimistic { inner, .. } => inner.commit().map_err(internal_error),
+            TransactionInner::Secondary { .. } => Ok(()),
+        }
     }
 
     pub fn rollback(&self) -> Result<()> {
-        self.inner.rollback().map_err(internal_error)
+        match &self.inner {
+            TransactionInner::Optimistic { inner, .. } => inner.rollback().map_err(internal_error),
+            TransactionInner::Secondary { .. } => Ok(()),
+        }
     }
 
-    pub fn get_snapshot(&self) -> RocksDBTransactionSnapshot<'_> {
-        RocksDBTransactionSnapshot {
-            db: Arc::clone(&self.db),
-            inner: self.inner.snapshot(),
+    /// Snapshots are unsupported on a secondary instance
+    pub fn get_snapshot(&self) -> Result<RocksDBTransactionSnapshot<'_>> {
+        match &self.inner {
+            TransactionInner::Optimistic { db, inner } => Ok(RocksDBTransactionSnapshot {
+                db: Arc::clone(db),
+                inner: inner.snapshot(),
+            }),
+            TransactionInner::Secondary { .. } => {
+                Err(internal_error("no transaction snapshot on secondary db"))
+            }
         }
     }
 
     pub fn set_savepoint(&self) {
-        self.inner.set_savepoint()
+        if let TransactionInner::Optimistic { inner, .. } = &self.inner {
+            inner.set_savepoint()
+        }
     }
 
     pub fn rollback_to_savepoint(&self) -> Result<()> {
-        self.inner.rollback_to_savepoint().map_err(internal_error)
+        match &self.inner {
+            TransactionInner::Optimistic { inner, .. } => {
+                inner.rollback_to_savepoint().map_err(internal_error)
+            }
+            TransactionInner::Secondary { .. } => Ok(()),
+        }
     }
 }
 
@@ -14,5 +14,9 @@ fn main() {
 
     if cfg!(any(feature = "asm", feature = "detect-asm")) && can_enable_asm {
         println!("cargo:rustc-cfg=has_asm");
+        // the AOT compiler of ckb-vm only supports unix, it's opt-in
+        if cfg!(feature = "aot") && is_unix {
+            println!("cargo:rustc-cfg=has_aot");
+        }
     }
 }
@@ -1,7 +1,7 @@
-use anyhow::Result;
-use gw_common::H256;
-use gw_config::{BackendConfig, BackendType};
-use gw_types::bytes::Bytes;
+use anyhow::{bail, Result};
+use gw_common::{blake2b::new_blake2b, H256};
+use gw_config::{BackendActivation, BackendConfig, BackendType};
+use gw_types::{bytes::Bytes, packed::BlockInfo, prelude::*};
 use std::{collections::HashMap, fs};
 
 #[derive(Clone)]
@@ -9,12 +9,34 @@ pub struct Backend {
     pub backend_type: BackendType,
     pub validator: Bytes,
     pub generator: Bytes,
+    /// Blake2b hash of `generator`
+    pub generator_code_hash: H256,
     pub validator_script_type_hash: H256,
+    /// Active from genesis if `None`
+    pub activation: Option<BackendActivation>,
+}
+
+impl Backend {
+    pub fn is_active(&self, block_info: &BlockInfo) -> bool {
+        match self.activation {
+            None => true,
+            Some(BackendActivation::BlockNumber(activation_number)) => {
+                let number: u64 = block_info.number().unpack();
+                number >= activation_number
+            }
+            Some(BackendActivation::Timestamp(activation_timestamp)) => {
+                let timestamp: u64 = block_info.timestamp().unpack();
+                timestamp >= activation_timestamp
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct BackendManage {
-    backends: HashMap<H256, Backend>,
+    /// Backends of each validator script type hash in registration order,
+    /// a later backend replaces earlier ones once it is activated
+    backends: HashMap<H256, Vec<Backend>>,
 }
 
 impl BackendManage {
@@ -36,9 +58,17 @@ impl BackendManage {
             validator_path,
             generator_path,
             validator_script_type_hash,
+            activation,
         } = config;
         let validator = fs::read(validator_path)?.into();
-        let generator = fs::read(generator_path)?.into();
+        let generator: Bytes = fs::read(generator_path)?.into();
+        let generator_code_hash = {
+            let mut hash = [0u8; 32];
+            let mut hasher = new_blake2b();
+            hasher.update(&generator);
+            hasher.finalize(&mut hash);
+            hash.into()
+        };
         let validator_script_type_hash = {
             let hash: [u8; 32] = validator_script_type_hash.into();
             hash.into()
@@ -47,22 +77,77 @@ impl BackendManage {
             backend_type,
             validator,
             generator,
+            generator_code_hash,
             validator_script_type_hash,
+            activation,
         };
-        self.register_backend(backend);
+        self.register_backend(backend)
+    }
+
+    /// Register a backend, or an upgrade of a registered backend which must
+    /// be activated after the previous one.
+    pub fn register_backend(&mut self, backend: Backend) -> Result<()> {
+        let versions = self
+            .backends
+            .entry(backend.validator_script_type_hash)
+            .or_default();
+        if let Some(prev) = versions.last() {
+            let is_later = match (prev.activation, backend.activation) {
+                (_, None) => false,
+                (None, Some(_)) => true,
+                (
+                    Some(BackendActivation::BlockNumber(a)),
+                    Some(BackendActivation::BlockNumber(b)),
+                )
+                | (Some(BackendActivation::Timestamp(a)), Some(BackendActivation::Timestamp(b))) => {
+                    b > a
+                }
+                // block number and timestamp aren't comparable
+                (Some(_), Some(_)) => bail!(
+                    "backend {:?} of validator script type hash {} mixes block number and timestamp activations",
+                    backend.backend_type,
+                    hex::encode(backend.validator_script_type_hash.as_slice())
+                ),
+            };
+            if !is_later {
+                bail!(
+                    "backend {:?} of validator script type hash {} must be activated after the previous one",
+                    backend.backend_type,
+                    hex::encode(backend.validator_script_type_hash.as_slice())
+                );
+            }
+        }
+        versions.push(backend);
         Ok(())
     }
 
-    pub fn register_backend(&mut self, backend: Backend) {
+    /// Get the latest backend
+    pub fn get_backend(&self, code_hash: &H256) -> Option<&Backend> {
         self.backends
-            .insert(backend.validator_script_type_hash, backend);
+            .get(code_hash)
+            .and_then(|versions| versions.last())
     }
 
-    pub fn get_backend(&self, code_hash: &H256) -> Option<&Backend> {
-        self.backends.get(code_hash)
+    /// Get the backend activated at the block
+    pub fn get_block_backend(&self, code_hash: &H256, block_info: &BlockInfo) -> Option<&Backend> {
+        self.backends.get(code_hash).and_then(|versions| {
+            versions
+                .iter()
+                .rev()
+                .find(|backend| backend.is_active(block_info))
+        })
     }
 
-    pub fn get_backends(&self) -> &HashMap<H256, Backend> {
-        &self.backends
+    /// Get backends activated at the block
+    pub fn get_block_backends<'a>(
+        &'a self,
+        block_info: &'a BlockInfo,
+    ) -> impl Iterator<Item = &'a Backend> + 'a {
+        self.backends.values().filter_map(move |versions| {
+            versions
+                .iter()
+                .rev()
+                .find(|backend| backend.is_active(block_info))
+        })
     }
 }
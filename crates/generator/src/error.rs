@@ -209,6 +209,8 @@ pub enum BlockError {
     },
     #[error("Can't find checkpoint at index {index}")]
     CheckpointNotFound { index: usize },
+    #[error("Can't find transaction at index {index}")]
+    TransactionNotFound { index: u32 },
 }
 
 impl From<BlockError> for Error {
@@ -216,3 +218,12 @@ impl From<BlockError> for Error {
         Error::Block(err)
     }
 }
+
+/// Syscall extension registration error
+#[derive(Error, Debug, PartialEq, Clone, Eq)]
+pub enum SyscallExtensionError {
+    #[error("Syscall number {code} is reserved")]
+    Reserved { code: u64 },
+    #[error("Syscall number {code} is already registered")]
+    Duplicated { code: u64 },
+}
@@ -1,25 +1,43 @@
 use std::{
     collections::{HashMap, HashSet},
+    sync::Arc,
     time::Instant,
 };
 
 use crate::{
-    account_lock_manage::AccountLockManage,
+    account_lock_manage::{
+        secp256k1::{Secp256k1Eth, Secp256k1Tron},
+        AccountLockManage,
+    },
     backend_manage::BackendManage,
     constants::{MAX_READ_DATA_BYTES_LIMIT, MAX_WRITE_DATA_BYTES_LIMIT},
     erc20_creator_allowlist::SUDTProxyAccountAllowlist,
     error::{BlockError, TransactionValidateError, WithdrawalError},
+    parallel::{execute_speculatively, WriteSet},
+    profile::{run_profiled, CycleProfile},
+    program_cache::{CompiledProgram, ProgramCache},
     vm_cost_model::instruction_cycles,
-    Machine, VMVersion,
+    CoreMachine, Machine, VMVersion, VMVersionSchedule,
 };
 use crate::{
     backend_manage::Backend,
     error::{Error, TransactionError},
     sudt::build_l2_sudt_script,
 };
-use crate::{error::AccountError, syscalls::L2Syscalls};
-use crate::{error::LockAlgorithmError, traits::StateExt};
-use gw_ckb_hardfork::GLOBAL_VM_VERSION;
+use crate::{
+    error::LockAlgorithmError,
+    state_override::OverlayState,
+    traits::{apply_withdrawal_state, StateExt},
+};
+use crate::{
+    error::{AccountError, SyscallExtensionError},
+    syscalls::{
+        extension::{SyscallExtension, SyscallExtensions},
+        L2Syscalls,
+    },
+    trace::TxTrace,
+};
+use anyhow::{anyhow, Context};
 use gw_common::{
     builtins::CKB_SUDT_ACCOUNT_ID,
     error::Error as StateError,
@@ -28,14 +46,17 @@ use gw_common::{
     state::{build_account_field_key, to_short_address, State, GW_ACCOUNT_NONCE_TYPE},
     H256,
 };
-use gw_config::RPCConfig;
-use gw_store::{state::state_db::StateContext, transaction::StoreTransaction};
+use gw_config::{Config, ParallelExecutionConfig, RPCConfig};
+use gw_store::{
+    state::state_db::{StateContext, StateTree},
+    transaction::StoreTransaction,
+};
 use gw_traits::{ChainStore, CodeStore};
 use gw_tx_filter::polyjuice_contract_creator_allowlist::PolyjuiceContractCreatorAllowList;
 use gw_types::{
     bytes::Bytes,
     core::{ChallengeTargetType, ScriptHashType},
-    offchain::{RollupContext, RunResult},
+    offchain::{RollupContext, RunResult, TxAccessList},
     packed::{
         AccountMerkleState, BlockInfo, CellOutput, ChallengeTarget, DepositRequest, L2Block,
         L2Transaction, RawL2Block, RawL2Transaction, Script, TxReceipt, WithdrawalLockArgs,
@@ -44,7 +65,7 @@ use gw_types::{
     prelude::*,
 };
 
-use ckb_vm::{DefaultMachineBuilder, SupportMachine};
+use ckb_vm::{DefaultMachine, DefaultMachineBuilder, SupportMachine};
 
 #[cfg(has_asm)]
 use ckb_vm::machine::asm::AsmMachine;
@@ -62,6 +83,8 @@ pub enum ApplyBlockResult {
         withdrawal_receipts: Vec<WithdrawalReceipt>,
         prev_txs_state: AccountMerkleState,
         tx_receipts: Vec<TxReceipt>,
+        /// State keys touched by each tx
+        tx_access_lists: Vec<TxAccessList>,
         offchain_used_cycles: u64,
     },
     Challenge {
@@ -71,6 +94,16 @@ pub enum ApplyBlockResult {
     Error(Error),
 }
 
+/// Result of re-executing txs of a block
+pub struct ReplayedBlock {
+    /// Run result and account count after each tx
+    pub txs: Vec<Result<(RunResult, u32), TransactionError>>,
+    /// State key values written by withdrawals, deposits and the replayed txs
+    pub write_values: HashMap<H256, H256>,
+    /// Account count after the replayed txs
+    pub account_count: u32,
+}
+
 pub struct Generator {
     backend_manage: BackendManage,
     account_lock_manage: AccountLockManage,
@@ -78,6 +111,10 @@ pub struct Generator {
     sudt_proxy_account_whitelist: SUDTProxyAccountAllowlist,
     polyjuice_contract_creator_allowlist: Option<PolyjuiceContractCreatorAllowList>,
     default_l2tx_max_cycles: u64,
+    parallel_execution: ParallelExecutionConfig,
+    program_cache: ProgramCache,
+    syscall_extensions: SyscallExtensions,
+    vm_version_schedule: VMVersionSchedule,
 }
 
 impl Generator {
@@ -87,6 +124,8 @@ impl Generator {
         rollup_context: RollupContext,
         rpc_config: RPCConfig,
         default_l2tx_max_cycles: u64,
+        parallel_execution: ParallelExecutionConfig,
+        vm_version_schedule: VMVersionSchedule,
     ) -> Self {
         let polyjuice_contract_creator_allowlist =
             PolyjuiceContractCreatorAllowList::from_rpc_config(&rpc_config);
@@ -107,7 +146,51 @@ impl Generator {
             sudt_proxy_account_whitelist,
             polyjuice_contract_creator_allowlist,
             default_l2tx_max_cycles,
+            parallel_execution,
+            program_cache: Default::default(),
+            syscall_extensions: Default::default(),
+            vm_version_schedule,
+        }
+    }
+
+    /// Build the generator of a node config
+    pub fn from_config(config: &Config, rollup_context: RollupContext) -> anyhow::Result<Self> {
+        let backend_manage = BackendManage::from_config(config.backends.clone())
+            .with_context(|| "config backends")?;
+        let mut account_lock_manage = AccountLockManage::default();
+        let allowed_eoa_type_hashes = rollup_context.rollup_config.allowed_eoa_type_hashes();
+        let eth_lock_script_type_hash = allowed_eoa_type_hashes
+            .get(0)
+            .ok_or_else(|| anyhow!("Eth: No allowed EoA type hashes in the rollup config"))?;
+        account_lock_manage.register_lock_algorithm(
+            eth_lock_script_type_hash.unpack(),
+            Box::new(Secp256k1Eth::default()),
+        );
+        if let Some(code_hash) = allowed_eoa_type_hashes.get(1) {
+            account_lock_manage
+                .register_lock_algorithm(code_hash.unpack(), Box::new(Secp256k1Tron::default()))
         }
+        let vm_version_schedule = VMVersionSchedule::from_config(&config.chain.vm_version_schedule)
+            .with_context(|| "config vm version schedule")?;
+        Ok(Self::new(
+            backend_manage,
+            account_lock_manage,
+            rollup_context,
+            config.rpc.clone(),
+            config.mem_pool.submit_l2tx_max_cycles,
+            config.parallel_execution.clone(),
+            vm_version_schedule,
+        ))
+    }
+
+    /// Register a handler of an unused syscall number, must be called before
+    /// executing transactions
+    pub fn register_syscall_extension(
+        &mut self,
+        code: u64,
+        extension: Arc<dyn SyscallExtension>,
+    ) -> Result<(), SyscallExtensionError> {
+        self.syscall_extensions.register(code, extension)
     }
 
     pub fn rollup_context(&self) -> &RollupContext {
@@ -118,7 +201,23 @@ impl Generator {
         &self.account_lock_manage
     }
 
-    fn build_machine<'a, S: State + CodeStore, C: ChainStore>(
+    /// Compiled generator programs shared by all executions
+    pub fn program_cache(&self) -> &ProgramCache {
+        &self.program_cache
+    }
+
+    /// VM version switches of blocks, activated when blocks are synced
+    pub fn vm_version_schedule(&self) -> &VMVersionSchedule {
+        &self.vm_version_schedule
+    }
+
+    /// VM version of a block
+    pub fn vm_version(&self, block_number: u64) -> VMVersion {
+        self.vm_version_schedule.vm_version(block_number)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_default_machine<'a, S: State + CodeStore, C: ChainStore>(
         &'a self,
         run_result: &'a mut RunResult,
         chain: &'a C,
@@ -126,13 +225,9 @@ impl Generator {
         block_info: &'a BlockInfo,
         raw_tx: &'a RawL2Transaction,
         max_cycles: u64,
-    ) -> Machine<'a> {
-        let global_vm_version = smol::block_on(async { *GLOBAL_VM_VERSION.lock().await });
-        let vm_version = match global_vm_version {
-            0 => VMVersion::V0,
-            1 => VMVersion::V1,
-            ver => panic!("Unsupport VMVersion: {}", ver),
-        };
+        trace: Option<&'a mut TxTrace>,
+        vm_version: VMVersion,
+    ) -> DefaultMachine<'a, CoreMachine> {
         let core_machine = vm_version.init_core_machine(max_cycles);
         let machine_builder = DefaultMachineBuilder::new(core_machine)
             .syscall(Box::new(L2Syscalls {
@@ -144,14 +239,42 @@ impl Generator {
                 account_lock_manage: &self.account_lock_manage,
                 result: run_result,
                 code_store: state,
+                trace,
+                extensions: &self.syscall_extensions,
             }))
             .instruction_cycle_func(Box::new(instruction_cycles));
-        let default_machine = machine_builder.build();
+        machine_builder.build()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_machine<'a, S: State + CodeStore, C: ChainStore>(
+        &'a self,
+        run_result: &'a mut RunResult,
+        chain: &'a C,
+        state: &'a S,
+        block_info: &'a BlockInfo,
+        raw_tx: &'a RawL2Transaction,
+        max_cycles: u64,
+        trace: Option<&'a mut TxTrace>,
+        vm_version: VMVersion,
+        compiled_program: &'a CompiledProgram,
+    ) -> Machine<'a> {
+        let default_machine = self.build_default_machine(
+            run_result, chain, state, block_info, raw_tx, max_cycles, trace, vm_version,
+        );
 
-        #[cfg(has_asm)]
-        let machine = AsmMachine::new(default_machine, None);
+        #[cfg(has_aot)]
+        let machine = AsmMachine::new(default_machine, compiled_program.aot_code());
+        #[cfg(all(has_asm, not(has_aot)))]
+        let machine = {
+            let _ = compiled_program;
+            AsmMachine::new(default_machine, None)
+        };
         #[cfg(not(has_asm))]
-        let machine = TraceMachine::new(default_machine);
+        let machine = {
+            let _ = compiled_program;
+            TraceMachine::new(default_machine)
+        };
 
         machine
     }
@@ -434,6 +557,7 @@ impl Generator {
         // handle transactions
         let mut offchain_used_cycles: u64 = 0;
         let mut tx_receipts = Vec::with_capacity(args.l2block.transactions().len());
+        let mut tx_access_lists = Vec::with_capacity(args.l2block.transactions().len());
         let skip_checkpoint_check = skipped_invalid_block_list.contains(&block_hash.into());
         if skip_checkpoint_check {
             log::warn!(
@@ -442,7 +566,21 @@ impl Generator {
                 hex::encode(&block_hash)
             );
         }
-        for (tx_index, tx) in args.l2block.transactions().into_iter().enumerate() {
+        let txs: Vec<_> = args.l2block.transactions().into_iter().collect();
+        let mut speculative_results = if self.parallel_execution.enabled && txs.len() > 1 {
+            let raw_txs: Vec<_> = txs.iter().map(|tx| tx.raw()).collect();
+            let threads = match self.parallel_execution.threads {
+                0 => num_cpus::get(),
+                threads => threads,
+            };
+            execute_speculatively(self, chain, &state, &block_info, &raw_txs, threads)
+        } else {
+            Vec::new()
+        };
+        // writes of committed txs, speculative results read any of them are discarded
+        let mut block_writes = WriteSet::default();
+        let mut reexecuted_txs = 0;
+        for (tx_index, tx) in txs.into_iter().enumerate() {
             if let Err(err) = self.check_transaction_signature(&state, &tx) {
                 let target = build_challenge_target(
                     block_hash.into(),
@@ -478,14 +616,25 @@ impl Generator {
                 };
             }
 
+            let speculative_result = speculative_results
+                .get_mut(tx_index)
+                .and_then(Option::take)
+                .filter(|speculative| !block_writes.conflicts_with(&speculative.reads))
+                .map(|speculative| speculative.result);
+            if speculative_result.is_none() && !speculative_results.is_empty() {
+                reexecuted_txs += 1;
+            }
+
             // build call context
             // NOTICE users only allowed to send HandleMessage CallType txs
-            let run_result = match self.execute_transaction_with_default_max_cycles(
-                chain,
-                &state,
-                &block_info,
-                &raw_tx,
-            ) {
+            let run_result = match speculative_result.unwrap_or_else(|| {
+                self.execute_transaction_with_default_max_cycles(
+                    chain,
+                    &state,
+                    &block_info,
+                    &raw_tx,
+                )
+            }) {
                 Ok(run_result) => run_result,
                 Err(err) => {
                     let target = build_challenge_target(
@@ -505,6 +654,7 @@ impl Generator {
                 if let Err(err) = state.apply_run_result(&run_result) {
                     return ApplyBlockResult::Error(err);
                 }
+                block_writes.record(&run_result);
                 let account_state = state.get_merkle_state();
 
                 let expected_checkpoint = calculate_state_checkpoint(
@@ -545,6 +695,7 @@ impl Generator {
                     Ok(merkle_state) => merkle_state,
                     Err(err) => return ApplyBlockResult::Error(err),
                 };
+                tx_access_lists.push(TxAccessList::from(&run_result));
                 let tx_receipt =
                     TxReceipt::build_receipt(tx.witness_hash().into(), run_result, post_state);
 
@@ -553,18 +704,30 @@ impl Generator {
             }
         }
 
+        if !speculative_results.is_empty() {
+            log::debug!(
+                "[parallel execution] block #{} txs: {}, re-executed: {}",
+                block_number,
+                speculative_results.len(),
+                reexecuted_txs
+            );
+        }
+
         ApplyBlockResult::Success {
             withdrawal_receipts,
             prev_txs_state,
             tx_receipts,
+            tx_access_lists,
             offchain_used_cycles,
         }
     }
 
+    /// Load the backend of the account activated at the block
     pub fn load_backend<S: State + CodeStore>(
         &self,
         state: &S,
         script_hash: &H256,
+        block_info: &BlockInfo,
     ) -> Option<Backend> {
         log::debug!(
             "load_backend for script_hash: {}",
@@ -577,7 +740,8 @@ impl Generator {
                 if script.hash_type() == ScriptHashType::Type.into() {
                     let code_hash: [u8; 32] = script.code_hash().unpack();
                     log::debug!("load_backend by code_hash: {}", hex::encode(code_hash));
-                    self.backend_manage.get_backend(&code_hash.into())
+                    self.backend_manage
+                        .get_block_backend(&code_hash.into(), block_info)
                 } else {
                     log::error!(
                         "Found a invalid account script which hash_type is data: {:?}",
@@ -637,6 +801,223 @@ impl Generator {
         block_info: &BlockInfo,
         raw_tx: &RawL2Transaction,
         max_cycles: u64,
+    ) -> Result<RunResult, TransactionError> {
+        self.execute_transaction_inner(chain, state, block_info, raw_tx, max_cycles, None, None)
+    }
+
+    /// execute a layer2 tx and trace its syscalls, doesn't check exit code
+    pub fn trace_transaction<S: State + CodeStore, C: ChainStore>(
+        &self,
+        chain: &C,
+        state: &S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        max_cycles: u64,
+    ) -> Result<(RunResult, TxTrace), TransactionError> {
+        let mut trace = TxTrace::default();
+        let run_result = self.execute_transaction_inner(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            max_cycles,
+            Some(&mut trace),
+            None,
+        )?;
+        Ok((run_result, trace))
+    }
+
+    /// execute a layer2 tx and profile its cycles by the generator's
+    /// function symbols, doesn't check exit code
+    pub fn profile_transaction<S: State + CodeStore, C: ChainStore>(
+        &self,
+        chain: &C,
+        state: &S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        max_cycles: u64,
+    ) -> Result<(RunResult, CycleProfile), TransactionError> {
+        let mut profile = CycleProfile::default();
+        let run_result = self.execute_transaction_inner(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            max_cycles,
+            None,
+            Some(&mut profile),
+        )?;
+        Ok((run_result, profile))
+    }
+
+    /// Apply withdrawals and deposits of a block to the state before the
+    /// block, return the block info.
+    fn apply_block_pre_state<S: State + CodeStore>(
+        &self,
+        state: &mut S,
+        block: &L2Block,
+        deposit_requests: &[DepositRequest],
+    ) -> Result<BlockInfo, Error> {
+        let block_info = get_block_info(&block.raw());
+        let block_producer_id: u32 = block_info.block_producer_id().unpack();
+        for request in block.withdrawals().into_iter() {
+            apply_withdrawal_state(state, &self.rollup_context, block_producer_id, &request)?;
+        }
+        state.apply_deposit_requests(&self.rollup_context, deposit_requests)?;
+
+        Ok(block_info)
+    }
+
+    /// Return the read-only history state before a block
+    fn block_parent_history_state<'db>(
+        &self,
+        db: &'db StoreTransaction,
+        block: &L2Block,
+    ) -> Result<StateTree<'db>, Error> {
+        let block_number: u64 = block.raw().number().unpack();
+        db.state_tree(StateContext::ReadOnlyHistory(
+            block_number.saturating_sub(1),
+        ))
+        .map_err(|_| Error::State(StateError::Store))
+    }
+
+    /// Rebuild the state before the tx at `tx_index` of a block on top of the
+    /// state before the block, return the block info and the tx.
+    fn block_transaction_state<S: State + CodeStore, C: ChainStore>(
+        &self,
+        state: &mut S,
+        chain: &C,
+        block: &L2Block,
+        deposit_requests: &[DepositRequest],
+        tx_index: u32,
+    ) -> Result<(BlockInfo, L2Transaction), Error> {
+        let tx = block
+            .transactions()
+            .get(tx_index as usize)
+            .ok_or(BlockError::TransactionNotFound { index: tx_index })?;
+
+        let block_info = self.apply_block_pre_state(state, block, deposit_requests)?;
+        for prev_tx in block.transactions().into_iter().take(tx_index as usize) {
+            let run_result = self
+                .unchecked_execute_transaction(
+                    chain,
+                    &*state,
+                    &block_info,
+                    &prev_tx.raw(),
+                    self.default_l2tx_max_cycles,
+                )
+                .map_err(Error::Transaction)?;
+            state.apply_run_result(&run_result)?;
+        }
+
+        Ok((block_info, tx))
+    }
+
+    /// Re-execute the first `tx_count` txs of a block from the state before
+    /// the block. Replay stops at the first tx failed to execute, its error is
+    /// the last item of txs.
+    ///
+    /// Txs are executed on the history state before the block, nothing is
+    /// written to `db`. The replayed state is not merkleized, it's compared
+    /// by the written key values instead of the state root.
+    pub fn replay_block_transactions<C: ChainStore>(
+        &self,
+        db: &StoreTransaction,
+        chain: &C,
+        block: &L2Block,
+        deposit_requests: &[DepositRequest],
+        tx_count: usize,
+    ) -> Result<ReplayedBlock, Error> {
+        let history_state = self.block_parent_history_state(db, block)?;
+        let mut state = OverlayState::new(&history_state);
+        let block_info = self.apply_block_pre_state(&mut state, block, deposit_requests)?;
+        let mut txs = Vec::with_capacity(tx_count);
+        for tx in block.transactions().into_iter().take(tx_count) {
+            let run_result = match self.unchecked_execute_transaction(
+                chain,
+                &state,
+                &block_info,
+                &tx.raw(),
+                self.default_l2tx_max_cycles,
+            ) {
+                Ok(run_result) => run_result,
+                Err(err) => {
+                    txs.push(Err(err));
+                    break;
+                }
+            };
+            state.apply_run_result(&run_result)?;
+            txs.push(Ok((run_result, state.get_account_count()?)));
+        }
+
+        Ok(ReplayedBlock {
+            txs,
+            write_values: state.write_values().clone(),
+            account_count: state.get_account_count()?,
+        })
+    }
+
+    /// Re-execute the tx at `tx_index` of a block and trace its syscalls.
+    ///
+    /// The tx is executed on the history state before the block, nothing is
+    /// written to `db`.
+    pub fn trace_block_transaction<C: ChainStore>(
+        &self,
+        db: &StoreTransaction,
+        chain: &C,
+        block: &L2Block,
+        deposit_requests: &[DepositRequest],
+        tx_index: u32,
+    ) -> Result<(RunResult, TxTrace), Error> {
+        let history_state = self.block_parent_history_state(db, block)?;
+        let mut state = OverlayState::new(&history_state);
+        let (block_info, tx) =
+            self.block_transaction_state(&mut state, chain, block, deposit_requests, tx_index)?;
+        self.trace_transaction(
+            chain,
+            &state,
+            &block_info,
+            &tx.raw(),
+            self.default_l2tx_max_cycles,
+        )
+        .map_err(Error::Transaction)
+    }
+
+    /// Re-execute the tx at `tx_index` of a block and profile its cycles.
+    ///
+    /// The tx is executed on the history state before the block, nothing is
+    /// written to `db`.
+    pub fn profile_block_transaction<C: ChainStore>(
+        &self,
+        db: &StoreTransaction,
+        chain: &C,
+        block: &L2Block,
+        deposit_requests: &[DepositRequest],
+        tx_index: u32,
+    ) -> Result<(RunResult, CycleProfile), Error> {
+        let history_state = self.block_parent_history_state(db, block)?;
+        let mut state = OverlayState::new(&history_state);
+        let (block_info, tx) =
+            self.block_transaction_state(&mut state, chain, block, deposit_requests, tx_index)?;
+        self.profile_transaction(
+            chain,
+            &state,
+            &block_info,
+            &tx.raw(),
+            self.default_l2tx_max_cycles,
+        )
+        .map_err(Error::Transaction)
+    }
+
+    fn execute_transaction_inner<S: State + CodeStore, C: ChainStore>(
+        &self,
+        chain: &C,
+        state: &S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        max_cycles: u64,
+        trace: Option<&mut TxTrace>,
+        profile: Option<&mut CycleProfile>,
     ) -> Result<RunResult, TransactionError> {
         if let Some(polyjuice_contract_creator_allowlist) =
             self.polyjuice_contract_creator_allowlist.as_ref()
@@ -657,33 +1038,62 @@ impl Generator {
         let sender_id: u32 = raw_tx.from_id().unpack();
         let nonce_before_execution = state.get_nonce(sender_id)?;
 
+        let account_id = raw_tx.to_id().unpack();
+        let script_hash = state.get_script_hash(account_id)?;
+        let backend = self
+            .load_backend(state, &script_hash, block_info)
+            .ok_or(TransactionError::BackendNotFound { script_hash })?;
+        let vm_version = self.vm_version(block_info.number().unpack());
+
         let mut run_result = RunResult::default();
         let used_cycles;
         let exit_code;
-        {
-            let mut machine = self.build_machine(
-                &mut run_result,
-                chain,
-                state,
-                block_info,
-                raw_tx,
-                max_cycles,
-            );
-            let account_id = raw_tx.to_id().unpack();
-            let script_hash = state.get_script_hash(account_id)?;
-            let backend = self
-                .load_backend(state, &script_hash)
-                .ok_or(TransactionError::BackendNotFound { script_hash })?;
-            machine.load_program(&backend.generator, &[])?;
-            let t = Instant::now();
-            exit_code = machine.run()?;
-            log::debug!(
-                "[execute tx] VM run time: {}ms, exit code: {}",
-                t.elapsed().as_millis(),
-                exit_code
-            );
-            used_cycles = machine.machine.cycles();
+        let t = Instant::now();
+        match profile {
+            Some(profile) => {
+                let mut machine = self.build_default_machine(
+                    &mut run_result,
+                    chain,
+                    state,
+                    block_info,
+                    raw_tx,
+                    max_cycles,
+                    trace,
+                    vm_version,
+                );
+                machine.load_program(&backend.generator, &[])?;
+                let (code, profiled) = run_profiled(&mut machine, &backend.generator)?;
+                *profile = profiled;
+                exit_code = code;
+                used_cycles = machine.cycles();
+            }
+            None => {
+                let compiled_program = self.program_cache.get_or_compile(
+                    &backend.generator_code_hash,
+                    &backend.generator,
+                    vm_version,
+                );
+                let mut machine = self.build_machine(
+                    &mut run_result,
+                    chain,
+                    state,
+                    block_info,
+                    raw_tx,
+                    max_cycles,
+                    trace,
+                    vm_version,
+                    &compiled_program,
+                );
+                machine.load_program(&backend.generator, &[])?;
+                exit_code = machine.run()?;
+                used_cycles = machine.machine.cycles();
+            }
         }
+        log::debug!(
+            "[execute tx] VM run time: {}ms, exit code: {}",
+            t.elapsed().as_millis(),
+            exit_code
+        );
         // record used cycles
         log::debug!("run_result.used_cycles = {}", used_cycles);
         run_result.used_cycles = used_cycles;
@@ -793,8 +1203,12 @@ impl Generator {
         Ok((output, data))
     }
 
-    pub fn get_backends(&self) -> &HashMap<H256, Backend> {
-        self.backend_manage.get_backends()
+    /// Get backends activated at the block
+    pub fn get_block_backends<'a>(
+        &'a self,
+        block_info: &'a BlockInfo,
+    ) -> impl Iterator<Item = &'a Backend> + 'a {
+        self.backend_manage.get_block_backends(block_info)
     }
 }
 
@@ -9,8 +9,13 @@ pub mod erc20_creator_allowlist;
 pub mod error;
 pub mod generator;
 pub mod genesis;
+pub mod parallel;
+pub mod profile;
+pub mod program_cache;
+pub mod state_override;
 pub mod sudt;
 pub mod syscalls;
+pub mod trace;
 pub mod traits;
 pub mod types;
 pub mod vm_cost_model;
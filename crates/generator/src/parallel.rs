@@ -0,0 +1,282 @@
+//! Optimistic parallel execution of block transactions
+//!
+//! Transactions of a block are executed speculatively on worker threads
+//! against the state before the first transaction. Workers don't access the
+//! store, their state reads are served by the thread which owns the state and
+//! recorded in a read set. The block verifier commits the results in order,
+//! a result is discarded if it read anything written by a committed
+//! transaction of the block, and the transaction is re-executed on the latest
+//! state, so the state roots are identical to serial execution.
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use gw_common::{error::Error as StateError, state::State, H256};
+use gw_db::error::Error as DBError;
+use gw_traits::{ChainStore, CodeStore};
+use gw_types::{
+    bytes::Bytes,
+    offchain::RunResult,
+    packed::{BlockInfo, RawL2Transaction, Script},
+};
+
+use crate::{error::TransactionError, Generator};
+
+/// Everything a speculative execution read from the state
+#[derive(Default)]
+pub struct ReadSet {
+    keys: HashSet<H256>,
+    account_count: bool,
+    scripts: HashSet<H256>,
+    short_addresses: Vec<Vec<u8>>,
+    data: HashSet<H256>,
+}
+
+/// Everything committed transactions of a block wrote to the state
+#[derive(Default)]
+pub struct WriteSet {
+    keys: HashSet<H256>,
+    account_count: bool,
+    scripts: HashSet<H256>,
+    data: HashSet<H256>,
+}
+
+impl WriteSet {
+    /// Record the writes of a committed transaction, must match `StateExt::apply_run_result`
+    pub fn record(&mut self, run_result: &RunResult) {
+        self.keys.extend(run_result.write_values.keys());
+        self.account_count |= run_result.account_count.is_some();
+        self.scripts.extend(run_result.new_scripts.keys());
+        self.data.extend(run_result.write_data.keys());
+    }
+
+    pub fn conflicts_with(&self, reads: &ReadSet) -> bool {
+        (self.account_count && reads.account_count)
+            || !self.keys.is_disjoint(&reads.keys)
+            || !self.scripts.is_disjoint(&reads.scripts)
+            || !self.data.is_disjoint(&reads.data)
+            || reads.short_addresses.iter().any(|short_address| {
+                self.scripts
+                    .iter()
+                    .any(|script_hash| script_hash.as_slice().starts_with(short_address))
+            })
+    }
+}
+
+pub struct SpeculativeResult {
+    pub result: Result<RunResult, TransactionError>,
+    pub reads: ReadSet,
+}
+
+enum ReadRequest {
+    Raw(H256),
+    AccountCount,
+    Script(H256),
+    ScriptHashByShortAddress(Vec<u8>),
+    Data(H256),
+    BlockHashByNumber(u64),
+}
+
+enum ReadResponse {
+    Raw(Result<H256, StateError>),
+    AccountCount(Result<u32, StateError>),
+    Script(Option<Script>),
+    ScriptHash(Option<H256>),
+    Data(Option<Bytes>),
+    BlockHash(Result<Option<H256>, DBError>),
+}
+
+enum Message {
+    Read {
+        request: ReadRequest,
+        reply: Sender<ReadResponse>,
+    },
+    Done {
+        index: usize,
+        result: SpeculativeResult,
+    },
+}
+
+/// Read-only state of a worker thread, reads are sent to the state owner
+struct RemoteState {
+    messages: Sender<Message>,
+    reply_tx: Sender<ReadResponse>,
+    reply_rx: Receiver<ReadResponse>,
+    reads: RefCell<ReadSet>,
+}
+
+impl RemoteState {
+    fn new(messages: Sender<Message>) -> Self {
+        let (reply_tx, reply_rx) = unbounded();
+        RemoteState {
+            messages,
+            reply_tx,
+            reply_rx,
+            reads: Default::default(),
+        }
+    }
+
+    fn read(&self, request: ReadRequest) -> Option<ReadResponse> {
+        let reply = self.reply_tx.clone();
+        self.messages.send(Message::Read { request, reply }).ok()?;
+        self.reply_rx.recv().ok()
+    }
+
+    fn into_reads(self) -> ReadSet {
+        self.reads.into_inner()
+    }
+}
+
+impl State for RemoteState {
+    fn get_raw(&self, key: &H256) -> Result<H256, StateError> {
+        self.reads.borrow_mut().keys.insert(*key);
+        match self.read(ReadRequest::Raw(*key)) {
+            Some(ReadResponse::Raw(value)) => value,
+            _ => Err(StateError::Store),
+        }
+    }
+    fn update_raw(&mut self, _key: H256, _value: H256) -> Result<(), StateError> {
+        unreachable!("speculative state is read-only")
+    }
+    fn update_multi_raws(&mut self, _pairs: Vec<(H256, H256)>) -> Result<(), StateError> {
+        unreachable!("speculative state is read-only")
+    }
+    fn get_account_count(&self) -> Result<u32, StateError> {
+        self.reads.borrow_mut().account_count = true;
+        match self.read(ReadRequest::AccountCount) {
+            Some(ReadResponse::AccountCount(count)) => count,
+            _ => Err(StateError::Store),
+        }
+    }
+    fn set_account_count(&mut self, _count: u32) -> Result<(), StateError> {
+        unreachable!("speculative state is read-only")
+    }
+    fn calculate_root(&self) -> Result<H256, StateError> {
+        unreachable!("speculative state doesn't calculate root")
+    }
+}
+
+impl CodeStore for RemoteState {
+    fn insert_script(&mut self, _script_hash: H256, _script: Script) {
+        unreachable!("speculative state is read-only")
+    }
+    fn get_script(&self, script_hash: &H256) -> Option<Script> {
+        self.reads.borrow_mut().scripts.insert(*script_hash);
+        match self.read(ReadRequest::Script(*script_hash)) {
+            Some(ReadResponse::Script(script)) => script,
+            _ => None,
+        }
+    }
+    fn get_script_hash_by_short_address(&self, short_address: &[u8]) -> Option<H256> {
+        self.reads
+            .borrow_mut()
+            .short_addresses
+            .push(short_address.to_vec());
+        match self.read(ReadRequest::ScriptHashByShortAddress(
+            short_address.to_vec(),
+        )) {
+            Some(ReadResponse::ScriptHash(script_hash)) => script_hash,
+            _ => None,
+        }
+    }
+    fn insert_data(&mut self, _data_hash: H256, _code: Bytes) {
+        unreachable!("speculative state is read-only")
+    }
+    fn get_data(&self, data_hash: &H256) -> Option<Bytes> {
+        self.reads.borrow_mut().data.insert(*data_hash);
+        match self.read(ReadRequest::Data(*data_hash)) {
+            Some(ReadResponse::Data(data)) => data,
+            _ => None,
+        }
+    }
+}
+
+impl ChainStore for RemoteState {
+    fn get_block_hash_by_number(&self, number: u64) -> Result<Option<H256>, DBError> {
+        match self.read(ReadRequest::BlockHashByNumber(number)) {
+            Some(ReadResponse::BlockHash(block_hash)) => block_hash,
+            _ => Err("speculative state is closed".to_string().into()),
+        }
+    }
+}
+
+fn serve_read<S: State + CodeStore, C: ChainStore>(
+    state: &S,
+    chain: &C,
+    request: ReadRequest,
+) -> ReadResponse {
+    match request {
+        ReadRequest::Raw(key) => ReadResponse::Raw(state.get_raw(&key)),
+        ReadRequest::AccountCount => ReadResponse::AccountCount(state.get_account_count()),
+        ReadRequest::Script(script_hash) => ReadResponse::Script(state.get_script(&script_hash)),
+        ReadRequest::ScriptHashByShortAddress(short_address) => {
+            ReadResponse::ScriptHash(state.get_script_hash_by_short_address(&short_address))
+        }
+        ReadRequest::Data(data_hash) => ReadResponse::Data(state.get_data(&data_hash)),
+        ReadRequest::BlockHashByNumber(number) => {
+            ReadResponse::BlockHash(chain.get_block_hash_by_number(number))
+        }
+    }
+}
+
+/// Execute transactions speculatively on `threads` workers against `state`.
+///
+/// Return the result of each transaction, `None` if a worker failed to
+/// execute it.
+pub fn execute_speculatively<S: State + CodeStore, C: ChainStore>(
+    generator: &Generator,
+    chain: &C,
+    state: &S,
+    block_info: &BlockInfo,
+    raw_txs: &[RawL2Transaction],
+    threads: usize,
+) -> Vec<Option<SpeculativeResult>> {
+    let mut results: Vec<Option<SpeculativeResult>> = raw_txs.iter().map(|_| None).collect();
+    let next_index = AtomicUsize::new(0);
+    let (messages_tx, messages_rx) = unbounded();
+
+    let ret = crossbeam_utils::thread::scope(|scope| {
+        for _ in 0..threads.min(raw_txs.len()) {
+            let messages_tx = messages_tx.clone();
+            let next_index = &next_index;
+            scope.spawn(move |_| loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let raw_tx = match raw_txs.get(index) {
+                    Some(raw_tx) => raw_tx,
+                    None => break,
+                };
+                let remote_state = RemoteState::new(messages_tx.clone());
+                let result = generator.execute_transaction_with_default_max_cycles(
+                    &remote_state,
+                    &remote_state,
+                    block_info,
+                    raw_tx,
+                );
+                let reads = remote_state.into_reads();
+                let result = SpeculativeResult { result, reads };
+                if messages_tx.send(Message::Done { index, result }).is_err() {
+                    break;
+                }
+            });
+        }
+        // the loop ends once all workers exit
+        drop(messages_tx);
+        for message in messages_rx.iter() {
+            match message {
+                Message::Read { request, reply } => {
+                    let _ = reply.send(serve_read(state, chain, request));
+                }
+                Message::Done { index, result } => results[index] = Some(result),
+            }
+        }
+    });
+    if ret.is_err() {
+        log::warn!("[parallel execution] a worker panicked, re-execute its transactions serially");
+    }
+
+    results
+}
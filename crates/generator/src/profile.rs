@@ -0,0 +1,278 @@
+//! Cycle profiling of a transaction execution
+//!
+//! The profiled machine is stepped one instruction at a time, the cycles of
+//! every instruction are added to the call stack at the instruction's PC. Call
+//! stacks are rebuilt from the function symbols of the generator ELF: jumping
+//! to the entry of a function is a call, jumping back into a function on the
+//! stack is a return. The profile is written as folded stacks, the input
+//! format of flamegraph tools like `inferno-flamegraph` and `flamegraph.pl`.
+
+use std::{collections::HashMap, convert::TryInto};
+
+use ckb_vm::{
+    decoder::build_decoder, CoreMachine, DefaultMachine, Error as VMError, SupportMachine,
+};
+
+use crate::types::CoreMachine as VMCoreMachine;
+
+const UNKNOWN_FUNCTION: &str = "[unknown]";
+
+// ELF64 constants
+const SHT_SYMTAB: u32 = 2;
+const STT_FUNC: u8 = 2;
+const SYM_SIZE: usize = 24;
+const SECTION_HEADER_SIZE: usize = 64;
+
+struct Function {
+    start: u64,
+    end: u64,
+    name: String,
+}
+
+/// Function symbols of a RISC-V ELF
+pub struct Symbols {
+    /// Sorted by start address
+    functions: Vec<Function>,
+}
+
+impl Symbols {
+    /// Parse function symbols from the `.symtab` section, symbols are empty if
+    /// the ELF is stripped or malformed.
+    pub fn parse(elf: &[u8]) -> Self {
+        let mut functions = parse_function_symbols(elf).unwrap_or_default();
+        functions.sort_by_key(|f| f.start);
+        functions.dedup_by_key(|f| f.start);
+        Symbols { functions }
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Index of the function containing `pc`
+    fn lookup(&self, pc: u64) -> Option<usize> {
+        let index = match self.functions.binary_search_by_key(&pc, |f| f.start) {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let function = &self.functions[index];
+        if pc < function.end || function.start == function.end {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    fn name(&self, index: usize) -> &str {
+        self.functions
+            .get(index)
+            .map(|f| f.name.as_str())
+            .unwrap_or(UNKNOWN_FUNCTION)
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Parse STT_FUNC symbols of a little endian ELF64
+fn parse_function_symbols(elf: &[u8]) -> Option<Vec<Function>> {
+    // magic, ELFCLASS64, ELFDATA2LSB
+    if elf.get(0..6)? != b"\x7fELF\x02\x01" {
+        return None;
+    }
+    let section_headers = read_u64(elf, 0x28)? as usize;
+    let section_count = read_u16(elf, 0x3c)? as usize;
+    let section = |index: usize| -> Option<(u32, usize, usize, u32)> {
+        let header = section_headers.checked_add(index.checked_mul(SECTION_HEADER_SIZE)?)?;
+        let header = elf.get(header..)?;
+        let sh_type = read_u32(header, 4)?;
+        let offset = read_u64(header, 0x18)? as usize;
+        let size = read_u64(header, 0x20)? as usize;
+        let link = read_u32(header, 0x28)?;
+        Some((sh_type, offset, size, link))
+    };
+
+    let (_, symtab_offset, symtab_size, strtab_index) = (0..section_count)
+        .filter_map(section)
+        .find(|(sh_type, ..)| *sh_type == SHT_SYMTAB)?;
+    let (_, strtab_offset, strtab_size, _) = section(strtab_index as usize)?;
+    let strtab = elf.get(strtab_offset..strtab_offset.checked_add(strtab_size)?)?;
+
+    let mut functions = Vec::new();
+    for i in 0..symtab_size / SYM_SIZE {
+        let sym = elf.get(symtab_offset.checked_add(i * SYM_SIZE)?..)?;
+        let st_name = read_u32(sym, 0)? as usize;
+        let st_info = *sym.get(4)?;
+        let st_value = read_u64(sym, 8)?;
+        let st_size = read_u64(sym, 16)?;
+        if st_info & 0xf != STT_FUNC || st_value == 0 {
+            continue;
+        }
+        let name = strtab
+            .get(st_name..)
+            .and_then(|s| s.split(|&c| c == 0).next())
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| format!("{:#x}", st_value));
+        functions.push(Function {
+            start: st_value,
+            end: st_value.saturating_add(st_size),
+            name,
+        });
+    }
+    Some(functions)
+}
+
+/// Cycles of each call stack
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CycleProfile {
+    /// Call stacks from the outermost function, and their self cycles
+    pub stacks: Vec<(Vec<String>, u64)>,
+}
+
+impl CycleProfile {
+    /// Folded stacks, one `outer;inner cycles` line for each stack
+    pub fn to_folded(&self) -> String {
+        let mut folded = String::new();
+        for (stack, cycles) in &self.stacks {
+            folded.push_str(&stack.join(";"));
+            folded.push(' ');
+            folded.push_str(&cycles.to_string());
+            folded.push('\n');
+        }
+        folded
+    }
+}
+
+struct Node {
+    parent: usize,
+    function: Option<usize>,
+    cycles: u64,
+}
+
+pub(crate) struct Profiler<'a> {
+    symbols: &'a Symbols,
+    nodes: Vec<Node>,
+    children: HashMap<(usize, Option<usize>), usize>,
+    /// Nodes of the current call stack, starts with the root node
+    stack: Vec<usize>,
+}
+
+impl<'a> Profiler<'a> {
+    pub(crate) fn new(symbols: &'a Symbols) -> Self {
+        let root = Node {
+            parent: 0,
+            function: None,
+            cycles: 0,
+        };
+        Profiler {
+            symbols,
+            nodes: vec![root],
+            children: Default::default(),
+            stack: vec![0],
+        }
+    }
+
+    fn push(&mut self, function: Option<usize>) {
+        let parent = *self.stack.last().expect("root");
+        let next_id = self.nodes.len();
+        let node = *self.children.entry((parent, function)).or_insert(next_id);
+        if node == next_id {
+            self.nodes.push(Node {
+                parent,
+                function,
+                cycles: 0,
+            });
+        }
+        self.stack.push(node);
+    }
+
+    /// Update the call stack by the PC of the next instruction, and add its cycles
+    pub(crate) fn step(&mut self, pc: u64, cycles: u64) {
+        let function = self.symbols.lookup(pc);
+        let top = *self.stack.last().expect("root");
+        let is_root = self.stack.len() == 1;
+        if is_root || self.nodes[top].function != function {
+            let is_entry = function.map(|f| self.symbols.functions[f].start == pc);
+            let caller = self
+                .stack
+                .iter()
+                .skip(1)
+                .rposition(|&node| self.nodes[node].function == function);
+            match (is_entry, caller) {
+                (Some(true), _) => self.push(function),
+                // return to the caller, the position skips the root
+                (_, Some(position)) => self.stack.truncate(position + 2),
+                // jump to another function
+                _ => {
+                    if !is_root {
+                        self.stack.pop();
+                    }
+                    self.push(function);
+                }
+            }
+        }
+        let top = *self.stack.last().expect("root");
+        self.nodes[top].cycles += cycles;
+    }
+
+    pub(crate) fn into_profile(self) -> CycleProfile {
+        let stacks = (1..self.nodes.len())
+            .filter(|&id| self.nodes[id].cycles > 0)
+            .map(|id| {
+                let mut stack = Vec::new();
+                let mut node = id;
+                while node != 0 {
+                    let function = self.nodes[node].function;
+                    let name = function
+                        .map(|f| self.symbols.name(f))
+                        .unwrap_or(UNKNOWN_FUNCTION);
+                    stack.push(name.to_string());
+                    node = self.nodes[node].parent;
+                }
+                stack.reverse();
+                (stack, self.nodes[id].cycles)
+            })
+            .collect();
+        CycleProfile { stacks }
+    }
+}
+
+/// Run a loaded machine to the end and profile cycles by the function symbols
+/// of `elf`, the consumed cycles are the same as running it normally.
+pub(crate) fn run_profiled(
+    machine: &mut DefaultMachine<'_, VMCoreMachine>,
+    elf: &[u8],
+) -> Result<(i8, CycleProfile), VMError> {
+    let symbols = Symbols::parse(elf);
+    let mut profiler = Profiler::new(&symbols);
+    let mut decoder = build_decoder::<u64>(machine.isa(), machine.version());
+    machine.set_running(true);
+    while machine.running() {
+        if machine.reset_signal() {
+            decoder.reset_instructions_cache();
+        }
+        let pc = *machine.pc();
+        let cycles = machine.cycles();
+        machine.step(&mut decoder)?;
+        profiler.step(pc, machine.cycles().saturating_sub(cycles));
+    }
+    Ok((machine.exit_code(), profiler.into_profile()))
+}
@@ -0,0 +1,104 @@
+//! Cache of compiled generator programs
+//!
+//! Compiling a generator to native code with the AOT compiler costs far more
+//! than executing a transaction, so compiled programs are cached by the
+//! generator code hash and VM version, and shared by every execution of the
+//! generator (mem-pool, RPC and block verification). AOT compiling is
+//! enabled by the `aot` feature, without it or on platforms without AOT
+//! support the cached programs are empty and programs are interpreted.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use gw_common::H256;
+use gw_types::bytes::Bytes;
+
+use crate::VMVersion;
+
+#[cfg(has_aot)]
+use crate::vm_cost_model::instruction_cycles;
+#[cfg(has_aot)]
+use ckb_vm::machine::aot::{AotCode, AotCompilingMachine};
+
+/// A compiled generator program
+#[derive(Default)]
+pub struct CompiledProgram {
+    #[cfg(has_aot)]
+    aot_code: Option<AotCode>,
+}
+
+impl CompiledProgram {
+    #[cfg(has_aot)]
+    fn compile(code_hash: &H256, program: &Bytes, vm_version: VMVersion) -> Self {
+        let compiled = AotCompilingMachine::load(
+            program,
+            Some(Box::new(instruction_cycles)),
+            vm_version.vm_isa(),
+            vm_version.vm_version(),
+        )
+        .and_then(|mut machine| machine.compile());
+        let aot_code = match compiled {
+            Ok(aot_code) => Some(aot_code),
+            Err(err) => {
+                // fallback to the interpreter
+                log::warn!(
+                    "[program cache] compile program {} error: {}",
+                    hex::encode(code_hash.as_slice()),
+                    err
+                );
+                None
+            }
+        };
+        CompiledProgram { aot_code }
+    }
+
+    #[cfg(not(has_aot))]
+    fn compile(_code_hash: &H256, _program: &Bytes, _vm_version: VMVersion) -> Self {
+        CompiledProgram::default()
+    }
+
+    #[cfg(has_aot)]
+    pub fn aot_code(&self) -> Option<&AotCode> {
+        self.aot_code.as_ref()
+    }
+}
+
+#[derive(Default)]
+pub struct ProgramCache {
+    programs: RwLock<HashMap<(H256, VMVersion), Arc<CompiledProgram>>>,
+}
+
+impl ProgramCache {
+    /// Get the compiled program, compile and cache it if not found
+    pub fn get_or_compile(
+        &self,
+        code_hash: &H256,
+        program: &Bytes,
+        vm_version: VMVersion,
+    ) -> Arc<CompiledProgram> {
+        let key = (*code_hash, vm_version);
+        if let Some(compiled) = self.programs.read().expect("program cache").get(&key) {
+            return Arc::clone(compiled);
+        }
+
+        let compiled = Arc::new(CompiledProgram::compile(code_hash, program, vm_version));
+        let mut programs = self.programs.write().expect("program cache");
+        // another thread may have compiled it
+        Arc::clone(programs.entry(key).or_insert(compiled))
+    }
+
+    pub fn len(&self) -> usize {
+        self.programs.read().expect("program cache").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop all compiled programs
+    pub fn clear(&self) {
+        self.programs.write().expect("program cache").clear();
+    }
+}
@@ -0,0 +1,209 @@
+//! State overrides of dry runs
+//!
+//! `OverlayState` is a writable layer over a read-only state, writes are kept
+//! in memory and reads fall back to the base state. State overrides are
+//! applied to the overlay before executing a transaction, the base state is
+//! never touched.
+
+use std::collections::HashMap;
+
+use gw_common::{
+    blake2b::new_blake2b,
+    error::Error as StateError,
+    h256_ext::H256Ext,
+    state::{
+        build_account_field_key, build_account_key, build_script_hash_to_account_id_key,
+        build_short_script_hash_to_script_hash_key, build_sudt_key, to_short_address, State,
+        DEFAULT_SHORT_SCRIPT_HASH_LEN, GW_ACCOUNT_SCRIPT_HASH_TYPE, SUDT_KEY_FLAG_BALANCE,
+    },
+    H256,
+};
+use gw_traits::CodeStore;
+use gw_types::{
+    bytes::Bytes,
+    offchain::{AccountOverride, StateOverride},
+    packed::Script,
+};
+
+use crate::error::{AccountError, Error};
+
+pub struct OverlayState<'a, S> {
+    base: &'a S,
+    kv: HashMap<H256, H256>,
+    account_count: Option<u32>,
+    scripts: HashMap<H256, Script>,
+    // `None` hides a short address of the base state
+    short_addresses: HashMap<Vec<u8>, Option<H256>>,
+    data: HashMap<H256, Bytes>,
+}
+
+impl<'a, S: State + CodeStore> OverlayState<'a, S> {
+    pub fn new(base: &'a S) -> Self {
+        OverlayState {
+            base,
+            kv: Default::default(),
+            account_count: None,
+            scripts: Default::default(),
+            short_addresses: Default::default(),
+            data: Default::default(),
+        }
+    }
+
+    /// State key values written to the overlay
+    pub fn write_values(&self) -> &HashMap<H256, H256> {
+        &self.kv
+    }
+
+    /// Apply overrides in order, later overrides of an account win
+    pub fn apply_state_override(&mut self, state_override: &StateOverride) -> Result<(), Error> {
+        for data in state_override.data.iter() {
+            let data_hash = {
+                let mut hasher = new_blake2b();
+                hasher.update(data);
+                let mut hash = [0u8; 32];
+                hasher.finalize(&mut hash);
+                hash.into()
+            };
+            self.insert_data(data_hash, data.clone());
+            self.store_data_hash(data_hash)?;
+        }
+        for account in state_override.accounts.iter() {
+            self.apply_account_override(account)?;
+        }
+        Ok(())
+    }
+
+    fn apply_account_override(&mut self, account: &AccountOverride) -> Result<(), Error> {
+        let id = account.id;
+        let account_count = self.get_account_count()?;
+        if let Some(script) = account.script.as_ref() {
+            let script_hash: H256 = script.hash().into();
+            self.insert_script(script_hash, script.clone());
+            if id == account_count {
+                self.create_account(script_hash)?;
+            } else if id < account_count {
+                self.replace_account_script_hash(id, script_hash)?;
+            } else {
+                return Err(AccountError::UnknownAccount.into());
+            }
+        } else if id >= account_count {
+            return Err(AccountError::ScriptNotFound { account_id: id }.into());
+        }
+
+        if let Some(nonce) = account.nonce {
+            self.set_nonce(id, nonce)?;
+        }
+        if !account.balances.is_empty() {
+            let script_hash = self.get_script_hash(id)?;
+            let short_address = to_short_address(&script_hash).to_vec();
+            for (sudt_id, balance) in account.balances.iter() {
+                let sudt_key = build_sudt_key(SUDT_KEY_FLAG_BALANCE, &short_address);
+                let raw_key = build_account_key(*sudt_id, &sudt_key);
+                self.update_raw(raw_key, H256::from_u128(*balance))?;
+            }
+        }
+        for (key, value) in account.storage.iter() {
+            self.update_value(id, key, *value)?;
+        }
+        Ok(())
+    }
+
+    fn replace_account_script_hash(&mut self, id: u32, script_hash: H256) -> Result<(), Error> {
+        let old_script_hash = self.get_script_hash(id)?;
+        if old_script_hash == script_hash {
+            return Ok(());
+        }
+        // remove indexes of the old script
+        self.update_raw(
+            build_script_hash_to_account_id_key(old_script_hash.as_slice()),
+            H256::zero(),
+        )?;
+        let old_short_script_hash = &old_script_hash.as_slice()[..DEFAULT_SHORT_SCRIPT_HASH_LEN];
+        if old_short_script_hash != &script_hash.as_slice()[..DEFAULT_SHORT_SCRIPT_HASH_LEN] {
+            self.update_raw(
+                build_short_script_hash_to_script_hash_key(old_short_script_hash),
+                H256::zero(),
+            )?;
+        }
+        let old_short_address = to_short_address(&old_script_hash);
+        if old_short_address != to_short_address(&script_hash) {
+            self.short_addresses
+                .insert(old_short_address.to_vec(), None);
+        }
+        self.update_raw(
+            build_account_field_key(id, GW_ACCOUNT_SCRIPT_HASH_TYPE),
+            script_hash,
+        )?;
+        self.update_raw(
+            build_script_hash_to_account_id_key(script_hash.as_slice()),
+            H256::from_u32(id),
+        )?;
+        self.update_raw(
+            build_short_script_hash_to_script_hash_key(
+                &script_hash.as_slice()[..DEFAULT_SHORT_SCRIPT_HASH_LEN],
+            ),
+            script_hash,
+        )?;
+        Ok(())
+    }
+}
+
+impl<'a, S: State> State for OverlayState<'a, S> {
+    fn get_raw(&self, key: &H256) -> Result<H256, StateError> {
+        match self.kv.get(key) {
+            Some(value) => Ok(*value),
+            None => self.base.get_raw(key),
+        }
+    }
+    fn update_raw(&mut self, key: H256, value: H256) -> Result<(), StateError> {
+        self.kv.insert(key, value);
+        Ok(())
+    }
+    fn update_multi_raws(&mut self, pairs: Vec<(H256, H256)>) -> Result<(), StateError> {
+        self.kv.extend(pairs);
+        Ok(())
+    }
+    fn get_account_count(&self) -> Result<u32, StateError> {
+        match self.account_count {
+            Some(count) => Ok(count),
+            None => self.base.get_account_count(),
+        }
+    }
+    fn set_account_count(&mut self, count: u32) -> Result<(), StateError> {
+        self.account_count = Some(count);
+        Ok(())
+    }
+    fn calculate_root(&self) -> Result<H256, StateError> {
+        // overrides are not merkleized
+        Err(StateError::Store)
+    }
+}
+
+impl<'a, S: CodeStore> CodeStore for OverlayState<'a, S> {
+    fn insert_script(&mut self, script_hash: H256, script: Script) {
+        self.short_addresses
+            .insert(to_short_address(&script_hash).to_vec(), Some(script_hash));
+        self.scripts.insert(script_hash, script);
+    }
+    fn get_script(&self, script_hash: &H256) -> Option<Script> {
+        match self.scripts.get(script_hash) {
+            Some(script) => Some(script.clone()),
+            None => self.base.get_script(script_hash),
+        }
+    }
+    fn get_script_hash_by_short_address(&self, short_address: &[u8]) -> Option<H256> {
+        match self.short_addresses.get(short_address) {
+            Some(script_hash) => *script_hash,
+            None => self.base.get_script_hash_by_short_address(short_address),
+        }
+    }
+    fn insert_data(&mut self, data_hash: H256, code: Bytes) {
+        self.data.insert(data_hash, code);
+    }
+    fn get_data(&self, data_hash: &H256) -> Option<Bytes> {
+        match self.data.get(data_hash) {
+            Some(data) => Some(data.clone()),
+            None => self.base.get_data(data_hash),
+        }
+    }
+}
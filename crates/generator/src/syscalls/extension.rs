@@ -0,0 +1,186 @@
+//! Syscall extensions
+//!
+//! Embedders register handlers for syscall numbers outside of the reserved
+//! ranges, the handlers are dispatched by `L2Syscalls` after the builtin
+//! syscalls.
+
+use std::{collections::HashMap, sync::Arc};
+
+use ckb_vm::{memory::Memory, Error as VMError, Register, SupportMachine};
+use gw_common::H256;
+use gw_types::{
+    bytes::Bytes,
+    packed::{BlockInfo, RawL2Transaction},
+};
+
+use crate::error::SyscallExtensionError;
+
+use super::{load_bytes, store_data};
+
+/// Syscalls handled by the VM, like exit
+const VM_RESERVED_SYSCALLS: std::ops::Range<u64> = 0..2000;
+/// CKB syscalls
+const CKB_RESERVED_SYSCALLS: std::ops::Range<u64> = 2000..3000;
+/// Godwoken syscalls
+const GW_RESERVED_SYSCALLS: std::ops::Range<u64> = 3000..4000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateAccess {
+    ReadOnly,
+    ReadWrite,
+}
+
+/// Handler of an extended syscall
+pub trait SyscallExtension: Send + Sync {
+    /// Name shown in execution traces
+    fn name(&self) -> &'static str;
+
+    fn state_access(&self) -> StateAccess {
+        StateAccess::ReadOnly
+    }
+
+    /// Handle the syscall, arguments are in registers `A0` to `A5`. The
+    /// returned code is set to `A0`, return `Err` to fail the VM.
+    ///
+    /// Cycles of the syscall must be charged by `SyscallMachine::add_cycles`.
+    fn handle(
+        &self,
+        machine: &mut dyn SyscallMachine,
+        ctx: &mut ExtensionContext,
+    ) -> Result<u8, VMError>;
+}
+
+/// Machine access of extensions
+pub trait SyscallMachine {
+    fn register(&self, index: usize) -> u64;
+    fn load_bytes(&mut self, addr: u64, len: usize) -> Result<Vec<u8>, VMError>;
+    fn store_bytes(&mut self, addr: u64, data: &[u8]) -> Result<(), VMError>;
+    /// Store data as builtin syscalls: `A0` buffer address, `A1` address of
+    /// the buffer size, `A2` offset of the data. Return the stored size.
+    fn store_data(&mut self, data: &[u8]) -> Result<u64, VMError>;
+    fn cycles(&self) -> u64;
+    /// Charge cycles, fail if exceeded the max cycles
+    fn add_cycles(&mut self, cycles: u64) -> Result<(), VMError>;
+}
+
+pub(crate) struct MachineAccess<'m, Mac>(pub(crate) &'m mut Mac);
+
+impl<'m, Mac: SupportMachine> SyscallMachine for MachineAccess<'m, Mac> {
+    fn register(&self, index: usize) -> u64 {
+        self.0.registers()[index].to_u64()
+    }
+    fn load_bytes(&mut self, addr: u64, len: usize) -> Result<Vec<u8>, VMError> {
+        load_bytes(self.0, addr, len)
+    }
+    fn store_bytes(&mut self, addr: u64, data: &[u8]) -> Result<(), VMError> {
+        self.0.memory_mut().store_bytes(addr, data)
+    }
+    fn store_data(&mut self, data: &[u8]) -> Result<u64, VMError> {
+        store_data(self.0, data)
+    }
+    fn cycles(&self) -> u64 {
+        self.0.cycles()
+    }
+    fn add_cycles(&mut self, cycles: u64) -> Result<(), VMError> {
+        self.0.add_cycles(cycles)
+    }
+}
+
+/// State of the executing transaction, reads and writes are recorded in the
+/// run result like builtin syscalls.
+pub(crate) trait ExtensionState {
+    fn get_raw(&mut self, key: &H256) -> Result<H256, VMError>;
+    fn update_raw(&mut self, key: H256, value: H256);
+    fn get_data(&self, data_hash: &H256) -> Option<Bytes>;
+    fn block_info(&self) -> &BlockInfo;
+    fn raw_tx(&self) -> &RawL2Transaction;
+}
+
+pub struct ExtensionContext<'a> {
+    state: &'a mut dyn ExtensionState,
+    access: StateAccess,
+    read_keys: Vec<H256>,
+    write_keys: Vec<H256>,
+}
+
+impl<'a> ExtensionContext<'a> {
+    pub(crate) fn new(state: &'a mut dyn ExtensionState, access: StateAccess) -> Self {
+        ExtensionContext {
+            state,
+            access,
+            read_keys: Vec::new(),
+            write_keys: Vec::new(),
+        }
+    }
+
+    pub fn get_raw(&mut self, key: &H256) -> Result<H256, VMError> {
+        self.read_keys.push(*key);
+        self.state.get_raw(key)
+    }
+
+    /// Fail if the extension is read-only
+    pub fn update_raw(&mut self, key: H256, value: H256) -> Result<(), VMError> {
+        if self.access != StateAccess::ReadWrite {
+            log::error!("syscall extension error: update state in read-only mode");
+            return Err(VMError::Unexpected);
+        }
+        self.write_keys.push(key);
+        self.state.update_raw(key, value);
+        Ok(())
+    }
+
+    pub fn get_data(&self, data_hash: &H256) -> Option<Bytes> {
+        self.state.get_data(data_hash)
+    }
+
+    pub fn block_info(&self) -> &BlockInfo {
+        self.state.block_info()
+    }
+
+    pub fn raw_tx(&self) -> &RawL2Transaction {
+        self.state.raw_tx()
+    }
+
+    /// Return keys read and written by the extension
+    pub(crate) fn into_accessed_keys(self) -> (Vec<H256>, Vec<H256>) {
+        (self.read_keys, self.write_keys)
+    }
+}
+
+/// Registered syscall extensions
+#[derive(Clone, Default)]
+pub struct SyscallExtensions {
+    extensions: HashMap<u64, Arc<dyn SyscallExtension>>,
+}
+
+impl SyscallExtensions {
+    /// Register an extension, syscall numbers in the VM, CKB and Godwoken
+    /// reserved ranges are rejected.
+    pub fn register(
+        &mut self,
+        code: u64,
+        extension: Arc<dyn SyscallExtension>,
+    ) -> Result<(), SyscallExtensionError> {
+        let is_reserved = [
+            VM_RESERVED_SYSCALLS,
+            CKB_RESERVED_SYSCALLS,
+            GW_RESERVED_SYSCALLS,
+        ]
+        .iter()
+        .any(|reserved| reserved.contains(&code));
+        if is_reserved {
+            return Err(SyscallExtensionError::Reserved { code });
+        }
+        if self.extensions.contains_key(&code) {
+            return Err(SyscallExtensionError::Duplicated { code });
+        }
+        self.extensions.insert(code, extension);
+        Ok(())
+    }
+
+    pub fn get(&self, code: u64) -> Option<&dyn SyscallExtension> {
+        self.extensions
+            .get(&code)
+            .map(|extension| extension.as_ref())
+    }
+}
@@ -1,4 +1,13 @@
-use crate::{account_lock_manage::AccountLockManage, syscalls::error_codes::GW_FATAL_UNKNOWN_ARGS};
+use crate::{
+    account_lock_manage::AccountLockManage,
+    syscalls::{
+        error_codes::GW_FATAL_UNKNOWN_ARGS,
+        extension::{
+            ExtensionContext, ExtensionState, MachineAccess, SyscallExtension, SyscallExtensions,
+        },
+    },
+    trace::{SyscallEvent, TxTrace},
+};
 use ckb_vm::{
     memory::Memory,
     registers::{A0, A1, A2, A3, A4, A5, A7},
@@ -30,6 +39,7 @@ use self::error_codes::{
 };
 
 pub mod error_codes;
+pub mod extension;
 
 /* Constants */
 // 25KB is max ethereum contract code size
@@ -57,6 +67,27 @@ const SYS_RECOVER_ACCOUNT: u64 = 3503;
 /* CKB compatible syscalls */
 const DEBUG_PRINT_SYSCALL_NUMBER: u64 = 2177;
 
+fn syscall_name(code: u64) -> &'static str {
+    match code {
+        SYS_CREATE => "sys_create",
+        SYS_STORE => "sys_store",
+        SYS_LOAD => "sys_load",
+        SYS_LOAD_ACCOUNT_SCRIPT => "sys_load_account_script",
+        SYS_SET_RETURN_DATA => "sys_set_return_data",
+        SYS_STORE_DATA => "sys_store_data",
+        SYS_LOAD_DATA => "sys_load_data",
+        SYS_LOAD_ROLLUP_CONFIG => "sys_load_rollup_config",
+        SYS_LOAD_TRANSACTION => "sys_load_transaction",
+        SYS_LOAD_BLOCKINFO => "sys_load_blockinfo",
+        SYS_GET_BLOCK_HASH => "sys_get_block_hash",
+        SYS_PAY_FEE => "sys_pay_fee",
+        SYS_LOG => "sys_log",
+        SYS_RECOVER_ACCOUNT => "sys_recover_account",
+        DEBUG_PRINT_SYSCALL_NUMBER => "debug_print",
+        _ => "unknown",
+    }
+}
+
 pub(crate) struct L2Syscalls<'a, S, C> {
     pub(crate) chain: &'a C,
     pub(crate) state: &'a S,
@@ -66,6 +97,8 @@ pub(crate) struct L2Syscalls<'a, S, C> {
     pub(crate) raw_tx: &'a RawL2Transaction,
     pub(crate) code_store: &'a dyn CodeStore,
     pub(crate) result: &'a mut RunResult,
+    pub(crate) trace: Option<&'a mut TxTrace>,
+    pub(crate) extensions: &'a SyscallExtensions,
 }
 
 fn load_data_u128<Mac: SupportMachine>(machine: &mut Mac, addr: u64) -> Result<u128, VMError> {
@@ -131,12 +164,49 @@ impl<'a, S: State, C: ChainStore, Mac: SupportMachine> Syscalls<Mac> for L2Sysca
 
     fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
         let code = machine.registers()[A7].to_u64();
+        if self.trace.is_none() {
+            return self.handle_ecall(code, machine);
+        }
+        if let Some(trace) = self.trace.as_deref_mut() {
+            let name = match self.extensions.get(code) {
+                Some(extension) => extension.name(),
+                None => syscall_name(code),
+            };
+            trace.enter(code, name, machine.cycles());
+        }
+
+        let handled = self.handle_ecall(code, machine)?;
+        if let Some(trace) = self.trace.as_deref_mut() {
+            if handled {
+                trace.exit(machine.registers()[A0].to_i8());
+            } else {
+                trace.discard();
+            }
+        }
+        Ok(handled)
+    }
+}
+
+impl<'a, S: State, C: ChainStore> L2Syscalls<'a, S, C> {
+    fn handle_ecall<Mac: SupportMachine>(
+        &mut self,
+        code: u64,
+        machine: &mut Mac,
+    ) -> Result<bool, VMError> {
         match code {
             SYS_STORE => {
                 let key_addr = machine.registers()[A0].to_u64();
                 let key = load_data_h256(machine, key_addr)?;
                 let value_addr = machine.registers()[A1].to_u64();
                 let value = load_data_h256(machine, value_addr)?;
+                if self.trace.is_some() {
+                    let old_value = self.peek_raw(&key)?;
+                    self.trace_event(|| SyscallEvent::Store {
+                        key,
+                        old_value,
+                        new_value: value,
+                    });
+                }
                 self.result.write_values.insert(key, value);
                 machine.set_register(A0, Mac::REG::from_u8(SUCCESS));
                 Ok(true)
@@ -146,6 +216,7 @@ impl<'a, S: State, C: ChainStore, Mac: SupportMachine> Syscalls<Mac> for L2Sysca
                 let key = load_data_h256(machine, key_addr)?;
                 let value_addr = machine.registers()[A1].to_u64();
                 let value = self.get_raw(&key)?;
+                self.trace_event(|| SyscallEvent::Load { key, value });
                 machine
                     .memory_mut()
                     .store_bytes(value_addr, value.as_slice())?;
@@ -179,6 +250,10 @@ impl<'a, S: State, C: ChainStore, Mac: SupportMachine> Syscalls<Mac> for L2Sysca
                     }
                 };
                 let script_hash = script.hash();
+                self.trace_event(|| SyscallEvent::Create {
+                    script_hash: Some(script_hash.into()),
+                    account_id: None,
+                });
 
                 // Return error if script_hash is exists
                 if self
@@ -273,6 +348,10 @@ impl<'a, S: State, C: ChainStore, Mac: SupportMachine> Syscalls<Mac> for L2Sysca
                     .new_scripts
                     .insert(script_hash.into(), script.as_slice().to_vec());
                 self.set_account_count(id + 1);
+                self.trace_event(|| SyscallEvent::Create {
+                    script_hash: Some(script_hash.into()),
+                    account_id: Some(id),
+                });
                 machine
                     .memory_mut()
                     .store32(&account_id_addr, &Mac::REG::from_u32(id))?;
@@ -339,10 +418,18 @@ impl<'a, S: State, C: ChainStore, Mac: SupportMachine> Syscalls<Mac> for L2Sysca
                 let data = match self.get_data(&data_hash) {
                     Some(data) => data,
                     None => {
+                        self.trace_event(|| SyscallEvent::LoadData {
+                            data_hash,
+                            data_len: None,
+                        });
                         machine.set_register(A0, Mac::REG::from_i8(GW_ERROR_NOT_FOUND));
                         return Ok(true);
                     }
                 };
+                self.trace_event(|| SyscallEvent::LoadData {
+                    data_hash,
+                    data_len: Some(data.len()),
+                });
                 store_data(machine, data.as_ref())?;
                 self.result.read_data.insert(data_hash, data.to_vec());
                 machine.set_register(A0, Mac::REG::from_u8(SUCCESS));
@@ -429,6 +516,11 @@ impl<'a, S: State, C: ChainStore, Mac: SupportMachine> Syscalls<Mac> for L2Sysca
                 let data_addr = machine.registers()[A3].to_u64();
 
                 let data = load_bytes(machine, data_addr, data_len as usize)?;
+                self.trace_event(|| SyscallEvent::Log {
+                    account_id,
+                    service_flag,
+                    data: data.clone(),
+                });
                 self.result.logs.push(
                     LogItem::new_builder()
                         .account_id(account_id.pack())
@@ -475,19 +567,62 @@ impl<'a, S: State, C: ChainStore, Mac: SupportMachine> Syscalls<Mac> for L2Sysca
                     sudt_id,
                     amount
                 );
+                self.trace_event(|| SyscallEvent::PayFee {
+                    short_address,
+                    sudt_id: sudt_id.into(),
+                    amount,
+                });
                 machine.set_register(A0, Mac::REG::from_u8(SUCCESS));
                 Ok(true)
             }
             DEBUG_PRINT_SYSCALL_NUMBER => {
-                self.output_debug(machine)?;
+                let message = self.output_debug(machine)?;
+                self.trace_event(|| SyscallEvent::DebugPrint { message });
                 Ok(true)
             }
-            _ => Ok(false),
+            code => {
+                let extensions = self.extensions;
+                match extensions.get(code) {
+                    Some(extension) => self.handle_extension(extension, machine),
+                    None => Ok(false),
+                }
+            }
+        }
+    }
+
+    fn handle_extension<Mac: SupportMachine>(
+        &mut self,
+        extension: &dyn SyscallExtension,
+        machine: &mut Mac,
+    ) -> Result<bool, VMError> {
+        let start_cycles = machine.cycles();
+        let mut ctx = ExtensionContext::new(self, extension.state_access());
+        let ret = extension.handle(&mut MachineAccess(machine), &mut ctx)?;
+        let (read_keys, write_keys) = ctx.into_accessed_keys();
+        machine.set_register(A0, Mac::REG::from_u8(ret));
+        let charged_cycles = machine.cycles().saturating_sub(start_cycles);
+        self.trace_event(|| SyscallEvent::Extension {
+            charged_cycles,
+            read_keys,
+            write_keys,
+        });
+        Ok(true)
+    }
+
+    fn trace_event<F: FnOnce() -> SyscallEvent>(&mut self, f: F) {
+        if let Some(trace) = self.trace.as_deref_mut() {
+            trace.set_event(f());
+        }
+    }
+
+    /// Get a value without recording it in read values
+    fn peek_raw(&self, key: &H256) -> Result<H256, VMError> {
+        match self.result.write_values.get(key) {
+            Some(value) => Ok(*value),
+            None => self.state.get_raw(key).map_err(|_| VMError::Unexpected),
         }
     }
-}
 
-impl<'a, S: State, C: ChainStore> L2Syscalls<'a, S, C> {
     fn get_raw(&mut self, key: &H256) -> Result<H256, VMError> {
         let value = match self.result.write_values.get(key) {
             Some(value) => *value,
@@ -559,7 +694,7 @@ impl<'a, S: State, C: ChainStore> L2Syscalls<'a, S, C> {
         Ok(Some(id))
     }
 
-    fn output_debug<Mac: SupportMachine>(&self, machine: &mut Mac) -> Result<(), VMError> {
+    fn output_debug<Mac: SupportMachine>(&self, machine: &mut Mac) -> Result<String, VMError> {
         let mut addr = machine.registers()[A0].to_u64();
         let mut buffer = Vec::new();
 
@@ -577,6 +712,24 @@ impl<'a, S: State, C: ChainStore> L2Syscalls<'a, S, C> {
 
         let s = String::from_utf8(buffer).map_err(|_| VMError::ParseError)?;
         log::debug!("[contract debug]: {}", s);
-        Ok(())
+        Ok(s)
+    }
+}
+
+impl<'a, S: State, C: ChainStore> ExtensionState for L2Syscalls<'a, S, C> {
+    fn get_raw(&mut self, key: &H256) -> Result<H256, VMError> {
+        L2Syscalls::get_raw(self, key)
+    }
+    fn update_raw(&mut self, key: H256, value: H256) {
+        self.result.write_values.insert(key, value);
+    }
+    fn get_data(&self, data_hash: &H256) -> Option<Bytes> {
+        L2Syscalls::get_data(self, data_hash)
+    }
+    fn block_info(&self) -> &BlockInfo {
+        self.block_info
+    }
+    fn raw_tx(&self) -> &RawL2Transaction {
+        self.raw_tx
     }
 }
@@ -0,0 +1,121 @@
+use crate::backend_manage::{Backend, BackendManage};
+use gw_common::H256;
+use gw_config::{BackendActivation, BackendType};
+use gw_types::{bytes::Bytes, packed::BlockInfo, prelude::*};
+
+const POLYJUICE_TYPE_HASH: [u8; 32] = [1u8; 32];
+const SUDT_TYPE_HASH: [u8; 32] = [2u8; 32];
+
+fn build_backend(
+    validator_script_type_hash: [u8; 32],
+    tag: u8,
+    activation: Option<BackendActivation>,
+) -> Backend {
+    Backend {
+        backend_type: BackendType::Polyjuice,
+        validator: Bytes::default(),
+        generator: Bytes::from(vec![tag]),
+        generator_code_hash: [tag; 32].into(),
+        validator_script_type_hash: validator_script_type_hash.into(),
+        activation,
+    }
+}
+
+fn new_block_info(number: u64, timestamp: u64) -> BlockInfo {
+    BlockInfo::new_builder()
+        .number(number.pack())
+        .timestamp(timestamp.pack())
+        .build()
+}
+
+#[test]
+fn test_register_backend() {
+    let mut backend_manage = BackendManage::from_config(vec![]).unwrap();
+    backend_manage
+        .register_backend(build_backend(POLYJUICE_TYPE_HASH, 0, None))
+        .unwrap();
+    backend_manage
+        .register_backend(build_backend(
+            POLYJUICE_TYPE_HASH,
+            1,
+            Some(BackendActivation::BlockNumber(100)),
+        ))
+        .unwrap();
+
+    // upgrades must be activated after the previous one
+    for activation in [
+        None,
+        Some(BackendActivation::BlockNumber(100)),
+        Some(BackendActivation::BlockNumber(50)),
+    ] {
+        assert!(backend_manage
+            .register_backend(build_backend(POLYJUICE_TYPE_HASH, 2, activation))
+            .is_err());
+    }
+    // block number and timestamp activations can't be mixed
+    assert!(backend_manage
+        .register_backend(build_backend(
+            POLYJUICE_TYPE_HASH,
+            2,
+            Some(BackendActivation::Timestamp(u64::MAX)),
+        ))
+        .is_err());
+
+    // backends of other validators are independent
+    backend_manage
+        .register_backend(build_backend(
+            SUDT_TYPE_HASH,
+            3,
+            Some(BackendActivation::Timestamp(1000)),
+        ))
+        .unwrap();
+    let latest = backend_manage
+        .get_backend(&POLYJUICE_TYPE_HASH.into())
+        .unwrap();
+    assert_eq!(latest.generator_code_hash, H256::from([1u8; 32]));
+}
+
+#[test]
+fn test_get_block_backend() {
+    let mut backend_manage = BackendManage::from_config(vec![]).unwrap();
+    for (tag, activation) in [
+        (0, None),
+        (1, Some(BackendActivation::BlockNumber(100))),
+        (2, Some(BackendActivation::BlockNumber(200))),
+    ] {
+        backend_manage
+            .register_backend(build_backend(POLYJUICE_TYPE_HASH, tag, activation))
+            .unwrap();
+    }
+    backend_manage
+        .register_backend(build_backend(
+            SUDT_TYPE_HASH,
+            3,
+            Some(BackendActivation::Timestamp(1000)),
+        ))
+        .unwrap();
+
+    let get_generator_tag = |code_hash: [u8; 32], number: u64, timestamp: u64| {
+        backend_manage
+            .get_block_backend(&code_hash.into(), &new_block_info(number, timestamp))
+            .map(|backend| backend.generator[0])
+    };
+    assert_eq!(get_generator_tag(POLYJUICE_TYPE_HASH, 0, 0), Some(0));
+    assert_eq!(get_generator_tag(POLYJUICE_TYPE_HASH, 99, 0), Some(0));
+    assert_eq!(get_generator_tag(POLYJUICE_TYPE_HASH, 100, 0), Some(1));
+    assert_eq!(get_generator_tag(POLYJUICE_TYPE_HASH, 199, 0), Some(1));
+    assert_eq!(get_generator_tag(POLYJUICE_TYPE_HASH, 200, 0), Some(2));
+    // no backend is active before the first activation
+    assert_eq!(get_generator_tag(SUDT_TYPE_HASH, 0, 999), None);
+    assert_eq!(get_generator_tag(SUDT_TYPE_HASH, 0, 1000), Some(3));
+    assert_eq!(get_generator_tag([0u8; 32], 0, 0), None);
+
+    // backends reported at a block
+    let block_info = new_block_info(150, 0);
+    let mut tags: Vec<u8> = backend_manage
+        .get_block_backends(&block_info)
+        .map(|backend| backend.generator[0])
+        .collect();
+    tags.sort_unstable();
+    assert_eq!(tags, vec![1]);
+}
@@ -31,7 +31,7 @@ fn test_init_genesis() {
     let genesis_block_hash: [u8; 32] = genesis.genesis.hash();
     assert_eq!(genesis_block_hash, GENESIS_BLOCK_HASH);
     let genesis_committed_info = L2BlockCommittedInfo::default();
-    let store: Store = Store::open_tmp().unwrap();
+    let store: Store = Store::open_memory();
     init_genesis(&store, &config, genesis_committed_info, Bytes::default()).unwrap();
     let db = store.begin_transaction();
     // check init values
@@ -1 +1,8 @@
+mod backend_manage;
 mod genesis;
+mod profile;
+mod program_cache;
+mod state_override;
+mod syscall_extension;
+mod trace;
+mod vm_version;
@@ -0,0 +1,105 @@
+use crate::profile::{CycleProfile, Profiler, Symbols};
+
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+/// Build an ELF64 only with a `.symtab` and its `.strtab`
+fn build_elf(symbols: &[(&str, u8, u64, u64)]) -> Vec<u8> {
+    let mut strtab = vec![0u8];
+    let mut symtab = vec![0u8; 24];
+    for (name, kind, value, size) in symbols {
+        symtab.extend_from_slice(&(strtab.len() as u32).to_le_bytes());
+        symtab.push(*kind);
+        symtab.push(0);
+        symtab.extend_from_slice(&1u16.to_le_bytes());
+        symtab.extend_from_slice(&value.to_le_bytes());
+        symtab.extend_from_slice(&size.to_le_bytes());
+        strtab.extend_from_slice(name.as_bytes());
+        strtab.push(0);
+    }
+
+    let strtab_offset = 64u64;
+    let symtab_offset = strtab_offset + strtab.len() as u64;
+    let section_headers = symtab_offset + symtab.len() as u64;
+    let mut elf = vec![0u8; 64];
+    elf[0..6].copy_from_slice(b"\x7fELF\x02\x01");
+    elf[0x28..0x30].copy_from_slice(&section_headers.to_le_bytes());
+    elf[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+    elf[0x3c..0x3e].copy_from_slice(&3u16.to_le_bytes());
+    elf.extend_from_slice(&strtab);
+    elf.extend_from_slice(&symtab);
+
+    let section_header = |sh_type: u32, offset: u64, size: u64, link: u32| {
+        let mut header = vec![0u8; 64];
+        header[4..8].copy_from_slice(&sh_type.to_le_bytes());
+        header[0x18..0x20].copy_from_slice(&offset.to_le_bytes());
+        header[0x20..0x28].copy_from_slice(&size.to_le_bytes());
+        header[0x28..0x2c].copy_from_slice(&link.to_le_bytes());
+        header
+    };
+    elf.extend(section_header(0, 0, 0, 0));
+    elf.extend(section_header(2, symtab_offset, symtab.len() as u64, 2));
+    elf.extend(section_header(3, strtab_offset, strtab.len() as u64, 0));
+    elf
+}
+
+fn main_and_foo_elf() -> Vec<u8> {
+    build_elf(&[
+        ("foo", STT_FUNC, 0x200, 0x50),
+        ("main", STT_FUNC, 0x100, 0x100),
+        ("data", STT_OBJECT, 0x400, 0x10),
+        ("undefined", STT_FUNC, 0, 0),
+        ("main_alias", STT_FUNC, 0x100, 0x100),
+    ])
+}
+
+#[test]
+fn test_parse_symbols() {
+    let elf = main_and_foo_elf();
+    // objects, undefined and duplicated functions are skipped
+    assert_eq!(Symbols::parse(&elf).len(), 2);
+
+    // stripped, truncated or malformed ELFs have no symbols
+    let stripped = build_elf(&[]);
+    assert!(Symbols::parse(&stripped).is_empty());
+    assert!(Symbols::parse(&elf[..elf.len() - 128]).is_empty());
+    assert!(Symbols::parse(&elf[..100]).is_empty());
+    assert!(Symbols::parse(&elf[..32]).is_empty());
+    let mut elf32 = elf.clone();
+    elf32[4] = 1;
+    assert!(Symbols::parse(&elf32).is_empty());
+    let mut bad_offset = elf;
+    bad_offset[0x28..0x30].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(Symbols::parse(&bad_offset).is_empty());
+    assert!(Symbols::parse(&[]).is_empty());
+}
+
+#[test]
+fn test_profiler_step() {
+    let symbols = Symbols::parse(&main_and_foo_elf());
+    let mut profiler = Profiler::new(&symbols);
+    // enter main
+    profiler.step(0x100, 1);
+    profiler.step(0x104, 1);
+    // call foo
+    profiler.step(0x200, 2);
+    profiler.step(0x204, 2);
+    // return to main
+    profiler.step(0x108, 1);
+    // jump out of known functions
+    profiler.step(0x300, 5);
+    let profile = profiler.into_profile();
+
+    let stack = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+    assert_eq!(
+        profile,
+        CycleProfile {
+            stacks: vec![
+                (stack(&["main"]), 3),
+                (stack(&["main", "foo"]), 4),
+                (stack(&["[unknown]"]), 5),
+            ]
+        }
+    );
+    assert_eq!(profile.to_folded(), "main 3\nmain;foo 4\n[unknown] 5\n");
+}
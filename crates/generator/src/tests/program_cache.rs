@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use crate::{program_cache::ProgramCache, VMVersion};
+use gw_common::H256;
+use gw_types::bytes::Bytes;
+
+#[test]
+fn test_program_cache() {
+    let cache = ProgramCache::default();
+    assert!(cache.is_empty());
+
+    // not a valid ELF, the program falls back to the interpreter
+    let program = Bytes::from_static(b"invalid program");
+    let code_hash: H256 = [1u8; 32].into();
+    let compiled = cache.get_or_compile(&code_hash, &program, VMVersion::V0);
+    #[cfg(has_aot)]
+    assert!(compiled.aot_code().is_none());
+
+    // hit, the failed compilation is cached as well
+    let hit = cache.get_or_compile(&code_hash, &program, VMVersion::V0);
+    assert!(Arc::ptr_eq(&compiled, &hit));
+    assert_eq!(cache.len(), 1);
+
+    // programs are keyed by code hash and VM version
+    let v1 = cache.get_or_compile(&code_hash, &program, VMVersion::V1);
+    assert!(!Arc::ptr_eq(&compiled, &v1));
+    let other = cache.get_or_compile(&[2u8; 32].into(), &program, VMVersion::V0);
+    assert!(!Arc::ptr_eq(&compiled, &other));
+    assert_eq!(cache.len(), 3);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    let recompiled = cache.get_or_compile(&code_hash, &program, VMVersion::V0);
+    assert!(!Arc::ptr_eq(&compiled, &recompiled));
+}
@@ -0,0 +1,145 @@
+use crate::{dummy_state::DummyState, state_override::OverlayState};
+use gw_common::{
+    h256_ext::H256Ext,
+    sparse_merkle_tree::H256,
+    state::{
+        build_short_script_hash_to_script_hash_key, to_short_address, State,
+        DEFAULT_SHORT_SCRIPT_HASH_LEN,
+    },
+};
+use gw_traits::CodeStore;
+use gw_types::{
+    bytes::Bytes,
+    offchain::{AccountOverride, StateOverride},
+    packed::Script,
+    prelude::*,
+};
+
+#[test]
+fn test_state_override() {
+    let mut state = DummyState::default();
+    let script = Script::new_builder().args(vec![1u8].pack()).build();
+    let script_hash: H256 = script.hash().into();
+    state.insert_script(script_hash, script);
+    let id = state.create_account(script_hash).unwrap();
+    let short_address = to_short_address(&script_hash).to_vec();
+    state.mint_sudt(1, &short_address, 100).unwrap();
+
+    let new_script = Script::new_builder().args(vec![2u8].pack()).build();
+    let new_script_hash: H256 = new_script.hash().into();
+    let data = Bytes::from(vec![42u8; 8]);
+    let state_override = StateOverride {
+        accounts: vec![
+            AccountOverride {
+                id,
+                nonce: Some(7),
+                balances: vec![(1, 1000)],
+                storage: vec![(H256::one(), H256::from_u32(42))],
+                ..Default::default()
+            },
+            AccountOverride {
+                id: id + 1,
+                script: Some(new_script),
+                ..Default::default()
+            },
+        ],
+        data: vec![data.clone()],
+    };
+    let mut overlay = OverlayState::new(&state);
+    overlay.apply_state_override(&state_override).unwrap();
+
+    assert_eq!(overlay.get_nonce(id).unwrap(), 7);
+    assert_eq!(overlay.get_sudt_balance(1, &short_address).unwrap(), 1000);
+    assert_eq!(
+        overlay.get_value(id, &H256::one()).unwrap(),
+        H256::from_u32(42)
+    );
+    assert_eq!(overlay.get_account_count().unwrap(), id + 2);
+    assert_eq!(
+        overlay
+            .get_account_id_by_script_hash(&new_script_hash)
+            .unwrap(),
+        Some(id + 1)
+    );
+    assert_eq!(
+        overlay.get_script_hash_by_short_address(to_short_address(&new_script_hash)),
+        Some(new_script_hash)
+    );
+    assert_eq!(overlay.get_data(&blake2b(&data)), Some(data.clone()));
+    assert!(overlay.is_data_hash_exist(&blake2b(&data)).unwrap());
+
+    // the base state is untouched
+    assert_eq!(state.get_nonce(id).unwrap(), 0);
+    assert_eq!(state.get_sudt_balance(1, &short_address).unwrap(), 100);
+    assert_eq!(state.get_account_count().unwrap(), id + 1);
+    assert!(state.get_data(&blake2b(&data)).is_none());
+
+    // accounts must be created in order
+    let state_override = StateOverride {
+        accounts: vec![AccountOverride {
+            id: id + 2,
+            nonce: Some(1),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let mut overlay = OverlayState::new(&state);
+    assert!(overlay.apply_state_override(&state_override).is_err());
+
+    // replacing the script of an account removes indexes of the old script
+    let replaced_script = Script::new_builder().args(vec![3u8].pack()).build();
+    let replaced_script_hash: H256 = replaced_script.hash().into();
+    let state_override = StateOverride {
+        accounts: vec![AccountOverride {
+            id,
+            script: Some(replaced_script),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let mut overlay = OverlayState::new(&state);
+    overlay.apply_state_override(&state_override).unwrap();
+    assert_eq!(overlay.get_script_hash(id).unwrap(), replaced_script_hash);
+    assert_eq!(
+        overlay
+            .get_account_id_by_script_hash(&replaced_script_hash)
+            .unwrap(),
+        Some(id)
+    );
+    assert_eq!(
+        overlay.get_script_hash_by_short_address(to_short_address(&replaced_script_hash)),
+        Some(replaced_script_hash)
+    );
+    assert_eq!(
+        overlay.get_account_id_by_script_hash(&script_hash).unwrap(),
+        None
+    );
+    assert_eq!(
+        overlay.get_script_hash_by_short_address(&short_address),
+        None
+    );
+    assert_eq!(
+        overlay
+            .get_raw(&build_short_script_hash_to_script_hash_key(
+                &script_hash.as_slice()[..DEFAULT_SHORT_SCRIPT_HASH_LEN]
+            ))
+            .unwrap(),
+        H256::zero()
+    );
+    assert_eq!(
+        state.get_account_id_by_script_hash(&script_hash).unwrap(),
+        Some(id)
+    );
+    assert_eq!(
+        state.get_script_hash_by_short_address(&short_address),
+        Some(script_hash)
+    );
+}
+
+fn blake2b(data: &[u8]) -> H256 {
+    let mut hasher = gw_common::blake2b::new_blake2b();
+    hasher.update(data);
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    hash.into()
+}
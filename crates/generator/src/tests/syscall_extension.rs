@@ -0,0 +1,276 @@
+use std::sync::Arc;
+
+use crate::{
+    account_lock_manage::AccountLockManage,
+    backend_manage::{Backend, BackendManage},
+    dummy_state::DummyState,
+    error::{SyscallExtensionError, TransactionError},
+    syscalls::extension::{
+        ExtensionContext, StateAccess, SyscallExtension, SyscallExtensions, SyscallMachine,
+    },
+    trace::SyscallEvent,
+    traits::StateExt,
+    Generator,
+};
+use ckb_vm::Error as VMError;
+use gw_common::{
+    blake2b::new_blake2b,
+    h256_ext::H256Ext,
+    state::{build_account_field_key, State, GW_ACCOUNT_NONCE_TYPE},
+    H256,
+};
+use gw_config::BackendType;
+use gw_db::error::Error as DBError;
+use gw_traits::ChainStore;
+use gw_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    offchain::RollupContext,
+    packed::{BlockInfo, RawL2Transaction, RollupConfig, Script},
+    prelude::*,
+};
+
+const EXTENSION_CODE: u64 = 4001;
+const EXTENSION_CYCLES: u64 = 100_000;
+const BACKEND_TYPE_HASH: [u8; 32] = [42u8; 32];
+const MAX_CYCLES: u64 = 7000_0000;
+
+struct Noop;
+
+impl SyscallExtension for Noop {
+    fn name(&self) -> &'static str {
+        "noop"
+    }
+
+    fn handle(
+        &self,
+        _machine: &mut dyn SyscallMachine,
+        _ctx: &mut ExtensionContext,
+    ) -> Result<u8, VMError> {
+        Ok(0)
+    }
+}
+
+#[test]
+fn test_register_syscall_extension() {
+    let mut extensions = SyscallExtensions::default();
+    // exit of the VM and builtin syscalls
+    for code in [0, 93, 1999, 2177, 3101, 3503].iter() {
+        assert_eq!(
+            extensions.register(*code, Arc::new(Noop)),
+            Err(SyscallExtensionError::Reserved { code: *code })
+        );
+    }
+
+    extensions.register(4001, Arc::new(Noop)).unwrap();
+    assert_eq!(
+        extensions.register(4001, Arc::new(Noop)),
+        Err(SyscallExtensionError::Duplicated { code: 4001 })
+    );
+    assert_eq!(extensions.get(4001).map(|ext| ext.name()), Some("noop"));
+    assert!(extensions.get(4002).is_none());
+}
+
+/// Increase the nonce of the tx sender
+struct IncreaseNonce {
+    access: StateAccess,
+}
+
+impl SyscallExtension for IncreaseNonce {
+    fn name(&self) -> &'static str {
+        "increase_nonce"
+    }
+
+    fn state_access(&self) -> StateAccess {
+        self.access
+    }
+
+    fn handle(
+        &self,
+        machine: &mut dyn SyscallMachine,
+        ctx: &mut ExtensionContext,
+    ) -> Result<u8, VMError> {
+        machine.add_cycles(EXTENSION_CYCLES)?;
+        let from_id: u32 = ctx.raw_tx().from_id().unpack();
+        let nonce_key = build_account_field_key(from_id, GW_ACCOUNT_NONCE_TYPE);
+        let nonce = ctx.get_raw(&nonce_key)?.to_u32();
+        ctx.update_raw(nonce_key, H256::from_u32(nonce + 1))?;
+        Ok(0)
+    }
+}
+
+struct DummyChainStore;
+
+impl ChainStore for DummyChainStore {
+    fn get_block_hash_by_number(&self, _number: u64) -> Result<Option<H256>, DBError> {
+        Ok(None)
+    }
+}
+
+/// Build a RISC-V ELF64 loading `code` as a single segment
+fn build_program(code: &[u32]) -> Bytes {
+    const BASE_ADDR: u64 = 0x10000;
+    const HEADERS_SIZE: u64 = 64 + 56;
+    let file_size = HEADERS_SIZE + code.len() as u64 * 4;
+
+    let mut elf = vec![0u8; HEADERS_SIZE as usize];
+    // ELF64, little endian, ELF version 1
+    elf[0..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+    // executable, RISC-V, version 1
+    elf[0x10..0x12].copy_from_slice(&2u16.to_le_bytes());
+    elf[0x12..0x14].copy_from_slice(&243u16.to_le_bytes());
+    elf[0x14..0x18].copy_from_slice(&1u32.to_le_bytes());
+    elf[0x18..0x20].copy_from_slice(&(BASE_ADDR + HEADERS_SIZE).to_le_bytes());
+    // program headers follow the ELF header
+    elf[0x20..0x28].copy_from_slice(&64u64.to_le_bytes());
+    elf[0x34..0x36].copy_from_slice(&64u16.to_le_bytes());
+    elf[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+    elf[0x38..0x3a].copy_from_slice(&1u16.to_le_bytes());
+    elf[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+
+    // PT_LOAD, readable and executable, the whole file is loaded
+    let program_header = &mut elf[64..];
+    program_header[0..4].copy_from_slice(&1u32.to_le_bytes());
+    program_header[4..8].copy_from_slice(&5u32.to_le_bytes());
+    program_header[0x10..0x18].copy_from_slice(&BASE_ADDR.to_le_bytes());
+    program_header[0x18..0x20].copy_from_slice(&BASE_ADDR.to_le_bytes());
+    program_header[0x20..0x28].copy_from_slice(&file_size.to_le_bytes());
+    program_header[0x28..0x30].copy_from_slice(&file_size.to_le_bytes());
+    program_header[0x30..0x38].copy_from_slice(&0x1000u64.to_le_bytes());
+
+    for instruction in code {
+        elf.extend_from_slice(&instruction.to_le_bytes());
+    }
+    elf.into()
+}
+
+fn build_generator(extension: IncreaseNonce) -> Generator {
+    // call the extension and exit with its return code
+    let program = build_program(&[
+        0x0000_18b7, // lui a7, 1
+        0xfa18_889b, // addiw a7, a7, -95
+        0x0000_0073, // ecall
+        0x05d0_0893, // li a7, 93
+        0x0000_0073, // ecall
+    ]);
+    let generator_code_hash = {
+        let mut hash = [0u8; 32];
+        let mut hasher = new_blake2b();
+        hasher.update(&program);
+        hasher.finalize(&mut hash);
+        hash.into()
+    };
+    let mut backend_manage = BackendManage::from_config(Vec::new()).unwrap();
+    backend_manage
+        .register_backend(Backend {
+            backend_type: BackendType::Unknown,
+            validator: Bytes::default(),
+            generator: program,
+            generator_code_hash,
+            validator_script_type_hash: BACKEND_TYPE_HASH.into(),
+            activation: None,
+        })
+        .unwrap();
+    let rollup_context = RollupContext {
+        rollup_config: RollupConfig::default(),
+        rollup_script_hash: H256::zero(),
+    };
+    let mut generator = Generator::new(
+        backend_manage,
+        AccountLockManage::default(),
+        rollup_context,
+        Default::default(),
+        MAX_CYCLES,
+        Default::default(),
+        Default::default(),
+    );
+    generator
+        .register_syscall_extension(EXTENSION_CODE, Arc::new(extension))
+        .unwrap();
+    generator
+}
+
+/// Create a sender and a contract account of the test backend
+fn build_tx(state: &mut DummyState) -> RawL2Transaction {
+    let sender_id = state
+        .create_account_from_script(
+            Script::new_builder()
+                .code_hash([1u8; 32].pack())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+        )
+        .unwrap();
+    let contract_id = state
+        .create_account_from_script(
+            Script::new_builder()
+                .code_hash(BACKEND_TYPE_HASH.pack())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+        )
+        .unwrap();
+    RawL2Transaction::new_builder()
+        .from_id(sender_id.pack())
+        .to_id(contract_id.pack())
+        .build()
+}
+
+#[test]
+fn test_execute_syscall_extension() {
+    let mut state = DummyState::default();
+    let raw_tx = build_tx(&mut state);
+    let sender_id: u32 = raw_tx.from_id().unpack();
+    let nonce_key = build_account_field_key(sender_id, GW_ACCOUNT_NONCE_TYPE);
+    let block_info = BlockInfo::default();
+
+    let generator = build_generator(IncreaseNonce {
+        access: StateAccess::ReadWrite,
+    });
+    let (run_result, trace) = generator
+        .trace_transaction(&DummyChainStore, &state, &block_info, &raw_tx, MAX_CYCLES)
+        .unwrap();
+    assert_eq!(run_result.exit_code, 0);
+    // writes of the extension are in the run result
+    assert_eq!(
+        run_result.write_values.get(&nonce_key),
+        Some(&H256::from_u32(1))
+    );
+    assert!(run_result.read_values.contains_key(&nonce_key));
+    assert!(run_result.used_cycles > EXTENSION_CYCLES);
+    let extension_trace = trace
+        .syscalls
+        .iter()
+        .find(|syscall| syscall.code == EXTENSION_CODE)
+        .expect("extension trace");
+    assert_eq!(extension_trace.name, "increase_nonce");
+    assert_eq!(extension_trace.ret, Some(0));
+    assert_eq!(
+        extension_trace.event,
+        SyscallEvent::Extension {
+            charged_cycles: EXTENSION_CYCLES,
+            read_keys: vec![nonce_key],
+            write_keys: vec![nonce_key],
+        }
+    );
+
+    // charged cycles count against the max cycles
+    let err = generator
+        .unchecked_execute_transaction(
+            &DummyChainStore,
+            &state,
+            &block_info,
+            &raw_tx,
+            run_result.used_cycles - 1,
+        )
+        .unwrap_err();
+    assert!(matches!(err, TransactionError::VM(_)), "{:?}", err);
+
+    // read-only extensions can't update state
+    let generator = build_generator(IncreaseNonce {
+        access: StateAccess::ReadOnly,
+    });
+    let err = generator
+        .unchecked_execute_transaction(&DummyChainStore, &state, &block_info, &raw_tx, MAX_CYCLES)
+        .unwrap_err();
+    assert_eq!(err, TransactionError::VM(VMError::Unexpected));
+    assert_eq!(state.get_nonce(sender_id).unwrap(), 0);
+}
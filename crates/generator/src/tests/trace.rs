@@ -0,0 +1,40 @@
+use crate::trace::{SyscallEvent, SyscallTrace, TxTrace};
+
+#[test]
+fn test_tx_trace() {
+    let mut trace = TxTrace::default();
+    trace.enter(2177, "debug_print", 100);
+    trace.set_event(SyscallEvent::DebugPrint {
+        message: "hello".to_string(),
+    });
+    trace.exit(0);
+    // failed syscall has no return code
+    trace.enter(3101, "sys_store", 250);
+    // unknown syscall is discarded
+    trace.enter(9999, "unknown", 300);
+    trace.discard();
+
+    assert_eq!(
+        trace.syscalls,
+        vec![
+            SyscallTrace {
+                code: 2177,
+                name: "debug_print",
+                cycles: 100,
+                total_cycles: 100,
+                ret: Some(0),
+                event: SyscallEvent::DebugPrint {
+                    message: "hello".to_string()
+                },
+            },
+            SyscallTrace {
+                code: 3101,
+                name: "sys_store",
+                cycles: 150,
+                total_cycles: 250,
+                ret: None,
+                event: SyscallEvent::Other,
+            },
+        ]
+    );
+}
@@ -0,0 +1,70 @@
+use crate::{VMVersion, VMVersionSchedule};
+use gw_config::VMVersionSwitch;
+
+#[test]
+fn test_vm_version_schedule() {
+    let schedule = VMVersionSchedule::default();
+    assert_eq!(schedule.vm_version(0), VMVersion::V0);
+    assert_eq!(schedule.vm_version(u64::MAX), VMVersion::V0);
+    assert!(!schedule.activate(u64::MAX, 0));
+
+    // switches apply from the first block submitted in their epochs
+    let schedule =
+        VMVersionSchedule::new(vec![(100, VMVersion::V0), (200, VMVersion::V1)]).unwrap();
+    assert_eq!(schedule.vm_version(u64::MAX), VMVersion::V0);
+    assert!(!schedule.activate(99, 10));
+    assert!(schedule.activate(150, 20));
+    assert!(!schedule.activate(199, 30));
+    assert_eq!(schedule.vm_version(u64::MAX), VMVersion::V0);
+    assert!(schedule.activate(250, 40));
+    assert_eq!(schedule.activations(), vec![20, 40]);
+    assert_eq!(schedule.vm_version(0), VMVersion::V0);
+    assert_eq!(schedule.vm_version(39), VMVersion::V0);
+    assert_eq!(schedule.vm_version(40), VMVersion::V1);
+
+    // activated switches are never moved
+    assert!(!schedule.activate(300, 10));
+    assert_eq!(schedule.activations(), vec![20, 40]);
+
+    let restored =
+        VMVersionSchedule::new(vec![(100, VMVersion::V0), (200, VMVersion::V1)]).unwrap();
+    restored.restore_activations(vec![20, 40]).unwrap();
+    assert_eq!(restored.vm_version(40), VMVersion::V1);
+    assert!(restored.restore_activations(vec![40, 20]).is_err());
+    assert!(restored.restore_activations(vec![20, 40, 60]).is_err());
+
+    // L1 epochs and versions must be strictly increasing
+    for switches in [
+        vec![(200, VMVersion::V0), (100, VMVersion::V1)],
+        vec![(100, VMVersion::V0), (100, VMVersion::V1)],
+        vec![(100, VMVersion::V1), (200, VMVersion::V0)],
+        vec![(100, VMVersion::V1), (200, VMVersion::V1)],
+    ] {
+        assert!(VMVersionSchedule::new(switches).is_err());
+    }
+    let switches = vec![
+        VMVersionSwitch {
+            l1_epoch: 200,
+            vm_version: 1,
+        },
+        VMVersionSwitch {
+            l1_epoch: 100,
+            vm_version: 0,
+        },
+    ];
+    assert!(VMVersionSchedule::from_config(&switches).is_err());
+}
+
+#[test]
+fn test_check_rfc_0032() {
+    let unscheduled = VMVersionSchedule::default();
+    assert!(unscheduled.check_rfc_0032(u64::MAX).is_ok());
+    // RFC 0032 is announced on L1 but VM 1 isn't scheduled
+    let err = unscheduled.check_rfc_0032(5414).unwrap_err();
+    assert!(err.to_string().contains("VM 1 is not scheduled"), "{}", err);
+
+    let schedule = VMVersionSchedule::new(vec![(5414, VMVersion::V1)]).unwrap();
+    assert!(schedule.check_rfc_0032(5414).is_ok());
+    assert!(schedule.check_rfc_0032(5415).is_err());
+    assert!(schedule.check_rfc_0032(u64::MAX).is_err());
+}
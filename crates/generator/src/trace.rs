@@ -0,0 +1,97 @@
+//! Trace syscalls and debug prints of a transaction execution
+
+use gw_common::H256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyscallEvent {
+    Load {
+        key: H256,
+        value: H256,
+    },
+    Store {
+        key: H256,
+        old_value: H256,
+        new_value: H256,
+    },
+    Create {
+        script_hash: Option<H256>,
+        /// Id of the created account
+        account_id: Option<u32>,
+    },
+    Log {
+        account_id: u32,
+        service_flag: u8,
+        data: Vec<u8>,
+    },
+    PayFee {
+        short_address: Vec<u8>,
+        sudt_id: u32,
+        amount: u128,
+    },
+    LoadData {
+        data_hash: H256,
+        /// Length of the data, `None` if not found
+        data_len: Option<usize>,
+    },
+    DebugPrint {
+        message: String,
+    },
+    /// Registered syscall extensions
+    Extension {
+        /// Cycles charged by the extension
+        charged_cycles: u64,
+        read_keys: Vec<H256>,
+        write_keys: Vec<H256>,
+    },
+    /// Syscalls without details
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyscallTrace {
+    pub code: u64,
+    pub name: &'static str,
+    /// Cycles consumed since the previous syscall
+    pub cycles: u64,
+    /// Total cycles consumed when the syscall is invoked
+    pub total_cycles: u64,
+    /// Return code in register A0, `None` if the syscall fails the VM
+    pub ret: Option<i8>,
+    pub event: SyscallEvent,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxTrace {
+    pub syscalls: Vec<SyscallTrace>,
+}
+
+impl TxTrace {
+    pub(crate) fn enter(&mut self, code: u64, name: &'static str, total_cycles: u64) {
+        let last_cycles = self.syscalls.last().map(|s| s.total_cycles).unwrap_or(0);
+        self.syscalls.push(SyscallTrace {
+            code,
+            name,
+            cycles: total_cycles.saturating_sub(last_cycles),
+            total_cycles,
+            ret: None,
+            event: SyscallEvent::Other,
+        });
+    }
+
+    pub(crate) fn set_event(&mut self, event: SyscallEvent) {
+        if let Some(syscall) = self.syscalls.last_mut() {
+            syscall.event = event;
+        }
+    }
+
+    pub(crate) fn exit(&mut self, ret: i8) {
+        if let Some(syscall) = self.syscalls.last_mut() {
+            syscall.ret = Some(ret);
+        }
+    }
+
+    /// Remove the last entry of an unknown syscall
+    pub(crate) fn discard(&mut self) {
+        self.syscalls.pop();
+    }
+}
@@ -226,49 +226,7 @@ impl<S: State + CodeStore> StateExt for S {
         block_producer_id: u32,
         request: &WithdrawalRequest,
     ) -> Result<WithdrawalReceipt, Error> {
-        let raw = request.raw();
-        let account_script_hash: H256 = raw.account_script_hash().unpack();
-        let l2_sudt_script_hash: [u8; 32] =
-            build_l2_sudt_script(ctx, &raw.sudt_script_hash().unpack()).hash();
-        let amount: u128 = raw.amount().unpack();
-        let withdrawal_short_address = to_short_address(&account_script_hash);
-        // find user account
-        let id = self
-            .get_account_id_by_script_hash(&account_script_hash)?
-            .ok_or(AccountError::UnknownAccount)?; // find Simple UDT account
-        let capacity: u64 = raw.capacity().unpack();
-        // pay fee to block producer
-        {
-            let sudt_id: u32 = raw.fee().sudt_id().unpack();
-            let amount: u128 = raw.fee().amount().unpack();
-            let block_producer_script_hash = self.get_script_hash(block_producer_id)?;
-            let block_producer_short_address = to_short_address(&block_producer_script_hash);
-            self.pay_fee(
-                withdrawal_short_address,
-                block_producer_short_address,
-                sudt_id,
-                amount,
-            )?;
-        }
-        // burn CKB
-        self.burn_sudt(
-            CKB_SUDT_ACCOUNT_ID,
-            withdrawal_short_address,
-            capacity.into(),
-        )?;
-        let sudt_id = self
-            .get_account_id_by_script_hash(&l2_sudt_script_hash.into())?
-            .ok_or(AccountError::UnknownSUDT)?;
-        if sudt_id != CKB_SUDT_ACCOUNT_ID {
-            // burn sudt
-            self.burn_sudt(sudt_id, withdrawal_short_address, amount)?;
-        } else if amount != 0 {
-            return Err(WithdrawalError::WithdrawFakedCKB.into());
-        }
-        // increase nonce
-        let nonce = self.get_nonce(id)?;
-        let new_nonce = nonce.checked_add(1).ok_or(AccountError::NonceOverflow)?;
-        self.set_nonce(id, new_nonce)?;
+        apply_withdrawal_state(self, ctx, block_producer_id, request)?;
 
         let post_state = {
             let account_root = self.calculate_root()?;
@@ -286,3 +244,58 @@ impl<S: State + CodeStore> StateExt for S {
         Ok(receipt)
     }
 }
+
+/// Apply the state changes of a withdrawal without building the receipt, so
+/// it works on states without merkle roots.
+pub(crate) fn apply_withdrawal_state<S: State + CodeStore>(
+    state: &mut S,
+    ctx: &RollupContext,
+    block_producer_id: u32,
+    request: &WithdrawalRequest,
+) -> Result<(), Error> {
+    let raw = request.raw();
+    let account_script_hash: H256 = raw.account_script_hash().unpack();
+    let l2_sudt_script_hash: [u8; 32] =
+        build_l2_sudt_script(ctx, &raw.sudt_script_hash().unpack()).hash();
+    let amount: u128 = raw.amount().unpack();
+    let withdrawal_short_address = to_short_address(&account_script_hash);
+    // find user account
+    let id = state
+        .get_account_id_by_script_hash(&account_script_hash)?
+        .ok_or(AccountError::UnknownAccount)?; // find Simple UDT account
+    let capacity: u64 = raw.capacity().unpack();
+    // pay fee to block producer
+    {
+        let sudt_id: u32 = raw.fee().sudt_id().unpack();
+        let amount: u128 = raw.fee().amount().unpack();
+        let block_producer_script_hash = state.get_script_hash(block_producer_id)?;
+        let block_producer_short_address = to_short_address(&block_producer_script_hash);
+        state.pay_fee(
+            withdrawal_short_address,
+            block_producer_short_address,
+            sudt_id,
+            amount,
+        )?;
+    }
+    // burn CKB
+    state.burn_sudt(
+        CKB_SUDT_ACCOUNT_ID,
+        withdrawal_short_address,
+        capacity.into(),
+    )?;
+    let sudt_id = state
+        .get_account_id_by_script_hash(&l2_sudt_script_hash.into())?
+        .ok_or(AccountError::UnknownSUDT)?;
+    if sudt_id != CKB_SUDT_ACCOUNT_ID {
+        // burn sudt
+        state.burn_sudt(sudt_id, withdrawal_short_address, amount)?;
+    } else if amount != 0 {
+        return Err(WithdrawalError::WithdrawFakedCKB.into());
+    }
+    // increase nonce
+    let nonce = state.get_nonce(id)?;
+    let new_nonce = nonce.checked_add(1).ok_or(AccountError::NonceOverflow)?;
+    state.set_nonce(id, new_nonce)?;
+
+    Ok(())
+}
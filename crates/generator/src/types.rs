@@ -2,8 +2,13 @@ use ckb_vm::{
     machine::{VERSION0, VERSION1},
     ISA_B, ISA_IMC, ISA_MOP,
 };
+use gw_config::VMVersionSwitch;
 use gw_types::packed::{ChallengeTarget, ChallengeWitness};
-use std::fmt::{self, Display};
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+    sync::RwLock,
+};
 
 #[cfg(has_asm)]
 use ckb_vm::machine::asm::{AsmCoreMachine, AsmMachine};
@@ -29,7 +34,7 @@ pub type CoreMachine = Box<AsmCoreMachine>;
 pub type CoreMachine = DefaultCoreMachine<u64, WXorXMemory<SparseMemory<u64>>>;
 
 /// The version of CKB VM.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum VMVersion {
     /// CKB VM 0 with Syscall version 1.
     V0 = 0,
@@ -74,6 +79,130 @@ impl VMVersion {
     }
 }
 
+impl TryFrom<u32> for VMVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(version: u32) -> Result<Self, Self::Error> {
+        match version {
+            0 => Ok(Self::V0),
+            1 => Ok(Self::V1),
+            ver => Err(anyhow::anyhow!("unsupported VM version {}", ver)),
+        }
+    }
+}
+
+/// VM versions of L2 blocks. Switches are scheduled at L1 epochs, L2 blocks
+/// don't record the L1 epoch they are submitted in, so a switch applies from
+/// the first L2 block submitted in or after its epoch, which is recorded by
+/// `activate` when blocks are synced from L1.
+#[derive(Debug, Default)]
+pub struct VMVersionSchedule {
+    /// Switches in increasing L1 epoch and VM version
+    switches: Vec<(u64, VMVersion)>,
+    /// First L2 blocks of activated switches, a prefix of `switches`
+    activations: RwLock<Vec<u64>>,
+}
+
+impl VMVersionSchedule {
+    /// Both L1 epochs and VM versions of switches must be strictly
+    /// increasing, the VM version is never downgraded.
+    pub fn new(switches: Vec<(u64, VMVersion)>) -> anyhow::Result<Self> {
+        for pair in switches.windows(2) {
+            let ((prev_epoch, prev_version), (epoch, version)) = (pair[0], pair[1]);
+            if epoch <= prev_epoch || version <= prev_version {
+                anyhow::bail!(
+                    "VM version switch {:?} at L1 epoch {} must be after switch {:?} at L1 epoch {}",
+                    version,
+                    epoch,
+                    prev_version,
+                    prev_epoch
+                );
+            }
+        }
+        Ok(VMVersionSchedule {
+            switches,
+            activations: Default::default(),
+        })
+    }
+
+    pub fn from_config(switches: &[VMVersionSwitch]) -> anyhow::Result<Self> {
+        let switches = switches
+            .iter()
+            .map(|switch| Ok((switch.l1_epoch, VMVersion::try_from(switch.vm_version)?)))
+            .collect::<anyhow::Result<_>>()?;
+        Self::new(switches)
+    }
+
+    /// VM 1 must be scheduled at the L1 epoch RFC 0032 is enabled, `u64::MAX`
+    /// if RFC 0032 isn't scheduled on L1
+    pub fn check_rfc_0032(&self, rfc_0032_epoch: u64) -> anyhow::Result<()> {
+        let vm1_epoch = self
+            .switches
+            .iter()
+            .find(|(_, version)| *version == VMVersion::V1)
+            .map(|(epoch, _)| *epoch);
+        match vm1_epoch {
+            Some(epoch) if epoch == rfc_0032_epoch => Ok(()),
+            Some(epoch) => Err(anyhow::anyhow!(
+                "VM 1 is scheduled at L1 epoch {}, but RFC 0032 is enabled at L1 epoch {}",
+                epoch,
+                rfc_0032_epoch
+            )),
+            None if rfc_0032_epoch != u64::MAX => Err(anyhow::anyhow!(
+                "RFC 0032 is enabled at L1 epoch {}, but VM 1 is not scheduled",
+                rfc_0032_epoch
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// First L2 blocks of activated switches
+    pub fn activations(&self) -> Vec<u64> {
+        self.activations.read().expect("activations").clone()
+    }
+
+    /// Restore activations recorded by `activate`
+    pub fn restore_activations(&self, activations: Vec<u64>) -> anyhow::Result<()> {
+        if activations.len() > self.switches.len()
+            || activations.windows(2).any(|pair| pair[1] < pair[0])
+        {
+            anyhow::bail!(
+                "VM version activations {:?} don't match the schedule {:?}",
+                activations,
+                self.switches
+            );
+        }
+        *self.activations.write().expect("activations") = activations;
+        Ok(())
+    }
+
+    /// Activate switches scheduled in or before `l1_epoch` from `block_number`,
+    /// the L2 block submitted in `l1_epoch`. Activated switches are never
+    /// moved, returns whether any switch is activated.
+    pub fn activate(&self, l1_epoch: u64, block_number: u64) -> bool {
+        let mut activations = self.activations.write().expect("activations");
+        let block_number = block_number.max(activations.last().copied().unwrap_or(0));
+        let pending = self.switches[activations.len()..]
+            .iter()
+            .take_while(|(epoch, _)| *epoch <= l1_epoch)
+            .count();
+        activations.extend(std::iter::repeat(block_number).take(pending));
+        pending > 0
+    }
+
+    /// Blocks before the first activated switch use VM 0
+    pub fn vm_version(&self, block_number: u64) -> VMVersion {
+        let activations = self.activations.read().expect("activations");
+        self.switches
+            .iter()
+            .zip(activations.iter())
+            .rev()
+            .find(|(_, activation)| **activation <= block_number)
+            .map(|((_, version), _)| *version)
+            .unwrap_or(VMVersion::V0)
+    }
+}
+
 #[cfg(has_asm)]
 pub(crate) type Machine<'a> = AsmMachine<'a>;
 #[cfg(not(has_asm))]
@@ -1,4 +1,4 @@
-use crate::godwoken::ChallengeTargetType;
+use crate::godwoken::{ChallengeTargetType, RunResult};
 
 use ckb_fixed_hash::H256 as JsonH256;
 use ckb_jsonrpc_types as json_types;
@@ -44,3 +44,78 @@ pub enum DumpChallengeTarget {
         target_type: ChallengeTargetType,
     },
 }
+
+/// Result of `gw_debug_trace_transaction` and `gw_debug_trace_raw_transaction`
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct TxTrace {
+    pub exit_code: i8,
+    pub used_cycles: json_types::Uint64,
+    pub run_result: RunResult,
+    pub syscalls: Vec<SyscallTrace>,
+}
+
+/// Result of `gw_debug_profile_transaction` and `gw_debug_profile_raw_transaction`
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct TxProfile {
+    pub exit_code: i8,
+    pub used_cycles: json_types::Uint64,
+    /// Cycles of call stacks in folded format, one `outer;inner cycles` line
+    /// for each stack
+    pub folded_stacks: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SyscallTrace {
+    pub code: json_types::Uint64,
+    pub name: String,
+    /// Cycles consumed since the previous syscall
+    pub cycles: json_types::Uint64,
+    pub total_cycles: json_types::Uint64,
+    /// Return code, null if the syscall fails the VM
+    pub ret: Option<i8>,
+    pub event: SyscallEvent,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyscallEvent {
+    Load {
+        key: JsonH256,
+        value: JsonH256,
+    },
+    Store {
+        key: JsonH256,
+        old_value: JsonH256,
+        new_value: JsonH256,
+    },
+    Create {
+        script_hash: Option<JsonH256>,
+        account_id: Option<json_types::Uint32>,
+    },
+    Log {
+        account_id: json_types::Uint32,
+        service_flag: json_types::Uint32,
+        data: json_types::JsonBytes,
+    },
+    PayFee {
+        short_address: json_types::JsonBytes,
+        sudt_id: json_types::Uint32,
+        amount: json_types::Uint128,
+    },
+    LoadData {
+        data_hash: JsonH256,
+        data_len: Option<json_types::Uint32>,
+    },
+    DebugPrint {
+        message: String,
+    },
+    Extension {
+        charged_cycles: json_types::Uint64,
+        read_keys: Vec<JsonH256>,
+        write_keys: Vec<JsonH256>,
+    },
+    Other,
+}
@@ -1,4 +1,4 @@
-use crate::blockchain::Script;
+use crate::blockchain::{OutPoint, Script};
 use anyhow::{anyhow, Error as JsonError};
 use ckb_fixed_hash::H256;
 pub use ckb_jsonrpc_types::Uint32;
@@ -135,6 +135,9 @@ pub struct TxReceipt {
     pub post_state: AccountMerkleState,
     pub read_data_hashes: Vec<H256>,
     pub logs: Vec<LogItem>,
+    // state keys touched by the tx, only returned if requested and recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_list: Option<TxAccessList>,
 }
 
 impl From<TxReceipt> for packed::TxReceipt {
@@ -144,6 +147,7 @@ impl From<TxReceipt> for packed::TxReceipt {
             post_state,
             read_data_hashes,
             logs,
+            ..
         } = json;
         let tx_witness_hash: [u8; 32] = tx_witness_hash.into();
         let read_data_hashes: Vec<_> = read_data_hashes
@@ -181,6 +185,31 @@ impl From<packed::TxReceipt> for TxReceipt {
             post_state,
             read_data_hashes,
             logs,
+            access_list: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct TxAccessList {
+    pub read_keys: Vec<H256>,
+    pub write_keys: Vec<H256>,
+}
+
+impl From<offchain::TxAccessList> for TxAccessList {
+    fn from(data: offchain::TxAccessList) -> TxAccessList {
+        let to_json = |keys: Vec<gw_common::H256>| -> Vec<H256> {
+            keys.into_iter()
+                .map(|key| {
+                    let key: [u8; 32] = key.into();
+                    key.into()
+                })
+                .collect()
+        };
+        TxAccessList {
+            read_keys: to_json(data.read_keys),
+            write_keys: to_json(data.write_keys),
         }
     }
 }
@@ -1019,6 +1048,9 @@ pub struct RunResult {
     pub return_data: JsonBytes,
     // log data
     pub logs: Vec<LogItem>,
+    // state overrides of a dry run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_override: Option<StateOverride>,
 }
 
 impl From<offchain::RunResult> for RunResult {
@@ -1029,10 +1061,87 @@ impl From<offchain::RunResult> for RunResult {
         RunResult {
             return_data: JsonBytes::from_vec(return_data),
             logs: logs.into_iter().map(Into::into).collect(),
+            state_override: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct StateOverride {
+    #[serde(default)]
+    pub accounts: Vec<AccountOverride>,
+    // data to inject
+    #[serde(default)]
+    pub data: Vec<JsonBytes>,
+}
+
+impl From<StateOverride> for offchain::StateOverride {
+    fn from(json: StateOverride) -> offchain::StateOverride {
+        let StateOverride { accounts, data } = json;
+        offchain::StateOverride {
+            accounts: accounts.into_iter().map(Into::into).collect(),
+            data: data.into_iter().map(JsonBytes::into_bytes).collect(),
         }
     }
 }
 
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountOverride {
+    // an account is created if the id equals the account count
+    pub id: Uint32,
+    pub script: Option<Script>,
+    pub nonce: Option<Uint32>,
+    #[serde(default)]
+    pub balances: Vec<SUDTBalanceOverride>,
+    #[serde(default)]
+    pub storage: Vec<StorageOverride>,
+}
+
+impl From<AccountOverride> for offchain::AccountOverride {
+    fn from(json: AccountOverride) -> offchain::AccountOverride {
+        let AccountOverride {
+            id,
+            script,
+            nonce,
+            balances,
+            storage,
+        } = json;
+        offchain::AccountOverride {
+            id: id.into(),
+            script: script.map(Into::into),
+            nonce: nonce.map(Into::into),
+            balances: balances
+                .into_iter()
+                .map(|b| (b.sudt_id.into(), b.balance.into()))
+                .collect(),
+            storage: storage
+                .into_iter()
+                .map(|s| {
+                    let key: [u8; 32] = s.key.into();
+                    let value: [u8; 32] = s.value.into();
+                    (key.into(), value.into())
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SUDTBalanceOverride {
+    pub sudt_id: Uint32,
+    pub balance: Uint128,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct StorageOverride {
+    pub key: H256,
+    pub value: H256,
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct NodeInfo {
@@ -1068,3 +1177,102 @@ impl From<offchain::ErrorTxReceipt> for ErrorTxReceipt {
         }
     }
 }
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+impl Default for Order {
+    fn default() -> Self {
+        Self::Asc
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Pagination<T> {
+    pub objects: Vec<T>,
+    /// Pass it to the next request to continue, `None` if there are no more objects
+    pub last_cursor: Option<JsonBytes>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountTransaction {
+    pub block_number: Uint64,
+    pub block_hash: H256,
+    pub tx_index: Uint32,
+    pub transaction: L2TransactionView,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountInfo {
+    pub id: Uint32,
+    pub script_hash: H256,
+    pub script: Option<Script>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalStatus {
+    /// In mem-pool, waiting to be packaged
+    Pending,
+    /// Included in a layer2 block
+    Committed,
+    /// The layer2 block is submitted, withdrawal cell is created on layer1
+    CellCreated,
+    /// The layer2 block is finalized, withdrawal cell can be unlocked
+    Finalized,
+    /// Withdrawal cell is consumed on layer1
+    Unlocked,
+}
+
+impl Default for WithdrawalStatus {
+    fn default() -> Self {
+        Self::Pending
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct WithdrawalWithStatus {
+    pub withdrawal: WithdrawalRequest,
+    pub status: WithdrawalStatus,
+    pub block_number: Option<Uint64>,
+    pub block_hash: Option<H256>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositStatus {
+    /// Deposit cell is live on layer1, not collected by mem-pool yet
+    CellCreated,
+    /// Collected by mem-pool, waiting to be packaged
+    Pending,
+    /// Included in a layer2 block
+    Committed,
+    /// The layer2 block is finalized
+    Finalized,
+}
+
+impl Default for DepositStatus {
+    fn default() -> Self {
+        Self::Pending
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DepositWithStatus {
+    pub out_point: OutPoint,
+    pub request: DepositRequest,
+    pub status: DepositStatus,
+    pub block_number: Option<Uint64>,
+    pub block_hash: Option<H256>,
+    /// Credited account, `None` if the account is not created yet
+    pub account_id: Option<Uint32>,
+}
@@ -7,7 +7,7 @@ pub mod batch;
 mod constants;
 pub mod custodian;
 pub mod default_provider;
-mod deposit;
+pub mod deposit;
 mod mem_block;
 pub mod pool;
 pub mod restore_manager;
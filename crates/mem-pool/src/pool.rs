@@ -234,8 +234,6 @@ impl MemPool {
         offchain_validator_context: Option<OffChainValidatorContext>,
         config: MemPoolConfig,
     ) -> Result<Self> {
-        let pending = Default::default();
-
         let tip_block = store.get_tip_block()?;
         let tip = (tip_block.hash().into(), tip_block.raw().number().unpack());
 
@@ -248,13 +246,42 @@ impl MemPool {
             Arc::clone(&config),
         );
 
-        let restore_manager = RestoreManager::build(&config.restore_path)?;
-        let (is_restored, mem_block) = match restore_manager.restore_from_latest() {
-            Ok(Some((restored, timestamp))) => {
-                log::info!("[mem-pool] restore mem block from timestamp {}", timestamp);
-                (true, MemBlock::unpack(restored))
+        let restore_manager = RestoreManager::build_with_retention(
+            &config.restore_path,
+            config.restore_retention.clone(),
+        )?;
+        // Resume the mem block and pending queues persisted in db, fallback to
+        // the mem block saved by restore manager.
+        let persisted = {
+            let db = store.begin_transaction();
+            let persisted = restore_persisted_state(&db, block_producer_id)?;
+            if persisted.is_none() {
+                // stale pending queues must not be resumed with another mem block
+                db.clear_mem_pool_pending()?;
+                db.commit()?;
             }
-            _ => (false, MemBlock::with_block_producer(block_producer_id)),
+            persisted
+        };
+        let (is_restored, mem_block, pending) = match persisted {
+            Some((mem_block, pending)) => {
+                log::info!(
+                    "[mem-pool] resume mem block #{} with {} txs from db",
+                    mem_block.block_info().number().unpack(),
+                    mem_block.txs().len()
+                );
+                (true, mem_block, pending)
+            }
+            None => match restore_manager.restore_from_latest() {
+                Ok(Some((restored, timestamp))) => {
+                    log::info!("[mem-pool] restore mem block from timestamp {}", timestamp);
+                    (true, MemBlock::unpack(restored), Default::default())
+                }
+                _ => (
+                    false,
+                    MemBlock::with_block_producer(block_producer_id),
+                    Default::default(),
+                ),
+            },
         };
         let reverted_block_root = {
             let db = store.begin_transaction();
@@ -325,7 +352,7 @@ impl MemPool {
         }
 
         smol::spawn(async move {
-            restore_manager.delete_before_one_hour();
+            restore_manager.apply_retention();
         })
         .detach();
 
@@ -402,6 +429,7 @@ impl MemPool {
         log::debug!("[push tx] finalize tx time: {}ms", t.elapsed().as_millis());
 
         // save tx receipt in mem pool
+        db.insert_mem_pool_block_tx(self.mem_block.txs().len() as u32, &tx_hash)?;
         self.mem_block.push_tx(tx_hash, &tx_receipt);
         self.mem_block
             .set_post_merkle_state(tx_receipt.post_state());
@@ -412,6 +440,7 @@ impl MemPool {
         db.insert_mem_pool_transaction(&tx_hash, tx.clone())?;
         let entry_list = self.pending.entry(account_id).or_default();
         entry_list.txs.push(tx);
+        persist_pending_entry(db, account_id, entry_list)?;
 
         Ok(())
     }
@@ -470,6 +499,7 @@ impl MemPool {
             .expect("get account_id");
         let entry_list = self.pending.entry(account_id).or_default();
         entry_list.withdrawals.push(withdrawal.clone());
+        persist_pending_entry(db, account_id, entry_list)?;
         // Add to pool
         db.insert_mem_pool_withdrawal(&withdrawal_hash, withdrawal)?;
         Ok(())
@@ -994,6 +1024,7 @@ impl MemPool {
                 let withdrawal_hash: H256 = withdrawal.hash().into();
                 db.remove_mem_pool_withdrawal(&withdrawal_hash)?;
             }
+            persist_pending_entry(db, account_id, list)?;
             // Delete empty entry
             if list.is_empty() {
                 remove_list.push(account_id);
@@ -1060,6 +1091,8 @@ impl MemPool {
             crate::deposit::sanitize_deposit_cells(self.generator.rollup_context(), cells)
         };
         self.finalize_deposits(db, deposit_cells)?;
+        // persist mem block before txs, txs are persisted one by one
+        db.set_mem_pool_block(&self.mem_block.pack())?;
         // re-inject txs
         for tx in txs {
             if let Err(err) = self.push_transaction_with_db(db, tx.clone()) {
@@ -1353,3 +1386,70 @@ impl MemPool {
         Ok(tx_receipt)
     }
 }
+
+/// Persist hashes of an account's pending txs and withdrawals
+fn persist_pending_entry(db: &StoreTransaction, account_id: u32, list: &EntryList) -> Result<()> {
+    let tx_hashes = list.txs.iter().map(|tx| tx.hash().into()).collect();
+    let withdrawal_hashes = list
+        .withdrawals
+        .iter()
+        .map(|withdrawal| withdrawal.hash().into())
+        .collect();
+    db.update_mem_pool_pending(account_id, tx_hashes, withdrawal_hashes)?;
+    Ok(())
+}
+
+/// Load the mem block and pending queues persisted in db, the mem block is
+/// validated against the tip block by the caller.
+fn restore_persisted_state(
+    db: &StoreTransaction,
+    block_producer_id: u32,
+) -> Result<Option<(MemBlock, HashMap<u32, EntryList>)>> {
+    let mut mem_block = match db.get_mem_pool_block()? {
+        Some(packed) => MemBlock::unpack(packed),
+        None => return Ok(None),
+    };
+    if mem_block.block_producer_id() != block_producer_id {
+        log::warn!("persisted mem block producer not matched");
+        return Ok(None);
+    }
+
+    // txs are appended after the mem block is persisted
+    for tx_hash in db.get_mem_pool_block_txs() {
+        let receipt = match db.get_mem_pool_transaction_receipt(&tx_hash)? {
+            Some(receipt) => receipt,
+            None => {
+                log::warn!("persisted mem block tx receipt not found");
+                return Ok(None);
+            }
+        };
+        mem_block.push_tx(tx_hash, &receipt);
+        mem_block.set_post_merkle_state(receipt.post_state());
+    }
+
+    let mut pending: HashMap<u32, EntryList> = HashMap::new();
+    for (account_id, (tx_hashes, withdrawal_hashes)) in db.get_mem_pool_pending() {
+        let mut list = EntryList::default();
+        for tx_hash in tx_hashes {
+            match db.get_mem_pool_transaction(&tx_hash)? {
+                Some(tx) => list.txs.push(tx),
+                None => {
+                    log::warn!("persisted pending tx not found");
+                    return Ok(None);
+                }
+            }
+        }
+        for withdrawal_hash in withdrawal_hashes {
+            match db.get_mem_pool_withdrawal(&withdrawal_hash)? {
+                Some(withdrawal) => list.withdrawals.push(withdrawal),
+                None => {
+                    log::warn!("persisted pending withdrawal not found");
+                    return Ok(None);
+                }
+            }
+        }
+        pending.insert(account_id, list);
+    }
+
+    Ok(Some((mem_block, pending)))
+}
@@ -1,28 +1,102 @@
 use crate::mem_block::MemBlock;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use gw_common::blake2b::new_blake2b;
+use gw_config::RestoreRetentionConfig;
 use gw_types::packed;
 use gw_types::prelude::Entity;
 
+use std::convert::TryInto;
 use std::ffi::OsStr;
 use std::fs::{create_dir_all, read, read_dir, remove_file, write};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 const MEM_BLOCK_FILENAME_PREFIX: &str = "mem_block_timestamp_";
-const ONE_HOUR: Duration = Duration::from_secs(60 * 60);
+
+/// Format version of mem block files, bump it on layout changes
+pub const MEM_BLOCK_FILE_VERSION: u32 = 1;
+/// File layout: version(u32 LE) | blake2b checksum of mem block | packed mem block
+const MEM_BLOCK_FILE_HEADER_SIZE: usize = 4 + 32;
+
+/// A mem block file in the restore path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedMemBlock {
+    pub timestamp: u128,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+pub fn encode_mem_block_file(packed: &packed::MemBlock) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(MEM_BLOCK_FILE_HEADER_SIZE + packed.as_slice().len());
+    buf.extend_from_slice(&MEM_BLOCK_FILE_VERSION.to_le_bytes());
+    buf.extend_from_slice(&checksum(packed.as_slice()));
+    buf.extend_from_slice(packed.as_slice());
+    buf
+}
+
+/// Decode a mem block file, returns error if the file is corrupt or has an
+/// incompatible version. Files saved before the header was introduced contain
+/// only the packed mem block and are still accepted.
+pub fn decode_mem_block_file(content: &[u8]) -> Result<packed::MemBlock> {
+    if content.len() < MEM_BLOCK_FILE_HEADER_SIZE {
+        return decode_headerless_file(content);
+    }
+    let (header, body) = content.split_at(MEM_BLOCK_FILE_HEADER_SIZE);
+    let version = u32::from_le_bytes(header[..4].try_into().expect("version"));
+    if version != MEM_BLOCK_FILE_VERSION {
+        // Headerless files start with the molecule total size instead
+        return decode_headerless_file(content).map_err(|_| {
+            anyhow!(
+                "incompatible version {}, expected {}",
+                version,
+                MEM_BLOCK_FILE_VERSION
+            )
+        });
+    }
+    if header[4..] != checksum(body) {
+        bail!("checksum mismatch");
+    }
+    packed::MemBlock::from_slice(body).map_err(|err| anyhow!("invalid mem block {}", err))
+}
+
+fn decode_headerless_file(content: &[u8]) -> Result<packed::MemBlock> {
+    packed::MemBlock::from_slice(content)
+        .map_err(|err| anyhow!("invalid headerless mem block {}", err))
+}
+
+pub fn read_mem_block_file<P: AsRef<Path>>(path: P) -> Result<packed::MemBlock> {
+    decode_mem_block_file(&read(path)?)
+}
+
+fn checksum(data: &[u8]) -> [u8; 32] {
+    let mut hasher = new_blake2b();
+    hasher.update(data);
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
 
 #[derive(Clone)]
 pub struct RestoreManager {
     restore_path: PathBuf,
+    retention: RestoreRetentionConfig,
 }
 
 impl RestoreManager {
     pub fn build<P: AsRef<Path>>(restore_path: &P) -> Result<Self> {
+        Self::build_with_retention(restore_path, RestoreRetentionConfig::default())
+    }
+
+    pub fn build_with_retention<P: AsRef<Path>>(
+        restore_path: &P,
+        retention: RestoreRetentionConfig,
+    ) -> Result<Self> {
         create_dir_all(restore_path.as_ref())?;
 
         Ok(RestoreManager {
             restore_path: restore_path.as_ref().to_owned(),
+            retention,
         })
     }
 
@@ -39,69 +113,72 @@ impl RestoreManager {
         let file_path = self.block_file_path(timestamp);
         log::info!("[mem-pool] save restore save mem block {:?}", file_path);
 
-        let packed = mem_block.pack();
-        write(file_path, packed.as_slice())?;
+        write(file_path, encode_mem_block_file(&mem_block.pack()))?;
 
         Ok(())
     }
 
-    pub fn restore_from_latest(&self) -> Result<Option<(packed::MemBlock, u128)>> {
-        let mut dir = read_dir(self.restore_path.clone())?;
-        let mut opt_latest_timestamp = None;
-        while let Some(Ok(file)) = dir.next() {
-            let file_path = file.path();
-            let file_name = match file_path.file_name().map(OsStr::to_str) {
-                Some(Some(file_name)) => file_name,
-                _ => continue,
+    /// List saved mem block files, sorted by timestamp
+    pub fn list(&self) -> Result<Vec<SavedMemBlock>> {
+        let mut saved = Vec::new();
+        for entry in read_dir(&self.restore_path)? {
+            let entry = entry?;
+            let path = entry.path();
+            let timestamp = match parse_timestamp(&path) {
+                Some(timestamp) => timestamp,
+                None => continue,
             };
+            let size = entry.metadata()?.len();
+            saved.push(SavedMemBlock {
+                timestamp,
+                path,
+                size,
+            });
+        }
+        saved.sort_by_key(|saved| saved.timestamp);
+        Ok(saved)
+    }
 
-            let (_, str_timestamp) = file_name.split_at(MEM_BLOCK_FILENAME_PREFIX.len());
-            if let Ok(timestamp) = str_timestamp.parse() {
-                if opt_latest_timestamp.is_none() || Some(timestamp) > opt_latest_timestamp {
-                    opt_latest_timestamp = Some(timestamp);
+    /// Restore the latest valid mem block, corrupt or incompatible files are
+    /// skipped
+    pub fn restore_from_latest(&self) -> Result<Option<(packed::MemBlock, u128)>> {
+        for saved in self.list()?.into_iter().rev() {
+            match read_mem_block_file(&saved.path) {
+                Ok(block) => return Ok(Some((block, saved.timestamp))),
+                Err(err) => {
+                    log::warn!("[mem-pool] skip restore file {:?}: {}", saved.path, err);
                 }
             }
         }
 
-        let timestamp = match opt_latest_timestamp {
-            Some(timestamp) => timestamp,
-            None => return Ok(None),
-        };
-        let file_path = self.block_file_path(timestamp);
-
-        let block = packed::MemBlock::from_slice(&read(file_path)?)?;
-        Ok(Some((block, timestamp)))
+        Ok(None)
     }
 
     pub fn restore_from_timestamp(&self, timestamp: u128) -> Result<Option<packed::MemBlock>> {
-        let mut dir = read_dir(self.restore_path.clone())?;
-        let mut opt_timestamp_found = None;
-        while let Some(Ok(file)) = dir.next() {
-            let file_path = file.path();
-            let file_name = match file_path.file_name().map(OsStr::to_str) {
-                Some(Some(file_name)) => file_name,
-                _ => continue,
-            };
-
-            let (_, str_timestamp) = file_name.split_at(MEM_BLOCK_FILENAME_PREFIX.len());
-            if let Ok(file_timestamp) = str_timestamp.parse() {
-                if file_timestamp == timestamp {
-                    opt_timestamp_found = Some(file_timestamp);
-                    break;
-                }
-            }
+        let saved = self
+            .list()?
+            .into_iter()
+            .find(|saved| saved.timestamp == timestamp);
+
+        match saved {
+            Some(saved) => Ok(Some(read_mem_block_file(&saved.path)?)),
+            None => Ok(None),
         }
-
-        let file_path = match opt_timestamp_found {
-            Some(timestamp) => self.block_file_path(timestamp),
-            None => return Ok(None),
-        };
-
-        let block = packed::MemBlock::from_slice(&read(file_path)?)?;
-        Ok(Some(block))
     }
 
-    pub fn delete_before_one_hour(&self) {
+    /// Delete files out of the retention limits, the latest file is kept
+    pub fn apply_retention(&self) {
+        let saved = match self.list() {
+            Ok(saved) => saved,
+            Err(err) => {
+                log::warn!(
+                    "[mem-pool] save restore open {:?} error {}",
+                    self.restore_path,
+                    err
+                );
+                return;
+            }
+        };
         let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
             Ok(duration) => duration,
             Err(err) => {
@@ -109,14 +186,34 @@ impl RestoreManager {
                 return;
             }
         };
-
-        let before_one_hour = now.saturating_sub(ONE_HOUR).as_millis();
-        self.delete_before_timestamp(before_one_hour);
+        let RestoreRetentionConfig {
+            max_files,
+            max_age_secs,
+            max_total_bytes,
+        } = self.retention;
+        let min_timestamp = max_age_secs
+            .map(|secs| now.saturating_sub(Duration::from_secs(secs)).as_millis())
+            .unwrap_or(0);
+
+        let mut total_bytes = 0u64;
+        for (i, saved) in saved.iter().rev().enumerate() {
+            total_bytes = total_bytes.saturating_add(saved.size);
+            if i == 0 {
+                continue;
+            }
+            let out_of_count = max_files.map(|max| i >= max).unwrap_or(false);
+            let out_of_size = max_total_bytes
+                .map(|max| total_bytes > max)
+                .unwrap_or(false);
+            if out_of_count || out_of_size || saved.timestamp < min_timestamp {
+                self.remove_file(&saved.path);
+            }
+        }
     }
 
     pub fn delete_before_timestamp(&self, before_timestamp: u128) {
-        let mut dir = match read_dir(self.restore_path.clone()) {
-            Ok(dir) => dir,
+        let saved = match self.list() {
+            Ok(saved) => saved,
             Err(err) => {
                 log::warn!(
                     "[mem-pool] save restore open {:?} error {}",
@@ -127,32 +224,23 @@ impl RestoreManager {
             }
         };
 
-        while let Some(Ok(file)) = dir.next() {
-            let file_path = file.path();
-            let file_name = match file_path.file_name().map(OsStr::to_str) {
-                Some(Some(file_name)) => file_name,
-                _ => continue,
-            };
-
-            let (_, str_timestamp) = file_name.split_at(MEM_BLOCK_FILENAME_PREFIX.len());
-            let timestamp = match str_timestamp.parse() {
-                Ok(timestamp) => timestamp,
-                Err(_) => continue,
-            };
-
-            if timestamp < before_timestamp {
-                let file_path = self.block_file_path(timestamp);
-                if let Err(err) = remove_file(file_path.clone()) {
-                    log::warn!(
-                        "[mem-pool] save restore delete {:?} error {}",
-                        file_path,
-                        err
-                    );
-                }
+        for saved in saved {
+            if saved.timestamp < before_timestamp {
+                self.remove_file(&saved.path);
             }
         }
     }
 
+    fn remove_file(&self, file_path: &Path) {
+        if let Err(err) = remove_file(file_path) {
+            log::warn!(
+                "[mem-pool] save restore delete {:?} error {}",
+                file_path,
+                err
+            );
+        }
+    }
+
     fn block_file_path(&self, timestamp: u128) -> PathBuf {
         let file_name = format!("{}{}", MEM_BLOCK_FILENAME_PREFIX, timestamp);
         let mut file_path = self.restore_path.to_owned();
@@ -161,15 +249,25 @@ impl RestoreManager {
     }
 }
 
+fn parse_timestamp(file_path: &Path) -> Option<u128> {
+    let file_name = file_path.file_name().and_then(OsStr::to_str)?;
+    file_name
+        .strip_prefix(MEM_BLOCK_FILENAME_PREFIX)?
+        .parse()
+        .ok()
+}
+
 #[cfg(test)]
 mod tests {
+    use std::fs::write;
     use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+    use gw_config::RestoreRetentionConfig;
     use gw_types::prelude::Entity;
 
     use crate::mem_block::MemBlock;
 
-    use super::RestoreManager;
+    use super::{encode_mem_block_file, RestoreManager, MEM_BLOCK_FILENAME_PREFIX};
 
     #[test]
     fn test_restore_manager() {
@@ -217,4 +315,107 @@ mod tests {
             .unwrap();
         assert!(opt_restored.is_none());
     }
+
+    #[test]
+    fn test_restore_skip_invalid_files() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let restore_manager = RestoreManager::build(&tmp_dir).unwrap();
+
+        let mem_block = MemBlock::with_block_producer(666);
+        restore_manager.save_with_timestamp(&mem_block, 1).unwrap();
+
+        // Corrupt file
+        let mut corrupt = encode_mem_block_file(&MemBlock::with_block_producer(999).pack());
+        *corrupt.last_mut().unwrap() ^= 1;
+        write(
+            tmp_dir
+                .path()
+                .join(format!("{}2", MEM_BLOCK_FILENAME_PREFIX)),
+            corrupt,
+        )
+        .unwrap();
+        // Truncated file
+        write(
+            tmp_dir
+                .path()
+                .join(format!("{}3", MEM_BLOCK_FILENAME_PREFIX)),
+            b"xx",
+        )
+        .unwrap();
+        // Unrelated file
+        write(tmp_dir.path().join("x"), b"x").unwrap();
+
+        let (restored_packed, timestamp) = restore_manager
+            .restore_from_latest()
+            .unwrap()
+            .expect("saved");
+        assert_eq!(timestamp, 1);
+        assert_eq!(mem_block.pack().as_slice(), restored_packed.as_slice());
+        assert!(restore_manager.restore_from_timestamp(2).is_err());
+        assert!(restore_manager.restore_from_timestamp(3).is_err());
+
+        // File saved without header before upgrade
+        let legacy_mem_block = MemBlock::with_block_producer(777);
+        write(
+            tmp_dir
+                .path()
+                .join(format!("{}4", MEM_BLOCK_FILENAME_PREFIX)),
+            legacy_mem_block.pack().as_slice(),
+        )
+        .unwrap();
+        let (restored_packed, timestamp) = restore_manager
+            .restore_from_latest()
+            .unwrap()
+            .expect("saved");
+        assert_eq!(timestamp, 4);
+        assert_eq!(
+            legacy_mem_block.pack().as_slice(),
+            restored_packed.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_restore_retention() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let retention = RestoreRetentionConfig {
+            max_files: Some(2),
+            max_age_secs: Some(60),
+            max_total_bytes: None,
+        };
+        let restore_manager = RestoreManager::build_with_retention(&tmp_dir, retention).unwrap();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        let mem_block = MemBlock::with_block_producer(666);
+        // Out of max age
+        restore_manager
+            .save_with_timestamp(&mem_block, now - 120_000)
+            .unwrap();
+        for timestamp in [now - 3000, now - 2000, now - 1000] {
+            restore_manager
+                .save_with_timestamp(&mem_block, timestamp)
+                .unwrap();
+        }
+
+        restore_manager.apply_retention();
+        let timestamps: Vec<u128> = restore_manager
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|saved| saved.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![now - 2000, now - 1000]);
+
+        // The latest file is always kept
+        let retention = RestoreRetentionConfig {
+            max_files: Some(0),
+            max_age_secs: None,
+            max_total_bytes: Some(0),
+        };
+        let restore_manager = RestoreManager::build_with_retention(&tmp_dir, retention).unwrap();
+        restore_manager.apply_retention();
+        assert_eq!(restore_manager.list().unwrap().len(), 1);
+    }
 }
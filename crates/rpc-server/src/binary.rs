@@ -0,0 +1,321 @@
+//! Binary RPC transport
+//!
+//! A length-prefixed molecule-over-TCP alternative of the JSON-RPC server for
+//! internal services. Requests are answered by the same queries as JSON-RPC
+//! (see `crate::query`), results are returned as molecule bytes instead of
+//! hex encoded JSON.
+//!
+//! Frames are prefixed by a u32 little-endian length of the rest of the frame.
+//!
+//! Request:  `| len: u32 | request_id: u32 | method_len: u8 | method | params |`
+//! Response: `| len: u32 | request_id: u32 | status: u8 | payload |`
+//!
+//! Params are the concatenation of molecule encoded arguments in the order of
+//! the JSON-RPC method, an optional trailing block number can be omitted.
+//!
+//! If an auth token is configured, the first request of a connection must be
+//! `gw_auth` with the token as params, otherwise the connection is closed.
+
+use crate::{cache::RPCCache, query};
+use anyhow::{anyhow, Result};
+use gw_common::H256;
+use gw_jsonrpc_types::godwoken::{L2BlockStatus, L2TransactionStatus};
+use gw_store::Store;
+use gw_types::{bytes::Bytes, packed::RollupConfig, prelude::*};
+use smol::{prelude::*, Async};
+use std::{
+    convert::TryInto,
+    io::ErrorKind,
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::Arc,
+};
+
+/// Reject frames larger than 16MB
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+const STATUS_OK: u8 = 0;
+const STATUS_NOT_FOUND: u8 = 1;
+const STATUS_ERROR: u8 = 2;
+
+const AUTH_METHOD: &str = "gw_auth";
+
+pub struct BinaryHandler {
+    store: Store,
+    rollup_config: RollupConfig,
+    rpc_cache: Option<Arc<RPCCache>>,
+    auth_token: Option<Bytes>,
+}
+
+impl BinaryHandler {
+    pub fn new(
+        store: Store,
+        rollup_config: RollupConfig,
+        rpc_cache: Option<Arc<RPCCache>>,
+    ) -> Self {
+        BinaryHandler {
+            store,
+            rollup_config,
+            rpc_cache,
+            auth_token: None,
+        }
+    }
+
+    pub fn with_auth_token(mut self, auth_token: Option<String>) -> Self {
+        self.auth_token = auth_token.map(Bytes::from);
+        self
+    }
+
+    pub fn has_auth_token(&self) -> bool {
+        self.auth_token.is_some()
+    }
+
+    /// Returns the payload of a successful call, `None` if the requested item
+    /// is not found.
+    pub fn handle(&self, method: &str, params: &[u8]) -> Result<Option<Bytes>> {
+        let mut params = ParamsReader(params);
+        let store = &self.store;
+        let cache = self.rpc_cache.as_deref();
+        let payload = match method {
+            "gw_ping" => Some(Bytes::from_static(b"pong")),
+            "gw_get_tip_block_hash" => Some(to_bytes(&store.get_tip_block_hash()?)),
+            "gw_get_block_hash" => {
+                let block_number = params.read_u64()?;
+                query::get_block_hash(store, block_number)?.map(|hash| to_bytes(&hash))
+            }
+            "gw_get_block" => {
+                let block_hash = params.read_h256()?;
+                let block_opt = query::get_block(store, &self.rollup_config, &block_hash)?;
+                block_opt.map(|(block, status)| {
+                    let status = match status {
+                        L2BlockStatus::Unfinalized => 0u8,
+                        L2BlockStatus::Finalized => 1,
+                        L2BlockStatus::Reverted => 2,
+                    };
+                    with_status(status, block.as_slice())
+                })
+            }
+            "gw_get_block_by_number" => {
+                let block_number = params.read_u64()?;
+                query::get_block_by_number(store, cache, block_number)?.map(|b| b.as_bytes())
+            }
+            "gw_get_transaction" => {
+                let tx_hash = params.read_h256()?;
+                let tx_opt = query::get_transaction(store, &tx_hash)?;
+                tx_opt.map(|(tx, status)| {
+                    let status = match status {
+                        L2TransactionStatus::Pending => 0u8,
+                        L2TransactionStatus::Committed => 1,
+                    };
+                    with_status(status, tx.as_slice())
+                })
+            }
+            "gw_get_transaction_receipt" => {
+                let tx_hash = params.read_h256()?;
+                query::get_transaction_receipt(store, &tx_hash)?.map(|r| r.as_bytes())
+            }
+            "gw_get_script" => {
+                let script_hash = params.read_h256()?;
+                query::get_script(store, cache, &script_hash)?.map(|s| s.as_bytes())
+            }
+            "gw_get_script_hash" => {
+                let account_id = params.read_u32()?;
+                Some(to_bytes(&query::get_script_hash(store, cache, account_id)?))
+            }
+            "gw_get_account_id_by_script_hash" => {
+                let script_hash = params.read_h256()?;
+                query::get_account_id_by_script_hash(store, cache, &script_hash)?
+                    .map(|id| id.pack().as_bytes())
+            }
+            "gw_get_nonce" => {
+                let account_id = params.read_u32()?;
+                let block_number = params.read_u64_opt()?;
+                let nonce = query::get_nonce(store, account_id, block_number)?;
+                Some(nonce.pack().as_bytes())
+            }
+            "gw_get_balance" => {
+                let short_address = params.read_bytes()?;
+                let sudt_id = params.read_u32()?;
+                let block_number = params.read_u64_opt()?;
+                let balance = query::get_balance(store, &short_address, sudt_id, block_number)?;
+                Some(balance.pack().as_bytes())
+            }
+            "gw_get_storage_at" => {
+                let account_id = params.read_u32()?;
+                let key = params.read_h256()?;
+                let block_number = params.read_u64_opt()?;
+                let value = query::get_storage_at(store, account_id, &key, block_number)?;
+                Some(to_bytes(&value))
+            }
+            "gw_get_data" => {
+                let data_hash = params.read_h256()?;
+                query::get_data(store, &data_hash)?
+            }
+            method => return Err(anyhow!("method {} not found", method)),
+        };
+        params.finish()?;
+        Ok(payload)
+    }
+
+    async fn serve_connection(&self, mut stream: Async<TcpStream>) -> Result<()> {
+        let mut authorized = self.auth_token.is_none();
+        loop {
+            let mut len_buf = [0u8; 4];
+            match stream.read_exact(&mut len_buf).await {
+                Ok(()) => {}
+                // connection closed by peer
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+            let len = u32::from_le_bytes(len_buf) as usize;
+            if len > MAX_FRAME_SIZE {
+                return Err(anyhow!("frame size {} exceeds limit", len));
+            }
+            let mut frame = vec![0u8; len];
+            stream.read_exact(&mut frame).await?;
+
+            let (request_id, method, params) = parse_request(&frame)?;
+            if !authorized {
+                authorized = method == AUTH_METHOD && self.check_auth_token(params);
+                if !authorized {
+                    let payload = Bytes::from_static(b"unauthorized");
+                    write_response(&mut stream, request_id, STATUS_ERROR, &payload).await?;
+                    return Err(anyhow!("unauthorized"));
+                }
+                write_response(&mut stream, request_id, STATUS_OK, &[]).await?;
+                continue;
+            }
+
+            let (status, payload) = match self.handle(method, params) {
+                Ok(Some(payload)) => (STATUS_OK, payload),
+                Ok(None) => (STATUS_NOT_FOUND, Bytes::new()),
+                Err(err) => (STATUS_ERROR, Bytes::from(err.to_string())),
+            };
+            write_response(&mut stream, request_id, status, &payload).await?;
+        }
+    }
+
+    // compare in constant time
+    fn check_auth_token(&self, token: &[u8]) -> bool {
+        match self.auth_token {
+            Some(ref expected) if expected.len() == token.len() => {
+                expected
+                    .iter()
+                    .zip(token)
+                    .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                    == 0
+            }
+            _ => false,
+        }
+    }
+}
+
+pub async fn start_binary_server(listen_addr: SocketAddr, handler: BinaryHandler) -> Result<()> {
+    let listener = Async::<TcpListener>::bind(listen_addr)?;
+    serve_binary(listener, handler).await
+}
+
+/// Serve requests of an already bound listener
+pub async fn serve_binary(listener: Async<TcpListener>, handler: BinaryHandler) -> Result<()> {
+    log::info!(
+        "Binary RPC server listening on {}",
+        listener.get_ref().local_addr()?
+    );
+
+    let handler = Arc::new(handler);
+    loop {
+        let (stream, peer_addr) = listener.accept().await?;
+        let handler = Arc::clone(&handler);
+        smol::spawn(async move {
+            if let Err(err) = handler.serve_connection(stream).await {
+                log::debug!("[binary rpc] close connection {}: {}", peer_addr, err);
+            }
+        })
+        .detach();
+    }
+}
+
+async fn write_response(
+    stream: &mut Async<TcpStream>,
+    request_id: u32,
+    status: u8,
+    payload: &[u8],
+) -> Result<()> {
+    let mut response = Vec::with_capacity(9 + payload.len());
+    response.extend_from_slice(&((5 + payload.len()) as u32).to_le_bytes());
+    response.extend_from_slice(&request_id.to_le_bytes());
+    response.push(status);
+    response.extend_from_slice(payload);
+    stream.write_all(&response).await?;
+    Ok(())
+}
+
+fn parse_request(frame: &[u8]) -> Result<(u32, &str, &[u8])> {
+    if frame.len() < 5 {
+        return Err(anyhow!("invalid request frame"));
+    }
+    let request_id = u32::from_le_bytes(frame[..4].try_into()?);
+    let method_len = frame[4] as usize;
+    let method_end = 5 + method_len;
+    if frame.len() < method_end {
+        return Err(anyhow!("invalid request method"));
+    }
+    let method = std::str::from_utf8(&frame[5..method_end])?;
+    Ok((request_id, method, &frame[method_end..]))
+}
+
+fn to_bytes(hash: &H256) -> Bytes {
+    Bytes::copy_from_slice(hash.as_slice())
+}
+
+fn with_status(status: u8, data: &[u8]) -> Bytes {
+    let mut buf = Vec::with_capacity(1 + data.len());
+    buf.push(status);
+    buf.extend_from_slice(data);
+    buf.into()
+}
+
+struct ParamsReader<'a>(&'a [u8]);
+
+impl<'a> ParamsReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(anyhow!("invalid params"));
+        }
+        let (data, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(data)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn read_u64_opt(&mut self) -> Result<Option<u64>> {
+        if self.0.is_empty() {
+            return Ok(None);
+        }
+        self.read_u64().map(Some)
+    }
+
+    fn read_h256(&mut self) -> Result<H256> {
+        let buf: [u8; 32] = self.take(32)?.try_into()?;
+        Ok(buf.into())
+    }
+
+    // molecule `Bytes`: u32 length followed by the data
+    fn read_bytes(&mut self) -> Result<Bytes> {
+        let len = self.read_u32()? as usize;
+        Ok(Bytes::copy_from_slice(self.take(len)?))
+    }
+
+    fn finish(&self) -> Result<()> {
+        if !self.0.is_empty() {
+            return Err(anyhow!("unexpected trailing params"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+//! LRU cache for RPC responses of immutable data
+//!
+//! Only data that can't change until a chain revert is cached: blocks below
+//! the tip, scripts and script hashes of accounts created at the tip. The
+//! cache checks the tip on every access and is cleared once it detects the
+//! previous tip is no longer on the main chain.
+
+use anyhow::{anyhow, Result};
+use gw_common::H256;
+use gw_store::Store;
+use gw_types::prelude::*;
+use lru::LruCache;
+use serde::Serialize;
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+type CachedValue = Arc<dyn Any + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    method: &'static str,
+    params: Vec<u8>,
+}
+
+/// Keys are built from typed params, so every method shares the same
+/// encoding: numbers are big-endian, hashes are raw bytes.
+impl CacheKey {
+    pub fn from_number(method: &'static str, number: u64) -> Self {
+        CacheKey {
+            method,
+            params: number.to_be_bytes().to_vec(),
+        }
+    }
+
+    pub fn from_hash(method: &'static str, hash: &H256) -> Self {
+        CacheKey {
+            method,
+            params: hash.as_slice().to_vec(),
+        }
+    }
+}
+
+/// Tip snapshot the cached entries were validated against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedTip {
+    pub number: u64,
+    pub hash: H256,
+    pub account_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct CacheStats {
+    pub capacity: usize,
+    pub len: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct Inner {
+    entries: LruCache<CacheKey, CachedValue>,
+    tip: Option<CachedTip>,
+}
+
+pub struct RPCCache {
+    inner: Mutex<Inner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl RPCCache {
+    pub fn new(capacity: usize) -> Self {
+        let inner = Inner {
+            entries: LruCache::new(capacity),
+            tip: None,
+        };
+        RPCCache {
+            inner: Mutex::new(inner),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Sync with the store tip, clear all entries if the chain was reverted
+    pub fn refresh_tip(&self, store: &Store) -> Result<CachedTip> {
+        let tip_block_hash = store.get_tip_block_hash()?;
+        let mut inner = self.inner.lock().expect("rpc cache lock");
+        if let Some(tip) = inner.tip.as_ref() {
+            if tip.hash == tip_block_hash {
+                return Ok(tip.clone());
+            }
+        }
+
+        let db = store.begin_transaction();
+        let raw_block = db
+            .get_block(&tip_block_hash)?
+            .ok_or_else(|| anyhow!("can't find tip block"))?
+            .raw();
+        let new_tip = CachedTip {
+            number: raw_block.number().unpack(),
+            hash: tip_block_hash,
+            account_count: raw_block.post_account().count().unpack(),
+        };
+
+        // The old tip must stay on the main chain, otherwise blocks or accounts
+        // we have cached may be reverted.
+        if let Some(old_tip) = inner.tip.take() {
+            let is_ancestor = old_tip.number <= new_tip.number
+                && db.get_block_hash_by_number(old_tip.number)? == Some(old_tip.hash);
+            if !is_ancestor {
+                log::info!(
+                    "[RPC cache] chain reverted from #{} to #{}, clear {} entries",
+                    old_tip.number,
+                    new_tip.number,
+                    inner.entries.len()
+                );
+                inner.entries.clear();
+            }
+        }
+
+        inner.tip = Some(new_tip.clone());
+        Ok(new_tip)
+    }
+
+    /// Return cached value of `key`, or load it and cache the result if
+    /// `is_immutable` says it won't change until a revert.
+    pub fn get_or_load<T, F, C>(
+        &self,
+        store: &Store,
+        key: CacheKey,
+        load: F,
+        is_immutable: C,
+    ) -> Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Result<T>,
+        C: FnOnce(&CachedTip, &T) -> bool,
+    {
+        let tip = self.refresh_tip(store)?;
+        if let Some(value) = self.get::<T>(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let value = load()?;
+        if is_immutable(&tip, &value) {
+            self.insert(&tip, key, value.clone());
+        }
+        Ok(value)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock().expect("rpc cache lock");
+        CacheStats {
+            capacity: inner.entries.cap(),
+            len: inner.entries.len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn get<T: Clone + 'static>(&self, key: &CacheKey) -> Option<T> {
+        let mut inner = self.inner.lock().expect("rpc cache lock");
+        inner
+            .entries
+            .get(key)
+            .and_then(|value| value.downcast_ref::<T>())
+            .cloned()
+    }
+
+    fn insert<T: Send + Sync + 'static>(&self, tip: &CachedTip, key: CacheKey, value: T) {
+        let mut inner = self.inner.lock().expect("rpc cache lock");
+        // Tip changed while loading, the value may come from a reverted chain
+        if inner.tip.as_ref() != Some(tip) {
+            return;
+        }
+        inner.entries.put(key, Arc::new(value));
+    }
+}
@@ -1,2 +1,5 @@
+pub mod binary;
+pub mod cache;
+pub mod query;
 pub mod registry;
 pub mod server;
@@ -0,0 +1,641 @@
+//! Transport independent queries
+//!
+//! Both the JSON-RPC server and the binary server answer requests through
+//! these functions, so results stay identical across transports. Values are
+//! returned as packed types, each transport takes care of the encoding.
+//!
+//! Queries of immutable data take an optional `RPCCache`, which is shared by
+//! the transports.
+
+use crate::cache::{CacheKey, RPCCache};
+use anyhow::{anyhow, Result};
+use gw_common::{state::State, H256};
+use gw_db::Direction;
+use gw_jsonrpc_types::godwoken::{
+    DepositStatus, L2BlockStatus, L2TransactionStatus, WithdrawalStatus,
+};
+use gw_store::{
+    state::state_db::StateContext,
+    transaction::{account_tx_index::AccountTransactionKey, StoreTransaction},
+    Store,
+};
+use gw_traits::CodeStore;
+use gw_types::{
+    core::ScriptHashType,
+    offchain::{self, RollupContext},
+    packed::{self, RawL2Block, RollupConfig},
+    prelude::*,
+};
+
+pub fn get_block(
+    store: &Store,
+    rollup_config: &RollupConfig,
+    block_hash: &H256,
+) -> Result<Option<(packed::L2Block, L2BlockStatus)>> {
+    let db = store.begin_transaction();
+    let block = match db.get_block(block_hash)? {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+
+    // check block status
+    let mut status = L2BlockStatus::Unfinalized;
+    if !db.reverted_block_smt()?.get(block_hash)?.is_zero() {
+        // block is reverted
+        status = L2BlockStatus::Reverted;
+    } else {
+        // return None if block is not on the main chain
+        if &db.block_smt()?.get(&block.smt_key().into())? != block_hash {
+            return Ok(None);
+        }
+
+        // block is on main chain
+        let tip_block_number = db.get_last_valid_tip_block()?.raw().number().unpack();
+        let block_number = block.raw().number().unpack();
+        if tip_block_number >= block_number + rollup_config.finality_blocks().unpack() {
+            status = L2BlockStatus::Finalized;
+        }
+    }
+
+    Ok(Some((block, status)))
+}
+
+pub fn get_block_by_number(
+    store: &Store,
+    cache: Option<&RPCCache>,
+    block_number: u64,
+) -> Result<Option<packed::L2Block>> {
+    let load = || -> Result<Option<packed::L2Block>> {
+        let db = store.begin_transaction();
+        let block_hash = match db.get_block_hash_by_number(block_number)? {
+            Some(hash) => hash,
+            None => return Ok(None),
+        };
+        Ok(db.get_block(&block_hash)?)
+    };
+
+    match cache {
+        Some(cache) => {
+            let key = CacheKey::from_number("gw_get_block_by_number", block_number);
+            // the tip block may be reverted
+            cache.get_or_load(store, key, load, |tip, block_opt| {
+                block_opt.is_some() && block_number < tip.number
+            })
+        }
+        None => load(),
+    }
+}
+
+pub fn get_block_hash(store: &Store, block_number: u64) -> Result<Option<H256>> {
+    let db = store.begin_transaction();
+    Ok(db.get_block_hash_by_number(block_number)?)
+}
+
+pub fn get_transaction(
+    store: &Store,
+    tx_hash: &H256,
+) -> Result<Option<(packed::L2Transaction, L2TransactionStatus)>> {
+    let db = store.begin_transaction();
+    let tx_opt;
+    let status;
+    match db.get_transaction_info(tx_hash)? {
+        Some(tx_info) => {
+            let tx_block_number = tx_info.block_number().unpack();
+
+            // return None if tx's committed block is reverted
+            if !db
+                .reverted_block_smt()?
+                .get(&RawL2Block::compute_smt_key(tx_block_number).into())?
+                .is_zero()
+            {
+                // block is reverted
+                return Ok(None);
+            }
+
+            tx_opt = db.get_transaction_by_key(&tx_info.key())?;
+            status = L2TransactionStatus::Committed;
+        }
+        None => {
+            tx_opt = db.get_mem_pool_transaction(tx_hash)?;
+            status = L2TransactionStatus::Pending;
+        }
+    };
+
+    Ok(tx_opt.map(|tx| (tx, status)))
+}
+
+pub fn get_transaction_receipt(store: &Store, tx_hash: &H256) -> Result<Option<packed::TxReceipt>> {
+    let db = store.begin_transaction();
+    // search from db
+    if let Some(receipt) = db.get_transaction_receipt(tx_hash)? {
+        return Ok(Some(receipt));
+    }
+    // search from mem pool
+    Ok(db.get_mem_pool_transaction_receipt(tx_hash)?)
+}
+
+/// Access list of a tx in blocks, `None` if not recorded
+pub fn get_transaction_access_list(
+    store: &Store,
+    tx_hash: &H256,
+) -> Result<Option<offchain::TxAccessList>> {
+    let db = store.begin_transaction();
+    let tx_info = match db.get_transaction_info(tx_hash)? {
+        Some(tx_info) => tx_info,
+        None => return Ok(None),
+    };
+    Ok(db.get_transaction_access_list(&tx_info.key())?)
+}
+
+pub fn get_account_id_by_script_hash(
+    store: &Store,
+    cache: Option<&RPCCache>,
+    script_hash: &H256,
+) -> Result<Option<u32>> {
+    let load = || -> Result<Option<u32>> {
+        let db = store.begin_transaction();
+        let tree = db.mem_pool_state_tree()?;
+        Ok(tree.get_account_id_by_script_hash(script_hash)?)
+    };
+
+    match cache {
+        Some(cache) => {
+            let key = CacheKey::from_hash("gw_get_account_id_by_script_hash", script_hash);
+            // accounts created in mem-pool are not immutable yet
+            cache.get_or_load(store, key, load, |tip, account_id_opt| {
+                account_id_opt.map_or(false, |id| id < tip.account_count)
+            })
+        }
+        None => load(),
+    }
+}
+
+pub fn get_script(
+    store: &Store,
+    cache: Option<&RPCCache>,
+    script_hash: &H256,
+) -> Result<Option<packed::Script>> {
+    let load = || -> Result<Option<packed::Script>> {
+        let db = store.begin_transaction();
+        let tree = db.mem_pool_state_tree()?;
+        Ok(tree.get_script(script_hash))
+    };
+
+    match cache {
+        Some(cache) => {
+            // script is content addressed, only its existence may change
+            let key = CacheKey::from_hash("gw_get_script", script_hash);
+            cache.get_or_load(store, key, load, |_tip, script_opt| script_opt.is_some())
+        }
+        None => load(),
+    }
+}
+
+pub fn get_script_hash(store: &Store, cache: Option<&RPCCache>, account_id: u32) -> Result<H256> {
+    let load = || -> Result<H256> {
+        let db = store.begin_transaction();
+        let tree = db.mem_pool_state_tree()?;
+        Ok(tree.get_script_hash(account_id)?)
+    };
+
+    match cache {
+        Some(cache) => {
+            let key = CacheKey::from_number("gw_get_script_hash", account_id.into());
+            cache.get_or_load(store, key, load, |tip, _script_hash| {
+                account_id < tip.account_count
+            })
+        }
+        None => load(),
+    }
+}
+
+pub fn get_nonce(store: &Store, account_id: u32, block_number: Option<u64>) -> Result<u32> {
+    let db = store.begin_transaction();
+    let nonce = match block_number {
+        Some(block_number) => {
+            db.check_history_available(block_number)?;
+            let tree = db.state_tree(StateContext::ReadOnlyHistory(block_number))?;
+            tree.get_nonce(account_id)?
+        }
+        None => {
+            let tree = db.mem_pool_state_tree()?;
+            tree.get_nonce(account_id)?
+        }
+    };
+    Ok(nonce)
+}
+
+pub fn get_balance(
+    store: &Store,
+    short_address: &[u8],
+    sudt_id: u32,
+    block_number: Option<u64>,
+) -> Result<u128> {
+    let db = store.begin_transaction();
+    let balance = match block_number {
+        Some(block_number) => {
+            db.check_history_available(block_number)?;
+            let tree = db.state_tree(StateContext::ReadOnlyHistory(block_number))?;
+            tree.get_sudt_balance(sudt_id, short_address)?
+        }
+        None => {
+            let tree = db.mem_pool_state_tree()?;
+            tree.get_sudt_balance(sudt_id, short_address)?
+        }
+    };
+    Ok(balance)
+}
+
+pub fn get_storage_at(
+    store: &Store,
+    account_id: u32,
+    key: &H256,
+    block_number: Option<u64>,
+) -> Result<H256> {
+    let db = store.begin_transaction();
+    let value = match block_number {
+        Some(block_number) => {
+            db.check_history_available(block_number)?;
+            let tree = db.state_tree(StateContext::ReadOnlyHistory(block_number))?;
+            tree.get_value(account_id, key)?
+        }
+        None => {
+            let tree = db.mem_pool_state_tree()?;
+            tree.get_value(account_id, key)?
+        }
+    };
+    Ok(value)
+}
+
+pub fn get_data(store: &Store, data_hash: &H256) -> Result<Option<gw_types::bytes::Bytes>> {
+    let db = store.begin_transaction();
+    let tree = db.mem_pool_state_tree()?;
+    Ok(tree.get_data(data_hash))
+}
+
+/// Max number of objects returned by a paginated query
+pub const MAX_PAGE_SIZE: usize = 100;
+
+pub struct AccountTransactionRecord {
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub tx_index: u32,
+    pub transaction: packed::L2Transaction,
+}
+
+/// Returns transactions sent or received by the account and the cursor of the
+/// next page.
+pub fn get_transactions_by_account(
+    store: &Store,
+    account_id: u32,
+    direction: Direction,
+    cursor: Option<&AccountTransactionKey>,
+    limit: usize,
+) -> Result<(Vec<AccountTransactionRecord>, Option<AccountTransactionKey>)> {
+    let db = store.begin_transaction();
+    // probe one more key, so there's no cursor for an empty next page
+    let mut keys = db.get_account_transaction_keys(account_id, direction, cursor, limit + 1)?;
+    let next_cursor = if keys.len() > limit {
+        keys.truncate(limit);
+        keys.last().map(|(key, _)| key.clone())
+    } else {
+        None
+    };
+
+    let mut records = Vec::with_capacity(keys.len());
+    for (key, tx_key) in keys {
+        let transaction = db
+            .get_transaction_by_key(&tx_key)?
+            .ok_or_else(|| anyhow!("can't find indexed transaction"))?;
+        let block_hash = {
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(&tx_key.as_slice()[..32]);
+            hash.into()
+        };
+        records.push(AccountTransactionRecord {
+            block_number: key.block_number(),
+            block_hash,
+            tx_index: key.tx_index(),
+            transaction,
+        });
+    }
+    Ok((records, next_cursor))
+}
+
+/// Returns (id, script hash, script) of accounts in `[from_id, from_id + limit)`
+pub fn get_accounts(
+    store: &Store,
+    from_id: u32,
+    limit: usize,
+) -> Result<Vec<(u32, H256, Option<packed::Script>)>> {
+    let db = store.begin_transaction();
+    let tree = db.mem_pool_state_tree()?;
+    let account_count = tree.get_account_count()?;
+    let to_id = from_id.saturating_add(limit as u32).min(account_count);
+
+    let mut accounts = Vec::with_capacity(to_id.saturating_sub(from_id) as usize);
+    for id in from_id..to_id {
+        let script_hash = tree.get_script_hash(id)?;
+        let script = tree.get_script(&script_hash);
+        accounts.push((id, script_hash, script));
+    }
+    Ok(accounts)
+}
+
+pub struct WithdrawalRecord {
+    pub withdrawal: packed::WithdrawalRequest,
+    pub status: WithdrawalStatus,
+    /// Hash and number of the block which includes the withdrawal
+    pub block: Option<(H256, u64)>,
+    /// Index of the withdrawal in the block
+    pub index: Option<u32>,
+    /// Hash of the layer1 transaction which submits the block
+    pub submit_tx_hash: Option<H256>,
+}
+
+/// Returns the withdrawal and its status known by the local store.
+///
+/// The status is at most `Finalized`, whether the withdrawal cell is unlocked
+/// must be checked on layer1.
+pub fn get_withdrawal(
+    store: &Store,
+    rollup_context: &RollupContext,
+    withdrawal_hash: &H256,
+) -> Result<Option<WithdrawalRecord>> {
+    let db = store.begin_transaction();
+    if let Some(info) = db.get_withdrawal_info(withdrawal_hash)? {
+        // ignore withdrawals of reverted or bad blocks
+        if let Some((block_hash, block_number, is_finalized)) =
+            committed_block(&db, rollup_context, &info)?
+        {
+            if let Some(withdrawal) = db.get_withdrawal_by_key(&info.key())? {
+                let submit_tx_hash: Option<H256> = db
+                    .get_l2block_committed_info(&block_hash)?
+                    .map(|info| info.transaction_hash().unpack());
+                let status = if is_finalized {
+                    WithdrawalStatus::Finalized
+                } else if submit_tx_hash.is_some() {
+                    WithdrawalStatus::CellCreated
+                } else {
+                    WithdrawalStatus::Committed
+                };
+                let index = {
+                    let mut index = [0u8; 4];
+                    index.copy_from_slice(&info.key().as_slice()[32..]);
+                    u32::from_be_bytes(index)
+                };
+                return Ok(Some(WithdrawalRecord {
+                    withdrawal,
+                    status,
+                    block: Some((block_hash, block_number)),
+                    index: Some(index),
+                    submit_tx_hash,
+                }));
+            }
+        }
+    }
+
+    // search from mem pool
+    let withdrawal_opt = db.get_mem_pool_withdrawal(withdrawal_hash)?;
+    Ok(withdrawal_opt.map(|withdrawal| WithdrawalRecord {
+        withdrawal,
+        status: WithdrawalStatus::Pending,
+        block: None,
+        index: None,
+        submit_tx_hash: None,
+    }))
+}
+
+/// Returns the out point of the withdrawal cell created by the submit
+/// transaction of the withdrawal's block.
+///
+/// Withdrawals with the same account, owner and capacity have identical cells,
+/// so the cell is located by the withdrawal index: the submit transaction
+/// outputs withdrawal cells of the block in the order of `block.withdrawals()`.
+pub fn withdrawal_cell_out_point(
+    rollup_context: &RollupContext,
+    record: &WithdrawalRecord,
+    submit_tx: &packed::Transaction,
+) -> Option<packed::OutPoint> {
+    let (block_hash, _) = record.block?;
+    let index = record.index? as usize;
+    let tx_hash = record.submit_tx_hash?;
+
+    let withdrawal_script_type_hash = rollup_context.rollup_config.withdrawal_script_type_hash();
+    let is_withdrawal_cell_of_block = |output: &packed::CellOutput| {
+        let lock = output.lock();
+        if lock.code_hash() != withdrawal_script_type_hash
+            || lock.hash_type() != ScriptHashType::Type.into()
+        {
+            return false;
+        }
+        // lock args: rollup_script_hash(32 bytes) | WithdrawalLockArgs
+        let args = lock.args().raw_data();
+        if args.len() < 32
+            || args[..32] != rollup_context.rollup_script_hash.as_slice()[..]
+            || packed::WithdrawalLockArgsReader::verify(&args[32..], false).is_err()
+        {
+            return false;
+        }
+        let lock_args = packed::WithdrawalLockArgs::new_unchecked(args.slice(32..));
+        let withdrawal_block_hash: H256 = lock_args.withdrawal_block_hash().unpack();
+        withdrawal_block_hash == block_hash
+    };
+
+    let outputs = submit_tx.raw().outputs();
+    let output_index = outputs
+        .into_iter()
+        .enumerate()
+        .filter(|(_, output)| is_withdrawal_cell_of_block(output))
+        .nth(index)
+        .map(|(output_index, _)| output_index as u32)?;
+    let out_point = packed::OutPoint::new_builder()
+        .tx_hash(tx_hash.pack())
+        .index(output_index.pack())
+        .build();
+    Some(out_point)
+}
+
+/// Returns the status of the withdrawal with its layer1 cell, `live_cell` is
+/// the cell at `withdrawal_cell_out_point` if it is still live. A finalized
+/// withdrawal is unlocked once its cell is consumed.
+pub fn withdrawal_status_on_layer1(
+    record: &WithdrawalRecord,
+    live_cell: Option<&offchain::CellInfo>,
+) -> WithdrawalStatus {
+    match record.status {
+        WithdrawalStatus::Finalized if live_cell.is_none() => WithdrawalStatus::Unlocked,
+        status => status,
+    }
+}
+
+pub struct DepositRecord {
+    pub out_point: packed::OutPoint,
+    pub request: packed::DepositRequest,
+    pub status: DepositStatus,
+    /// Hash and number of the block which includes the deposit
+    pub block: Option<(H256, u64)>,
+    pub account_id: Option<u32>,
+}
+
+/// Returns the deposit of the layer1 deposit cell if it is included in a block.
+pub fn get_deposit(
+    store: &Store,
+    rollup_context: &RollupContext,
+    out_point: &packed::OutPoint,
+) -> Result<Option<DepositRecord>> {
+    let db = store.begin_transaction();
+    match db.get_deposit_info(out_point)? {
+        Some(info) => to_deposit_record(&db, rollup_context, out_point.to_owned(), &info),
+        None => Ok(None),
+    }
+}
+
+/// Returns included deposits of the deposit cells created by the layer1 transaction.
+pub fn get_deposits_by_tx_hash(
+    store: &Store,
+    rollup_context: &RollupContext,
+    tx_hash: &H256,
+) -> Result<Vec<DepositRecord>> {
+    let db = store.begin_transaction();
+    let mut deposits = Vec::new();
+    for (out_point, info) in db.get_deposit_info_by_tx_hash(tx_hash)? {
+        if let Some(record) = to_deposit_record(&db, rollup_context, out_point, &info)? {
+            deposits.push(record);
+        }
+    }
+    Ok(deposits)
+}
+
+/// Returns deposits collected by mem-pool but not included in a block yet.
+///
+/// Deposits of the mem block are read from the persisted mem block, which is
+/// updated once mem-pool collects deposits.
+pub fn get_pending_deposits(store: &Store) -> Result<Vec<DepositRecord>> {
+    let db = store.begin_transaction();
+    let mem_block = match db.get_mem_pool_block()? {
+        Some(mem_block) => mem_block,
+        None => return Ok(Vec::new()),
+    };
+    let tree = db.mem_pool_state_tree()?;
+    let mut deposits = Vec::new();
+    for info in mem_block.deposits().into_iter() {
+        let request = info.request();
+        let account_id = tree.get_account_id_by_script_hash(&request.script().hash().into())?;
+        deposits.push(DepositRecord {
+            out_point: info.cell().out_point(),
+            request,
+            status: DepositStatus::Pending,
+            block: None,
+            account_id,
+        });
+    }
+    Ok(deposits)
+}
+
+pub fn get_pending_deposit(
+    store: &Store,
+    out_point: &packed::OutPoint,
+) -> Result<Option<DepositRecord>> {
+    let pending_deposit = get_pending_deposits(store)?
+        .into_iter()
+        .find(|record| record.out_point.as_slice() == out_point.as_slice());
+    Ok(pending_deposit)
+}
+
+/// Returns deposits of live layer1 deposit cells, which are neither collected
+/// by mem-pool nor included in a block.
+pub fn get_layer1_deposits(
+    store: &Store,
+    cells: Vec<offchain::DepositInfo>,
+    pending_deposits: &[DepositRecord],
+) -> Result<Vec<DepositRecord>> {
+    let db = store.begin_transaction();
+    let tree = db.mem_pool_state_tree()?;
+    let mut deposits = Vec::new();
+    for info in cells {
+        let out_point = info.cell.out_point;
+        let is_collected = pending_deposits
+            .iter()
+            .any(|record| record.out_point.as_slice() == out_point.as_slice());
+        // the indexer may lag behind the chain
+        if is_collected || db.get_deposit_info(&out_point)?.is_some() {
+            continue;
+        }
+        let request = info.request;
+        let account_id = tree.get_account_id_by_script_hash(&request.script().hash().into())?;
+        deposits.push(DepositRecord {
+            out_point,
+            request,
+            status: DepositStatus::CellCreated,
+            block: None,
+            account_id,
+        });
+    }
+    Ok(deposits)
+}
+
+fn to_deposit_record(
+    db: &StoreTransaction,
+    rollup_context: &RollupContext,
+    out_point: packed::OutPoint,
+    info: &packed::TransactionInfo,
+) -> Result<Option<DepositRecord>> {
+    // ignore deposits of reverted or bad blocks
+    let (block_hash, block_number, is_finalized) = match committed_block(db, rollup_context, info)?
+    {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+    let index = {
+        let mut index = [0u8; 4];
+        index.copy_from_slice(&info.key().as_slice()[32..]);
+        u32::from_be_bytes(index)
+    };
+    let request = match db
+        .get_block_deposit_requests(&block_hash)?
+        .and_then(|requests| requests.into_iter().nth(index as usize))
+    {
+        Some(request) => request,
+        None => return Ok(None),
+    };
+
+    let tree = db.mem_pool_state_tree()?;
+    let account_id = tree.get_account_id_by_script_hash(&request.script().hash().into())?;
+    let status = if is_finalized {
+        DepositStatus::Finalized
+    } else {
+        DepositStatus::Committed
+    };
+    Ok(Some(DepositRecord {
+        out_point,
+        request,
+        status,
+        block: Some((block_hash, block_number)),
+        account_id,
+    }))
+}
+
+/// Returns (block hash, block number, is finalized) of the block referred by
+/// the info, `None` if the block isn't on the valid main chain.
+fn committed_block(
+    db: &StoreTransaction,
+    rollup_context: &RollupContext,
+    info: &packed::TransactionInfo,
+) -> Result<Option<(H256, u64, bool)>> {
+    let block_number: u64 = info.block_number().unpack();
+    let block_hash = {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&info.key().as_slice()[..32]);
+        H256::from(hash)
+    };
+
+    let tip_block_number = db.get_last_valid_tip_block()?.raw().number().unpack();
+    if block_number > tip_block_number
+        || db.get_block_hash_by_number(block_number)? != Some(block_hash)
+    {
+        return Ok(None);
+    }
+    let is_finalized = block_number <= rollup_context.last_finalized_block_number(tip_block_number);
+    Ok(Some((block_hash, block_number, is_finalized)))
+}
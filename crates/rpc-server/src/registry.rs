@@ -1,27 +1,52 @@
+use crate::{
+    binary::BinaryHandler,
+    cache::{CacheStats, RPCCache},
+    query,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use ckb_types::prelude::{Builder, Entity};
 use gw_chain::chain::Chain;
 use gw_challenge::offchain::OffChainMockContext;
 use gw_common::{blake2b::new_blake2b, state::State, H256};
-use gw_config::{BackendType, DebugConfig, MemPoolConfig, NodeMode, RPCMethods, RPCServerConfig};
-use gw_generator::{error::TransactionError, sudt::build_l2_sudt_script, Generator};
+use gw_config::{
+    BackendType, DebugConfig, MemPoolConfig, NodeMode, RPCConfig, RPCMethods, RPCServerConfig,
+};
+use gw_db::Direction;
+use gw_generator::{
+    error::TransactionError, state_override::OverlayState, sudt::build_l2_sudt_script, Generator,
+};
 use gw_jsonrpc_types::{
-    blockchain::Script,
+    blockchain::{OutPoint, Script},
     ckb_jsonrpc_types::{JsonBytes, Uint128, Uint32},
-    debugger::{DumpChallengeTarget, ReprMockTransaction},
+    debugger::{
+        DumpChallengeTarget, ReprMockTransaction, SyscallEvent, SyscallTrace, TxProfile, TxTrace,
+    },
     godwoken::{
-        BackendInfo, ErrorTxReceipt, FeeConfig, GlobalState, L2BlockCommittedInfo, L2BlockStatus,
-        L2BlockView, L2BlockWithStatus, L2TransactionStatus, L2TransactionWithStatus, NodeInfo,
-        RunResult, TxReceipt,
+        AccountInfo, AccountTransaction, BackendInfo, DepositWithStatus, ErrorTxReceipt, FeeConfig,
+        GlobalState, L2BlockCommittedInfo, L2BlockView, L2BlockWithStatus, L2TransactionWithStatus,
+        NodeInfo, Order, Pagination, RunResult, StateOverride, TxReceipt, WithdrawalStatus,
+        WithdrawalWithStatus,
     },
     test_mode::{ShouldProduceBlock, TestModePayload},
 };
-use gw_mem_pool::batch::{BatchError, MemPoolBatch};
-use gw_store::{chain_view::ChainView, state::state_db::StateContext, CfMemStat, Store};
-use gw_traits::CodeStore;
+use gw_mem_pool::{
+    batch::{BatchError, MemPoolBatch},
+    deposit::sanitize_deposit_cells,
+};
+use gw_rpc_client::rpc_client::RPCClient;
+use gw_store::{
+    chain_view::ChainView,
+    state::state_db::StateContext,
+    transaction::{
+        account_tx_index::AccountTransactionKey, prune::HistoryPrunedError, StoreTransaction,
+    },
+    CfMemStat, Store,
+};
+use gw_traits::{ChainStore, CodeStore};
 use gw_types::{
-    packed::{self, BlockInfo, RawL2Block, RollupConfig},
+    offchain,
+    packed::{self, BlockInfo, RollupConfig},
     prelude::*,
 };
 use gw_version::Version;
@@ -46,6 +71,7 @@ type GwUint64 = gw_jsonrpc_types::ckb_jsonrpc_types::Uint64;
 
 const HEADER_NOT_FOUND_ERR_CODE: i64 = -32000;
 const INVALID_NONCE_ERR_CODE: i64 = -32001;
+const HISTORY_PRUNED_ERR_CODE: i64 = -32002;
 const INTERNAL_ERROR_ERR_CODE: i64 = -32099;
 const METHOD_NOT_AVAILABLE_ERR_CODE: i64 = -32601;
 const INVALID_PARAM_ERR_CODE: i64 = -32602;
@@ -59,6 +85,21 @@ fn header_not_found_err() -> RpcError {
     }
 }
 
+fn history_pruned_err() -> RpcError {
+    RpcError::Provided {
+        code: HISTORY_PRUNED_ERR_CODE,
+        message: "history is pruned",
+    }
+}
+
+fn history_query_err(err: anyhow::Error) -> RpcError {
+    if err.is::<HistoryPrunedError>() {
+        history_pruned_err()
+    } else {
+        err.into()
+    }
+}
+
 fn mem_pool_is_disabled_err() -> RpcError {
     RpcError::Provided {
         code: METHOD_NOT_AVAILABLE_ERR_CODE,
@@ -99,10 +140,11 @@ pub struct Registry {
     rollup_config: RollupConfig,
     debug_config: DebugConfig,
     mem_pool_config: MemPoolConfig,
-    backend_info: Vec<BackendInfo>,
     node_mode: NodeMode,
     mem_pool_batch: Option<MemPoolBatch>,
     server_config: RPCServerConfig,
+    rpc_cache: Option<Arc<RPCCache>>,
+    rpc_client: RPCClient,
 }
 
 impl Registry {
@@ -119,11 +161,16 @@ impl Registry {
         node_mode: NodeMode,
         mem_pool_batch: Option<MemPoolBatch>,
         server_config: RPCServerConfig,
+        rpc_config: RPCConfig,
+        rpc_client: RPCClient,
     ) -> Self
     where
         T: TestModeRPC + Send + Sync + 'static,
     {
-        let backend_info = get_backend_info(generator.clone());
+        let rpc_cache = match rpc_config.cache_size {
+            Some(cache_size) if cache_size > 0 => Some(Arc::new(RPCCache::new(cache_size))),
+            _ => None,
+        };
         Self {
             store,
             generator,
@@ -134,13 +181,25 @@ impl Registry {
             chain,
             offchain_mock_context,
             mem_pool_config,
-            backend_info,
             node_mode,
             mem_pool_batch,
             server_config,
+            rpc_cache,
+            rpc_client,
         }
     }
 
+    /// Build handler of the binary transport, it shares queries and the cache
+    /// with JSON-RPC
+    pub fn build_binary_handler(&self) -> BinaryHandler {
+        BinaryHandler::new(
+            self.store.clone(),
+            self.rollup_config.clone(),
+            self.rpc_cache.clone(),
+        )
+        .with_auth_token(self.server_config.binary_auth_token.clone())
+    }
+
     pub fn build_rpc_server(self) -> Result<RPCServer> {
         let mut server = JsonrpcServer::new();
 
@@ -149,8 +208,9 @@ impl Registry {
             .with_data(Data::new(self.store))
             .with_data(Data::new(self.rollup_config))
             .with_data(Data::new(self.mem_pool_config))
-            .with_data(Data::new(self.backend_info))
             .with_data(Data::new(self.mem_pool_batch))
+            .with_data(Data::new(self.rpc_cache))
+            .with_data(Data::new(self.rpc_client))
             .with_method("gw_ping", ping)
             .with_method("gw_get_tip_block_hash", get_tip_block_hash)
             .with_method("gw_get_block_hash", get_block_hash)
@@ -173,6 +233,15 @@ impl Registry {
             .with_method("gw_get_data", get_data)
             .with_method("gw_get_transaction", get_transaction)
             .with_method("gw_get_transaction_receipt", get_transaction_receipt)
+            .with_method(
+                "gw_get_transactions_by_account",
+                get_transactions_by_account,
+            )
+            .with_method("gw_get_accounts", get_accounts)
+            .with_method("gw_get_withdrawal", get_withdrawal)
+            .with_method("gw_get_deposit", get_deposit)
+            .with_method("gw_get_deposits_by_tx_hash", get_deposits_by_tx_hash)
+            .with_method("gw_get_pending_deposits", get_pending_deposits)
             .with_method("gw_execute_l2transaction", execute_l2transaction)
             .with_method("gw_execute_raw_l2transaction", execute_raw_l2transaction)
             .with_method(
@@ -205,6 +274,16 @@ impl Registry {
                 .with_method(
                     "debug_dump_cancel_challenge_tx",
                     debug_dump_cancel_challenge_tx,
+                )
+                .with_method("gw_debug_trace_transaction", debug_trace_transaction)
+                .with_method(
+                    "gw_debug_trace_raw_transaction",
+                    debug_trace_raw_transaction,
+                )
+                .with_method("gw_debug_profile_transaction", debug_profile_transaction)
+                .with_method(
+                    "gw_debug_profile_raw_transaction",
+                    debug_profile_raw_transaction,
                 );
         }
 
@@ -218,7 +297,11 @@ impl Registry {
                 RPCMethods::Test => {
                     server = server
                         .with_method("gw_dump_mem_block", dump_mem_block)
-                        .with_method("gw_get_rocksdb_mem_stats", get_rocksdb_memory_stats);
+                        .with_method("gw_get_rocksdb_mem_stats", get_rocksdb_memory_stats)
+                        .with_method("gw_get_rpc_cache_stats", get_rpc_cache_stats);
+                }
+                RPCMethods::Admin => {
+                    server = server.with_method("gw_create_db_checkpoint", create_db_checkpoint);
                 }
             }
         }
@@ -227,10 +310,10 @@ impl Registry {
     }
 }
 
-fn get_backend_info(generator: Arc<Generator>) -> Vec<BackendInfo> {
+/// Get info of backends activated at the block
+fn get_backend_info(generator: &Generator, block_info: &BlockInfo) -> Vec<BackendInfo> {
     generator
-        .get_backends()
-        .values()
+        .get_block_backends(block_info)
         .map(|b| {
             let mut validator_code_hash = [0u8; 32];
             let mut hasher = new_blake2b();
@@ -294,33 +377,8 @@ async fn get_transaction(
             (to_h256(tx_hash), verbose)
         }
     };
-    let db = store.begin_transaction();
-    let tx_opt;
-    let status;
-    match db.get_transaction_info(&tx_hash)? {
-        Some(tx_info) => {
-            let tx_block_number = tx_info.block_number().unpack();
-
-            // return None if tx's committed block is reverted
-            if !db
-                .reverted_block_smt()?
-                .get(&RawL2Block::compute_smt_key(tx_block_number).into())?
-                .is_zero()
-            {
-                // block is reverted
-                return Ok(None);
-            }
-
-            tx_opt = db.get_transaction_by_key(&tx_info.key())?;
-            status = L2TransactionStatus::Committed;
-        }
-        None => {
-            tx_opt = db.get_mem_pool_transaction(&tx_hash)?;
-            status = L2TransactionStatus::Pending;
-        }
-    };
-
-    Ok(tx_opt.map(|tx| match verbose {
+    let tx_opt = query::get_transaction(&store, &tx_hash)?;
+    Ok(tx_opt.map(|(tx, status)| match verbose {
         GetTxVerbose::OnlyStatus => L2TransactionWithStatus {
             transaction: None,
             status,
@@ -332,6 +390,87 @@ async fn get_transaction(
     }))
 }
 
+// account_id, order, limit, cursor
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum GetTransactionsByAccountParams {
+    Default((AccountID, Order, Uint32)),
+    WithCursor((AccountID, Order, Uint32, Option<JsonBytes>)),
+}
+
+async fn get_transactions_by_account(
+    Params(param): Params<GetTransactionsByAccountParams>,
+    store: Data<Store>,
+) -> Result<Pagination<AccountTransaction>, RpcError> {
+    let (account_id, order, limit, cursor) = match param {
+        GetTransactionsByAccountParams::Default((account_id, order, limit)) => {
+            (account_id, order, limit, None)
+        }
+        GetTransactionsByAccountParams::WithCursor((account_id, order, limit, cursor)) => {
+            (account_id, order, limit, cursor)
+        }
+    };
+    let account_id: u32 = account_id.into();
+    let limit = limit.value() as usize;
+    if limit == 0 || limit > query::MAX_PAGE_SIZE {
+        return Err(invalid_param_err("invalid limit"));
+    }
+    let cursor = match cursor {
+        Some(cursor) => {
+            let cursor = cursor.into_bytes();
+            if cursor.len() != 16 {
+                return Err(invalid_param_err("invalid cursor"));
+            }
+            let key = AccountTransactionKey::from_slice(&cursor);
+            if key.account_id() != account_id {
+                return Err(invalid_param_err("cursor doesn't belong to account"));
+            }
+            Some(key)
+        }
+        None => None,
+    };
+    let direction = match order {
+        Order::Asc => Direction::Forward,
+        Order::Desc => Direction::Reverse,
+    };
+
+    let (records, next_cursor) =
+        query::get_transactions_by_account(&store, account_id, direction, cursor.as_ref(), limit)?;
+    let objects = records
+        .into_iter()
+        .map(|record| AccountTransaction {
+            block_number: record.block_number.into(),
+            block_hash: to_jsonh256(record.block_hash),
+            tx_index: record.tx_index.into(),
+            transaction: record.transaction.into(),
+        })
+        .collect();
+    let last_cursor = next_cursor.map(|key| JsonBytes::from_vec(key.as_slice().to_vec()));
+    Ok(Pagination {
+        objects,
+        last_cursor,
+    })
+}
+
+async fn get_accounts(
+    Params((from_id, limit)): Params<(AccountID, Uint32)>,
+    store: Data<Store>,
+) -> Result<Vec<AccountInfo>, RpcError> {
+    let limit = limit.value() as usize;
+    if limit == 0 || limit > query::MAX_PAGE_SIZE {
+        return Err(invalid_param_err("invalid limit"));
+    }
+    let accounts = query::get_accounts(&store, from_id.into(), limit)?;
+    Ok(accounts
+        .into_iter()
+        .map(|(id, script_hash, script)| AccountInfo {
+            id: id.into(),
+            script_hash: to_jsonh256(script_hash),
+            script: script.map(Into::into),
+        })
+        .collect())
+}
+
 async fn get_block_committed_info(
     Params((block_hash,)): Params<(JsonH256,)>,
     store: Data<Store>,
@@ -352,32 +491,8 @@ async fn get_block(
     rollup_config: Data<RollupConfig>,
 ) -> Result<Option<L2BlockWithStatus>> {
     let block_hash = to_h256(block_hash);
-    let db = store.begin_transaction();
-    let block = match db.get_block(&block_hash)? {
-        Some(block) => block,
-        None => return Ok(None),
-    };
-
-    // check block status
-    let mut status = L2BlockStatus::Unfinalized;
-    if !db.reverted_block_smt()?.get(&block_hash)?.is_zero() {
-        // block is reverted
-        status = L2BlockStatus::Reverted;
-    } else {
-        // return None if block is not on the main chain
-        if db.block_smt()?.get(&block.smt_key().into())? != block_hash {
-            return Ok(None);
-        }
-
-        // block is on main chain
-        let tip_block_number = db.get_last_valid_tip_block()?.raw().number().unpack();
-        let block_number = block.raw().number().unpack();
-        if tip_block_number >= block_number + rollup_config.finality_blocks().unpack() {
-            status = L2BlockStatus::Finalized;
-        }
-    }
-
-    Ok(Some(L2BlockWithStatus {
+    let block_opt = query::get_block(&store, &rollup_config, &block_hash)?;
+    Ok(block_opt.map(|(block, status)| L2BlockWithStatus {
         block: block.into(),
         status,
     }))
@@ -386,18 +501,11 @@ async fn get_block(
 async fn get_block_by_number(
     Params((block_number,)): Params<(gw_jsonrpc_types::ckb_jsonrpc_types::Uint64,)>,
     store: Data<Store>,
+    rpc_cache: Data<Option<Arc<RPCCache>>>,
 ) -> Result<Option<L2BlockView>> {
     let block_number = block_number.value();
-    let db = store.begin_transaction();
-    let block_hash = match db.get_block_hash_by_number(block_number)? {
-        Some(hash) => hash,
-        None => return Ok(None),
-    };
-    let block_opt = db.get_block(&block_hash)?.map(|block| {
-        let block_view: L2BlockView = block.into();
-        block_view
-    });
-    Ok(block_opt)
+    let block_opt = query::get_block_by_number(&store, rpc_cache.as_deref(), block_number)?;
+    Ok(block_opt.map(Into::into))
 }
 
 async fn get_block_hash(
@@ -405,8 +513,7 @@ async fn get_block_hash(
     store: Data<Store>,
 ) -> Result<Option<JsonH256>> {
     let block_number = block_number.value();
-    let db = store.begin_transaction();
-    let hash_opt = db.get_block_hash_by_number(block_number)?.map(to_jsonh256);
+    let hash_opt = query::get_block_hash(&store, block_number)?.map(to_jsonh256);
     Ok(hash_opt)
 }
 
@@ -415,23 +522,126 @@ async fn get_tip_block_hash(store: Data<Store>) -> Result<JsonH256> {
     Ok(to_jsonh256(tip_block_hash))
 }
 
+// tx_hash, with_access_list
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum GetTxReceiptParams {
+    Default((JsonH256,)),
+    WithAccessList((JsonH256, bool)),
+}
+
 async fn get_transaction_receipt(
-    Params((tx_hash,)): Params<(JsonH256,)>,
+    Params(param): Params<GetTxReceiptParams>,
     store: Data<Store>,
 ) -> Result<Option<TxReceipt>> {
+    let (tx_hash, with_access_list) = match param {
+        GetTxReceiptParams::Default((tx_hash,)) => (to_h256(tx_hash), false),
+        GetTxReceiptParams::WithAccessList((tx_hash, with_access_list)) => {
+            (to_h256(tx_hash), with_access_list)
+        }
+    };
+    let mut receipt: TxReceipt = match query::get_transaction_receipt(&store, &tx_hash)? {
+        Some(receipt) => receipt.into(),
+        None => return Ok(None),
+    };
+    if with_access_list {
+        receipt.access_list = query::get_transaction_access_list(&store, &tx_hash)?.map(Into::into);
+    }
+    Ok(Some(receipt))
+}
+
+async fn get_withdrawal(
+    Params((withdrawal_hash,)): Params<(JsonH256,)>,
+    store: Data<Store>,
+    generator: Data<Generator>,
+    rpc_client: Data<RPCClient>,
+) -> Result<Option<WithdrawalWithStatus>> {
+    let withdrawal_hash = to_h256(withdrawal_hash);
+    let record = match query::get_withdrawal(&store, generator.rollup_context(), &withdrawal_hash)?
+    {
+        Some(record) => record,
+        None => return Ok(None),
+    };
+
+    // finalized withdrawal cell can be unlocked by the owner, check it on layer1
+    let mut status = record.status;
+    if let (WithdrawalStatus::Finalized, Some(submit_tx_hash)) = (status, record.submit_tx_hash) {
+        let submit_tx = rpc_client.get_transaction(submit_tx_hash).await?;
+        let rollup_context = generator.rollup_context();
+        let out_point =
+            submit_tx.and_then(|tx| query::withdrawal_cell_out_point(rollup_context, &record, &tx));
+        if let Some(out_point) = out_point {
+            let live_cell = rpc_client.get_cell(out_point).await?;
+            status = query::withdrawal_status_on_layer1(&record, live_cell.as_ref());
+        }
+    }
+
+    Ok(Some(WithdrawalWithStatus {
+        withdrawal: record.withdrawal.into(),
+        status,
+        block_number: record.block.map(|(_, number)| number.into()),
+        block_hash: record.block.map(|(hash, _)| to_jsonh256(hash)),
+    }))
+}
+
+async fn get_deposit(
+    Params((out_point,)): Params<(OutPoint,)>,
+    store: Data<Store>,
+    generator: Data<Generator>,
+    mem_pool_batch: Data<Option<MemPoolBatch>>,
+) -> Result<Option<DepositWithStatus>, RpcError> {
+    let out_point: packed::OutPoint = out_point.into();
+    if let Some(record) = query::get_deposit(&store, generator.rollup_context(), &out_point)? {
+        return Ok(Some(to_deposit_with_status(record)));
+    }
+
+    // search from mem block
+    if mem_pool_batch.is_none() {
+        return Ok(None);
+    }
+    let pending_deposit = query::get_pending_deposit(&store, &out_point)?;
+    Ok(pending_deposit.map(to_deposit_with_status))
+}
+
+async fn get_deposits_by_tx_hash(
+    Params((tx_hash,)): Params<(JsonH256,)>,
+    store: Data<Store>,
+    generator: Data<Generator>,
+) -> Result<Vec<DepositWithStatus>> {
     let tx_hash = to_h256(tx_hash);
-    let db = store.begin_transaction();
-    // search from db
-    if let Some(receipt) = db.get_transaction_receipt(&tx_hash)?.map(|receipt| {
-        let receipt: TxReceipt = receipt.into();
-        receipt
-    }) {
-        return Ok(Some(receipt));
+    let deposits = query::get_deposits_by_tx_hash(&store, generator.rollup_context(), &tx_hash)?;
+    Ok(deposits.into_iter().map(to_deposit_with_status).collect())
+}
+
+async fn get_pending_deposits(
+    store: Data<Store>,
+    generator: Data<Generator>,
+    mem_pool_batch: Data<Option<MemPoolBatch>>,
+    rpc_client: Data<RPCClient>,
+) -> Result<Vec<DepositWithStatus>, RpcError> {
+    if mem_pool_batch.is_none() {
+        return Err(mem_pool_is_disabled_err());
     }
-    // search from mem pool
-    match db.get_mem_pool_transaction_receipt(&tx_hash)? {
-        Some(receipt) => Ok(Some(receipt.into())),
-        None => Ok(None),
+
+    let mut deposits = query::get_pending_deposits(&store)?;
+    // deposit cells on layer1 which mem-pool hasn't collected yet
+    let cells = {
+        let cells = rpc_client.query_deposit_cells(query::MAX_PAGE_SIZE).await?;
+        sanitize_deposit_cells(generator.rollup_context(), cells)
+    };
+    let layer1_deposits = query::get_layer1_deposits(&store, cells, &deposits)?;
+    deposits.extend(layer1_deposits);
+    Ok(deposits.into_iter().map(to_deposit_with_status).collect())
+}
+
+fn to_deposit_with_status(record: query::DepositRecord) -> DepositWithStatus {
+    DepositWithStatus {
+        out_point: record.out_point.into(),
+        request: record.request.into(),
+        status: record.status,
+        block_number: record.block.map(|(_, number)| number.into()),
+        block_hash: record.block.map(|(hash, _)| to_jsonh256(hash)),
+        account_id: record.account_id.map(Into::into),
     }
 }
 
@@ -483,12 +693,62 @@ async fn execute_l2transaction(
     Ok(run_result.into())
 }
 
-// raw_l2tx, block_number
+// raw_l2tx, block_number, state_override
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
 enum ExecuteRawL2TransactionParams {
     Tip((JsonBytes,)),
     Number((JsonBytes, Option<GwUint64>)),
+    StateOverride((JsonBytes, Option<GwUint64>, Option<StateOverride>)),
+}
+
+impl ExecuteRawL2TransactionParams {
+    fn into_parts(self) -> (JsonBytes, Option<u64>, Option<StateOverride>) {
+        let (raw_l2tx, block_number_opt, state_override) = match self {
+            ExecuteRawL2TransactionParams::Tip(p) => (p.0, None, None),
+            ExecuteRawL2TransactionParams::Number((raw_l2tx, block_number_opt)) => {
+                (raw_l2tx, block_number_opt, None)
+            }
+            ExecuteRawL2TransactionParams::StateOverride(p) => p,
+        };
+        (
+            raw_l2tx,
+            block_number_opt.map(|n| n.value()),
+            state_override,
+        )
+    }
+
+    /// Debug RPCs don't support state overrides
+    fn into_parts_without_override(self) -> Result<(JsonBytes, Option<u64>), RpcError> {
+        match self.into_parts() {
+            (raw_l2tx, block_number_opt, None) => Ok((raw_l2tx, block_number_opt)),
+            (_, _, Some(_)) => Err(invalid_param_err("state override is not supported")),
+        }
+    }
+}
+
+/// Execute a raw tx on the state, overrides are applied to an overlay of the
+/// state and never written back.
+fn execute_raw_l2transaction_with_override<S: State + CodeStore, C: ChainStore>(
+    generator: &Generator,
+    chain: &C,
+    state: &S,
+    block_info: &BlockInfo,
+    raw_l2tx: &packed::RawL2Transaction,
+    max_cycles: u64,
+    state_override: Option<&offchain::StateOverride>,
+) -> Result<offchain::RunResult> {
+    let run_result = match state_override {
+        Some(state_override) => {
+            let mut overlay = OverlayState::new(state);
+            overlay.apply_state_override(state_override)?;
+            generator
+                .unchecked_execute_transaction(chain, &overlay, block_info, raw_l2tx, max_cycles)?
+        }
+        None => generator
+            .unchecked_execute_transaction(chain, state, block_info, raw_l2tx, max_cycles)?,
+    };
+    Ok(run_result)
 }
 
 async fn execute_raw_l2transaction(
@@ -497,41 +757,16 @@ async fn execute_raw_l2transaction(
     store: Data<Store>,
     generator: Data<Generator>,
 ) -> Result<RunResult, RpcError> {
-    let (raw_l2tx, block_number_opt) = match params {
-        ExecuteRawL2TransactionParams::Tip(p) => (p.0, None),
-        ExecuteRawL2TransactionParams::Number(p) => p,
-    };
-    let block_number_opt = block_number_opt.map(|n| n.value());
+    let (raw_l2tx, block_number_opt, json_state_override) = params.into_parts();
+    let state_override: Option<offchain::StateOverride> =
+        json_state_override.clone().map(Into::into);
 
     let raw_l2tx_bytes = raw_l2tx.into_bytes();
     let raw_l2tx = packed::RawL2Transaction::from_slice(&raw_l2tx_bytes)?;
 
     let db = store.begin_transaction();
 
-    let block_info = match block_number_opt {
-        Some(block_number) => {
-            let block_hash = match db.get_block_hash_by_number(block_number)? {
-                Some(block_hash) => block_hash,
-                None => return Err(header_not_found_err()),
-            };
-            let raw_block = match store.get_block(&block_hash)? {
-                Some(block) => block.raw(),
-                None => return Err(header_not_found_err()),
-            };
-            let block_producer_id = raw_block.block_producer_id();
-            let timestamp = raw_block.timestamp();
-            let number: u64 = raw_block.number().unpack();
-
-            BlockInfo::new_builder()
-                .block_producer_id(block_producer_id)
-                .timestamp(timestamp)
-                .number(number.pack())
-                .build()
-        }
-        None => db
-            .get_mem_pool_block_info()?
-            .expect("get mem pool block info"),
-    };
+    let block_info = get_block_info(&store, &db, block_number_opt)?;
 
     let execute_l2tx_max_cycles = mem_pool_config.execute_l2tx_max_cycles;
     let tx_hash: H256 = raw_l2tx.hash().into();
@@ -547,22 +782,26 @@ async fn execute_raw_l2transaction(
         let run_result = match block_number_opt {
             Some(block_number) => {
                 let state = db.state_tree(StateContext::ReadOnlyHistory(block_number))?;
-                generator.unchecked_execute_transaction(
+                execute_raw_l2transaction_with_override(
+                    &generator,
                     &chain_view,
                     &state,
                     &block_info,
                     &raw_l2tx,
                     execute_l2tx_max_cycles,
+                    state_override.as_ref(),
                 )?
             }
             None => {
                 let state = db.mem_pool_state_tree()?;
-                generator.unchecked_execute_transaction(
+                execute_raw_l2transaction_with_override(
+                    &generator,
                     &chain_view,
                     &state,
                     &block_info,
                     &raw_l2tx,
                     execute_l2tx_max_cycles,
+                    state_override.as_ref(),
                 )?
             }
         };
@@ -585,18 +824,57 @@ async fn execute_raw_l2transaction(
         });
     }
 
-    Ok(run_result.into())
+    let mut run_result: RunResult = run_result.into();
+    run_result.state_override = json_state_override;
+    Ok(run_result)
+}
+
+/// Get info of the block, or the mem block if the block number is unset
+fn get_block_info(
+    store: &Store,
+    db: &StoreTransaction,
+    block_number_opt: Option<u64>,
+) -> Result<BlockInfo, RpcError> {
+    let block_info = match block_number_opt {
+        Some(block_number) => {
+            if db.check_history_available(block_number).is_err() {
+                return Err(history_pruned_err());
+            }
+            let block_hash = match db.get_block_hash_by_number(block_number)? {
+                Some(block_hash) => block_hash,
+                None => return Err(header_not_found_err()),
+            };
+            let raw_block = match store.get_block(&block_hash)? {
+                Some(block) => block.raw(),
+                None => return Err(header_not_found_err()),
+            };
+            let block_producer_id = raw_block.block_producer_id();
+            let timestamp = raw_block.timestamp();
+            let number: u64 = raw_block.number().unpack();
+
+            BlockInfo::new_builder()
+                .block_producer_id(block_producer_id)
+                .timestamp(timestamp)
+                .number(number.pack())
+                .build()
+        }
+        None => db
+            .get_mem_pool_block_info()?
+            .expect("get mem pool block info"),
+    };
+    Ok(block_info)
 }
 
 fn get_backend_type(
     state: gw_store::state::mem_pool_state_db::MemPoolStateTree,
     generator: Data<Generator>,
     raw_l2tx: &packed::RawL2Transaction,
+    block_info: &BlockInfo,
 ) -> Result<BackendType, RpcError> {
     let to_id = raw_l2tx.to_id().unpack();
     let script_hash = state.get_script_hash(to_id)?;
     let backend_type = generator
-        .load_backend(&state, &script_hash)
+        .load_backend(&state, &script_hash, block_info)
         .map(|backend| backend.backend_type)
         .ok_or(RpcError::Full {
             code: INVALID_PARAM_ERR_CODE,
@@ -649,7 +927,8 @@ async fn submit_l2transaction(
 
     // check tx fee or gasPrice of the l2tx
     let raw_l2tx = tx.raw();
-    let backend_type = get_backend_type(tree, generator, &raw_l2tx)?;
+    let block_info = get_block_info(&store, &db, None)?;
+    let backend_type = get_backend_type(tree, generator, &raw_l2tx, &block_info)?;
     gw_utils::fee::check_l2tx_fee(&mem_pool_config.fee_config, &raw_l2tx, backend_type).map_err(
         |err| {
             log::debug!("check_fee_ret err: {}", err);
@@ -739,17 +1018,13 @@ async fn get_balance(
         GetBalanceParams::Number(p) => p,
     };
 
-    let db = store.begin_transaction();
-    let balance = match block_number {
-        Some(block_number) => {
-            let tree = db.state_tree(StateContext::ReadOnlyHistory(block_number.into()))?;
-            tree.get_sudt_balance(sudt_id.into(), short_address.as_bytes())?
-        }
-        None => {
-            let tree = db.mem_pool_state_tree()?;
-            tree.get_sudt_balance(sudt_id.into(), short_address.as_bytes())?
-        }
-    };
+    let balance = query::get_balance(
+        &store,
+        short_address.as_bytes(),
+        sudt_id.into(),
+        block_number.map(Into::into),
+    )
+    .map_err(history_query_err)?;
     Ok(balance.into())
 }
 
@@ -770,19 +1045,14 @@ async fn get_storage_at(
         GetStorageAtParams::Number(p) => p,
     };
 
-    let db = store.begin_transaction();
-    let value = match block_number {
-        Some(block_number) => {
-            let tree = db.state_tree(StateContext::ReadOnlyHistory(block_number.into()))?;
-            let key: H256 = to_h256(key);
-            tree.get_value(account_id.into(), &key)?
-        }
-        None => {
-            let tree = db.mem_pool_state_tree()?;
-            let key: H256 = to_h256(key);
-            tree.get_value(account_id.into(), &key)?
-        }
-    };
+    let key: H256 = to_h256(key);
+    let value = query::get_storage_at(
+        &store,
+        account_id.into(),
+        &key,
+        block_number.map(Into::into),
+    )
+    .map_err(history_query_err)?;
 
     let json_value = to_jsonh256(value);
     Ok(json_value)
@@ -791,17 +1061,12 @@ async fn get_storage_at(
 async fn get_account_id_by_script_hash(
     Params((script_hash,)): Params<(JsonH256,)>,
     store: Data<Store>,
+    rpc_cache: Data<Option<Arc<RPCCache>>>,
 ) -> Result<Option<AccountID>, RpcError> {
-    let db = store.begin_transaction();
-    let tree = db.mem_pool_state_tree()?;
-
     let script_hash = to_h256(script_hash);
-
-    let account_id_opt = tree
-        .get_account_id_by_script_hash(&script_hash)?
-        .map(Into::into);
-
-    Ok(account_id_opt)
+    let account_id_opt =
+        query::get_account_id_by_script_hash(&store, rpc_cache.as_deref(), &script_hash)?;
+    Ok(account_id_opt.map(Into::into))
 }
 
 // account_id, block_number
@@ -821,42 +1086,28 @@ async fn get_nonce(
         GetNonceParams::Number(p) => p,
     };
 
-    let db = store.begin_transaction();
-    let nonce = match block_number {
-        Some(block_number) => {
-            let tree = db.state_tree(StateContext::ReadOnlyHistory(block_number.into()))?;
-            tree.get_nonce(account_id.into())?
-        }
-        None => {
-            let tree = db.mem_pool_state_tree()?;
-            tree.get_nonce(account_id.into())?
-        }
-    };
-
+    let nonce = query::get_nonce(&store, account_id.into(), block_number.map(Into::into))
+        .map_err(history_query_err)?;
     Ok(nonce.into())
 }
 
 async fn get_script(
     Params((script_hash,)): Params<(JsonH256,)>,
     store: Data<Store>,
+    rpc_cache: Data<Option<Arc<RPCCache>>>,
 ) -> Result<Option<Script>, RpcError> {
-    let db = store.begin_transaction();
-    let tree = db.mem_pool_state_tree()?;
-
     let script_hash = to_h256(script_hash);
-    let script_opt = tree.get_script(&script_hash).map(Into::into);
-
-    Ok(script_opt)
+    let script_opt = query::get_script(&store, rpc_cache.as_deref(), &script_hash)?;
+    Ok(script_opt.map(Into::into))
 }
 
 async fn get_script_hash(
     Params((account_id,)): Params<(AccountID,)>,
     store: Data<Store>,
+    rpc_cache: Data<Option<Arc<RPCCache>>>,
 ) -> Result<JsonH256, RpcError> {
-    let db = store.begin_transaction();
-    let tree = db.mem_pool_state_tree()?;
-
-    let script_hash = tree.get_script_hash(account_id.into())?;
+    let account_id: u32 = account_id.into();
+    let script_hash = query::get_script_hash(&store, rpc_cache.as_deref(), account_id)?;
     Ok(to_jsonh256(script_hash))
 }
 
@@ -887,13 +1138,7 @@ async fn get_data(
         GetDataParams::Number(p) => p,
     };
 
-    let db = store.begin_transaction();
-    let tree = db.mem_pool_state_tree()?;
-
-    let data_opt = tree
-        .get_data(&to_h256(data_hash))
-        .map(JsonBytes::from_bytes);
-
+    let data_opt = query::get_data(&store, &to_h256(data_hash))?.map(JsonBytes::from_bytes);
     Ok(data_opt)
 }
 
@@ -906,10 +1151,16 @@ async fn compute_l2_sudt_script_hash(
     Ok(to_jsonh256(l2_sudt_script.hash().into()))
 }
 
-async fn get_node_info(backend_info: Data<Vec<BackendInfo>>) -> Result<NodeInfo> {
+async fn get_node_info(store: Data<Store>, generator: Data<Generator>) -> Result<NodeInfo> {
+    let raw_tip_block = store.get_tip_block()?.raw();
+    let tip_block_info = BlockInfo::new_builder()
+        .block_producer_id(raw_tip_block.block_producer_id())
+        .timestamp(raw_tip_block.timestamp())
+        .number(raw_tip_block.number())
+        .build();
     Ok(NodeInfo {
         version: Version::current().to_string(),
-        backends: backend_info.clone(),
+        backends: get_backend_info(&generator, &tip_block_info),
     })
 }
 
@@ -994,6 +1245,305 @@ async fn debug_dump_cancel_challenge_tx(
     })
 }
 
+/// Return the block number and index of a committed tx, `None` if not found
+fn get_transaction_position(
+    db: &StoreTransaction,
+    tx_hash: &H256,
+) -> Result<Option<(u64, u32)>, RpcError> {
+    let tx_info = match db.get_transaction_info(tx_hash)? {
+        Some(tx_info) => tx_info,
+        None => return Ok(None),
+    };
+    let block_number: u64 = tx_info.block_number().unpack();
+    if db
+        .check_history_available(block_number.saturating_sub(1))
+        .is_err()
+    {
+        return Err(history_pruned_err());
+    }
+    let tx_index = {
+        let mut index = [0u8; 4];
+        index.copy_from_slice(&tx_info.key().as_slice()[32..]);
+        u32::from_be_bytes(index)
+    };
+    Ok(Some((block_number, tx_index)))
+}
+
+/// Return the block and its deposit requests
+fn get_block_with_deposits(
+    db: &StoreTransaction,
+    block_number: u64,
+) -> Result<(packed::L2Block, Vec<packed::DepositRequest>)> {
+    let block_hash = db
+        .get_block_hash_by_number(block_number)?
+        .ok_or_else(|| anyhow::anyhow!("block #{} not found", block_number))?;
+    let block = db
+        .get_block(&block_hash)?
+        .ok_or_else(|| anyhow::anyhow!("block #{} not found", block_number))?;
+    let deposit_requests = db
+        .get_block_deposit_requests(&block_hash)?
+        .unwrap_or_default();
+    Ok((block, deposit_requests))
+}
+
+async fn debug_trace_transaction(
+    Params((tx_hash,)): Params<(JsonH256,)>,
+    store: Data<Store>,
+    generator: Data<Generator>,
+) -> Result<Option<TxTrace>, RpcError> {
+    let tx_hash = to_h256(tx_hash);
+    let db = store.begin_transaction();
+    let (block_number, tx_index) = match get_transaction_position(&db, &tx_hash)? {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    // re-execute tx in task on the history state before its block
+    let task: smol::Task<Result<_>> = smol::spawn(async move {
+        let (block, deposit_requests) = get_block_with_deposits(&db, block_number)?;
+        let chain_view = ChainView::new(&db, block.raw().parent_block_hash().unpack());
+        let (run_result, trace) = generator.trace_block_transaction(
+            &db,
+            &chain_view,
+            &block,
+            &deposit_requests,
+            tx_index,
+        )?;
+        Ok((run_result, trace))
+    });
+
+    let (run_result, trace) = task.await?;
+    Ok(Some(to_json_tx_trace(run_result, trace)))
+}
+
+async fn debug_trace_raw_transaction(
+    Params(params): Params<ExecuteRawL2TransactionParams>,
+    mem_pool_config: Data<MemPoolConfig>,
+    store: Data<Store>,
+    generator: Data<Generator>,
+) -> Result<TxTrace, RpcError> {
+    let (raw_l2tx, block_number_opt) = params.into_parts_without_override()?;
+
+    let raw_l2tx_bytes = raw_l2tx.into_bytes();
+    let raw_l2tx = packed::RawL2Transaction::from_slice(&raw_l2tx_bytes)?;
+
+    let db = store.begin_transaction();
+    let block_info = get_block_info(&store, &db, block_number_opt)?;
+    let execute_l2tx_max_cycles = mem_pool_config.execute_l2tx_max_cycles;
+
+    // trace tx in task
+    let task: smol::Task<Result<_>> = smol::spawn(async move {
+        let chain_view = {
+            let tip_block_hash = db.get_tip_block_hash()?;
+            ChainView::new(&db, tip_block_hash)
+        };
+        let traced = match block_number_opt {
+            Some(block_number) => {
+                let state = db.state_tree(StateContext::ReadOnlyHistory(block_number))?;
+                generator.trace_transaction(
+                    &chain_view,
+                    &state,
+                    &block_info,
+                    &raw_l2tx,
+                    execute_l2tx_max_cycles,
+                )?
+            }
+            None => {
+                let state = db.mem_pool_state_tree()?;
+                generator.trace_transaction(
+                    &chain_view,
+                    &state,
+                    &block_info,
+                    &raw_l2tx,
+                    execute_l2tx_max_cycles,
+                )?
+            }
+        };
+        Ok(traced)
+    });
+
+    let (run_result, trace) = task.await?;
+    Ok(to_json_tx_trace(run_result, trace))
+}
+
+async fn debug_profile_transaction(
+    Params((tx_hash,)): Params<(JsonH256,)>,
+    store: Data<Store>,
+    generator: Data<Generator>,
+) -> Result<Option<TxProfile>, RpcError> {
+    let tx_hash = to_h256(tx_hash);
+    let db = store.begin_transaction();
+    let (block_number, tx_index) = match get_transaction_position(&db, &tx_hash)? {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    // re-execute tx in task on the history state before its block
+    let task: smol::Task<Result<_>> = smol::spawn(async move {
+        let (block, deposit_requests) = get_block_with_deposits(&db, block_number)?;
+        let chain_view = ChainView::new(&db, block.raw().parent_block_hash().unpack());
+        let (run_result, profile) = generator.profile_block_transaction(
+            &db,
+            &chain_view,
+            &block,
+            &deposit_requests,
+            tx_index,
+        )?;
+        Ok((run_result, profile))
+    });
+
+    let (run_result, profile) = task.await?;
+    Ok(Some(to_json_tx_profile(run_result, profile)))
+}
+
+async fn debug_profile_raw_transaction(
+    Params(params): Params<ExecuteRawL2TransactionParams>,
+    mem_pool_config: Data<MemPoolConfig>,
+    store: Data<Store>,
+    generator: Data<Generator>,
+) -> Result<TxProfile, RpcError> {
+    let (raw_l2tx, block_number_opt) = params.into_parts_without_override()?;
+
+    let raw_l2tx_bytes = raw_l2tx.into_bytes();
+    let raw_l2tx = packed::RawL2Transaction::from_slice(&raw_l2tx_bytes)?;
+
+    let db = store.begin_transaction();
+    let block_info = get_block_info(&store, &db, block_number_opt)?;
+    let execute_l2tx_max_cycles = mem_pool_config.execute_l2tx_max_cycles;
+
+    // profile tx in task
+    let task: smol::Task<Result<_>> = smol::spawn(async move {
+        let chain_view = {
+            let tip_block_hash = db.get_tip_block_hash()?;
+            ChainView::new(&db, tip_block_hash)
+        };
+        let profiled = match block_number_opt {
+            Some(block_number) => {
+                let state = db.state_tree(StateContext::ReadOnlyHistory(block_number))?;
+                generator.profile_transaction(
+                    &chain_view,
+                    &state,
+                    &block_info,
+                    &raw_l2tx,
+                    execute_l2tx_max_cycles,
+                )?
+            }
+            None => {
+                let state = db.mem_pool_state_tree()?;
+                generator.profile_transaction(
+                    &chain_view,
+                    &state,
+                    &block_info,
+                    &raw_l2tx,
+                    execute_l2tx_max_cycles,
+                )?
+            }
+        };
+        Ok(profiled)
+    });
+
+    let (run_result, profile) = task.await?;
+    Ok(to_json_tx_profile(run_result, profile))
+}
+
+fn to_json_tx_profile(
+    run_result: gw_types::offchain::RunResult,
+    profile: gw_generator::profile::CycleProfile,
+) -> TxProfile {
+    TxProfile {
+        exit_code: run_result.exit_code,
+        used_cycles: run_result.used_cycles.into(),
+        folded_stacks: profile.to_folded(),
+    }
+}
+
+fn to_json_tx_trace(
+    run_result: gw_types::offchain::RunResult,
+    trace: gw_generator::trace::TxTrace,
+) -> TxTrace {
+    use gw_generator::trace::SyscallEvent as Event;
+
+    let syscalls = trace
+        .syscalls
+        .into_iter()
+        .map(|syscall| {
+            let event = match syscall.event {
+                Event::Load { key, value } => SyscallEvent::Load {
+                    key: to_jsonh256(key),
+                    value: to_jsonh256(value),
+                },
+                Event::Store {
+                    key,
+                    old_value,
+                    new_value,
+                } => SyscallEvent::Store {
+                    key: to_jsonh256(key),
+                    old_value: to_jsonh256(old_value),
+                    new_value: to_jsonh256(new_value),
+                },
+                Event::Create {
+                    script_hash,
+                    account_id,
+                } => SyscallEvent::Create {
+                    script_hash: script_hash.map(to_jsonh256),
+                    account_id: account_id.map(Into::into),
+                },
+                Event::Log {
+                    account_id,
+                    service_flag,
+                    data,
+                } => SyscallEvent::Log {
+                    account_id: account_id.into(),
+                    service_flag: u32::from(service_flag).into(),
+                    data: JsonBytes::from_vec(data),
+                },
+                Event::PayFee {
+                    short_address,
+                    sudt_id,
+                    amount,
+                } => SyscallEvent::PayFee {
+                    short_address: JsonBytes::from_vec(short_address),
+                    sudt_id: sudt_id.into(),
+                    amount: amount.into(),
+                },
+                Event::LoadData {
+                    data_hash,
+                    data_len,
+                } => SyscallEvent::LoadData {
+                    data_hash: to_jsonh256(data_hash),
+                    data_len: data_len.map(|len| (len as u32).into()),
+                },
+                Event::DebugPrint { message } => SyscallEvent::DebugPrint { message },
+                Event::Extension {
+                    charged_cycles,
+                    read_keys,
+                    write_keys,
+                } => SyscallEvent::Extension {
+                    charged_cycles: charged_cycles.into(),
+                    read_keys: read_keys.into_iter().map(to_jsonh256).collect(),
+                    write_keys: write_keys.into_iter().map(to_jsonh256).collect(),
+                },
+                Event::Other => SyscallEvent::Other,
+            };
+            SyscallTrace {
+                code: syscall.code.into(),
+                name: syscall.name.to_string(),
+                cycles: syscall.cycles.into(),
+                total_cycles: syscall.total_cycles.into(),
+                ret: syscall.ret,
+                event,
+            }
+        })
+        .collect();
+
+    TxTrace {
+        exit_code: run_result.exit_code,
+        used_cycles: run_result.used_cycles.into(),
+        run_result: run_result.into(),
+        syscalls,
+    }
+}
+
 async fn start_profiler() -> Result<()> {
     log::info!("profiler started");
     *PROFILER_GUARD.lock().unwrap() = Some(ProfilerGuard::new(100).unwrap());
@@ -1031,3 +1581,22 @@ async fn dump_mem_block(mem_pool_batch: Data<Option<MemPoolBatch>>) -> Result<Js
 async fn get_rocksdb_memory_stats(store: Data<Store>) -> Result<Vec<CfMemStat>, RpcError> {
     Ok(store.gather_mem_stats())
 }
+
+async fn get_rpc_cache_stats(
+    rpc_cache: Data<Option<Arc<RPCCache>>>,
+) -> Result<Option<CacheStats>, RpcError> {
+    Ok(rpc_cache.as_deref().map(RPCCache::stats))
+}
+
+async fn create_db_checkpoint(
+    Params((path,)): Params<(String,)>,
+    store: Data<Store>,
+) -> Result<(), RpcError> {
+    let path = std::path::PathBuf::from(path);
+    if path.exists() {
+        return Err(invalid_param_err("checkpoint path already exists"));
+    }
+    store.create_checkpoint(&path)?;
+    log::info!("create db checkpoint in {}", path.display());
+    Ok(())
+}
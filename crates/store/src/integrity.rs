@@ -0,0 +1,457 @@
+//! Store integrity check
+//!
+//! Rebuild the account SMT and the block SMT from their leaves, replay the
+//! block state records to check each block's post account, check the latest
+//! record of each state key against the leaves, and check the main chain
+//! index and the transaction and receipt entries of main chain blocks. The
+//! SMTs are rebuilt in memory, the check is meant for an offline store.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use anyhow::{anyhow, Result};
+use gw_common::{smt::SMT, sparse_merkle_tree::default_store::DefaultStore, H256};
+use gw_db::{
+    schema::{
+        Col, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_BLOCK_SMT_LEAF, COLUMN_BLOCK_STATE_RECORD,
+        COLUMN_BLOCK_STATE_REVERSE_RECORD, COLUMN_INDEX, COLUMN_TRANSACTION,
+        COLUMN_TRANSACTION_INFO, COLUMN_TRANSACTION_RECEIPT,
+    },
+    IteratorMode,
+};
+use gw_types::{
+    packed::{self, L2Block, TransactionKey},
+    prelude::*,
+};
+
+use crate::{
+    traits::KVStore,
+    transaction::{
+        state::{BlockStateRecordKey, BlockStateRecordKeyReverse, NUMBER_OF_CONFIRMATION},
+        StoreTransaction,
+    },
+    Store,
+};
+
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    pub tip_block_number: u64,
+    pub checked_blocks: u64,
+    /// Checks that can't be done on this store, e.g. history is pruned
+    pub skipped: Vec<String>,
+    pub corruptions: Vec<String>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.corruptions.is_empty()
+    }
+
+    fn corrupt(&mut self, msg: String) {
+        self.corruptions.push(msg);
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tip block: #{}", self.tip_block_number)?;
+        writeln!(f, "checked blocks: {}", self.checked_blocks)?;
+        for msg in self.skipped.iter() {
+            writeln!(f, "skipped: {}", msg)?;
+        }
+        for msg in self.corruptions.iter() {
+            writeln!(f, "corrupted: {}", msg)?;
+        }
+        if self.is_ok() {
+            write!(f, "no corruption found")
+        } else {
+            write!(f, "{} corruptions found", self.corruptions.len())
+        }
+    }
+}
+
+/// Check the store, corruptions are collected in the report. Returns error only
+/// if the store can't be read, e.g. the tip block is missing.
+pub fn check_integrity(store: &Store) -> Result<IntegrityReport> {
+    let db = store.begin_transaction();
+    let tip_block = db.get_tip_block()?;
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    let mut report = IntegrityReport {
+        tip_block_number,
+        ..Default::default()
+    };
+
+    check_account_smt(&db, &tip_block, &mut report)?;
+    check_block_smt(&db, &tip_block, &mut report)?;
+    let main_chain = check_index(&db, &tip_block, &mut report);
+    check_blocks(&db, tip_block_number, &main_chain, &mut report)?;
+    check_latest_state_records(&db, tip_block_number, &mut report);
+    check_transaction_infos(&db, tip_block_number, &mut report);
+
+    Ok(report)
+}
+
+fn check_account_smt(
+    db: &StoreTransaction,
+    tip_block: &L2Block,
+    report: &mut IntegrityReport,
+) -> Result<()> {
+    let root = check_smt_leaves(db, COLUMN_ACCOUNT_SMT_LEAF, "account", report)?;
+    let post_account_root: H256 = tip_block.raw().post_account().merkle_root().unpack();
+    if root != post_account_root {
+        report.corrupt("account smt leaves mismatch tip block post account".to_string());
+    }
+    Ok(())
+}
+
+fn check_block_smt(
+    db: &StoreTransaction,
+    tip_block: &L2Block,
+    report: &mut IntegrityReport,
+) -> Result<()> {
+    let root = check_smt_leaves(db, COLUMN_BLOCK_SMT_LEAF, "block", report)?;
+    let meta_root = db.get_block_smt_root()?;
+    if root != meta_root {
+        report.corrupt("block smt leaves mismatch block smt root in meta".to_string());
+    }
+    let tip_block_hash: H256 = tip_block.hash().into();
+    if let Some(global_state) = db.get_block_post_global_state(&tip_block_hash)? {
+        let global_state_root: H256 = global_state.block().merkle_root().unpack();
+        if meta_root != global_state_root {
+            report.corrupt("block smt root in meta mismatch tip block global state".to_string());
+        }
+    } else {
+        report.corrupt("tip block post global state not found".to_string());
+    }
+    Ok(())
+}
+
+/// Rebuild the account SMT root from the stored leaves
+pub fn rebuild_account_smt_root(db: &StoreTransaction) -> Result<H256> {
+    rebuild_smt_root_strict(db, COLUMN_ACCOUNT_SMT_LEAF, "account")
+}
+
+/// Rebuild the block SMT root from the stored leaves
+pub fn rebuild_block_smt_root(db: &StoreTransaction) -> Result<H256> {
+    rebuild_smt_root_strict(db, COLUMN_BLOCK_SMT_LEAF, "block")
+}
+
+fn rebuild_smt_root_strict(db: &StoreTransaction, leaf_col: Col, name: &str) -> Result<H256> {
+    let (root, malformed) = rebuild_smt_root(db, leaf_col, name)?;
+    if malformed > 0 {
+        return Err(anyhow!("{} malformed {} smt leaves", malformed, name));
+    }
+    Ok(root)
+}
+
+fn check_smt_leaves(
+    db: &StoreTransaction,
+    leaf_col: Col,
+    name: &str,
+    report: &mut IntegrityReport,
+) -> Result<H256> {
+    let (root, malformed) = rebuild_smt_root(db, leaf_col, name)?;
+    if malformed > 0 {
+        report.corrupt(format!("{} malformed {} smt leaves", malformed, name));
+    }
+    Ok(root)
+}
+
+/// Returns the root and the number of malformed leaves
+fn rebuild_smt_root(db: &StoreTransaction, leaf_col: Col, name: &str) -> Result<(H256, usize)> {
+    let mut tree: SMT<DefaultStore<H256>> = Default::default();
+    let mut malformed = 0;
+    for (key, value) in db.get_iter(leaf_col, IteratorMode::Start) {
+        match (to_h256(&key), to_h256(&value)) {
+            (Some(key), Some(value)) => {
+                tree.update(key, value)
+                    .map_err(|err| anyhow!("rebuild {} smt error {}", name, err))?;
+            }
+            _ => malformed += 1,
+        }
+    }
+    Ok((*tree.root(), malformed))
+}
+
+/// Check block number => hash and hash => number are bijective and cover the
+/// main chain from genesis to tip, returns the main chain block hashes.
+fn check_index(
+    db: &StoreTransaction,
+    tip_block: &L2Block,
+    report: &mut IntegrityReport,
+) -> BTreeMap<u64, H256> {
+    let mut number_to_hash = BTreeMap::new();
+    let mut hash_to_number = HashMap::new();
+    for (key, value) in db.get_iter(COLUMN_INDEX, IteratorMode::Start) {
+        match key.len() {
+            8 => {
+                let number: u64 = packed::Uint64Reader::from_slice_should_be_ok(&key)
+                    .to_entity()
+                    .unpack();
+                match to_h256(&value) {
+                    Some(hash) => {
+                        number_to_hash.insert(number, hash);
+                    }
+                    None => report.corrupt(format!("malformed index of block #{}", number)),
+                }
+            }
+            32 => match packed::Uint64Reader::from_slice(&value) {
+                Ok(number) => {
+                    let hash = to_h256(&key).expect("checked length");
+                    hash_to_number.insert(hash, number.unpack());
+                }
+                Err(_) => report.corrupt("malformed block hash index".to_string()),
+            },
+            _ => report.corrupt("malformed index key".to_string()),
+        }
+    }
+
+    for (number, hash) in number_to_hash.iter() {
+        if hash_to_number.get(hash) != Some(number) {
+            report.corrupt(format!(
+                "block #{} hash index mismatch number index",
+                number
+            ));
+        }
+    }
+    for (hash, number) in hash_to_number.iter() {
+        if number_to_hash.get(number) != Some(hash) {
+            report.corrupt(format!(
+                "block #{} number index mismatch hash index",
+                number
+            ));
+        }
+    }
+
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    for number in 0..=tip_block_number {
+        if !number_to_hash.contains_key(&number) {
+            report.corrupt(format!("main chain block #{} index not found", number));
+        }
+    }
+    for number in number_to_hash.range(tip_block_number + 1..).map(|(n, _)| n) {
+        report.corrupt(format!("block #{} above tip in main chain index", number));
+    }
+    let tip_block_hash: H256 = tip_block.hash().into();
+    if number_to_hash.get(&tip_block_number) != Some(&tip_block_hash) {
+        report.corrupt("tip block mismatch main chain index".to_string());
+    }
+
+    number_to_hash
+}
+
+/// Returns the block number at or below which state records are pruned.
+///
+/// Pruning on attaching blocks records the pruned block number, but stores
+/// pruned before the number was recorded don't have it. State records before
+/// the confirmation window of them are removed too.
+fn state_records_pruned_block_number(db: &StoreTransaction, tip_block_number: u64) -> Option<u64> {
+    db.get_pruned_block_number().or_else(|| {
+        tip_block_number
+            .checked_sub(NUMBER_OF_CONFIRMATION + 1)
+            .filter(|number| *number > 0)
+    })
+}
+
+/// Replay state records to check post account of main chain blocks, and check
+/// transactions and receipts of them.
+fn check_blocks(
+    db: &StoreTransaction,
+    tip_block_number: u64,
+    main_chain: &BTreeMap<u64, H256>,
+    report: &mut IntegrityReport,
+) -> Result<()> {
+    // blocks at or below it may be removed by the history pruner
+    let pruned_block_number = db.get_pruned_block_number();
+    // state records of pruned blocks are incomplete, they can't be replayed
+    let mut state_tree: Option<SMT<DefaultStore<H256>>> = match state_records_pruned_block_number(
+        db,
+        tip_block_number,
+    ) {
+        Some(pruned) => {
+            report.skipped.push(format!(
+                    "state records of block #0 to #{} are pruned, post account of blocks are not checked",
+                    pruned
+                ));
+            None
+        }
+        None => Some(Default::default()),
+    };
+    let mut records = db
+        .get_iter(COLUMN_BLOCK_STATE_RECORD, IteratorMode::Start)
+        .peekable();
+
+    for (&number, block_hash) in main_chain.range(0..=tip_block_number) {
+        if let Some(tree) = state_tree.as_mut() {
+            while let Some((key, value)) = records.peek() {
+                if key.len() != 40 || value.len() != 32 {
+                    report.corrupt("malformed block state record".to_string());
+                    records.next();
+                    continue;
+                }
+                let record_key = BlockStateRecordKey::from_slice(key);
+                if record_key.block_number() > number {
+                    break;
+                }
+                let value = to_h256(value).expect("checked length");
+                tree.update(record_key.state_key(), value)
+                    .map_err(|err| anyhow!("replay block state record error {}", err))?;
+                records.next();
+            }
+        }
+
+        let block = match db.get_block(block_hash)? {
+            Some(block) => block,
+            None if pruned_block_number.map(|n| number <= n) == Some(true) => continue,
+            None => {
+                report.corrupt(format!("main chain block #{} not found", number));
+                continue;
+            }
+        };
+        if block.raw().number().unpack() != number {
+            report.corrupt(format!(
+                "main chain block #{} number mismatch index",
+                number
+            ));
+        }
+
+        if let Some(tree) = state_tree.as_ref() {
+            let post_account_root: H256 = block.raw().post_account().merkle_root().unpack();
+            if tree.root() != &post_account_root {
+                report.corrupt(format!(
+                    "block #{} post account mismatch state records",
+                    number
+                ));
+            }
+        }
+
+        for (index, tx) in block.transactions().into_iter().enumerate() {
+            let tx_key = TransactionKey::build_transaction_key(block_hash.pack(), index as u32);
+            if db.get(COLUMN_TRANSACTION, tx_key.as_slice()).is_none() {
+                report.corrupt(format!("block #{} tx {} not found", number, index));
+            }
+            if db
+                .get(COLUMN_TRANSACTION_RECEIPT, tx_key.as_slice())
+                .is_none()
+            {
+                report.corrupt(format!("block #{} tx {} receipt not found", number, index));
+            }
+            let tx_hash: H256 = tx.hash().into();
+            match db.get_transaction_info(&tx_hash)? {
+                Some(info) if info.key().as_slice() == tx_key.as_slice() => (),
+                _ => report.corrupt(format!("block #{} tx {} info mismatch", number, index)),
+            }
+        }
+        report.checked_blocks += 1;
+    }
+
+    if state_tree.is_some() && records.next().is_some() {
+        report.corrupt(format!(
+            "block state records above tip block #{}",
+            tip_block_number
+        ));
+    }
+    Ok(())
+}
+
+/// Check transaction infos point to existing transactions and receipts
+fn check_transaction_infos(
+    db: &StoreTransaction,
+    tip_block_number: u64,
+    report: &mut IntegrityReport,
+) {
+    let pruned_block_number = db.get_pruned_block_number();
+    let mut dangling = 0;
+    for (_, value) in db.get_iter(COLUMN_TRANSACTION_INFO, IteratorMode::Start) {
+        let info = match packed::TransactionInfoReader::from_slice(&value) {
+            Ok(info) => info.to_entity(),
+            Err(_) => {
+                dangling += 1;
+                continue;
+            }
+        };
+        let block_number: u64 = info.block_number().unpack();
+        let tx_key = info.key();
+        let is_dangling = block_number > tip_block_number
+            || db.get(COLUMN_TRANSACTION, tx_key.as_slice()).is_none()
+            || db
+                .get(COLUMN_TRANSACTION_RECEIPT, tx_key.as_slice())
+                .is_none();
+        // transactions of pruned blocks are removed by the history pruner
+        let is_pruned = pruned_block_number.map(|n| block_number <= n) == Some(true);
+        if is_dangling && !is_pruned {
+            dangling += 1;
+        }
+    }
+    if dangling > 0 {
+        report.corrupt(format!(
+            "{} transaction infos point to missing transactions or receipts",
+            dangling
+        ));
+    }
+}
+
+/// Check the latest state record of each state key matches the account SMT
+/// leaf. Records above the pruned block are checked even if they can't be
+/// replayed, a latest record at or below it may be followed by pruned ones.
+fn check_latest_state_records(
+    db: &StoreTransaction,
+    tip_block_number: u64,
+    report: &mut IntegrityReport,
+) {
+    let pruned_block_number = state_records_pruned_block_number(db, tip_block_number);
+    let mut mismatched = 0;
+    let mut check_latest = |latest: &BlockStateRecordKeyReverse| {
+        if pruned_block_number.map(|n| latest.block_number() <= n) == Some(true) {
+            return;
+        }
+        let state_key = latest.state_key();
+        let record_key = BlockStateRecordKey::new(latest.block_number(), &state_key);
+        let record = db
+            .get(COLUMN_BLOCK_STATE_RECORD, record_key.as_slice())
+            .and_then(|value| to_h256(&value));
+        let leaf = db
+            .get(COLUMN_ACCOUNT_SMT_LEAF, state_key.as_slice())
+            .and_then(|value| to_h256(&value))
+            .unwrap_or_else(H256::zero);
+        if record != Some(leaf) {
+            mismatched += 1;
+        }
+    };
+
+    // reverse records are sorted by state key, then block number
+    let mut latest: Option<BlockStateRecordKeyReverse> = None;
+    for (key, _) in db.get_iter(COLUMN_BLOCK_STATE_REVERSE_RECORD, IteratorMode::Start) {
+        if key.len() != 40 {
+            continue;
+        }
+        let reverse_key = BlockStateRecordKeyReverse::from_slice(&key);
+        if let Some(prev) = latest.as_ref() {
+            if prev.state_key() != reverse_key.state_key() {
+                check_latest(prev);
+            }
+        }
+        latest = Some(reverse_key);
+    }
+    if let Some(prev) = latest.as_ref() {
+        check_latest(prev);
+    }
+
+    if mismatched > 0 {
+        report.corrupt(format!(
+            "{} latest block state records mismatch account smt leaves",
+            mismatched
+        ));
+    }
+}
+
+fn to_h256(bytes: &[u8]) -> Option<H256> {
+    if bytes.len() != 32 {
+        return None;
+    }
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(bytes);
+    Some(buf.into())
+}
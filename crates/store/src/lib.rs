@@ -1,6 +1,9 @@
 pub mod chain_view;
+pub mod integrity;
+pub mod prune;
 pub mod smt;
 pub mod state;
+pub mod state_snapshot;
 mod store_impl;
 pub mod traits;
 pub mod transaction;
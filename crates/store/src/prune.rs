@@ -0,0 +1,65 @@
+//! History pruner
+//!
+//! Keep history of the last `retained_blocks` blocks before the last
+//! finalized block. Each round prunes at most `max_blocks_per_round` blocks in
+//! one transaction, so deletions are spread over time and RocksDB compacts the
+//! block number prefixed state records in the background.
+
+use anyhow::Result;
+use gw_config::PruneConfig;
+use gw_types::{offchain::RollupContext, prelude::*};
+
+use crate::Store;
+
+#[derive(Clone)]
+pub struct HistoryPruner {
+    store: Store,
+    rollup_context: RollupContext,
+    config: PruneConfig,
+}
+
+impl HistoryPruner {
+    pub fn new(store: Store, rollup_context: RollupContext, config: PruneConfig) -> Self {
+        HistoryPruner {
+            store,
+            rollup_context,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &PruneConfig {
+        &self.config
+    }
+
+    /// Prune a round of blocks, return the new pruned block number if any
+    /// block is pruned.
+    pub fn prune(&self) -> Result<Option<u64>> {
+        let db = self.store.begin_transaction();
+        let tip_number: u64 = db.get_last_valid_tip_block()?.raw().number().unpack();
+        let retained_block_number = self
+            .rollup_context
+            .last_finalized_block_number(tip_number)
+            .saturating_sub(self.config.retained_blocks);
+
+        // keep the genesis block
+        let start = db.get_pruned_block_number().unwrap_or(0) + 1;
+        if start >= retained_block_number {
+            return Ok(None);
+        }
+        let end = (retained_block_number - 1)
+            .min(start.saturating_add(self.config.max_blocks_per_round.max(1) - 1));
+        for block_number in start..=end {
+            db.prune_block_history(block_number, retained_block_number)?;
+        }
+        db.set_pruned_block_number(end)?;
+        db.commit()?;
+
+        log::debug!(
+            "Pruned history of block #{} to #{}, retain from #{}",
+            start,
+            end,
+            retained_block_number
+        );
+        Ok(Some(end))
+    }
+}
@@ -0,0 +1,327 @@
+//! State snapshot
+//!
+//! A state snapshot contains what a node needs to continue syncing from the
+//! snapshot tip: the account SMT, scripts and data, the block SMT, the reverted
+//! block SMT, the main chain index, and the tip block with its global state and
+//! committed info. History states, transactions and blocks below the tip are
+//! not included, the imported store treats them as pruned.
+//!
+//! Format:
+//!
+//! | magic | version(1 byte) | tip block hash(32 bytes) | records | end(1 byte) | checksum(32 bytes) |
+//!
+//! record: | col(1 byte) | key len(u32 LE) | key | value len(u32 LE) | value |
+//!
+//! The checksum is the blake2b hash of all the bytes before it.
+
+use anyhow::{anyhow, bail, Result};
+use gw_common::{
+    blake2b::{new_blake2b, Blake2b},
+    H256,
+};
+use gw_db::{
+    schema::{
+        Col, COLUMN_ACCOUNT_SMT_BRANCH, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_ASSET_SCRIPT, COLUMN_BLOCK,
+        COLUMN_BLOCK_DEPOSIT_REQUESTS, COLUMN_BLOCK_GLOBAL_STATE, COLUMN_BLOCK_SMT_BRANCH,
+        COLUMN_BLOCK_SMT_LEAF, COLUMN_DATA, COLUMN_INDEX, COLUMN_L2BLOCK_COMMITTED_INFO,
+        COLUMN_META, COLUMN_REVERTED_BLOCK_SMT_BRANCH, COLUMN_REVERTED_BLOCK_SMT_LEAF,
+        COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_SCRIPT, COLUMN_SCRIPT_PREFIX,
+        META_BLOCK_SMT_ROOT_KEY, META_CHAIN_ID_KEY, META_LAST_VALID_TIP_BLOCK_HASH_KEY,
+        META_REVERTED_BLOCK_SMT_ROOT_KEY, META_TIP_BLOCK_HASH_KEY,
+    },
+    IteratorMode,
+};
+use gw_types::{
+    packed::{GlobalState, L2Block, L2BlockCommittedInfo},
+    prelude::*,
+};
+use std::io::{Read, Write};
+
+use crate::{integrity, traits::KVStore, Store};
+
+const MAGIC: &[u8] = b"GWSTATESNAPSHOT";
+const VERSION: u8 = 0;
+const RECORDS_END: u8 = u8::MAX;
+// flush the import write batch when it reaches 64MB
+const WRITE_BATCH_SIZE: usize = 64 * 1024 * 1024;
+
+/// Columns exported as a whole
+const STATE_COLUMNS: [Col; 11] = [
+    COLUMN_ACCOUNT_SMT_BRANCH,
+    COLUMN_ACCOUNT_SMT_LEAF,
+    COLUMN_SCRIPT,
+    COLUMN_SCRIPT_PREFIX,
+    COLUMN_DATA,
+    COLUMN_ASSET_SCRIPT,
+    COLUMN_BLOCK_SMT_BRANCH,
+    COLUMN_BLOCK_SMT_LEAF,
+    COLUMN_REVERTED_BLOCK_SMT_BRANCH,
+    COLUMN_REVERTED_BLOCK_SMT_LEAF,
+    COLUMN_REVERTED_BLOCK_SMT_ROOT,
+];
+
+/// Columns only the tip block entry is exported
+const TIP_BLOCK_COLUMNS: [Col; 4] = [
+    COLUMN_BLOCK,
+    COLUMN_BLOCK_GLOBAL_STATE,
+    COLUMN_L2BLOCK_COMMITTED_INFO,
+    COLUMN_BLOCK_DEPOSIT_REQUESTS,
+];
+
+const META_KEYS: [&[u8]; 5] = [
+    META_CHAIN_ID_KEY,
+    META_TIP_BLOCK_HASH_KEY,
+    META_LAST_VALID_TIP_BLOCK_HASH_KEY,
+    META_BLOCK_SMT_ROOT_KEY,
+    META_REVERTED_BLOCK_SMT_ROOT_KEY,
+];
+
+pub struct StateSnapshotTip {
+    pub block: L2Block,
+    pub global_state: GlobalState,
+    pub committed_info: L2BlockCommittedInfo,
+}
+
+/// Export the state at the store tip. The tip must be valid and committed on
+/// layer1, and the store must not be written during the export.
+pub fn export_state_snapshot<W: Write>(store: &Store, writer: W) -> Result<StateSnapshotTip> {
+    let db = store.begin_transaction();
+    let tip = read_tip(store)?;
+    let tip_hash: H256 = tip.block.hash().into();
+    if db.get_last_valid_tip_block_hash()? != tip_hash {
+        bail!("tip block is invalid, wait for it to be reverted");
+    }
+
+    let mut writer = HashWriter::new(writer);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    writer.write_all(tip_hash.as_slice())?;
+
+    for key in META_KEYS.iter() {
+        if let Some(value) = db.get(COLUMN_META, key) {
+            write_record(&mut writer, COLUMN_META, key, &value)?;
+        }
+    }
+    for col in TIP_BLOCK_COLUMNS.iter() {
+        if let Some(value) = db.get(*col, tip_hash.as_slice()) {
+            write_record(&mut writer, *col, tip_hash.as_slice(), &value)?;
+        }
+    }
+    // main chain block number => hash and hash => number
+    for col in STATE_COLUMNS.iter().chain(&[COLUMN_INDEX]) {
+        for (key, value) in db.get_iter(*col, IteratorMode::Start) {
+            write_record(&mut writer, *col, &key, &value)?;
+        }
+    }
+
+    writer.write_all(&[RECORDS_END])?;
+    let (mut writer, checksum) = writer.finalize();
+    writer.write_all(&checksum)?;
+    writer.flush()?;
+
+    Ok(tip)
+}
+
+/// Import a snapshot into an empty store. The checksum and the state roots are
+/// verified after all records are written, a store with a failed import must
+/// be removed.
+pub fn import_state_snapshot<R: Read>(store: &Store, reader: R) -> Result<StateSnapshotTip> {
+    if store.has_genesis()? {
+        bail!("store is not empty");
+    }
+
+    let mut batch = store.new_write_batch()?;
+    read_state_snapshot(reader, |col, key, value| {
+        batch.put(col, key, value)?;
+        if batch.size_in_bytes() >= WRITE_BATCH_SIZE {
+            store.write(&batch)?;
+            batch.clear()?;
+        }
+        Ok(())
+    })?;
+    store.write(&batch)?;
+
+    let tip = read_tip(store)?;
+    verify_state_roots(store, &tip)?;
+
+    // history below the tip isn't in the snapshot
+    let tip_number: u64 = tip.block.raw().number().unpack();
+    if tip_number > 0 {
+        let db = store.begin_transaction();
+        db.set_pruned_block_number(tip_number - 1)?;
+        db.commit()?;
+    }
+    Ok(tip)
+}
+
+/// Rebuild the account SMT and the block SMT roots from the imported leaves and
+/// check them against the tip global state. The checksum only detects damage
+/// in transfer, a snapshot exported from a corrupted store passes it.
+fn verify_state_roots(store: &Store, tip: &StateSnapshotTip) -> Result<()> {
+    let db = store.begin_transaction();
+    let tip_hash: H256 = tip.block.hash().into();
+    let global_state_tip_hash: H256 = tip.global_state.tip_block_hash().unpack();
+    if global_state_tip_hash != tip_hash {
+        bail!("tip block mismatch global state tip");
+    }
+    let account = tip.global_state.account();
+    if tip.block.raw().post_account().as_slice() != account.as_slice() {
+        bail!("tip block post account mismatch global state");
+    }
+
+    let account_root: H256 = account.merkle_root().unpack();
+    if integrity::rebuild_account_smt_root(&db)? != account_root {
+        bail!("imported account smt leaves mismatch global state");
+    }
+    let block_root: H256 = tip.global_state.block().merkle_root().unpack();
+    if integrity::rebuild_block_smt_root(&db)? != block_root {
+        bail!("imported block smt leaves mismatch global state");
+    }
+    if db.get_block_smt_root()? != block_root {
+        bail!("imported block smt root mismatch global state");
+    }
+    Ok(())
+}
+
+/// Verify the snapshot checksum, return the snapshot tip block hash
+pub fn verify_state_snapshot<R: Read>(reader: R) -> Result<H256> {
+    read_state_snapshot(reader, |_, _, _| Ok(()))
+}
+
+fn read_state_snapshot<R, F>(reader: R, mut f: F) -> Result<H256>
+where
+    R: Read,
+    F: FnMut(Col, &[u8], &[u8]) -> Result<()>,
+{
+    let mut reader = HashReader::new(reader);
+    let mut magic = vec![0u8; MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        bail!("not a state snapshot");
+    }
+    let version = read_u8(&mut reader)?;
+    if version != VERSION {
+        bail!("unsupported state snapshot version {}", version);
+    }
+    let mut tip_hash = [0u8; 32];
+    reader.read_exact(&mut tip_hash)?;
+
+    loop {
+        let col = read_u8(&mut reader)?;
+        if col == RECORDS_END {
+            break;
+        }
+        let key = read_bytes(&mut reader)?;
+        let value = read_bytes(&mut reader)?;
+        f(col, &key, &value)?;
+    }
+
+    let (mut reader, expected_checksum) = reader.finalize();
+    let mut checksum = [0u8; 32];
+    reader.read_exact(&mut checksum)?;
+    if checksum != expected_checksum {
+        bail!("state snapshot checksum mismatch");
+    }
+
+    Ok(tip_hash.into())
+}
+
+fn read_tip(store: &Store) -> Result<StateSnapshotTip> {
+    let db = store.begin_transaction();
+    let block = db.get_tip_block()?;
+    let block_hash: H256 = block.hash().into();
+    let global_state = db
+        .get_block_post_global_state(&block_hash)?
+        .ok_or_else(|| anyhow!("tip block global state not found"))?;
+    let committed_info = db
+        .get_l2block_committed_info(&block_hash)?
+        .ok_or_else(|| anyhow!("tip block is not committed on layer1"))?;
+    Ok(StateSnapshotTip {
+        block,
+        global_state,
+        committed_info,
+    })
+}
+
+fn write_record<W: Write>(writer: &mut W, col: Col, key: &[u8], value: &[u8]) -> Result<()> {
+    writer.write_all(&[col])?;
+    writer.write_all(&(key.len() as u32).to_le_bytes())?;
+    writer.write_all(key)?;
+    writer.write_all(&(value.len() as u32).to_le_bytes())?;
+    writer.write_all(value)?;
+    Ok(())
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let mut buf = vec![0u8; u32::from_le_bytes(len) as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+struct HashWriter<W> {
+    inner: W,
+    hasher: Blake2b,
+}
+
+impl<W: Write> HashWriter<W> {
+    fn new(inner: W) -> Self {
+        HashWriter {
+            inner,
+            hasher: new_blake2b(),
+        }
+    }
+
+    fn finalize(self) -> (W, [u8; 32]) {
+        let mut hash = [0u8; 32];
+        self.hasher.finalize(&mut hash);
+        (self.inner, hash)
+    }
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct HashReader<R> {
+    inner: R,
+    hasher: Blake2b,
+}
+
+impl<R: Read> HashReader<R> {
+    fn new(inner: R) -> Self {
+        HashReader {
+            inner,
+            hasher: new_blake2b(),
+        }
+    }
+
+    fn finalize(self) -> (R, [u8; 32]) {
+        let mut hash = [0u8; 32];
+        self.hasher.finalize(&mut hash);
+        (self.inner, hash)
+    }
+}
+
+impl<R: Read> Read for HashReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
+    }
+}
@@ -1,8 +1,8 @@
 //! Storage implementation
 
-use crate::transaction::StoreTransaction;
-use crate::write_batch::StoreWriteBatch;
-use anyhow::Result;
+use crate::transaction::{StoreTransaction, TransactionInner};
+use crate::write_batch::{StoreWriteBatch, WriteBatchInner};
+use anyhow::{bail, Result};
 use gw_common::{error::Error, smt::H256};
 use gw_db::{
     schema::{
@@ -10,22 +10,46 @@ use gw_db::{
         COLUMN_META, COLUMN_TRANSACTION, COLUMN_TRANSACTION_RECEIPT, META_CHAIN_ID_KEY,
         META_TIP_BLOCK_HASH_KEY,
     },
-    CfMemStat, DBPinnableSlice, RocksDB,
+    CfMemStat, MemoryDB, RocksDB, RocksDBSecondary,
 };
 use gw_types::{
     offchain::global_state_from_slice,
     packed::{self, GlobalState, L2Block, L2Transaction},
     prelude::*,
 };
+use std::path::Path;
 
 #[derive(Clone)]
 pub struct Store {
-    db: RocksDB,
+    db: StoreDB,
+    /// Remove state records of finalized blocks on attaching blocks, it's
+    /// disabled if history is pruned by `HistoryPruner`
+    prune_on_attach: bool,
 }
 
-impl<'a> Store {
+#[derive(Clone)]
+enum StoreDB {
+    Primary(RocksDB),
+    /// Read-only, follows the primary instance
+    Secondary(RocksDBSecondary),
+    Memory(MemoryDB),
+}
+
+impl Store {
     pub fn new(db: RocksDB) -> Self {
-        Store { db }
+        Store {
+            db: StoreDB::Primary(db),
+            prune_on_attach: true,
+        }
+    }
+
+    /// A read-only store which follows the primary process's db, call
+    /// `try_catch_up_with_primary` to see the primary's new writes.
+    pub fn new_secondary(db: RocksDBSecondary) -> Self {
+        Store {
+            db: StoreDB::Secondary(db),
+            prune_on_attach: true,
+        }
     }
 
     pub fn open_tmp() -> Result<Self> {
@@ -33,10 +57,42 @@ impl<'a> Store {
         Ok(Self::new(db))
     }
 
-    fn get(&'a self, col: Col, key: &[u8]) -> Option<DBPinnableSlice<'a>> {
-        self.db
-            .get_pinned(col, key)
-            .expect("db operation should be ok")
+    /// A store kept in memory, data is dropped with the last clone of it
+    pub fn open_memory() -> Self {
+        Store {
+            db: StoreDB::Memory(MemoryDB::open(COLUMNS)),
+            prune_on_attach: true,
+        }
+    }
+
+    /// History is pruned by `HistoryPruner`, so attaching blocks doesn't
+    /// remove state records of finalized blocks.
+    pub fn enable_history_pruner(&mut self) {
+        self.prune_on_attach = false;
+    }
+
+    pub fn is_secondary(&self) -> bool {
+        matches!(self.db, StoreDB::Secondary(_))
+    }
+
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        if let StoreDB::Secondary(ref db) = self.db {
+            db.try_catch_up_with_primary()?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
+        match self.db {
+            StoreDB::Primary(ref db) => db
+                .get_pinned(col, key)
+                .map(|v| v.map(|v| Box::<[u8]>::from(v.as_ref()))),
+            StoreDB::Secondary(ref db) => db
+                .get_pinned(col, key)
+                .map(|v| v.map(|v| Box::<[u8]>::from(v.as_ref()))),
+            StoreDB::Memory(ref db) => db.get(col, key).map(|v| v.map(Vec::into_boxed_slice)),
+        }
+        .expect("db operation should be ok")
     }
 
     // fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIter {
@@ -44,23 +100,61 @@ impl<'a> Store {
     // }
 
     pub fn begin_transaction(&self) -> StoreTransaction {
+        let inner = match self.db {
+            StoreDB::Primary(ref db) => TransactionInner::RocksDB(db.transaction()),
+            StoreDB::Secondary(ref db) => TransactionInner::RocksDB(db.transaction()),
+            StoreDB::Memory(ref db) => TransactionInner::Memory(db.transaction()),
+        };
         StoreTransaction {
-            inner: self.db.transaction(),
+            inner,
+            prune_on_attach: self.prune_on_attach,
         }
     }
 
     pub fn gather_mem_stats(&self) -> Vec<CfMemStat> {
-        self.db.gather_mem_stats()
+        match self.db {
+            StoreDB::Primary(ref db) => db.gather_mem_stats(),
+            StoreDB::Secondary(_) | StoreDB::Memory(_) => Vec::new(),
+        }
     }
 
-    pub fn new_write_batch(&self) -> StoreWriteBatch {
-        StoreWriteBatch {
-            inner: self.db.new_write_batch(),
+    /// Create a consistent checkpoint of the store while it's running
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        match self.db {
+            StoreDB::Primary(ref db) => db.create_checkpoint(path)?,
+            StoreDB::Secondary(_) => bail!("can't create checkpoint of a secondary store"),
+            StoreDB::Memory(_) => bail!("can't create checkpoint of a memory store"),
         }
+        Ok(())
+    }
+
+    /// Secondary store is read-only, it returns error
+    pub fn new_write_batch(&self) -> Result<StoreWriteBatch, Error> {
+        let inner = match self.db {
+            StoreDB::Primary(ref db) => WriteBatchInner::RocksDB(db.new_write_batch()),
+            StoreDB::Secondary(_) => {
+                log::error!("Store error: secondary store is read-only");
+                return Err(Error::Store);
+            }
+            StoreDB::Memory(ref db) => WriteBatchInner::Memory(db.new_write_batch()),
+        };
+        Ok(StoreWriteBatch { inner })
     }
 
     pub fn write(&self, write_batch: &StoreWriteBatch) -> Result<(), Error> {
-        if let Err(err) = self.db.write(&write_batch.inner) {
+        let result = match (&self.db, &write_batch.inner) {
+            (StoreDB::Primary(db), WriteBatchInner::RocksDB(batch)) => db.write(batch),
+            (StoreDB::Memory(db), WriteBatchInner::Memory(batch)) => db.write(batch),
+            (StoreDB::Secondary(_), _) => {
+                log::error!("Store error: secondary store is read-only");
+                return Err(Error::Store);
+            }
+            _ => {
+                log::error!("Store error: write batch of another store");
+                return Err(Error::Store);
+            }
+        };
+        if let Err(err) = result {
             log::error!("Store error: {}", err);
             return Err(Error::Store);
         }
@@ -0,0 +1,108 @@
+use crate::{traits::KVStore, transaction::account_tx_index::AccountTransactionKey, Store};
+use gw_db::{
+    schema::{COLUMN_BLOCK, COLUMN_INDEX},
+    Direction,
+};
+use gw_types::{
+    packed::{L2Block, L2Transaction, L2TransactionVec, RawL2Block, RawL2Transaction},
+    prelude::*,
+};
+
+fn build_block(number: u64, txs: &[(u32, u32)]) -> L2Block {
+    let txs = txs.iter().map(|(from_id, to_id)| {
+        let raw = RawL2Transaction::new_builder()
+            .from_id(from_id.pack())
+            .to_id(to_id.pack())
+            .build();
+        L2Transaction::new_builder().raw(raw).build()
+    });
+    L2Block::new_builder()
+        .raw(RawL2Block::new_builder().number(number.pack()).build())
+        .transactions(L2TransactionVec::new_builder().extend(txs).build())
+        .build()
+}
+
+#[test]
+fn test_account_transaction_index() {
+    let store = Store::open_memory();
+    let block_1 = build_block(1, &[(2, 3), (4, 2)]);
+    let block_2 = build_block(2, &[(2, 2), (3, 4)]);
+
+    let db = store.begin_transaction();
+    db.insert_account_transaction_index(&block_1).unwrap();
+    db.insert_account_transaction_index(&block_2).unwrap();
+    db.commit().unwrap();
+
+    let db = store.begin_transaction();
+    let positions = |keys: Vec<(AccountTransactionKey, _)>| -> Vec<(u64, u32)> {
+        keys.into_iter()
+            .map(|(key, _)| (key.block_number(), key.tx_index()))
+            .collect()
+    };
+
+    let keys = db
+        .get_account_transaction_keys(2, Direction::Forward, None, 10)
+        .unwrap();
+    assert_eq!(positions(keys), vec![(1, 0), (1, 1), (2, 0)]);
+
+    let keys = db
+        .get_account_transaction_keys(2, Direction::Reverse, None, 10)
+        .unwrap();
+    assert_eq!(positions(keys), vec![(2, 0), (1, 1), (1, 0)]);
+
+    // paginate with cursor
+    let keys = db
+        .get_account_transaction_keys(2, Direction::Forward, None, 2)
+        .unwrap();
+    let cursor = keys.last().unwrap().0.clone();
+    let keys = db
+        .get_account_transaction_keys(2, Direction::Forward, Some(&cursor), 2)
+        .unwrap();
+    assert_eq!(positions(keys), vec![(2, 0)]);
+
+    // detach block removes the index
+    db.remove_account_transaction_index(&block_2).unwrap();
+    db.commit().unwrap();
+    let db = store.begin_transaction();
+    let keys = db
+        .get_account_transaction_keys(4, Direction::Forward, None, 10)
+        .unwrap();
+    assert_eq!(positions(keys), vec![(1, 1)]);
+}
+
+#[test]
+fn test_backfill_account_transaction_index() {
+    let store = Store::open_memory();
+    let blocks = vec![
+        build_block(0, &[]),
+        build_block(1, &[(2, 3)]),
+        build_block(2, &[(3, 2)]),
+    ];
+
+    // blocks attached before the index existed
+    let db = store.begin_transaction();
+    for block in blocks.iter() {
+        let block_number = block.raw().number();
+        let block_hash = block.hash();
+        db.insert_raw(COLUMN_BLOCK, &block_hash, block.as_slice())
+            .unwrap();
+        db.insert_raw(COLUMN_INDEX, block_number.as_slice(), &block_hash)
+            .unwrap();
+        db.set_tip_block_hash(block_hash.into()).unwrap();
+    }
+    db.commit().unwrap();
+
+    assert_eq!(store.backfill_account_transaction_index().unwrap(), 3);
+    let db = store.begin_transaction();
+    let keys = db
+        .get_account_transaction_keys(2, Direction::Forward, None, 10)
+        .unwrap();
+    let positions: Vec<_> = keys
+        .into_iter()
+        .map(|(key, _)| (key.block_number(), key.tx_index()))
+        .collect();
+    assert_eq!(positions, vec![(1, 0), (2, 0)]);
+
+    // backfill only runs once
+    assert_eq!(store.backfill_account_transaction_index().unwrap(), 0);
+}
@@ -1,2 +1,4 @@
+mod account_tx_index;
 mod state_db;
 mod transaction;
+mod tx_access_list;
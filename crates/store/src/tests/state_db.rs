@@ -1,8 +1,17 @@
-use crate::{state::state_db::StateContext, traits::KVStore, transaction::StoreTransaction, Store};
+use crate::{
+    state::state_db::StateContext,
+    traits::KVStore,
+    transaction::{prune::HistoryPrunedError, state::NUMBER_OF_CONFIRMATION, StoreTransaction},
+    Store,
+};
 use gw_common::{h256_ext::H256Ext, merkle_utils::calculate_state_checkpoint, state::State, H256};
-use gw_db::schema::COLUMN_BLOCK;
+use gw_db::{schema::COLUMN_BLOCK, Direction};
 use gw_types::{
-    packed::{AccountMerkleState, L2Block, RawL2Block, SubmitTransactions},
+    packed::{
+        AccountMerkleState, GlobalState, L2Block, L2Transaction, L2TransactionVec, OutPoint,
+        RawL2Block, RawL2Transaction, RawWithdrawalRequest, SubmitTransactions, SubmitWithdrawals,
+        TxReceipt, WithdrawalReceipt, WithdrawalRequest, WithdrawalRequestVec,
+    },
     prelude::{Builder, Entity, Pack},
 };
 
@@ -35,7 +44,7 @@ fn commit_block(db: &StoreTransaction, block: L2Block) {
 
 #[test]
 fn test_state_with_version() {
-    let store = Store::open_tmp().unwrap();
+    let store = Store::open_memory();
     let mut prev_txs_state_checkpoint = calculate_state_checkpoint(&H256::zero(), 0);
     // setup genesis block
     let genesis = L2Block::new_builder()
@@ -195,3 +204,252 @@ fn test_state_with_version() {
         assert_eq!(v, H256::zero());
     }
 }
+
+#[test]
+fn test_prune_history() {
+    let store = Store::open_memory();
+    let prev_txs_state_checkpoint = calculate_state_checkpoint(&H256::zero(), 0);
+    let genesis = L2Block::new_builder()
+        .raw(
+            RawL2Block::new_builder()
+                .submit_transactions(
+                    SubmitTransactions::new_builder()
+                        .prev_state_checkpoint(prev_txs_state_checkpoint.pack())
+                        .build(),
+                )
+                .build(),
+        )
+        .build();
+    let db = store.begin_transaction();
+    db.set_block_smt_root(H256::zero()).unwrap();
+    commit_block(&db, genesis);
+    db.commit().unwrap();
+
+    // key => value of blocks 1, 2 and 3
+    let updates = [vec![(1, 1), (2, 2), (3, 3)], vec![(1, 11)], vec![(2, 22)]];
+    let mut block_hashes = Vec::new();
+    for (i, pairs) in updates.iter().enumerate() {
+        let block_number = i as u64 + 1;
+        let db = store.begin_transaction();
+        let mut state = db
+            .state_tree(StateContext::AttachBlock(block_number))
+            .unwrap();
+        for (k, v) in pairs {
+            state
+                .update_raw(H256::from_u32(*k), H256::from_u32(*v))
+                .unwrap();
+        }
+        let block = build_block(&state, block_number, prev_txs_state_checkpoint);
+        block_hashes.push(H256::from(block.hash()));
+        commit_block(&db, block);
+        db.commit().unwrap();
+    }
+
+    let db = store.begin_transaction();
+    db.prune_block_history(1, 3).unwrap();
+    db.prune_block_history(2, 3).unwrap();
+    db.set_pruned_block_number(2).unwrap();
+    db.commit().unwrap();
+
+    let db = store.begin_transaction();
+    assert_eq!(
+        db.check_history_available(2),
+        Err(HistoryPrunedError {
+            block_number: 2,
+            pruned_block_number: 2
+        })
+    );
+    assert!(db.state_tree(StateContext::ReadOnlyHistory(1)).is_err());
+    assert!(db.get_block(&block_hashes[0]).unwrap().is_none());
+    assert!(db.get_block(&block_hashes[1]).unwrap().is_none());
+    assert!(db.get_block(&block_hashes[2]).unwrap().is_some());
+
+    // history from the retained block is kept
+    let state = db.state_tree(StateContext::ReadOnlyHistory(3)).unwrap();
+    for (k, v) in [(1, 11), (2, 22), (3, 3)].iter() {
+        let value = state.get_raw(&H256::from_u32(*k)).unwrap();
+        assert_eq!(value, H256::from_u32(*v));
+    }
+    // only the latest record of each key is left
+    assert!(db.get_history_state(1, &H256::from_u32(1)).is_none());
+    assert_eq!(
+        db.get_history_state(2, &H256::from_u32(1)),
+        Some(H256::from_u32(11))
+    );
+}
+
+#[test]
+fn test_prune_finalized_block_state_record() {
+    let store = Store::open_memory();
+    let prev_txs_state_checkpoint = calculate_state_checkpoint(&H256::zero(), 0);
+    let genesis = L2Block::new_builder()
+        .raw(
+            RawL2Block::new_builder()
+                .submit_transactions(
+                    SubmitTransactions::new_builder()
+                        .prev_state_checkpoint(prev_txs_state_checkpoint.pack())
+                        .build(),
+                )
+                .build(),
+        )
+        .build();
+    let db = store.begin_transaction();
+    db.set_block_smt_root(H256::zero()).unwrap();
+    commit_block(&db, genesis);
+    db.commit().unwrap();
+
+    // records of block #1 are removed on attaching the block just past the
+    // confirmation window
+    let window_tip = NUMBER_OF_CONFIRMATION + 1;
+    for block_number in 1..=window_tip + 1 {
+        let db = store.begin_transaction();
+        let mut state = db
+            .state_tree(StateContext::AttachBlock(block_number))
+            .unwrap();
+        state
+            .update_raw(H256::from_u32(1), H256::from_u64(block_number))
+            .unwrap();
+        let block = build_block(&state, block_number, prev_txs_state_checkpoint);
+        commit_block(&db, block);
+        db.commit().unwrap();
+
+        if block_number == window_tip {
+            let db = store.begin_transaction();
+            assert_eq!(db.get_pruned_block_number(), None);
+            assert!(db.state_tree(StateContext::ReadOnlyHistory(1)).is_ok());
+        }
+    }
+
+    let db = store.begin_transaction();
+    assert_eq!(db.get_pruned_block_number(), Some(1));
+    assert_eq!(
+        db.check_history_available(1),
+        Err(HistoryPrunedError {
+            block_number: 1,
+            pruned_block_number: 1
+        })
+    );
+    assert!(db.state_tree(StateContext::ReadOnlyHistory(1)).is_err());
+    let state = db.state_tree(StateContext::ReadOnlyHistory(2)).unwrap();
+    assert_eq!(
+        state.get_raw(&H256::from_u32(1)).unwrap(),
+        H256::from_u64(2)
+    );
+}
+
+#[test]
+fn test_prune_block_indexes() {
+    let store = Store::open_memory();
+    let prev_txs_state_checkpoint = calculate_state_checkpoint(&H256::zero(), 0);
+    let genesis = L2Block::new_builder()
+        .raw(
+            RawL2Block::new_builder()
+                .submit_transactions(
+                    SubmitTransactions::new_builder()
+                        .prev_state_checkpoint(prev_txs_state_checkpoint.pack())
+                        .build(),
+                )
+                .build(),
+        )
+        .build();
+    let db = store.begin_transaction();
+    db.set_block_smt_root(H256::zero()).unwrap();
+    commit_block(&db, genesis);
+    db.commit().unwrap();
+
+    // block 1 contains a tx, a withdrawal and a deposit
+    let tx = L2Transaction::new_builder()
+        .raw(
+            RawL2Transaction::new_builder()
+                .from_id(2u32.pack())
+                .to_id(3u32.pack())
+                .build(),
+        )
+        .build();
+    let withdrawal = WithdrawalRequest::new_builder()
+        .raw(
+            RawWithdrawalRequest::new_builder()
+                .nonce(1u32.pack())
+                .build(),
+        )
+        .build();
+    let deposit_out_point = OutPoint::new_builder()
+        .tx_hash(H256::from_u32(1).pack())
+        .build();
+    let block = L2Block::new_builder()
+        .raw(
+            RawL2Block::new_builder()
+                .number(1u64.pack())
+                .submit_transactions(
+                    SubmitTransactions::new_builder()
+                        .tx_count(1u32.pack())
+                        .prev_state_checkpoint(prev_txs_state_checkpoint.pack())
+                        .build(),
+                )
+                .submit_withdrawals(
+                    SubmitWithdrawals::new_builder()
+                        .withdrawal_count(1u32.pack())
+                        .build(),
+                )
+                .state_checkpoint_list(vec![H256::zero(), H256::zero()].pack())
+                .build(),
+        )
+        .transactions(L2TransactionVec::new_builder().push(tx.clone()).build())
+        .withdrawals(
+            WithdrawalRequestVec::new_builder()
+                .push(withdrawal.clone())
+                .build(),
+        )
+        .build();
+    let block_hash: H256 = block.hash().into();
+    let db = store.begin_transaction();
+    db.insert_block(
+        block.clone(),
+        GlobalState::default(),
+        vec![WithdrawalReceipt::default()],
+        AccountMerkleState::default(),
+        vec![TxReceipt::default()],
+        Vec::new(),
+    )
+    .unwrap();
+    db.insert_block_deposit_info(&block, &[deposit_out_point.clone()])
+        .unwrap();
+    db.attach_block(block).unwrap();
+    db.commit().unwrap();
+
+    let db = store.begin_transaction();
+    let state = db.state_tree(StateContext::AttachBlock(2)).unwrap();
+    commit_block(&db, build_block(&state, 2, prev_txs_state_checkpoint));
+    db.commit().unwrap();
+
+    let tx_hash: H256 = tx.hash().into();
+    let withdrawal_hash: H256 = withdrawal.hash().into();
+    let db = store.begin_transaction();
+    assert!(db.get_transaction_info(&tx_hash).unwrap().is_some());
+    assert!(db.get_withdrawal_info(&withdrawal_hash).unwrap().is_some());
+    assert!(db.get_deposit_info(&deposit_out_point).unwrap().is_some());
+    let account_tx_keys = db
+        .get_account_transaction_keys(2, Direction::Forward, None, 10)
+        .unwrap();
+    assert_eq!(account_tx_keys.len(), 1);
+
+    db.prune_block_history(1, 2).unwrap();
+    db.set_pruned_block_number(1).unwrap();
+    db.commit().unwrap();
+
+    let db = store.begin_transaction();
+    assert!(db.get_block(&block_hash).unwrap().is_none());
+    assert!(db.get_transaction_info(&tx_hash).unwrap().is_none());
+    assert!(db.get_withdrawal_info(&withdrawal_hash).unwrap().is_none());
+    assert!(db.get_deposit_info(&deposit_out_point).unwrap().is_none());
+    assert!(db
+        .get_block_deposit_out_points(&block_hash)
+        .unwrap()
+        .is_none());
+    for account_id in [2, 3].iter() {
+        let account_tx_keys = db
+            .get_account_transaction_keys(*account_id, Direction::Forward, None, 10)
+            .unwrap();
+        assert!(account_tx_keys.is_empty());
+    }
+}
@@ -1,10 +1,44 @@
 use crate::{traits::KVStore, Store};
-use gw_db::{DBRawIterator, Direction::Forward, IteratorMode};
+use gw_db::{iter::DBRawIter, Direction::Forward, IteratorMode};
 use std::collections::HashMap;
 
-#[test]
-fn insert_and_get() {
-    let store = Store::open_tmp().unwrap();
+/// The in-memory store must behave like RocksDB, run tests on both
+macro_rules! test_stores {
+    ($($name:ident),*) => {
+        mod rocksdb {
+            use crate::Store;
+            $(
+                #[test]
+                fn $name() {
+                    super::$name(Store::open_tmp().unwrap());
+                }
+            )*
+        }
+
+        mod memory {
+            use crate::Store;
+            $(
+                #[test]
+                fn $name() {
+                    super::$name(Store::open_memory());
+                }
+            )*
+        }
+    };
+}
+
+test_stores!(
+    insert_and_get,
+    delete,
+    insert_without_commit,
+    intersect_transactions,
+    seek_for_prev,
+    seek_for_prev_with_suffix,
+    delete_range,
+    range_search_and_delete
+);
+
+fn insert_and_get(store: Store) {
     let store_txn = store.begin_transaction();
 
     store_txn.insert_raw(0, &[0, 0], &[0, 0, 0]).unwrap();
@@ -40,9 +74,7 @@ fn insert_and_get() {
     assert_eq!(Some(&vec![2u8, 2, 2]), r.get(&vec![2]));
 }
 
-#[test]
-fn delete() {
-    let store = Store::open_tmp().unwrap();
+fn delete(store: Store) {
     let store_txn = store.begin_transaction();
 
     store_txn.insert_raw(1, &[2], &[1, 1, 1]).unwrap();
@@ -59,9 +91,7 @@ fn delete() {
     assert!(store_txn.commit().is_ok());
 }
 
-#[test]
-fn insert_without_commit() {
-    let store = Store::open_tmp().unwrap();
+fn insert_without_commit(store: Store) {
     let store_txn = store.begin_transaction();
 
     // insert without commit
@@ -75,9 +105,7 @@ fn insert_without_commit() {
     assert!(store_txn.get(1, &[1, 1]).is_none());
 }
 
-#[test]
-fn intersect_transactions() {
-    let store = Store::open_tmp().unwrap();
+fn intersect_transactions(store: Store) {
     let store_txn_1 = store.begin_transaction();
     let store_txn_2 = store.begin_transaction();
     let store_txn_3 = store.begin_transaction();
@@ -143,9 +171,7 @@ fn intersect_transactions() {
     assert!(store_txn_2.commit().is_err());
 }
 
-#[test]
-fn seek_for_prev() {
-    let store = Store::open_tmp().unwrap();
+fn seek_for_prev(store: Store) {
     let store_txn = store.begin_transaction();
 
     store_txn.insert_raw(1, &[0], &[0, 0, 0]).unwrap();
@@ -184,7 +210,7 @@ fn seek_for_prev() {
     assert_eq!(Some(&vec![3u8, 3, 3]), r.get(&vec![3]));
 
     let iter = store_txn.get_iter(1, IteratorMode::Start);
-    let mut raw_iter: DBRawIterator = iter.into();
+    let mut raw_iter: DBRawIter = iter.into();
     raw_iter.seek_for_prev([5]);
     assert_eq!(&[3], raw_iter.key().unwrap());
     assert_eq!(&[3, 3, 3], raw_iter.value().unwrap());
@@ -202,9 +228,7 @@ fn seek_for_prev() {
     assert_eq!(&[1, 1, 1], raw_iter.value().unwrap());
 }
 
-#[test]
-fn seek_for_prev_with_suffix() {
-    let store = Store::open_tmp().unwrap();
+fn seek_for_prev_with_suffix(store: Store) {
     let store_txn = store.begin_transaction();
 
     let (block_num_1, tx_idx_5) = (1u64, 5u32);
@@ -276,7 +300,7 @@ fn seek_for_prev_with_suffix() {
     let key_1_with_ver_1_4 = [&key_1[..], &block_num_1.to_be_bytes(), &4u32.to_be_bytes()].concat();
 
     let iter = store_txn.get_iter(1, IteratorMode::Start);
-    let mut raw_iter: DBRawIterator = iter.into();
+    let mut raw_iter: DBRawIter = iter.into();
 
     raw_iter.seek_for_prev(key_3_with_ver_257_1);
     assert_eq!(&key_3_with_ver_256_2, &raw_iter.key().unwrap());
@@ -311,10 +335,7 @@ fn seek_for_prev_with_suffix() {
     assert!(raw_iter.key().is_none());
 }
 
-#[test]
-fn delete_range() {
-    let store = Store::open_tmp().unwrap();
-
+fn delete_range(store: Store) {
     let store_txn = store.begin_transaction();
     store_txn.insert_raw(1, &[0], &[0, 0]).unwrap();
     store_txn.insert_raw(1, &[1], &[1, 1]).unwrap();
@@ -322,8 +343,8 @@ fn delete_range() {
     store_txn.insert_raw(1, &[3], &[3, 3]).unwrap();
     store_txn.commit().unwrap();
 
-    let mut batch = store.new_write_batch();
-    batch.inner.delete_range(1, &[0], &[3]).unwrap();
+    let mut batch = store.new_write_batch().unwrap();
+    batch.delete_range(1, &[0], &[3]).unwrap();
     store.write(&batch).unwrap();
 
     assert!(store_txn.get(1, &[0]).is_none());
@@ -332,10 +353,7 @@ fn delete_range() {
     assert!(store_txn.get(1, &[3]).is_some());
 }
 
-#[test]
-fn range_search_and_delete() {
-    let store = Store::open_tmp().unwrap();
-
+fn range_search_and_delete(store: Store) {
     let store_txn = store.begin_transaction();
     store_txn.insert_raw(1, &[0, 0], &[1, 0, 0]).unwrap();
     store_txn.insert_raw(1, &[0, 1], &[1, 0, 1]).unwrap();
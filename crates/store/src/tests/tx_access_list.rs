@@ -0,0 +1,41 @@
+use crate::Store;
+use gw_common::{h256_ext::H256Ext, H256};
+use gw_types::{
+    offchain::TxAccessList,
+    packed::{L2Block, L2Transaction, L2TransactionVec, RawL2Block, TransactionKey},
+    prelude::*,
+};
+
+#[test]
+fn test_tx_access_list() {
+    let store = Store::open_memory();
+    let block = L2Block::new_builder()
+        .raw(RawL2Block::new_builder().number(1u64.pack()).build())
+        .transactions(
+            L2TransactionVec::new_builder()
+                .extend(vec![L2Transaction::default(); 2])
+                .build(),
+        )
+        .build();
+    let access_lists = vec![
+        TxAccessList {
+            read_keys: vec![H256::from_u32(1), H256::from_u32(2)],
+            write_keys: vec![H256::from_u32(2)],
+        },
+        TxAccessList::default(),
+    ];
+
+    let db = store.begin_transaction();
+    db.insert_block_tx_access_lists(&block, &access_lists)
+        .unwrap();
+    db.commit().unwrap();
+
+    let db = store.begin_transaction();
+    for (index, access_list) in access_lists.iter().enumerate() {
+        let tx_key = TransactionKey::build_transaction_key(block.hash().pack(), index as u32);
+        let stored = db.get_transaction_access_list(&tx_key).unwrap();
+        assert_eq!(stored.as_ref(), Some(access_list));
+    }
+    let tx_key = TransactionKey::build_transaction_key(block.hash().pack(), 2);
+    assert_eq!(db.get_transaction_access_list(&tx_key).unwrap(), None);
+}
@@ -0,0 +1,195 @@
+//! Index of the transactions sent or received by an account
+//!
+//! The index is built when a block is attached to the main chain and removed
+//! when the block is detached, so it only contains main chain transactions.
+//! Blocks attached before the index existed are indexed once by
+//! `Store::backfill_account_transaction_index`.
+
+use gw_db::{
+    error::Error,
+    schema::{COLUMN_ACCOUNT_TRANSACTION, COLUMN_META, META_ACCOUNT_TRANSACTION_INDEXED_KEY},
+    Direction, IteratorMode,
+};
+use gw_types::{
+    packed::{self, TransactionKey},
+    prelude::*,
+};
+use std::collections::HashSet;
+
+use super::StoreTransaction;
+use crate::{traits::KVStore, Store};
+
+/// Blocks indexed per db transaction of the backfill
+const BACKFILL_BLOCKS_PER_BATCH: u64 = 1000;
+
+impl Store {
+    /// Index main chain blocks attached before the index existed, it's a no-op
+    /// once done. Pruned blocks are skipped, blocks below the pruned block
+    /// number are still indexed if only their state records are pruned.
+    /// Returns the number of indexed blocks.
+    pub fn backfill_account_transaction_index(&self) -> Result<u64, Error> {
+        let db = self.begin_transaction();
+        if db
+            .get(COLUMN_META, META_ACCOUNT_TRANSACTION_INDEXED_KEY)
+            .is_some()
+        {
+            return Ok(0);
+        }
+        let tip_number: u64 = db.get_tip_block()?.raw().number().unpack();
+
+        let mut indexed = 0;
+        let mut batch_start = 0;
+        while batch_start <= tip_number {
+            let batch_end = tip_number.min(batch_start + BACKFILL_BLOCKS_PER_BATCH - 1);
+            let db = self.begin_transaction();
+            for block_number in batch_start..=batch_end {
+                let block_hash = match db.get_block_hash_by_number(block_number)? {
+                    Some(block_hash) => block_hash,
+                    None => continue,
+                };
+                if let Some(block) = db.get_block(&block_hash)? {
+                    // keys are deterministic, re-index is harmless
+                    db.insert_account_transaction_index(&block)?;
+                    indexed += 1;
+                }
+            }
+            db.commit()?;
+            log::info!(
+                "Backfill account transaction index #{} to #{}",
+                batch_start,
+                batch_end
+            );
+            batch_start = batch_end + 1;
+        }
+
+        let db = self.begin_transaction();
+        db.insert_raw(COLUMN_META, META_ACCOUNT_TRANSACTION_INDEXED_KEY, &[1])?;
+        db.commit()?;
+        Ok(indexed)
+    }
+}
+
+impl StoreTransaction {
+    pub(crate) fn insert_account_transaction_index(
+        &self,
+        block: &packed::L2Block,
+    ) -> Result<(), Error> {
+        let block_hash = block.hash();
+        let block_number: u64 = block.raw().number().unpack();
+        for (index, tx) in block.transactions().into_iter().enumerate() {
+            let tx_key = TransactionKey::build_transaction_key(block_hash.pack(), index as u32);
+            for account_id in tx_accounts(&tx) {
+                let key = AccountTransactionKey::new(account_id, block_number, index as u32);
+                self.insert_raw(
+                    COLUMN_ACCOUNT_TRANSACTION,
+                    key.as_slice(),
+                    tx_key.as_slice(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn remove_account_transaction_index(
+        &self,
+        block: &packed::L2Block,
+    ) -> Result<(), Error> {
+        let block_number: u64 = block.raw().number().unpack();
+        for (index, tx) in block.transactions().into_iter().enumerate() {
+            for account_id in tx_accounts(&tx) {
+                let key = AccountTransactionKey::new(account_id, block_number, index as u32);
+                self.delete(COLUMN_ACCOUNT_TRANSACTION, key.as_slice())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return at most `limit` transaction keys of the account, ordered by
+    /// block number and tx index. Iteration starts after `cursor` if provided.
+    pub fn get_account_transaction_keys(
+        &self,
+        account_id: u32,
+        direction: Direction,
+        cursor: Option<&AccountTransactionKey>,
+        limit: usize,
+    ) -> Result<Vec<(AccountTransactionKey, TransactionKey)>, Error> {
+        let start_key = match (cursor, direction) {
+            (Some(cursor), _) => cursor.clone(),
+            (None, Direction::Forward) => AccountTransactionKey::new(account_id, 0, 0),
+            (None, Direction::Reverse) => {
+                AccountTransactionKey::new(account_id, u64::MAX, u32::MAX)
+            }
+        };
+        if start_key.account_id() != account_id {
+            return Err(Error::from("cursor doesn't belong to account".to_string()));
+        }
+
+        let iter = self.get_iter(
+            COLUMN_ACCOUNT_TRANSACTION,
+            IteratorMode::From(start_key.as_slice(), direction),
+        );
+        let records = iter
+            .map(|(key, value)| {
+                let key = AccountTransactionKey::from_slice(&key);
+                let tx_key = packed::TransactionKeyReader::from_slice_should_be_ok(value.as_ref())
+                    .to_entity();
+                (key, tx_key)
+            })
+            .take_while(|(key, _)| key.account_id() == account_id)
+            .filter(|(key, _)| cursor != Some(key))
+            .take(limit)
+            .collect();
+        Ok(records)
+    }
+}
+
+fn tx_accounts(tx: &packed::L2Transaction) -> HashSet<u32> {
+    let raw = tx.raw();
+    let mut accounts = HashSet::with_capacity(2);
+    accounts.insert(raw.from_id().unpack());
+    accounts.insert(raw.to_id().unpack());
+    accounts
+}
+
+// account_id(4 bytes) | block_number(8 bytes) | tx_index(4 bytes)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountTransactionKey([u8; 16]);
+
+impl AccountTransactionKey {
+    pub fn new(account_id: u32, block_number: u64, tx_index: u32) -> Self {
+        let mut inner = [0u8; 16];
+        // use BE, so keys of an account are sorted by block number and index
+        inner[..4].copy_from_slice(&account_id.to_be_bytes());
+        inner[4..12].copy_from_slice(&block_number.to_be_bytes());
+        inner[12..].copy_from_slice(&tx_index.to_be_bytes());
+        AccountTransactionKey(inner)
+    }
+
+    pub fn account_id(&self) -> u32 {
+        let mut inner = [0u8; 4];
+        inner.copy_from_slice(&self.0[..4]);
+        u32::from_be_bytes(inner)
+    }
+
+    pub fn block_number(&self) -> u64 {
+        let mut inner = [0u8; 8];
+        inner.copy_from_slice(&self.0[4..12]);
+        u64::from_be_bytes(inner)
+    }
+
+    pub fn tx_index(&self) -> u32 {
+        let mut inner = [0u8; 4];
+        inner.copy_from_slice(&self.0[12..]);
+        u32::from_be_bytes(inner)
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let mut inner = [0u8; 16];
+        inner.copy_from_slice(bytes);
+        AccountTransactionKey(inner)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
@@ -6,14 +6,24 @@ use gw_db::{
     error::Error,
     schema::{
         COLUMN_ACCOUNT_SMT_BRANCH, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_MEM_POOL_ACCOUNT_SMT_BRANCH,
-        COLUMN_MEM_POOL_ACCOUNT_SMT_LEAF, COLUMN_MEM_POOL_DATA, COLUMN_MEM_POOL_SCRIPT,
-        COLUMN_MEM_POOL_SCRIPT_PREFIX, COLUMN_MEM_POOL_TRANSACTION,
-        COLUMN_MEM_POOL_TRANSACTION_RECEIPT, COLUMN_MEM_POOL_WITHDRAWAL, COLUMN_META,
-        META_MEM_POOL_BLOCK_INFO,
+        COLUMN_MEM_POOL_ACCOUNT_SMT_LEAF, COLUMN_MEM_POOL_BLOCK_TX, COLUMN_MEM_POOL_DATA,
+        COLUMN_MEM_POOL_PENDING, COLUMN_MEM_POOL_SCRIPT, COLUMN_MEM_POOL_SCRIPT_PREFIX,
+        COLUMN_MEM_POOL_TRANSACTION, COLUMN_MEM_POOL_TRANSACTION_RECEIPT,
+        COLUMN_MEM_POOL_WITHDRAWAL, COLUMN_META, META_MEM_POOL_BLOCK, META_MEM_POOL_BLOCK_INFO,
     },
     IteratorMode,
 };
 use gw_types::{packed, prelude::*};
+use std::collections::HashMap;
+
+const PENDING_TXS: u8 = 0;
+const PENDING_WITHDRAWALS: u8 = 1;
+
+fn pending_key(account_id: u32, kind: u8) -> [u8; 5] {
+    let mut key = [kind; 5];
+    key[..4].copy_from_slice(&account_id.to_be_bytes());
+    key
+}
 
 use super::StoreTransaction;
 use crate::{
@@ -71,6 +81,7 @@ impl StoreTransaction {
             COLUMN_MEM_POOL_SCRIPT_PREFIX,
             COLUMN_MEM_POOL_ACCOUNT_SMT_LEAF,
             COLUMN_MEM_POOL_ACCOUNT_SMT_BRANCH,
+            COLUMN_MEM_POOL_BLOCK_TX,
         ] {
             for (k, _v) in self.get_iter(col, IteratorMode::Start) {
                 self.delete(col, &k)?;
@@ -170,4 +181,77 @@ impl StoreTransaction {
                 packed::BlockInfoReader::from_slice_should_be_ok(slice.as_ref()).to_entity()
             }))
     }
+
+    /// Save the mem block before txs, txs are appended by
+    /// `insert_mem_pool_block_tx`
+    pub fn set_mem_pool_block(&self, mem_block: &packed::MemBlock) -> Result<(), Error> {
+        self.insert_raw(COLUMN_META, META_MEM_POOL_BLOCK, mem_block.as_slice())
+    }
+
+    pub fn get_mem_pool_block(&self) -> Result<Option<packed::MemBlock>, Error> {
+        Ok(self.get(COLUMN_META, META_MEM_POOL_BLOCK).map(|slice| {
+            packed::MemBlockReader::from_slice_should_be_ok(slice.as_ref()).to_entity()
+        }))
+    }
+
+    pub fn insert_mem_pool_block_tx(&self, index: u32, tx_hash: &H256) -> Result<(), Error> {
+        self.insert_raw(
+            COLUMN_MEM_POOL_BLOCK_TX,
+            &index.to_be_bytes(),
+            tx_hash.as_slice(),
+        )
+    }
+
+    /// Txs of the mem block in order
+    pub fn get_mem_pool_block_txs(&self) -> Vec<H256> {
+        self.get_iter(COLUMN_MEM_POOL_BLOCK_TX, IteratorMode::Start)
+            .map(|(_key, value)| packed::Byte32Reader::from_slice_should_be_ok(&value).unpack())
+            .collect()
+    }
+
+    /// Save hashes of an account's pending txs and withdrawals, the entry is
+    /// removed if both are empty.
+    pub fn update_mem_pool_pending(
+        &self,
+        account_id: u32,
+        tx_hashes: Vec<H256>,
+        withdrawal_hashes: Vec<H256>,
+    ) -> Result<(), Error> {
+        for (kind, hashes) in [
+            (PENDING_TXS, tx_hashes),
+            (PENDING_WITHDRAWALS, withdrawal_hashes),
+        ] {
+            let key = pending_key(account_id, kind);
+            if hashes.is_empty() {
+                self.delete(COLUMN_MEM_POOL_PENDING, &key)?;
+            } else {
+                self.insert_raw(COLUMN_MEM_POOL_PENDING, &key, hashes.pack().as_slice())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns account id => (pending tx hashes, pending withdrawal hashes)
+    pub fn get_mem_pool_pending(&self) -> HashMap<u32, (Vec<H256>, Vec<H256>)> {
+        let mut pending: HashMap<u32, (Vec<H256>, Vec<H256>)> = HashMap::new();
+        for (key, value) in self.get_iter(COLUMN_MEM_POOL_PENDING, IteratorMode::Start) {
+            let mut account_id = [0u8; 4];
+            account_id.copy_from_slice(&key[..4]);
+            let entry = pending.entry(u32::from_be_bytes(account_id)).or_default();
+            let hashes = packed::Byte32VecReader::from_slice_should_be_ok(&value).unpack();
+            if key[4] == PENDING_TXS {
+                entry.0 = hashes;
+            } else {
+                entry.1 = hashes;
+            }
+        }
+        pending
+    }
+
+    pub fn clear_mem_pool_pending(&self) -> Result<(), Error> {
+        for (k, _v) in self.get_iter(COLUMN_MEM_POOL_PENDING, IteratorMode::Start) {
+            self.delete(COLUMN_MEM_POOL_PENDING, &k)?;
+        }
+        Ok(())
+    }
 }
@@ -1,5 +1,9 @@
+pub mod account_tx_index;
 pub mod mem_pool_store;
+pub mod prune;
 pub mod state;
 mod store_transaction;
+pub mod tx_access_list;
 
 pub use store_transaction::StoreTransaction;
+pub(crate) use store_transaction::TransactionInner;
@@ -0,0 +1,172 @@
+//! History pruning
+//!
+//! State records of pruned blocks are removed unless they still hold the
+//! latest value of a state key at the retained block, so history lookups from
+//! the retained block are unaffected. Blocks, transactions, receipts and the
+//! transaction, withdrawal, deposit and account indexes of pruned blocks are
+//! removed, the main chain index, global states and committed infos are kept.
+
+use gw_common::H256;
+use gw_db::{
+    error::Error,
+    iter::DBRawIter,
+    schema::{
+        Col, COLUMN_BLOCK, COLUMN_BLOCK_DEPOSIT_OUT_POINTS, COLUMN_BLOCK_DEPOSIT_REQUESTS,
+        COLUMN_BLOCK_STATE_RECORD, COLUMN_BLOCK_STATE_REVERSE_RECORD, COLUMN_DEPOSIT_INFO,
+        COLUMN_META, COLUMN_TRANSACTION, COLUMN_TRANSACTION_INFO, COLUMN_TRANSACTION_RECEIPT,
+        COLUMN_WITHDRAWAL_INFO, META_PRUNED_BLOCK_NUMBER_KEY,
+    },
+    Direction, IteratorMode,
+};
+use gw_types::{
+    packed::{self, TransactionKey},
+    prelude::*,
+};
+use thiserror::Error as ThisError;
+
+use super::{
+    state::{BlockStateRecordKey, BlockStateRecordKeyReverse},
+    StoreTransaction,
+};
+use crate::traits::KVStore;
+
+#[derive(ThisError, Debug, Clone, PartialEq, Eq)]
+#[error("history of block #{block_number} is pruned, blocks at or below #{pruned_block_number} are pruned")]
+pub struct HistoryPrunedError {
+    pub block_number: u64,
+    pub pruned_block_number: u64,
+}
+
+impl From<HistoryPrunedError> for Error {
+    fn from(err: HistoryPrunedError) -> Self {
+        Error::from(err.to_string())
+    }
+}
+
+impl StoreTransaction {
+    /// History of blocks at or below the returned number is pruned
+    pub fn get_pruned_block_number(&self) -> Option<u64> {
+        self.get(COLUMN_META, META_PRUNED_BLOCK_NUMBER_KEY)
+            .map(|slice| {
+                packed::Uint64Reader::from_slice_should_be_ok(slice.as_ref())
+                    .to_entity()
+                    .unpack()
+            })
+    }
+
+    pub(crate) fn set_pruned_block_number(&self, block_number: u64) -> Result<(), Error> {
+        self.insert_raw(
+            COLUMN_META,
+            META_PRUNED_BLOCK_NUMBER_KEY,
+            block_number.pack().as_slice(),
+        )
+    }
+
+    pub fn check_history_available(&self, block_number: u64) -> Result<(), HistoryPrunedError> {
+        match self.get_pruned_block_number() {
+            Some(pruned_block_number) if block_number <= pruned_block_number => {
+                Err(HistoryPrunedError {
+                    block_number,
+                    pruned_block_number,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Prune history of the main chain block, history from
+    /// `retained_block_number` is kept.
+    pub(crate) fn prune_block_history(
+        &self,
+        block_number: u64,
+        retained_block_number: u64,
+    ) -> Result<(), Error> {
+        assert!(block_number < retained_block_number);
+
+        let state_keys: Vec<H256> = self
+            .iter_block_state_record(block_number)
+            .map(|record_key| record_key.state_key())
+            .collect();
+        for state_key in state_keys {
+            let latest_number = self
+                .latest_block_state_record_number(&state_key, retained_block_number)
+                .expect("record exists");
+            // records of the key are sorted by block number
+            let start_key = BlockStateRecordKeyReverse::new(0, &state_key);
+            let stale_numbers: Vec<u64> = self
+                .get_iter(
+                    COLUMN_BLOCK_STATE_REVERSE_RECORD,
+                    IteratorMode::From(start_key.as_slice(), Direction::Forward),
+                )
+                .map(|(key, _)| BlockStateRecordKeyReverse::from_slice(&key))
+                .take_while(|key| key.state_key() == state_key)
+                .map(|key| key.block_number())
+                .take_while(|number| *number <= block_number && *number < latest_number)
+                .collect();
+            for number in stale_numbers {
+                let record_key = BlockStateRecordKey::new(number, &state_key);
+                self.delete(COLUMN_BLOCK_STATE_RECORD, record_key.as_slice())?;
+                let reverse_key = BlockStateRecordKeyReverse::new(number, &state_key);
+                self.delete(COLUMN_BLOCK_STATE_REVERSE_RECORD, reverse_key.as_slice())?;
+            }
+        }
+
+        let block_hash = match self.get_block_hash_by_number(block_number)? {
+            Some(block_hash) => block_hash,
+            None => return Ok(()),
+        };
+        if let Some(block) = self.get_block(&block_hash)? {
+            for (index, tx) in block.transactions().into_iter().enumerate() {
+                let key = TransactionKey::build_transaction_key(block_hash.pack(), index as u32);
+                self.delete(COLUMN_TRANSACTION, key.as_slice())?;
+                self.delete(COLUMN_TRANSACTION_RECEIPT, key.as_slice())?;
+                self.delete_transaction_access_list(&key)?;
+                self.delete_info_of_block(COLUMN_TRANSACTION_INFO, &tx.hash(), &block_hash)?;
+            }
+            for withdrawal in block.withdrawals().into_iter() {
+                self.delete_info_of_block(COLUMN_WITHDRAWAL_INFO, &withdrawal.hash(), &block_hash)?;
+            }
+            self.remove_account_transaction_index(&block)?;
+        }
+        // deposit infos of blocks inserted before out points are recorded are kept
+        if let Some(out_points) = self.get_block_deposit_out_points(&block_hash)? {
+            for out_point in out_points {
+                self.delete_info_of_block(COLUMN_DEPOSIT_INFO, out_point.as_slice(), &block_hash)?;
+            }
+        }
+        self.delete(COLUMN_BLOCK_DEPOSIT_OUT_POINTS, block_hash.as_slice())?;
+        self.delete(COLUMN_BLOCK_DEPOSIT_REQUESTS, block_hash.as_slice())?;
+        self.delete(COLUMN_BLOCK, block_hash.as_slice())?;
+
+        Ok(())
+    }
+
+    /// Infos are overwritten if the item is included again after a revert, only
+    /// remove the info pointing to the pruned block.
+    fn delete_info_of_block(&self, col: Col, key: &[u8], block_hash: &H256) -> Result<(), Error> {
+        let is_of_block = self.get(col, key).map_or(false, |slice| {
+            let info = packed::TransactionInfoReader::from_slice_should_be_ok(slice.as_ref());
+            &info.key().as_slice()[..32] == block_hash.as_slice()
+        });
+        if is_of_block {
+            self.delete(col, key)?;
+        }
+        Ok(())
+    }
+
+    fn latest_block_state_record_number(&self, state_key: &H256, block_number: u64) -> Option<u64> {
+        let key = BlockStateRecordKeyReverse::new(block_number, state_key);
+        let mut raw_iter: DBRawIter = self
+            .get_iter(COLUMN_BLOCK_STATE_REVERSE_RECORD, IteratorMode::Start)
+            .into();
+        raw_iter.seek_for_prev(key.as_slice());
+        if !raw_iter.valid() {
+            return None;
+        }
+        raw_iter
+            .key()
+            .map(BlockStateRecordKeyReverse::from_slice)
+            .filter(|prev_key| &prev_key.state_key() == state_key)
+            .map(|prev_key| prev_key.block_number())
+    }
+}
@@ -1,11 +1,12 @@
 use gw_common::{smt::SMT, H256};
 use gw_db::{
     error::Error,
+    iter::DBRawIter,
     schema::{
         COLUMN_ACCOUNT_SMT_BRANCH, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_BLOCK_STATE_RECORD,
         COLUMN_BLOCK_STATE_REVERSE_RECORD,
     },
-    DBRawIterator, Direction, IteratorMode,
+    Direction, IteratorMode,
 };
 use gw_types::{packed::AccountMerkleState, prelude::*};
 
@@ -17,7 +18,7 @@ use crate::{
 };
 
 /// TODO use a variable instead of hardcode
-const NUMBER_OF_CONFIRMATION: u64 = 3600;
+pub(crate) const NUMBER_OF_CONFIRMATION: u64 = 3600;
 
 impl StoreTransaction {
     pub fn account_smt_store(&self) -> Result<SMTStore<'_, Self>, Error> {
@@ -37,6 +38,7 @@ impl StoreTransaction {
     pub fn state_tree(&self, context: StateContext) -> Result<StateTree<'_>, Error> {
         let block = match context {
             StateContext::ReadOnlyHistory(block_number) => {
+                self.check_history_available(block_number)?;
                 let block_hash = self
                     .get_block_hash_by_number(block_number)?
                     .ok_or_else(|| Error::from("can't find block".to_string()))?;
@@ -81,6 +83,9 @@ impl StoreTransaction {
 
     /// Prune finalized block state record
     /// The arg new_number is current block number
+    ///
+    /// All records of the block are removed, history of it and the blocks
+    /// below is marked as pruned.
     pub(crate) fn prune_finalized_block_state_record(&self, new_number: u64) -> Result<(), Error> {
         if new_number <= NUMBER_OF_CONFIRMATION {
             return Ok(());
@@ -89,7 +94,8 @@ impl StoreTransaction {
         if finalized_block_number == 0 {
             return Ok(());
         }
-        self.remove_block_state_record(finalized_block_number)
+        self.remove_block_state_record(finalized_block_number)?;
+        self.set_pruned_block_number(finalized_block_number)
     }
 
     pub(crate) fn remove_block_state_record(&self, block_number: u64) -> Result<(), Error> {
@@ -107,7 +113,7 @@ impl StoreTransaction {
 
     pub fn get_history_state(&self, block_number: u64, state_key: &H256) -> Option<H256> {
         let key = BlockStateRecordKeyReverse::new(block_number, state_key);
-        let mut raw_iter: DBRawIterator = self
+        let mut raw_iter: DBRawIter = self
             .get_iter(COLUMN_BLOCK_STATE_REVERSE_RECORD, IteratorMode::Start)
             .into();
         raw_iter.seek_for_prev(key.as_slice());
@@ -140,6 +146,23 @@ impl StoreTransaction {
         }
     }
 
+    /// State key values written by a block
+    pub fn get_block_state_record(&self, block_number: u64) -> Vec<(H256, H256)> {
+        let start_key = BlockStateRecordKey::new(block_number, &H256::zero());
+        self.get_iter(
+            COLUMN_BLOCK_STATE_RECORD,
+            IteratorMode::From(start_key.as_slice(), Direction::Forward),
+        )
+        .map(|(key, value)| (BlockStateRecordKey::from_slice(&key), value))
+        .take_while(|(key, _value)| key.block_number() == block_number)
+        .map(|(key, value)| {
+            let mut buf = [0u8; 32];
+            buf.copy_from_slice(&value);
+            (key.state_key(), buf.into())
+        })
+        .collect()
+    }
+
     pub(crate) fn iter_block_state_record(
         &self,
         block_number: u64,
@@ -199,25 +222,25 @@ impl BlockStateRecordKeyReverse {
         BlockStateRecordKeyReverse(inner)
     }
 
-    fn state_key(&self) -> H256 {
+    pub fn state_key(&self) -> H256 {
         let mut inner = [0u8; 32];
         inner.copy_from_slice(&self.0[..32]);
         inner.into()
     }
 
-    fn block_number(&self) -> u64 {
+    pub fn block_number(&self) -> u64 {
         let mut inner = [0u8; 8];
         inner.copy_from_slice(&self.0[32..]);
         u64::from_be_bytes(inner)
     }
 
-    fn from_slice(bytes: &[u8]) -> Self {
+    pub fn from_slice(bytes: &[u8]) -> Self {
         let mut inner = [0u8; 40];
         inner.copy_from_slice(bytes);
         BlockStateRecordKeyReverse(inner)
     }
 
-    fn as_slice(&self) -> &[u8] {
+    pub fn as_slice(&self) -> &[u8] {
         &self.0
     }
 }
@@ -5,16 +5,20 @@ use gw_common::h256_ext::H256Ext;
 use gw_common::{merkle_utils::calculate_state_checkpoint, smt::SMT, H256};
 use gw_db::schema::{
     Col, COLUMN_ASSET_SCRIPT, COLUMN_BAD_BLOCK_CHALLENGE_TARGET, COLUMN_BLOCK,
-    COLUMN_BLOCK_DEPOSIT_REQUESTS, COLUMN_BLOCK_GLOBAL_STATE, COLUMN_BLOCK_SMT_BRANCH,
-    COLUMN_BLOCK_SMT_LEAF, COLUMN_INDEX, COLUMN_L2BLOCK_COMMITTED_INFO, COLUMN_META,
-    COLUMN_REVERTED_BLOCK_SMT_BRANCH, COLUMN_REVERTED_BLOCK_SMT_LEAF,
-    COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_TRANSACTION, COLUMN_TRANSACTION_INFO,
-    COLUMN_TRANSACTION_RECEIPT, META_BLOCK_SMT_ROOT_KEY, META_CHAIN_ID_KEY,
-    META_LAST_VALID_TIP_BLOCK_HASH_KEY, META_MEM_BLOCK_ACCOUNT_SMT_COUNT_KEY,
-    META_MEM_BLOCK_ACCOUNT_SMT_ROOT_KEY, META_REVERTED_BLOCK_SMT_ROOT_KEY, META_TIP_BLOCK_HASH_KEY,
+    COLUMN_BLOCK_DEPOSIT_OUT_POINTS, COLUMN_BLOCK_DEPOSIT_REQUESTS, COLUMN_BLOCK_GLOBAL_STATE,
+    COLUMN_BLOCK_SMT_BRANCH, COLUMN_BLOCK_SMT_LEAF, COLUMN_DEPOSIT_INFO, COLUMN_INDEX,
+    COLUMN_L2BLOCK_COMMITTED_INFO, COLUMN_META, COLUMN_REVERTED_BLOCK_SMT_BRANCH,
+    COLUMN_REVERTED_BLOCK_SMT_LEAF, COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_TRANSACTION,
+    COLUMN_TRANSACTION_INFO, COLUMN_TRANSACTION_RECEIPT, COLUMN_WITHDRAWAL_INFO,
+    META_BLOCK_SMT_ROOT_KEY, META_CHAIN_ID_KEY, META_LAST_VALID_TIP_BLOCK_HASH_KEY,
+    META_MEM_BLOCK_ACCOUNT_SMT_COUNT_KEY, META_MEM_BLOCK_ACCOUNT_SMT_ROOT_KEY,
+    META_REVERTED_BLOCK_SMT_ROOT_KEY, META_TIP_BLOCK_HASH_KEY, META_VM_VERSION_ACTIVATIONS_KEY,
 };
 use gw_db::ReadOptions;
-use gw_db::{error::Error, iter::DBIter, DBIterator, IteratorMode, RocksDBTransaction};
+use gw_db::{
+    error::Error, iter::DBIter, DBIterator, Direction, IteratorMode, MemoryTransaction,
+    RocksDBTransaction,
+};
 use gw_types::offchain::global_state_from_slice;
 use gw_types::packed::Script;
 use gw_types::{
@@ -26,53 +30,87 @@ use gw_types::{
 use std::collections::HashSet;
 
 pub struct StoreTransaction {
-    pub(crate) inner: RocksDBTransaction,
+    pub(crate) inner: TransactionInner,
+    pub(crate) prune_on_attach: bool,
+}
+
+pub(crate) enum TransactionInner {
+    RocksDB(RocksDBTransaction),
+    Memory(MemoryTransaction),
 }
 
 impl KVStore for StoreTransaction {
     fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
-        self.inner
-            .get(col, key)
-            .expect("db operation should be ok")
-            .map(|v| Box::<[u8]>::from(v.as_ref()))
+        match self.inner {
+            TransactionInner::RocksDB(ref inner) => inner
+                .get(col, key)
+                .map(|v| v.map(|v| Box::<[u8]>::from(v.as_ref()))),
+            TransactionInner::Memory(ref inner) => {
+                inner.get(col, key).map(|v| v.map(Vec::into_boxed_slice))
+            }
+        }
+        .expect("db operation should be ok")
     }
 
     fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIter {
-        self.inner
-            .iter(col, mode)
-            .expect("db operation should be ok")
+        match self.inner {
+            TransactionInner::RocksDB(ref inner) => inner.iter(col, mode),
+            TransactionInner::Memory(ref inner) => inner.iter(col, mode),
+        }
+        .expect("db operation should be ok")
     }
 
     fn get_iter_opts(&self, col: Col, mode: IteratorMode, opts: &ReadOptions) -> DBIter {
-        self.inner
-            .iter_opt(col, mode, opts)
-            .expect("db operation should be ok")
+        match self.inner {
+            TransactionInner::RocksDB(ref inner) => inner.iter_opt(col, mode, opts),
+            // read options are ignored
+            TransactionInner::Memory(ref inner) => inner.iter(col, mode),
+        }
+        .expect("db operation should be ok")
     }
 
     fn insert_raw(&self, col: Col, key: &[u8], value: &[u8]) -> Result<(), Error> {
-        self.inner.put(col, key, value)
+        match self.inner {
+            TransactionInner::RocksDB(ref inner) => inner.put(col, key, value),
+            TransactionInner::Memory(ref inner) => inner.put(col, key, value),
+        }
     }
 
     fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error> {
-        self.inner.delete(col, key)
+        match self.inner {
+            TransactionInner::RocksDB(ref inner) => inner.delete(col, key),
+            TransactionInner::Memory(ref inner) => inner.delete(col, key),
+        }
     }
 }
 
 impl StoreTransaction {
     pub fn commit(&self) -> Result<(), Error> {
-        self.inner.commit()
+        match self.inner {
+            TransactionInner::RocksDB(ref inner) => inner.commit(),
+            TransactionInner::Memory(ref inner) => inner.commit(),
+        }
     }
 
     pub fn rollback(&self) -> Result<(), Error> {
-        self.inner.rollback()
+        match self.inner {
+            TransactionInner::RocksDB(ref inner) => inner.rollback(),
+            TransactionInner::Memory(ref inner) => inner.rollback(),
+        }
     }
 
     pub fn set_save_point(&self) {
-        self.inner.set_savepoint()
+        match self.inner {
+            TransactionInner::RocksDB(ref inner) => inner.set_savepoint(),
+            TransactionInner::Memory(ref inner) => inner.set_savepoint(),
+        }
     }
 
     pub fn rollback_to_save_point(&self) -> Result<(), Error> {
-        self.inner.rollback_to_savepoint()
+        match self.inner {
+            TransactionInner::RocksDB(ref inner) => inner.rollback_to_savepoint(),
+            TransactionInner::Memory(ref inner) => inner.rollback_to_savepoint(),
+        }
     }
 
     pub fn setup_chain_id(&self, chain_id: H256) -> Result<(), Error> {
@@ -194,6 +232,31 @@ impl StoreTransaction {
         )
     }
 
+    /// First blocks of activated VM version switches
+    pub fn get_vm_version_activations(&self) -> Result<Vec<u64>, Error> {
+        let slice = match self.get(COLUMN_META, META_VM_VERSION_ACTIVATIONS_KEY) {
+            Some(slice) => slice,
+            None => return Ok(Vec::new()),
+        };
+        let activations = slice
+            .chunks(8)
+            .map(|number| {
+                packed::Uint64Reader::from_slice(number)
+                    .map(|number| number.to_entity().unpack())
+                    .map_err(|err| Error::from(format!("invalid VM version activation {}", err)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(activations)
+    }
+
+    pub fn set_vm_version_activations(&self, activations: &[u64]) -> Result<(), Error> {
+        let value: Vec<u8> = activations
+            .iter()
+            .flat_map(|number| number.pack().as_slice().to_vec())
+            .collect();
+        self.insert_raw(COLUMN_META, META_VM_VERSION_ACTIVATIONS_KEY, &value)
+    }
+
     pub fn get_tip_block_hash(&self) -> Result<H256, Error> {
         let slice = self
             .get(COLUMN_META, META_TIP_BLOCK_HASH_KEY)
@@ -277,6 +340,37 @@ impl StoreTransaction {
             }))
     }
 
+    pub fn get_withdrawal_info(
+        &self,
+        withdrawal_hash: &H256,
+    ) -> Result<Option<packed::TransactionInfo>, Error> {
+        let info_opt = self
+            .get(COLUMN_WITHDRAWAL_INFO, withdrawal_hash.as_slice())
+            .map(|slice| {
+                packed::TransactionInfoReader::from_slice_should_be_ok(slice.as_ref()).to_entity()
+            });
+        Ok(info_opt)
+    }
+
+    /// Load withdrawal from the block, the key is built from block hash and
+    /// withdrawal index.
+    pub fn get_withdrawal_by_key(
+        &self,
+        withdrawal_key: &TransactionKey,
+    ) -> Result<Option<packed::WithdrawalRequest>, Error> {
+        // block_hash(32 bytes) | index(4 bytes, BE)
+        let key = withdrawal_key.as_slice();
+        let mut block_hash = [0u8; 32];
+        block_hash.copy_from_slice(&key[..32]);
+        let mut index = [0u8; 4];
+        index.copy_from_slice(&key[32..]);
+        let index = u32::from_be_bytes(index);
+        let withdrawal_opt = self
+            .get_block(&block_hash.into())?
+            .and_then(|block| block.withdrawals().get(index as usize));
+        Ok(withdrawal_opt)
+    }
+
     pub fn get_transaction_receipt(
         &self,
         tx_hash: &H256,
@@ -330,6 +424,86 @@ impl StoreTransaction {
         }
     }
 
+    pub fn get_deposit_info(
+        &self,
+        out_point: &packed::OutPoint,
+    ) -> Result<Option<packed::TransactionInfo>, Error> {
+        let info_opt = self
+            .get(COLUMN_DEPOSIT_INFO, out_point.as_slice())
+            .map(|slice| {
+                packed::TransactionInfoReader::from_slice_should_be_ok(slice.as_ref()).to_entity()
+            });
+        Ok(info_opt)
+    }
+
+    /// Return deposit info of all deposit cells created by the layer1 transaction
+    pub fn get_deposit_info_by_tx_hash(
+        &self,
+        tx_hash: &H256,
+    ) -> Result<Vec<(packed::OutPoint, packed::TransactionInfo)>, Error> {
+        // out point: tx_hash(32 bytes) | index(4 bytes)
+        let iter = self.get_iter(
+            COLUMN_DEPOSIT_INFO,
+            IteratorMode::From(tx_hash.as_slice(), Direction::Forward),
+        );
+        let deposits = iter
+            .take_while(|(key, _)| key.starts_with(tx_hash.as_slice()))
+            .map(|(key, value)| {
+                let out_point =
+                    packed::OutPointReader::from_slice_should_be_ok(key.as_ref()).to_entity();
+                let info = packed::TransactionInfoReader::from_slice_should_be_ok(value.as_ref())
+                    .to_entity();
+                (out_point, info)
+            })
+            .collect();
+        Ok(deposits)
+    }
+
+    /// Index deposits of the block by out points of the consumed deposit cells,
+    /// `deposit_out_points` must be in the same order as the block deposit requests.
+    pub fn insert_block_deposit_info(
+        &self,
+        block: &packed::L2Block,
+        deposit_out_points: &[packed::OutPoint],
+    ) -> Result<(), Error> {
+        let block_hash = block.hash();
+        for (index, out_point) in deposit_out_points.iter().enumerate() {
+            let key = TransactionKey::build_transaction_key(block_hash.pack(), index as u32);
+            let info = packed::TransactionInfo::new_builder()
+                .key(key)
+                .block_number(block.raw().number())
+                .build();
+            self.insert_raw(COLUMN_DEPOSIT_INFO, out_point.as_slice(), info.as_slice())?;
+        }
+        // out points are needed to remove the index when the block is pruned
+        let out_points = packed::OutPointVec::new_builder()
+            .extend(deposit_out_points.iter().cloned())
+            .build();
+        self.insert_raw(
+            COLUMN_BLOCK_DEPOSIT_OUT_POINTS,
+            block_hash.as_slice(),
+            out_points.as_slice(),
+        )?;
+        Ok(())
+    }
+
+    /// Out points of the deposit cells consumed by the block, in the same order
+    /// as the block deposit requests
+    pub fn get_block_deposit_out_points(
+        &self,
+        block_hash: &H256,
+    ) -> Result<Option<Vec<packed::OutPoint>>, Error> {
+        let out_points_opt = self
+            .get(COLUMN_BLOCK_DEPOSIT_OUT_POINTS, block_hash.as_slice())
+            .map(|slice| {
+                packed::OutPointVecReader::from_slice_should_be_ok(slice.as_ref())
+                    .to_entity()
+                    .into_iter()
+                    .collect()
+            });
+        Ok(out_points_opt)
+    }
+
     pub fn get_block_post_global_state(
         &self,
         block_hash: &H256,
@@ -574,6 +748,17 @@ impl StoreTransaction {
             )?;
         }
 
+        // build withdrawal info, it is overwritten if the withdrawal is
+        // packaged again after the block is reverted
+        for (index, withdrawal) in block.withdrawals().into_iter().enumerate() {
+            let key = TransactionKey::build_transaction_key(block_hash.pack(), index as u32);
+            let info = packed::TransactionInfo::new_builder()
+                .key(key)
+                .block_number(block.raw().number())
+                .build();
+            self.insert_raw(COLUMN_WITHDRAWAL_INFO, &withdrawal.hash(), info.as_slice())?;
+        }
+
         let post_states: Vec<AccountMerkleState> = {
             let withdrawal_post_states = withdrawal_receipts.into_iter().map(|w| w.post_state());
             let tx_post_states = tx_receipts.iter().map(|t| t.post_state());
@@ -621,6 +806,7 @@ impl StoreTransaction {
             let tx_hash = tx.hash();
             self.insert_raw(COLUMN_TRANSACTION_INFO, &tx_hash, info.as_slice())?;
         }
+        self.insert_account_transaction_index(&block)?;
 
         // build main chain index
         self.insert_raw(COLUMN_INDEX, raw_number.as_slice(), &block_hash)?;
@@ -636,7 +822,9 @@ impl StoreTransaction {
 
         // update tip
         self.insert_raw(COLUMN_META, META_TIP_BLOCK_HASH_KEY, &block_hash)?;
-        self.prune_finalized_block_state_record(raw_number.unpack())?;
+        if self.prune_on_attach {
+            self.prune_finalized_block_state_record(raw_number.unpack())?;
+        }
         self.set_last_valid_tip_block_hash(&block_hash.into())?;
 
         Ok(())
@@ -644,13 +832,16 @@ impl StoreTransaction {
 
     /// Delete block from DB
     pub fn detach_block(&self, block: &packed::L2Block) -> Result<(), Error> {
-        // remove transaction info
-        for tx in block.transactions().into_iter() {
+        let block_hash: H256 = block.hash().into();
+
+        // remove transaction info and access lists
+        for (index, tx) in block.transactions().into_iter().enumerate() {
             let tx_hash = tx.hash();
             self.delete(COLUMN_TRANSACTION_INFO, &tx_hash)?;
+            let key = TransactionKey::build_transaction_key(block_hash.pack(), index as u32);
+            self.delete_transaction_access_list(&key)?;
         }
-
-        let block_hash: H256 = block.hash().into();
+        self.remove_account_transaction_index(block)?;
 
         // remove index
         let block_number = block.raw().number();
@@ -0,0 +1,93 @@
+//! State keys touched by transactions, an optional extension of receipts
+//!
+//! Access lists are recorded when a block is inserted if enabled, and pruned
+//! with the receipts.
+
+use gw_common::H256;
+use gw_db::{error::Error, schema::COLUMN_TRANSACTION_ACCESS_LIST};
+use gw_types::{
+    offchain::TxAccessList,
+    packed::{self, TransactionKey},
+    prelude::*,
+};
+
+use super::StoreTransaction;
+use crate::traits::KVStore;
+
+const READ_KEYS: u8 = 0;
+const WRITE_KEYS: u8 = 1;
+
+// tx key(36 bytes) | kind(1 byte)
+fn access_list_key(tx_key: &TransactionKey, kind: u8) -> [u8; 37] {
+    let mut key = [0u8; 37];
+    key[..36].copy_from_slice(tx_key.as_slice());
+    key[36] = kind;
+    key
+}
+
+impl StoreTransaction {
+    /// Insert access lists of the block's txs in order
+    pub fn insert_block_tx_access_lists(
+        &self,
+        block: &packed::L2Block,
+        access_lists: &[TxAccessList],
+    ) -> Result<(), Error> {
+        debug_assert_eq!(block.transactions().len(), access_lists.len());
+        let block_hash = block.hash();
+        for (index, access_list) in access_lists.iter().enumerate() {
+            let tx_key = TransactionKey::build_transaction_key(block_hash.pack(), index as u32);
+            for (kind, keys) in [
+                (READ_KEYS, &access_list.read_keys),
+                (WRITE_KEYS, &access_list.write_keys),
+            ]
+            .iter()
+            {
+                let keys: packed::Byte32Vec = keys.pack();
+                self.insert_raw(
+                    COLUMN_TRANSACTION_ACCESS_LIST,
+                    &access_list_key(&tx_key, *kind),
+                    keys.as_slice(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_transaction_access_list(
+        &self,
+        tx_key: &TransactionKey,
+    ) -> Result<Option<TxAccessList>, Error> {
+        let get_keys = |kind: u8| -> Option<Vec<H256>> {
+            self.get(
+                COLUMN_TRANSACTION_ACCESS_LIST,
+                &access_list_key(tx_key, kind),
+            )
+            .map(|slice| {
+                packed::Byte32VecReader::from_slice_should_be_ok(slice.as_ref())
+                    .to_entity()
+                    .unpack()
+            })
+        };
+        match (get_keys(READ_KEYS), get_keys(WRITE_KEYS)) {
+            (Some(read_keys), Some(write_keys)) => Ok(Some(TxAccessList {
+                read_keys,
+                write_keys,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn delete_transaction_access_list(
+        &self,
+        tx_key: &TransactionKey,
+    ) -> Result<(), Error> {
+        self.delete(
+            COLUMN_TRANSACTION_ACCESS_LIST,
+            &access_list_key(tx_key, READ_KEYS),
+        )?;
+        self.delete(
+            COLUMN_TRANSACTION_ACCESS_LIST,
+            &access_list_key(tx_key, WRITE_KEYS),
+        )
+    }
+}
@@ -1,31 +1,62 @@
-use gw_db::{error::Error, schema::Col, RocksDBWriteBatch};
+use gw_db::{error::Error, schema::Col, MemoryWriteBatch, RocksDBWriteBatch};
 
 pub struct StoreWriteBatch {
-    pub(crate) inner: RocksDBWriteBatch,
+    pub(crate) inner: WriteBatchInner,
+}
+
+pub(crate) enum WriteBatchInner {
+    RocksDB(RocksDBWriteBatch),
+    Memory(MemoryWriteBatch),
 }
 
 impl StoreWriteBatch {
     pub fn put(&mut self, col: Col, key: &[u8], value: &[u8]) -> Result<(), Error> {
-        self.inner.put(col, key, value)
+        match self.inner {
+            WriteBatchInner::RocksDB(ref mut inner) => inner.put(col, key, value),
+            WriteBatchInner::Memory(ref mut inner) => inner.put(col, key, value),
+        }
     }
 
     pub fn delete(&mut self, col: Col, key: &[u8]) -> Result<(), Error> {
-        self.inner.delete(col, key)
+        match self.inner {
+            WriteBatchInner::RocksDB(ref mut inner) => inner.delete(col, key),
+            WriteBatchInner::Memory(ref mut inner) => inner.delete(col, key),
+        }
+    }
+
+    /// Remove entries in the range [from, to)
+    pub fn delete_range(&mut self, col: Col, from: &[u8], to: &[u8]) -> Result<(), Error> {
+        match self.inner {
+            WriteBatchInner::RocksDB(ref mut inner) => inner.delete_range(col, from, to),
+            WriteBatchInner::Memory(ref mut inner) => inner.delete_range(col, from, to),
+        }
     }
 
     pub fn size_in_bytes(&self) -> usize {
-        self.inner.size_in_bytes()
+        match self.inner {
+            WriteBatchInner::RocksDB(ref inner) => inner.size_in_bytes(),
+            WriteBatchInner::Memory(ref inner) => inner.size_in_bytes(),
+        }
     }
 
     pub fn len(&self) -> usize {
-        self.inner.len()
+        match self.inner {
+            WriteBatchInner::RocksDB(ref inner) => inner.len(),
+            WriteBatchInner::Memory(ref inner) => inner.len(),
+        }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.inner.is_empty()
+        match self.inner {
+            WriteBatchInner::RocksDB(ref inner) => inner.is_empty(),
+            WriteBatchInner::Memory(ref inner) => inner.is_empty(),
+        }
     }
 
     pub fn clear(&mut self) -> Result<(), Error> {
-        self.inner.clear()
+        match self.inner {
+            WriteBatchInner::RocksDB(ref mut inner) => inner.clear(),
+            WriteBatchInner::Memory(ref mut inner) => inner.clear(),
+        }
     }
 }
@@ -3,7 +3,7 @@
 use gw_block_producer::produce_block::{produce_block, ProduceBlockParam, ProduceBlockResult};
 use gw_chain::chain::{Chain, L1Action, L1ActionContext, SyncParam, UpdateAction};
 use gw_common::{blake2b::new_blake2b, H256};
-use gw_config::{BackendConfig, BackendType, ChainConfig, GenesisConfig};
+use gw_config::{BackendConfig, BackendType, ChainConfig, GenesisConfig, ParallelExecutionConfig};
 use gw_generator::{
     account_lock_manage::{always_success::AlwaysSuccess, AccountLockManage},
     backend_manage::BackendManage,
@@ -17,9 +17,9 @@ use gw_types::{
     core::ScriptHashType,
     offchain::{CellInfo, CollectedCustodianCells, DepositInfo, RollupContext},
     packed::{
-        CellOutput, DepositLockArgs, DepositRequest, L2BlockCommittedInfo, RawTransaction,
-        RollupAction, RollupActionUnion, RollupConfig, RollupSubmitBlock, Script, Transaction,
-        WitnessArgs,
+        CellOutput, DepositLockArgs, DepositRequest, L2BlockCommittedInfo, OutPoint,
+        RawTransaction, RollupAction, RollupActionUnion, RollupConfig, RollupSubmitBlock, Script,
+        Transaction, WitnessArgs,
     },
     prelude::*,
 };
@@ -75,40 +75,89 @@ pub fn build_backend_manage(rollup_config: &RollupConfig) -> BackendManage {
             validator_path: META_VALIDATOR_PATH.into(),
             generator_path: META_GENERATOR_PATH.into(),
             validator_script_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+            activation: None,
         },
         BackendConfig {
             backend_type: BackendType::Sudt,
             validator_path: SUDT_VALIDATOR_PATH.into(),
             generator_path: SUDT_GENERATOR_PATH.into(),
             validator_script_type_hash: sudt_validator_script_type_hash.into(),
+            activation: None,
         },
     ];
     BackendManage::from_config(configs).expect("default backend")
 }
 
-pub fn setup_chain(rollup_type_script: Script) -> Chain {
-    let mut account_lock_manage = AccountLockManage::default();
-    let rollup_config = RollupConfig::new_builder()
+fn default_rollup_config() -> RollupConfig {
+    RollupConfig::new_builder()
         .allowed_eoa_type_hashes(vec![*ALWAYS_SUCCESS_CODE_HASH].pack())
         .finality_blocks(DEFAULT_FINALITY_BLOCKS.pack())
-        .build();
+        .build()
+}
+
+fn default_account_lock_manage() -> AccountLockManage {
+    let mut account_lock_manage = AccountLockManage::default();
     account_lock_manage
         .register_lock_algorithm((*ALWAYS_SUCCESS_CODE_HASH).into(), Box::new(AlwaysSuccess));
-    let mut chain = setup_chain_with_account_lock_manage(
+    account_lock_manage
+}
+
+pub fn setup_chain(rollup_type_script: Script) -> Chain {
+    setup_chain_with_config(rollup_type_script, &ChainConfig::default())
+}
+
+pub fn setup_chain_with_config(rollup_type_script: Script, chain_config: &ChainConfig) -> Chain {
+    let mut chain = create_chain(
         rollup_type_script,
-        rollup_config,
-        account_lock_manage,
+        default_rollup_config(),
+        default_account_lock_manage(),
+        chain_config,
     );
     chain.complete_initial_syncing().unwrap();
     chain
 }
 
+/// Build a generator of the chain created by `setup_chain`
+pub fn build_generator(
+    rollup_type_script: &Script,
+    parallel_execution: ParallelExecutionConfig,
+) -> Generator {
+    let rollup_config = default_rollup_config();
+    let rollup_context = RollupContext {
+        rollup_script_hash: rollup_type_script.hash().into(),
+        rollup_config: rollup_config.clone(),
+    };
+    Generator::new(
+        build_backend_manage(&rollup_config),
+        default_account_lock_manage(),
+        rollup_context,
+        Default::default(),
+        7000_0000,
+        parallel_execution,
+        Default::default(),
+    )
+}
+
 pub fn setup_chain_with_account_lock_manage(
     rollup_type_script: Script,
     rollup_config: RollupConfig,
     account_lock_manage: AccountLockManage,
 ) -> Chain {
-    let store = Store::open_tmp().unwrap();
+    create_chain(
+        rollup_type_script,
+        rollup_config,
+        account_lock_manage,
+        &ChainConfig::default(),
+    )
+}
+
+fn create_chain(
+    rollup_type_script: Script,
+    rollup_config: RollupConfig,
+    account_lock_manage: AccountLockManage,
+    chain_config: &ChainConfig,
+) -> Chain {
+    let store = Store::open_memory();
     let rollup_script_hash = rollup_type_script.hash();
     let genesis_config = GenesisConfig {
         timestamp: 0,
@@ -129,6 +178,8 @@ pub fn setup_chain_with_account_lock_manage(
         rollup_context,
         Default::default(),
         7000_0000,
+        Default::default(),
+        Default::default(),
     ));
     init_genesis(
         &store,
@@ -151,7 +202,7 @@ pub fn setup_chain_with_account_lock_manage(
     Chain::create(
         &rollup_config,
         &rollup_type_script,
-        &ChainConfig::default(),
+        chain_config,
         store,
         generator,
         Some(Arc::new(Mutex::new(mem_pool))),
@@ -186,6 +237,15 @@ pub fn build_sync_tx(
         .build()
 }
 
+/// Out point of the deposit cell of the request in tests
+pub fn deposit_out_point(request: &DepositRequest) -> OutPoint {
+    let mut hasher = new_blake2b();
+    hasher.update(request.as_slice());
+    let mut tx_hash = [0u8; 32];
+    hasher.finalize(&mut tx_hash);
+    OutPoint::new_builder().tx_hash(tx_hash.pack()).build()
+}
+
 pub fn apply_block_result(
     chain: &mut Chain,
     rollup_cell: CellOutput,
@@ -196,11 +256,13 @@ pub fn apply_block_result(
     let l2block = block_result.block.clone();
     let transaction = build_sync_tx(rollup_cell, block_result);
     let l2block_committed_info = L2BlockCommittedInfo::default();
+    let deposit_out_points = deposit_requests.iter().map(deposit_out_point).collect();
 
     let update = L1Action {
         context: L1ActionContext::SubmitBlock {
             l2block,
             deposit_requests,
+            deposit_out_points,
             deposit_asset_scripts,
         },
         transaction,
@@ -258,7 +320,7 @@ pub fn construct_block(
         .into_iter()
         .map(|deposit| DepositInfo {
             cell: CellInfo {
-                out_point: Default::default(),
+                out_point: deposit_out_point(&deposit),
                 output: CellOutput::new_builder()
                     .lock(
                         Script::new_builder()
@@ -1,2 +1,3 @@
 pub mod chain;
 pub mod mem_pool_provider;
+pub mod rpc_server;
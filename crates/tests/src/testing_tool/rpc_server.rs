@@ -0,0 +1,62 @@
+use async_jsonrpc_client::HttpClient;
+use gw_block_producer::test_mode_control::TestModeControl;
+use gw_chain::chain::Chain;
+use gw_config::NodeMode;
+use gw_rpc_client::rpc_client::RPCClient;
+use gw_rpc_server::registry::Registry;
+use gw_types::packed::Script;
+use jsonrpc_v2::{MapRouter, RequestObject, Server};
+use smol::lock::Mutex;
+use std::sync::Arc;
+
+use super::chain::build_generator;
+
+/// Build a read-only JSON-RPC server of the chain created by `setup_chain`,
+/// L1 isn't reachable
+pub fn build_rpc_server(chain: Chain) -> Arc<Server<MapRouter>> {
+    let store = chain.store().clone();
+    let generator = Arc::new(build_generator(&Script::default(), Default::default()));
+    let rollup_context = generator.rollup_context().clone();
+    let rpc_client = {
+        let client = || HttpClient::new("http://127.0.0.1:1".to_string()).unwrap();
+        let rollup_type_script = ckb_types::packed::Script::default();
+        RPCClient::new(
+            rollup_type_script,
+            rollup_context.clone(),
+            client(),
+            client(),
+        )
+    };
+    Registry::new(
+        store,
+        generator,
+        None::<Box<TestModeControl>>,
+        rollup_context.rollup_config,
+        Default::default(),
+        Arc::new(Mutex::new(chain)),
+        None,
+        Default::default(),
+        NodeMode::ReadOnly,
+        None,
+        Default::default(),
+        Default::default(),
+        rpc_client,
+    )
+    .build_rpc_server()
+    .unwrap()
+}
+
+/// Handle a request in process, returns the JSON response object
+pub fn request(
+    rpc_server: &Server<MapRouter>,
+    method: &str,
+    params: Vec<serde_json::Value>,
+) -> serde_json::Value {
+    let request = RequestObject::request()
+        .with_method(method)
+        .with_params(params)
+        .with_id(1i64)
+        .finish();
+    let response = smol::block_on(rpc_server.handle(request));
+    serde_json::to_value(&response).unwrap()
+}
@@ -0,0 +1,108 @@
+use crate::testing_tool::chain::{apply_block_result, construct_block, setup_chain};
+
+use gw_rpc_server::binary::{serve_binary, BinaryHandler};
+use gw_types::{
+    packed::{CellOutput, L2Block, Script},
+    prelude::*,
+};
+use smol::Async;
+use std::{
+    convert::TryInto,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+const STATUS_OK: u8 = 0;
+const STATUS_NOT_FOUND: u8 = 1;
+const STATUS_ERROR: u8 = 2;
+
+fn request(stream: &mut TcpStream, request_id: u32, method: &str, params: &[u8]) -> (u8, Vec<u8>) {
+    let mut frame = Vec::new();
+    frame.extend_from_slice(&((5 + method.len() + params.len()) as u32).to_le_bytes());
+    frame.extend_from_slice(&request_id.to_le_bytes());
+    frame.push(method.len() as u8);
+    frame.extend_from_slice(method.as_bytes());
+    frame.extend_from_slice(params);
+    stream.write_all(&frame).unwrap();
+
+    let mut len_buf = [0u8; 4];
+    stream.read_exact(&mut len_buf).unwrap();
+    let mut response = vec![0u8; u32::from_le_bytes(len_buf) as usize];
+    stream.read_exact(&mut response).unwrap();
+    assert_eq!(
+        u32::from_le_bytes(response[..4].try_into().unwrap()),
+        request_id
+    );
+    (response[4], response[5..].to_vec())
+}
+
+#[test]
+fn test_binary_rpc_round_trip() {
+    let rollup_type_script = Script::default();
+    let mut chain = setup_chain(rollup_type_script.clone());
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = smol::block_on(mem_pool.lock());
+        construct_block(&chain, &mut mem_pool, Default::default()).unwrap()
+    };
+    apply_block_result(
+        &mut chain,
+        rollup_cell,
+        block_result,
+        vec![],
+        Default::default(),
+    );
+
+    let store = chain.store().clone();
+    let rollup_config = chain.generator().rollup_context().rollup_config.clone();
+    let handler = BinaryHandler::new(store.clone(), rollup_config, None)
+        .with_auth_token(Some("secret".to_string()));
+    let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0)).unwrap();
+    let address = listener.get_ref().local_addr().unwrap();
+    let _server = smol::spawn(serve_binary(listener, handler));
+
+    // requests before auth close the connection
+    let mut stream = TcpStream::connect(address).unwrap();
+    let (status, _) = request(&mut stream, 1, "gw_ping", &[]);
+    assert_eq!(status, STATUS_ERROR);
+    let mut stream = TcpStream::connect(address).unwrap();
+    let (status, _) = request(&mut stream, 1, "gw_auth", b"wrong");
+    assert_eq!(status, STATUS_ERROR);
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    let (status, _) = request(&mut stream, 1, "gw_auth", b"secret");
+    assert_eq!(status, STATUS_OK);
+    let (status, payload) = request(&mut stream, 2, "gw_ping", &[]);
+    assert_eq!((status, payload.as_slice()), (STATUS_OK, &b"pong"[..]));
+
+    let tip_block_hash = store.get_tip_block_hash().unwrap();
+    let (status, payload) = request(&mut stream, 3, "gw_get_tip_block_hash", &[]);
+    assert_eq!(
+        (status, payload.as_slice()),
+        (STATUS_OK, tip_block_hash.as_slice())
+    );
+
+    let (status, payload) = request(
+        &mut stream,
+        4,
+        "gw_get_block_by_number",
+        &1u64.to_le_bytes(),
+    );
+    assert_eq!(status, STATUS_OK);
+    let block = L2Block::from_slice(&payload).unwrap();
+    assert_eq!(block.hash(), <[u8; 32]>::from(tip_block_hash));
+
+    let (status, payload) = request(
+        &mut stream,
+        5,
+        "gw_get_block_by_number",
+        &2u64.to_le_bytes(),
+    );
+    assert_eq!((status, payload.len()), (STATUS_NOT_FOUND, 0));
+
+    let (status, _) = request(&mut stream, 6, "gw_get_block_by_number", &[0u8; 3]);
+    assert_eq!(status, STATUS_ERROR);
+}
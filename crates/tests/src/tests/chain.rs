@@ -1,19 +1,39 @@
-use crate::testing_tool::chain::{
-    build_sync_tx, construct_block, setup_chain, ALWAYS_SUCCESS_CODE_HASH,
+use crate::testing_tool::{
+    chain::{
+        build_sync_tx, construct_block, setup_chain, setup_chain_with_config,
+        ALWAYS_SUCCESS_CODE_HASH,
+    },
+    rpc_server::{build_rpc_server, request},
 };
+use crate::tests::trace::commit_transfer;
+use gw_block_producer::backup;
 use gw_chain::chain::{
     Chain, L1Action, L1ActionContext, RevertL1ActionContext, RevertedAction, RevertedL1Action,
     SyncParam, UpdateAction,
 };
 use gw_common::{
     builtins::CKB_SUDT_ACCOUNT_ID,
-    state::{to_short_address, State},
+    state::{build_account_field_key, to_short_address, State, GW_ACCOUNT_NONCE_TYPE},
     H256,
 };
-use gw_store::state::state_db::StateContext;
+use gw_config::ChainConfig;
+use gw_db::{
+    schema::{
+        COLUMN_ACCOUNT_SMT_LEAF, COLUMN_BLOCK_SMT_LEAF, COLUMN_BLOCK_STATE_RECORD,
+        COLUMN_BLOCK_STATE_REVERSE_RECORD, COLUMN_INDEX, COLUMN_META, META_PRUNED_BLOCK_NUMBER_KEY,
+    },
+    IteratorMode,
+};
+use gw_jsonrpc_types::godwoken::TxAccessList as JsonTxAccessList;
+use gw_store::{
+    integrity::check_integrity, state::state_db::StateContext, state_snapshot, traits::KVStore,
+    Store,
+};
 use gw_types::{
     core::ScriptHashType,
-    packed::{CellOutput, DepositRequest, GlobalState, L2BlockCommittedInfo, Script},
+    packed::{
+        CellOutput, DepositRequest, GlobalState, L2BlockCommittedInfo, Script, TransactionKey,
+    },
     prelude::*,
 };
 
@@ -40,6 +60,7 @@ fn produce_a_block(
         context: L1ActionContext::SubmitBlock {
             l2block,
             deposit_requests: vec![deposit],
+            deposit_out_points: Default::default(),
             deposit_asset_scripts: Default::default(),
         },
         transaction,
@@ -178,6 +199,7 @@ fn test_layer1_fork() {
             context: L1ActionContext::SubmitBlock {
                 l2block: block_result.block.clone(),
                 deposit_requests: vec![deposit],
+                deposit_out_points: Default::default(),
                 deposit_asset_scripts: Default::default(),
             },
             transaction: build_sync_tx(rollup_cell.clone(), block_result),
@@ -209,6 +231,7 @@ fn test_layer1_fork() {
         context: L1ActionContext::SubmitBlock {
             l2block: block_result.block.clone(),
             deposit_requests: vec![deposit],
+            deposit_out_points: Default::default(),
             deposit_asset_scripts: Default::default(),
         },
         transaction: build_sync_tx(rollup_cell.clone(), block_result),
@@ -242,6 +265,7 @@ fn test_layer1_fork() {
         context: L1ActionContext::SubmitBlock {
             l2block: block_result.block.clone(),
             deposit_requests: vec![deposit],
+            deposit_out_points: Default::default(),
             deposit_asset_scripts: Default::default(),
         },
         transaction: build_sync_tx(rollup_cell, block_result),
@@ -363,6 +387,7 @@ fn test_layer1_revert() {
         context: L1ActionContext::SubmitBlock {
             l2block: block_result.block.clone(),
             deposit_requests: vec![deposit],
+            deposit_out_points: Default::default(),
             deposit_asset_scripts: Default::default(),
         },
         transaction: build_sync_tx(rollup_cell.clone(), block_result),
@@ -396,6 +421,7 @@ fn test_layer1_revert() {
         context: L1ActionContext::SubmitBlock {
             l2block: block_result.block.clone(),
             deposit_requests: vec![deposit],
+            deposit_out_points: Default::default(),
             deposit_asset_scripts: Default::default(),
         },
         transaction: build_sync_tx(rollup_cell, block_result),
@@ -621,3 +647,296 @@ fn test_sync_blocks() {
 
     drop(chain2);
 }
+
+#[test]
+fn test_state_snapshot() {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash = rollup_type_script.hash();
+    let mut chain = setup_chain(rollup_type_script.clone());
+
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    let user_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.clone().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.to_vec();
+            args.push(42);
+            args.pack()
+        })
+        .build();
+    for (number, capacity) in [(1u64, 290u64), (2, 400)].iter() {
+        let deposit = DepositRequest::new_builder()
+            .capacity((capacity * CKB).pack())
+            .script(user_script.clone())
+            .build();
+        produce_a_block(&mut chain, deposit, rollup_cell.clone(), *number);
+    }
+
+    let mut snapshot = Vec::new();
+    let tip = state_snapshot::export_state_snapshot(chain.store(), &mut snapshot).unwrap();
+    let tip_hash: H256 = tip.block.hash().into();
+    assert_eq!(
+        state_snapshot::verify_state_snapshot(snapshot.as_slice()).unwrap(),
+        tip_hash
+    );
+
+    // corrupted snapshot
+    let mut corrupted = snapshot.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 1;
+    assert!(state_snapshot::verify_state_snapshot(corrupted.as_slice()).is_err());
+
+    // only import into an empty store
+    assert!(state_snapshot::import_state_snapshot(chain.store(), snapshot.as_slice()).is_err());
+
+    let store = Store::open_memory();
+    let imported = state_snapshot::import_state_snapshot(&store, snapshot.as_slice()).unwrap();
+    assert_eq!(imported.block.as_slice(), tip.block.as_slice());
+    assert_eq!(
+        imported.global_state.as_slice(),
+        tip.global_state.as_slice()
+    );
+
+    let db = store.begin_transaction();
+    let origin_db = chain.store().begin_transaction();
+    assert_eq!(
+        db.get_block_smt_root().unwrap(),
+        origin_db.get_block_smt_root().unwrap()
+    );
+    assert_eq!(
+        db.get_block_hash_by_number(1).unwrap(),
+        origin_db.get_block_hash_by_number(1).unwrap()
+    );
+    let tree = db.state_tree(StateContext::ReadOnly).unwrap();
+    let origin_tree = origin_db.state_tree(StateContext::ReadOnly).unwrap();
+    assert_eq!(
+        tree.calculate_root().unwrap(),
+        origin_tree.calculate_root().unwrap()
+    );
+    let script_hash: H256 = user_script.hash().into();
+    let balance = tree
+        .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, to_short_address(&script_hash))
+        .unwrap();
+    assert_eq!(balance, 690 * CKB as u128);
+
+    // a snapshot of a tampered account leaf passes the checksum but fails the
+    // import, the rebuilt account root mismatches the global state
+    let tamper_db = chain.store().begin_transaction();
+    let (key, value) = tamper_db
+        .get_iter(COLUMN_ACCOUNT_SMT_LEAF, IteratorMode::Start)
+        .next()
+        .expect("account leaf");
+    let mut tampered = value.to_vec();
+    tampered[0] ^= 1;
+    tamper_db
+        .insert_raw(COLUMN_ACCOUNT_SMT_LEAF, &key, &tampered)
+        .unwrap();
+    tamper_db.commit().unwrap();
+    let mut snapshot = Vec::new();
+    state_snapshot::export_state_snapshot(chain.store(), &mut snapshot).unwrap();
+    assert!(state_snapshot::verify_state_snapshot(snapshot.as_slice()).is_ok());
+    let store = Store::open_memory();
+    assert!(state_snapshot::import_state_snapshot(&store, snapshot.as_slice()).is_err());
+}
+
+#[test]
+fn test_check_integrity() {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash = rollup_type_script.hash();
+    let mut chain = setup_chain(rollup_type_script.clone());
+
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    let user_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.clone().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.to_vec();
+            args.push(42);
+            args.pack()
+        })
+        .build();
+    for (number, capacity) in [(1u64, 290u64), (2, 400)].iter() {
+        let deposit = DepositRequest::new_builder()
+            .capacity((capacity * CKB).pack())
+            .script(user_script.clone())
+            .build();
+        produce_a_block(&mut chain, deposit, rollup_cell.clone(), *number);
+    }
+
+    let report = check_integrity(chain.store()).unwrap();
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.tip_block_number, 2);
+    assert_eq!(report.checked_blocks, 3);
+
+    // simulate pruning on attaching blocks, which removes all state records
+    // of block #1 and marks it as pruned
+    let db = chain.store().begin_transaction();
+    let block_records: Vec<_> = db
+        .get_iter(COLUMN_BLOCK_STATE_RECORD, IteratorMode::Start)
+        .filter(|(key, _)| key[..8] == 1u64.to_be_bytes())
+        .collect();
+    assert!(!block_records.is_empty());
+    for (key, _) in block_records {
+        let reverse_key = [&key[8..], &key[..8]].concat();
+        db.delete(COLUMN_BLOCK_STATE_RECORD, &key).unwrap();
+        db.delete(COLUMN_BLOCK_STATE_REVERSE_RECORD, &reverse_key)
+            .unwrap();
+    }
+    db.insert_raw(
+        COLUMN_META,
+        META_PRUNED_BLOCK_NUMBER_KEY,
+        1u64.pack().as_slice(),
+    )
+    .unwrap();
+    db.commit().unwrap();
+
+    let report = check_integrity(chain.store()).unwrap();
+    assert!(report.is_ok(), "{}", report);
+    assert!(!report.skipped.is_empty());
+    assert_eq!(report.checked_blocks, 3);
+
+    // tamper a state record of the tip block, it can't be replayed but
+    // mismatches the account smt leaf
+    let db = chain.store().begin_transaction();
+    let (key, value) = db
+        .get_iter(COLUMN_BLOCK_STATE_RECORD, IteratorMode::Start)
+        .find(|(key, _)| key[..8] == 2u64.to_be_bytes())
+        .expect("record of block #2");
+    let mut tampered = value.to_vec();
+    tampered[0] ^= 1;
+    db.insert_raw(COLUMN_BLOCK_STATE_RECORD, &key, &tampered)
+        .unwrap();
+    db.commit().unwrap();
+
+    let report = check_integrity(chain.store()).unwrap();
+    assert!(!report.is_ok());
+
+    let db = chain.store().begin_transaction();
+    db.insert_raw(COLUMN_BLOCK_STATE_RECORD, &key, &value)
+        .unwrap();
+    db.commit().unwrap();
+
+    // lose block #1 number index
+    let db = chain.store().begin_transaction();
+    db.delete(COLUMN_INDEX, 1u64.pack().as_slice()).unwrap();
+    db.commit().unwrap();
+
+    let report = check_integrity(chain.store()).unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.checked_blocks, 2);
+}
+
+#[test]
+fn test_check_backup_tip() {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash = rollup_type_script.hash();
+    let mut chain = setup_chain(rollup_type_script.clone());
+
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    let user_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.clone().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.to_vec();
+            args.push(42);
+            args.pack()
+        })
+        .build();
+    let deposit = DepositRequest::new_builder()
+        .capacity((290u64 * CKB).pack())
+        .script(user_script)
+        .build();
+    produce_a_block(&mut chain, deposit, rollup_cell, 1);
+
+    let (tip_number, _tip_hash) = backup::check_tip(chain.store()).unwrap();
+    assert_eq!(tip_number, 1);
+
+    // corrupt a restored account leaf, roots in meta are untouched
+    let db = chain.store().begin_transaction();
+    let (key, value) = db
+        .get_iter(COLUMN_ACCOUNT_SMT_LEAF, IteratorMode::Start)
+        .next()
+        .expect("account leaf");
+    let mut corrupted = value.to_vec();
+    corrupted[0] ^= 1;
+    db.insert_raw(COLUMN_ACCOUNT_SMT_LEAF, &key, &corrupted)
+        .unwrap();
+    db.commit().unwrap();
+    assert!(backup::check_tip(chain.store()).is_err());
+
+    // restore the leaf and corrupt a block leaf
+    let db = chain.store().begin_transaction();
+    db.insert_raw(COLUMN_ACCOUNT_SMT_LEAF, &key, &value)
+        .unwrap();
+    let (key, _value) = db
+        .get_iter(COLUMN_BLOCK_SMT_LEAF, IteratorMode::Start)
+        .next()
+        .expect("block leaf");
+    db.delete(COLUMN_BLOCK_SMT_LEAF, &key).unwrap();
+    db.commit().unwrap();
+    assert!(backup::check_tip(chain.store()).is_err());
+}
+
+#[test]
+fn test_tx_access_list() {
+    for tx_access_list in [false, true] {
+        let chain_config = ChainConfig {
+            tx_access_list,
+            ..Default::default()
+        };
+        let chain = setup_chain_with_config(Script::default(), &chain_config);
+        let (chain, block, alice_id, _fee) = commit_transfer(chain);
+        let store = chain.store().clone();
+        let tx_hash: H256 = block.transactions().get(0).unwrap().hash().into();
+        let tx_key = TransactionKey::build_transaction_key(block.hash().pack(), 0);
+        let rpc_server = build_rpc_server(chain);
+        let tx_hash_param = serde_json::to_value(ckb_fixed_hash::H256(tx_hash.into())).unwrap();
+
+        let response = request(
+            &rpc_server,
+            "gw_get_transaction_receipt",
+            vec![tx_hash_param.clone()],
+        );
+        assert!(response["result"].is_object(), "{}", response);
+        assert!(response["result"].get("access_list").is_none());
+
+        let response = request(
+            &rpc_server,
+            "gw_get_transaction_receipt",
+            vec![tx_hash_param, serde_json::Value::Bool(true)],
+        );
+        assert!(response["result"].is_object(), "{}", response);
+        let access_list = store
+            .begin_transaction()
+            .get_transaction_access_list(&tx_key)
+            .unwrap();
+        if tx_access_list {
+            let access_list = access_list.expect("access list");
+            let nonce_key = build_account_field_key(alice_id, GW_ACCOUNT_NONCE_TYPE);
+            assert!(access_list.write_keys.contains(&nonce_key));
+            assert_eq!(
+                response["result"]["access_list"],
+                serde_json::to_value(JsonTxAccessList::from(access_list)).unwrap()
+            );
+        } else {
+            assert!(access_list.is_none());
+            assert!(response["result"].get("access_list").is_none());
+            continue;
+        }
+
+        // access lists of reverted blocks are removed
+        let db = store.begin_transaction();
+        let tip_block = db.get_tip_block().unwrap();
+        db.detach_block(&tip_block).unwrap();
+        db.detach_block(&block).unwrap();
+        db.commit().unwrap();
+        let db = store.begin_transaction();
+        assert_eq!(db.get_transaction_access_list(&tx_key).unwrap(), None);
+    }
+}
@@ -0,0 +1,129 @@
+use super::deposit_withdrawal::produce_empty_block;
+use crate::testing_tool::chain::{
+    apply_block_result, construct_block, deposit_out_point, setup_chain, ALWAYS_SUCCESS_CODE_HASH,
+    DEFAULT_FINALITY_BLOCKS,
+};
+
+use gw_common::H256;
+use gw_jsonrpc_types::godwoken::DepositStatus;
+use gw_rpc_server::query;
+use gw_types::{
+    core::ScriptHashType,
+    offchain::{CellInfo, DepositInfo},
+    packed::{CellOutput, DepositRequest, Script},
+    prelude::*,
+};
+
+fn build_deposit(rollup_script_hash: &H256, capacity: u64) -> DepositRequest {
+    let user_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.as_slice().to_vec();
+            args.push(42);
+            args.pack()
+        })
+        .build();
+    DepositRequest::new_builder()
+        .capacity(capacity.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .script(user_script)
+        .build()
+}
+
+fn to_deposit_info(request: DepositRequest) -> DepositInfo {
+    DepositInfo {
+        cell: CellInfo {
+            out_point: deposit_out_point(&request),
+            ..Default::default()
+        },
+        request,
+    }
+}
+
+#[test]
+fn test_deposit_status() {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash: H256 = rollup_type_script.hash().into();
+    let mut chain = setup_chain(rollup_type_script.clone());
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    let rollup_context = chain.generator().rollup_context().clone();
+
+    let deposit = build_deposit(&rollup_script_hash, 600_00000000);
+    let out_point = deposit_out_point(&deposit);
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = smol::block_on(mem_pool.lock());
+        construct_block(&chain, &mut mem_pool, vec![deposit.clone()]).unwrap()
+    };
+
+    // collected by mem-pool
+    let record = query::get_pending_deposit(chain.store(), &out_point)
+        .unwrap()
+        .expect("pending deposit");
+    assert_eq!(record.status, DepositStatus::Pending);
+    assert_eq!(record.request.as_slice(), deposit.as_slice());
+    assert!(
+        query::get_deposit(chain.store(), &rollup_context, &out_point)
+            .unwrap()
+            .is_none()
+    );
+
+    apply_block_result(
+        &mut chain,
+        rollup_cell.clone(),
+        block_result,
+        vec![deposit.clone()],
+        Default::default(),
+    );
+
+    // indexed by the consumed deposit cell
+    let tip_block = chain.store().get_tip_block().unwrap();
+    let tip_block_hash: H256 = tip_block.hash().into();
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    let record = query::get_deposit(chain.store(), &rollup_context, &out_point)
+        .unwrap()
+        .expect("deposit");
+    assert_eq!(record.status, DepositStatus::Committed);
+    assert_eq!(record.request.as_slice(), deposit.as_slice());
+    assert_eq!(record.block, Some((tip_block_hash, tip_block_number)));
+    assert!(record.account_id.is_some());
+    assert!(query::get_pending_deposit(chain.store(), &out_point)
+        .unwrap()
+        .is_none());
+
+    let tx_hash: H256 = out_point.tx_hash().unpack();
+    let records = query::get_deposits_by_tx_hash(chain.store(), &rollup_context, &tx_hash).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].out_point.as_slice(), out_point.as_slice());
+
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        produce_empty_block(&mut chain, rollup_cell.clone()).unwrap();
+    }
+    let record = query::get_deposit(chain.store(), &rollup_context, &out_point)
+        .unwrap()
+        .expect("deposit");
+    assert_eq!(record.status, DepositStatus::Finalized);
+
+    // live layer1 cells which are neither collected nor included
+    let pending_deposit = build_deposit(&rollup_script_hash, 700_00000000);
+    let pending = vec![query::DepositRecord {
+        out_point: deposit_out_point(&pending_deposit),
+        request: pending_deposit.clone(),
+        status: DepositStatus::Pending,
+        block: None,
+        account_id: None,
+    }];
+    let new_deposit = build_deposit(&rollup_script_hash, 800_00000000);
+    let cells = vec![
+        to_deposit_info(deposit),
+        to_deposit_info(pending_deposit),
+        to_deposit_info(new_deposit.clone()),
+    ];
+    let records = query::get_layer1_deposits(chain.store(), cells, &pending).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].request.as_slice(), new_deposit.as_slice());
+    assert_eq!(records[0].status, DepositStatus::CellCreated);
+}
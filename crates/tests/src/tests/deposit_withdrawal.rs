@@ -25,7 +25,7 @@ use gw_types::{
 
 use std::{collections::HashSet, iter::FromIterator};
 
-fn produce_empty_block(chain: &mut Chain, rollup_cell: CellOutput) -> Result<()> {
+pub(crate) fn produce_empty_block(chain: &mut Chain, rollup_cell: CellOutput) -> Result<()> {
     let block_result = {
         let mem_pool = chain.mem_pool().as_ref().unwrap();
         let mut mem_pool = smol::block_on(mem_pool.lock());
@@ -38,7 +38,7 @@ fn produce_empty_block(chain: &mut Chain, rollup_cell: CellOutput) -> Result<()>
     Ok(())
 }
 
-fn deposite_to_chain(
+pub(crate) fn deposite_to_chain(
     chain: &mut Chain,
     rollup_cell: CellOutput,
     user_script: Script,
@@ -75,14 +75,14 @@ fn deposite_to_chain(
     Ok(())
 }
 
-fn withdrawal_from_chain(
+pub(crate) fn withdrawal_from_chain(
     chain: &mut Chain,
     rollup_cell: CellOutput,
     user_script_hash: H256,
     capacity: u64,
     sudt_script_hash: H256,
     amount: u128,
-) -> Result<()> {
+) -> Result<WithdrawalRequest> {
     let withdrawal = {
         let raw = RawWithdrawalRequest::new_builder()
             .capacity(capacity.pack())
@@ -95,13 +95,13 @@ fn withdrawal_from_chain(
     let block_result = {
         let mem_pool = chain.mem_pool().as_ref().unwrap();
         let mut mem_pool = smol::block_on(mem_pool.lock());
-        mem_pool.push_withdrawal_request(withdrawal)?;
+        mem_pool.push_withdrawal_request(withdrawal.clone())?;
         construct_block(chain, &mut mem_pool, Vec::default()).unwrap()
     };
 
     // deposit
     apply_block_result(chain, rollup_cell, block_result, Vec::new(), HashSet::new());
-    Ok(())
+    Ok(withdrawal)
 }
 
 #[test]
@@ -0,0 +1,131 @@
+use crate::testing_tool::{
+    chain::{build_generator, setup_chain, ALWAYS_SUCCESS_CODE_HASH, DEFAULT_FINALITY_BLOCKS},
+    mem_pool_provider::DummyMemPoolProvider,
+};
+use crate::tests::deposit_withdrawal::{deposite_to_chain, produce_empty_block};
+
+use gw_chain::chain::Chain;
+use gw_common::H256;
+use gw_mem_pool::pool::MemPool;
+use gw_types::{
+    core::ScriptHashType,
+    packed::{CellOutput, RawWithdrawalRequest, Script, WithdrawalRequest},
+    prelude::*,
+};
+
+use std::sync::Arc;
+
+/// Deposit to an user and push a pending withdrawal of it into mem pool
+fn setup_pending_withdrawal(rollup_type_script: Script) -> (Chain, WithdrawalRequest) {
+    let rollup_script_hash = rollup_type_script.hash();
+    let mut chain = setup_chain(rollup_type_script.clone());
+    let capacity = 600_00000000;
+    let user_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.to_vec();
+            args.push(42);
+            args.pack()
+        })
+        .build();
+    let user_script_hash = user_script.hash();
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    deposite_to_chain(
+        &mut chain,
+        rollup_cell.clone(),
+        user_script,
+        capacity,
+        H256::zero(),
+        Script::default(),
+        0,
+    )
+    .unwrap();
+    // wait for deposit finalize
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        produce_empty_block(&mut chain, rollup_cell.clone()).unwrap();
+    }
+
+    let withdrawal = {
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity(300_00000000u64.pack())
+            .account_script_hash(user_script_hash.pack())
+            .sudt_script_hash(H256::zero().pack())
+            .build();
+        WithdrawalRequest::new_builder().raw(raw).build()
+    };
+    {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = smol::block_on(mem_pool.lock());
+        mem_pool
+            .push_withdrawal_request(withdrawal.clone())
+            .unwrap();
+    }
+    (chain, withdrawal)
+}
+
+#[test]
+fn test_restore_persisted_mem_pool() {
+    let rollup_type_script = Script::default();
+    let (chain, withdrawal) = setup_pending_withdrawal(rollup_type_script.clone());
+    let (mem_block_txs, mem_block_number) = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mem_pool = smol::block_on(mem_pool.lock());
+        let mem_block = mem_pool.mem_block();
+        let number: u64 = mem_block.block_info().number().unpack();
+        (mem_block.txs().to_vec(), number)
+    };
+
+    // restart mem pool from the same store
+    let mut mem_pool = MemPool::create(
+        0,
+        chain.store().clone(),
+        Arc::new(build_generator(&rollup_type_script, Default::default())),
+        Box::new(DummyMemPoolProvider::default()),
+        None,
+        None,
+        Default::default(),
+    )
+    .unwrap();
+    let number: u64 = mem_pool.mem_block().block_info().number().unpack();
+    assert_eq!(number, mem_block_number);
+    assert_eq!(mem_pool.mem_block().txs(), mem_block_txs.as_slice());
+
+    // the restored pending withdrawal is packaged into the new mem block
+    mem_pool.reset_mem_block().unwrap();
+    let withdrawal_hash: H256 = withdrawal.raw().hash().into();
+    assert!(mem_pool
+        .mem_block()
+        .withdrawals()
+        .contains(&withdrawal_hash));
+}
+
+#[test]
+fn test_clear_pending_of_discarded_mem_block() {
+    let rollup_type_script = Script::default();
+    let (chain, _withdrawal) = setup_pending_withdrawal(rollup_type_script.clone());
+    assert!(!chain
+        .store()
+        .begin_transaction()
+        .get_mem_pool_pending()
+        .is_empty());
+
+    // persisted mem block is discarded since the block producer changed
+    MemPool::create(
+        1,
+        chain.store().clone(),
+        Arc::new(build_generator(&rollup_type_script, Default::default())),
+        Box::new(DummyMemPoolProvider::default()),
+        None,
+        None,
+        Default::default(),
+    )
+    .unwrap();
+    assert!(chain
+        .store()
+        .begin_transaction()
+        .get_mem_pool_pending()
+        .is_empty());
+}
@@ -1,2 +1,11 @@
+mod binary_rpc;
 mod chain;
+mod deposit_status;
 mod deposit_withdrawal;
+mod mem_pool_persistence;
+mod parallel_execution;
+mod replay;
+mod rpc_cache;
+mod state_override;
+mod trace;
+mod withdrawal_status;
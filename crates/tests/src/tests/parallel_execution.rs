@@ -0,0 +1,154 @@
+use crate::testing_tool::chain::{
+    build_generator, construct_block, setup_chain, ALWAYS_SUCCESS_CODE_HASH,
+};
+use crate::tests::deposit_withdrawal::deposite_to_chain;
+
+use gw_common::{builtins::CKB_SUDT_ACCOUNT_ID, state::to_short_address, H256};
+use gw_config::ParallelExecutionConfig;
+use gw_generator::{
+    generator::{ApplyBlockArgs, ApplyBlockResult},
+    Generator,
+};
+use gw_store::{chain_view::ChainView, state::state_db::StateContext};
+use gw_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    packed::{
+        CellOutput, L2Transaction, RawL2Transaction, SUDTArgs, SUDTTransfer, Script, TxReceipt,
+    },
+    prelude::*,
+};
+
+use std::collections::HashSet;
+
+fn build_transfer_tx(from_id: u32, to_script: &Script, amount: u128) -> L2Transaction {
+    let args = SUDTArgs::new_builder()
+        .set(
+            SUDTTransfer::new_builder()
+                .to(to_short_address(&to_script.hash().into()).to_vec().pack())
+                .amount(amount.pack())
+                .fee(1000u128.pack())
+                .build(),
+        )
+        .build();
+    let raw_tx = RawL2Transaction::new_builder()
+        .from_id(from_id.pack())
+        .to_id(CKB_SUDT_ACCOUNT_ID.pack())
+        .nonce(0u32.pack())
+        .args(args.as_bytes().pack())
+        .build();
+    L2Transaction::new_builder().raw(raw_tx).build()
+}
+
+#[test]
+fn test_parallel_execution_matches_serial() {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash = rollup_type_script.hash();
+    let mut chain = setup_chain(rollup_type_script.clone());
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+    let build_user_script = |tag: u8| {
+        Script::new_builder()
+            .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args({
+                let mut args = rollup_script_hash.to_vec();
+                args.push(tag);
+                args.pack()
+            })
+            .build()
+    };
+    let users: Vec<Script> = (1..=3).map(build_user_script).collect();
+    let capacities = [1000_00000000u64, 300_00000000, 300_00000000];
+    for (user_script, capacity) in users.iter().zip(capacities.iter()) {
+        deposite_to_chain(
+            &mut chain,
+            rollup_cell.clone(),
+            user_script.clone(),
+            *capacity,
+            H256::zero(),
+            Script::default(),
+            0,
+        )
+        .unwrap();
+    }
+    let user_ids: Vec<u32> = {
+        let db = chain.store().begin_transaction();
+        let tree = db.state_tree(StateContext::ReadOnly).unwrap();
+        users
+            .iter()
+            .map(|script| {
+                tree.get_account_id_by_script_hash(&script.hash().into())
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect()
+    };
+
+    // the second tx spends more than the sender's balance before the block,
+    // its speculative result read the balance written by the first tx and
+    // must be re-executed
+    let txs = vec![
+        build_transfer_tx(user_ids[0], &users[1], 500_00000000),
+        build_transfer_tx(user_ids[1], &users[2], 700_00000000),
+        build_transfer_tx(user_ids[2], &users[0], 100_00000000),
+    ];
+    let block = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = smol::block_on(mem_pool.lock());
+        for tx in txs {
+            mem_pool.push_transaction(tx).unwrap();
+        }
+        construct_block(&chain, &mut mem_pool, Vec::default())
+            .unwrap()
+            .block
+    };
+    assert_eq!(block.transactions().len(), 3);
+
+    let apply_block = |generator: &Generator| -> Vec<Bytes> {
+        let db = chain.store().begin_transaction();
+        let chain_view = ChainView::new(&db, block.raw().parent_block_hash().unpack());
+        let args = ApplyBlockArgs {
+            l2block: block.clone(),
+            deposit_requests: Vec::new(),
+        };
+        let tx_receipts =
+            match generator.verify_and_apply_block(&db, &chain_view, args, &HashSet::new()) {
+                ApplyBlockResult::Success { tx_receipts, .. } => tx_receipts,
+                ApplyBlockResult::Challenge { error, .. } => panic!("challenge {:?}", error),
+                ApplyBlockResult::Error(err) => panic!("error {:?}", err),
+            };
+        // state is only verified, never committed
+        db.rollback().unwrap();
+        tx_receipts
+            .iter()
+            .map(|receipt| receipt.as_bytes())
+            .collect()
+    };
+
+    let serial_generator = build_generator(
+        &rollup_type_script,
+        ParallelExecutionConfig {
+            enabled: false,
+            threads: 0,
+        },
+    );
+    let parallel_generator = build_generator(
+        &rollup_type_script,
+        ParallelExecutionConfig {
+            enabled: true,
+            threads: 3,
+        },
+    );
+    let serial_receipts = apply_block(&serial_generator);
+    let parallel_receipts = apply_block(&parallel_generator);
+    assert_eq!(serial_receipts, parallel_receipts);
+
+    // receipts end with the post state of the block
+    let last_receipt = TxReceipt::from_slice(serial_receipts.last().unwrap()).unwrap();
+    assert_eq!(
+        last_receipt.post_state().as_slice(),
+        block.raw().post_account().as_slice()
+    );
+}
@@ -0,0 +1,51 @@
+use crate::tests::trace::setup_committed_transfer;
+
+use gw_common::{state::State, H256};
+use gw_store::{chain_view::ChainView, state::state_db::StateContext};
+use gw_types::{packed::TransactionKey, prelude::*};
+
+use std::collections::HashMap;
+
+#[test]
+fn test_replay_committed_block() {
+    let (chain, block, alice_id, _fee) = setup_committed_transfer();
+    let db = chain.store().begin_transaction();
+    let chain_view = ChainView::new(&db, block.raw().parent_block_hash().unpack());
+    let tx_count = block.transactions().len();
+    let replayed = chain
+        .generator()
+        .replay_block_transactions(&db, &chain_view, &block, &[], tx_count)
+        .unwrap();
+
+    // same as the stored receipts
+    assert_eq!(replayed.txs.len(), tx_count);
+    for (index, replayed_tx) in replayed.txs.iter().enumerate() {
+        let (run_result, account_count) = replayed_tx.as_ref().unwrap();
+        let tx_key = TransactionKey::build_transaction_key(block.hash().pack(), index as u32);
+        let receipt = db.get_transaction_receipt_by_key(&tx_key).unwrap().unwrap();
+        let expected_count: u32 = receipt.post_state().count().unpack();
+        assert_eq!(run_result.exit_code, 0);
+        assert_eq!(*account_count, expected_count);
+        assert_eq!(run_result.logs.len(), receipt.logs().len());
+    }
+
+    // same as the state written by the block
+    let block_number: u64 = block.raw().number().unpack();
+    let block_state: HashMap<H256, H256> = db
+        .get_block_state_record(block_number)
+        .into_iter()
+        .collect();
+    assert!(!block_state.is_empty());
+    assert_eq!(replayed.write_values, block_state);
+    let post_account_count: u32 = block.raw().post_account().count().unpack();
+    assert_eq!(replayed.account_count, post_account_count);
+
+    // tip state is untouched
+    assert_eq!(
+        db.state_tree(StateContext::ReadOnly)
+            .unwrap()
+            .get_nonce(alice_id)
+            .unwrap(),
+        1
+    );
+}
@@ -0,0 +1,128 @@
+use crate::testing_tool::chain::{
+    apply_block_result, construct_block, setup_chain, ALWAYS_SUCCESS_CODE_HASH,
+};
+
+use gw_chain::chain::Chain;
+use gw_common::H256;
+use gw_rpc_server::{cache::RPCCache, query};
+use gw_types::{
+    core::ScriptHashType,
+    packed::{CellOutput, DepositRequest, Script},
+    prelude::*,
+};
+
+fn produce_block(chain: &mut Chain, rollup_cell: CellOutput, deposits: Vec<DepositRequest>) {
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = smol::block_on(mem_pool.lock());
+        construct_block(chain, &mut mem_pool, deposits.clone()).unwrap()
+    };
+    apply_block_result(
+        chain,
+        rollup_cell,
+        block_result,
+        deposits,
+        Default::default(),
+    );
+}
+
+fn setup() -> (Chain, CellOutput) {
+    let rollup_type_script = Script::default();
+    let chain = setup_chain(rollup_type_script.clone());
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    (chain, rollup_cell)
+}
+
+#[test]
+fn test_rpc_cache_hit_and_miss() {
+    let (mut chain, rollup_cell) = setup();
+    produce_block(&mut chain, rollup_cell.clone(), vec![]);
+    produce_block(&mut chain, rollup_cell, vec![]);
+    let store = chain.store().clone();
+    let cache = RPCCache::new(16);
+
+    // blocks below the tip are cached
+    let block = query::get_block_by_number(&store, Some(&cache), 1).unwrap();
+    let cached_block = query::get_block_by_number(&store, Some(&cache), 1).unwrap();
+    assert!(block.is_some());
+    assert_eq!(block.map(|b| b.hash()), cached_block.map(|b| b.hash()));
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.len), (1, 1, 1));
+
+    // the tip block may be reverted, it's never cached
+    query::get_block_by_number(&store, Some(&cache), 2).unwrap();
+    query::get_block_by_number(&store, Some(&cache), 2).unwrap();
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.len), (1, 3, 1));
+
+    // missing blocks aren't cached
+    assert!(query::get_block_by_number(&store, Some(&cache), 3)
+        .unwrap()
+        .is_none());
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.len), (1, 4, 1));
+
+    // script hashes of committed accounts are cached
+    let script_hash = query::get_script_hash(&store, Some(&cache), 0).unwrap();
+    let cached_script_hash = query::get_script_hash(&store, Some(&cache), 0).unwrap();
+    assert_ne!(script_hash, H256::zero());
+    assert_eq!(script_hash, cached_script_hash);
+    let script = query::get_script(&store, Some(&cache), &script_hash).unwrap();
+    assert_eq!(script.map(|s| s.hash().into()), Some(script_hash));
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.len), (2, 6, 3));
+
+    // results are identical to uncached queries
+    assert_eq!(
+        query::get_script_hash(&store, None, 0).unwrap(),
+        cached_script_hash
+    );
+}
+
+#[test]
+fn test_rpc_cache_invalidation() {
+    let (mut chain, rollup_cell) = setup();
+    produce_block(&mut chain, rollup_cell.clone(), vec![]);
+    produce_block(&mut chain, rollup_cell.clone(), vec![]);
+
+    // a fork of the same genesis, block #1 is different
+    let (mut fork, _) = setup();
+    let user_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = Script::default().hash().to_vec();
+            args.push(42);
+            args.pack()
+        })
+        .build();
+    let deposit = DepositRequest::new_builder()
+        .capacity(600_00000000u64.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .script(user_script)
+        .build();
+    produce_block(&mut fork, rollup_cell.clone(), vec![deposit]);
+    produce_block(&mut fork, rollup_cell.clone(), vec![]);
+
+    let cache = RPCCache::new(16);
+    let block = query::get_block_by_number(chain.store(), Some(&cache), 1)
+        .unwrap()
+        .unwrap();
+    assert_eq!(cache.stats().len, 1);
+
+    // the cached tip isn't on the fork, entries are cleared
+    let fork_block = query::get_block_by_number(fork.store(), Some(&cache), 1)
+        .unwrap()
+        .unwrap();
+    assert_ne!(block.hash(), fork_block.hash());
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.len), (0, 2, 1));
+
+    // extending the chain keeps entries
+    produce_block(&mut fork, rollup_cell, vec![]);
+    query::get_block_by_number(fork.store(), Some(&cache), 1).unwrap();
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.len), (1, 2, 1));
+}
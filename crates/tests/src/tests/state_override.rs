@@ -0,0 +1,116 @@
+use crate::testing_tool::{
+    chain::ALWAYS_SUCCESS_CODE_HASH,
+    rpc_server::{build_rpc_server, request},
+};
+use crate::tests::trace::setup_committed_transfer;
+
+use gw_common::{
+    builtins::CKB_SUDT_ACCOUNT_ID,
+    state::{to_short_address, State},
+};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::JsonBytes,
+    godwoken::{AccountOverride, SUDTBalanceOverride, StateOverride},
+};
+use gw_store::state::state_db::StateContext;
+use gw_types::{
+    core::ScriptHashType,
+    packed::{RawL2Transaction, SUDTArgs, SUDTTransfer, Script},
+    prelude::*,
+};
+
+#[test]
+fn test_execute_raw_l2transaction_with_state_override() {
+    let (chain, _block, alice_id, _fee) = setup_committed_transfer();
+    let store = chain.store().clone();
+    let rollup_type_script = Script::default();
+    let bob_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_type_script.hash().to_vec();
+            args.push(2);
+            args.pack()
+        })
+        .build();
+    let (alice_short_address, alice_balance) = {
+        let db = store.begin_transaction();
+        let tree = db.state_tree(StateContext::ReadOnly).unwrap();
+        let short_address = to_short_address(&tree.get_script_hash(alice_id).unwrap()).to_vec();
+        let balance = tree
+            .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &short_address)
+            .unwrap();
+        (short_address, balance)
+    };
+
+    let rpc_server = build_rpc_server(chain);
+
+    // transfer more than the committed balance with an overridden nonce
+    let (nonce, balance, amount) = (7u32, alice_balance * 10, alice_balance * 2);
+    let args = SUDTArgs::new_builder()
+        .set(
+            SUDTTransfer::new_builder()
+                .to(to_short_address(&bob_script.hash().into()).to_vec().pack())
+                .amount(amount.pack())
+                .fee(1000u128.pack())
+                .build(),
+        )
+        .build();
+    let raw_tx = RawL2Transaction::new_builder()
+        .from_id(alice_id.pack())
+        .to_id(CKB_SUDT_ACCOUNT_ID.pack())
+        .nonce(nonce.pack())
+        .args(args.as_bytes().pack())
+        .build();
+    let state_override = StateOverride {
+        accounts: vec![AccountOverride {
+            id: alice_id.into(),
+            nonce: Some(nonce.into()),
+            balances: vec![SUDTBalanceOverride {
+                sudt_id: CKB_SUDT_ACCOUNT_ID.into(),
+                balance: balance.into(),
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let execute = |state_override: Option<&StateOverride>| {
+        let mut params = vec![
+            serde_json::to_value(JsonBytes::from_bytes(raw_tx.as_bytes())).unwrap(),
+            serde_json::Value::Null,
+        ];
+        if let Some(state_override) = state_override {
+            params.push(serde_json::to_value(state_override).unwrap());
+        }
+        request(&rpc_server, "gw_execute_raw_l2transaction", params)
+    };
+
+    // the committed balance is insufficient
+    let response = execute(None);
+    assert!(response.get("error").is_some(), "{}", response);
+
+    let response = execute(Some(&state_override));
+    assert!(response.get("error").is_none(), "{}", response);
+    assert_eq!(
+        response["result"]["state_override"],
+        serde_json::to_value(&state_override).unwrap()
+    );
+
+    // overrides are never written back
+    let db = store.begin_transaction();
+    let tree = db.state_tree(StateContext::ReadOnly).unwrap();
+    assert_eq!(tree.get_nonce(alice_id).unwrap(), 1);
+    assert_eq!(
+        tree.get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &alice_short_address)
+            .unwrap(),
+        alice_balance
+    );
+    let mem_pool_tree = db.mem_pool_state_tree().unwrap();
+    assert_eq!(mem_pool_tree.get_nonce(alice_id).unwrap(), 1);
+    assert_eq!(
+        mem_pool_tree
+            .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &alice_short_address)
+            .unwrap(),
+        alice_balance
+    );
+}
@@ -0,0 +1,194 @@
+use crate::testing_tool::chain::{
+    apply_block_result, construct_block, setup_chain, ALWAYS_SUCCESS_CODE_HASH,
+};
+use crate::tests::deposit_withdrawal::{deposite_to_chain, produce_empty_block};
+
+use gw_chain::chain::Chain;
+use gw_common::{
+    builtins::CKB_SUDT_ACCOUNT_ID,
+    h256_ext::H256Ext,
+    state::{build_account_field_key, to_short_address, State, GW_ACCOUNT_NONCE_TYPE},
+    H256,
+};
+use gw_generator::trace::SyscallEvent;
+use gw_store::{chain_view::ChainView, state::state_db::StateContext};
+use gw_types::{
+    core::ScriptHashType,
+    packed::{
+        CellOutput, L2Block, L2Transaction, RawL2Transaction, SUDTArgs, SUDTTransfer, Script,
+    },
+    prelude::*,
+};
+
+use std::collections::HashSet;
+
+pub(crate) fn setup_committed_transfer() -> (Chain, L2Block, u32, u128) {
+    commit_transfer(setup_chain(Script::default()))
+}
+
+/// Commit a CKB transfer from alice to bob, and produce an empty block on
+/// top of it
+pub(crate) fn commit_transfer(mut chain: Chain) -> (Chain, L2Block, u32, u128) {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash = rollup_type_script.hash();
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    let build_user_script = |tag: u8| {
+        Script::new_builder()
+            .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args({
+                let mut args = rollup_script_hash.to_vec();
+                args.push(tag);
+                args.pack()
+            })
+            .build()
+    };
+    let alice_script = build_user_script(1);
+    let bob_script = build_user_script(2);
+    for user_script in vec![alice_script.clone(), bob_script.clone()] {
+        deposite_to_chain(
+            &mut chain,
+            rollup_cell.clone(),
+            user_script,
+            600_00000000,
+            H256::zero(),
+            Script::default(),
+            0,
+        )
+        .unwrap();
+    }
+    let alice_id = {
+        let db = chain.store().begin_transaction();
+        let tree = db.state_tree(StateContext::ReadOnly).unwrap();
+        tree.get_account_id_by_script_hash(&alice_script.hash().into())
+            .unwrap()
+            .unwrap()
+    };
+
+    // transfer CKB from alice to bob
+    let (amount, fee) = (100_00000000u128, 1000u128);
+    let args = SUDTArgs::new_builder()
+        .set(
+            SUDTTransfer::new_builder()
+                .to(to_short_address(&bob_script.hash().into()).to_vec().pack())
+                .amount(amount.pack())
+                .fee(fee.pack())
+                .build(),
+        )
+        .build();
+    let raw_tx = RawL2Transaction::new_builder()
+        .from_id(alice_id.pack())
+        .to_id(CKB_SUDT_ACCOUNT_ID.pack())
+        .nonce(0u32.pack())
+        .args(args.as_bytes().pack())
+        .build();
+    let tx = L2Transaction::new_builder().raw(raw_tx).build();
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = smol::block_on(mem_pool.lock());
+        mem_pool.push_transaction(tx.clone()).unwrap();
+        construct_block(&chain, &mut mem_pool, Vec::default()).unwrap()
+    };
+    let block = block_result.block.clone();
+    assert_eq!(block.transactions().len(), 1);
+    apply_block_result(
+        &mut chain,
+        rollup_cell.clone(),
+        block_result,
+        Vec::new(),
+        HashSet::new(),
+    );
+    // the re-executed block is not the tip
+    produce_empty_block(&mut chain, rollup_cell).unwrap();
+    (chain, block, alice_id, fee)
+}
+
+#[test]
+fn test_trace_committed_transaction() {
+    let (chain, block, alice_id, fee) = setup_committed_transfer();
+    let db = chain.store().begin_transaction();
+    let chain_view = ChainView::new(&db, block.raw().parent_block_hash().unpack());
+    let (run_result, trace) = chain
+        .generator()
+        .trace_block_transaction(&db, &chain_view, &block, &[], 0)
+        .unwrap();
+    assert_eq!(run_result.exit_code, 0);
+
+    // tip state is untouched
+    assert_eq!(
+        db.state_tree(StateContext::ReadOnly)
+            .unwrap()
+            .get_nonce(alice_id)
+            .unwrap(),
+        1
+    );
+
+    // the nonce is increased from the state before the block
+    let nonce_key = build_account_field_key(alice_id, GW_ACCOUNT_NONCE_TYPE);
+    assert!(trace.syscalls.iter().any(|syscall| syscall.event
+        == SyscallEvent::Store {
+            key: nonce_key,
+            old_value: H256::zero(),
+            new_value: H256::from_u32(1),
+        }));
+    assert!(trace.syscalls.iter().any(|syscall| matches!(
+        syscall.event,
+        SyscallEvent::PayFee { sudt_id, amount, .. }
+            if sudt_id == CKB_SUDT_ACCOUNT_ID && amount == fee
+    )));
+    // cycles are accumulated between syscalls
+    let mut last_total_cycles = 0;
+    for syscall in trace.syscalls.iter() {
+        assert_eq!(
+            syscall.cycles,
+            syscall.total_cycles - last_total_cycles,
+            "{}",
+            syscall.name
+        );
+        assert!(syscall.total_cycles <= run_result.used_cycles);
+        last_total_cycles = syscall.total_cycles;
+    }
+    assert_eq!(
+        run_result.write_values.get(&nonce_key),
+        Some(&H256::from_u32(1))
+    );
+}
+
+#[test]
+fn test_profile_committed_transaction() {
+    let (chain, block, alice_id, _fee) = setup_committed_transfer();
+    let db = chain.store().begin_transaction();
+    let chain_view = ChainView::new(&db, block.raw().parent_block_hash().unpack());
+    let (run_result, profile) = chain
+        .generator()
+        .profile_block_transaction(&db, &chain_view, &block, &[], 0)
+        .unwrap();
+    assert_eq!(run_result.exit_code, 0);
+
+    // same result as the execution without profiling
+    let (traced_result, _trace) = chain
+        .generator()
+        .trace_block_transaction(&db, &chain_view, &block, &[], 0)
+        .unwrap();
+    assert_eq!(run_result.used_cycles, traced_result.used_cycles);
+    assert_eq!(run_result.write_values, traced_result.write_values);
+    assert_eq!(run_result.return_data, traced_result.return_data);
+
+    // every cycle is profiled to a call stack
+    assert!(!profile.stacks.is_empty());
+    assert!(profile.stacks.iter().all(|(stack, _)| !stack.is_empty()));
+    let profiled_cycles: u64 = profile.stacks.iter().map(|(_, cycles)| cycles).sum();
+    assert_eq!(profiled_cycles, run_result.used_cycles);
+    assert_eq!(profile.to_folded().lines().count(), profile.stacks.len());
+
+    // tip state is untouched
+    assert_eq!(
+        db.state_tree(StateContext::ReadOnly)
+            .unwrap()
+            .get_nonce(alice_id)
+            .unwrap(),
+        1
+    );
+}
@@ -0,0 +1,173 @@
+use super::deposit_withdrawal::{deposite_to_chain, produce_empty_block, withdrawal_from_chain};
+use crate::testing_tool::chain::{setup_chain, ALWAYS_SUCCESS_CODE_HASH, DEFAULT_FINALITY_BLOCKS};
+
+use gw_common::H256;
+use gw_generator::Generator;
+use gw_jsonrpc_types::godwoken::WithdrawalStatus;
+use gw_rpc_server::query::{self, WithdrawalRecord};
+use gw_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    offchain::{CellInfo, RollupContext},
+    packed::{CellOutput, OutPoint, RawTransaction, Script, Transaction, WithdrawalRequest},
+    prelude::*,
+};
+
+fn withdrawal_cell(
+    rollup_context: &RollupContext,
+    withdrawal: &WithdrawalRequest,
+    block_hash: &H256,
+) -> CellOutput {
+    let (output, _data) =
+        Generator::build_withdrawal_cell_output(rollup_context, withdrawal, block_hash, 0, None)
+            .expect("withdrawal cell");
+    output
+}
+
+#[test]
+fn test_withdrawal_status() {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash: H256 = rollup_type_script.hash().into();
+    let mut chain = setup_chain(rollup_type_script.clone());
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    let user_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.as_slice().to_vec();
+            args.push(42);
+            args.pack()
+        })
+        .build();
+    let user_script_hash: H256 = user_script.hash().into();
+    deposite_to_chain(
+        &mut chain,
+        rollup_cell.clone(),
+        user_script,
+        600_00000000,
+        H256::zero(),
+        Script::default(),
+        0,
+    )
+    .unwrap();
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        produce_empty_block(&mut chain, rollup_cell.clone()).unwrap();
+    }
+
+    let capacity = 300_00000000u64;
+    let withdrawal = withdrawal_from_chain(
+        &mut chain,
+        rollup_cell.clone(),
+        user_script_hash,
+        capacity,
+        H256::zero(),
+        0,
+    )
+    .unwrap();
+    let withdrawal_hash: H256 = withdrawal.hash().into();
+    let rollup_context = chain.generator().rollup_context().clone();
+
+    // indexed with the including block
+    let record = query::get_withdrawal(chain.store(), &rollup_context, &withdrawal_hash)
+        .unwrap()
+        .expect("withdrawal");
+    let tip_block = chain.store().get_tip_block().unwrap();
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    assert_eq!(record.withdrawal.as_slice(), withdrawal.as_slice());
+    assert_eq!(record.status, WithdrawalStatus::Committed);
+    let block_hash: H256 = tip_block.hash().into();
+    assert_eq!(record.block, Some((block_hash, tip_block_number)));
+
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        produce_empty_block(&mut chain, rollup_cell.clone()).unwrap();
+    }
+    let record = query::get_withdrawal(chain.store(), &rollup_context, &withdrawal_hash)
+        .unwrap()
+        .expect("withdrawal");
+    assert_eq!(record.status, WithdrawalStatus::Finalized);
+
+    assert_eq!(record.index, Some(0));
+
+    // an identical withdrawal in the same block, located by its index
+    let submit_tx_hash = H256::from([1u8; 32]);
+    let record = WithdrawalRecord {
+        submit_tx_hash: Some(submit_tx_hash),
+        ..record
+    };
+    let duplicate = WithdrawalRecord {
+        withdrawal: withdrawal.clone(),
+        status: WithdrawalStatus::Finalized,
+        block: record.block,
+        index: Some(1),
+        submit_tx_hash: Some(submit_tx_hash),
+    };
+    let cell = withdrawal_cell(&rollup_context, &withdrawal, &block_hash);
+    let other_block_cell = withdrawal_cell(&rollup_context, &withdrawal, &H256::from([2u8; 32]));
+    let outputs = vec![
+        CellOutput::default(),
+        cell.clone(),
+        other_block_cell,
+        cell.clone(),
+        CellOutput::default(),
+    ];
+    let submit_tx = {
+        let outputs_data: Vec<Bytes> = vec![Bytes::new(); outputs.len()];
+        let raw = RawTransaction::new_builder()
+            .outputs(outputs.pack())
+            .outputs_data(outputs_data.pack())
+            .build();
+        Transaction::new_builder().raw(raw).build()
+    };
+    let out_point = |index: u32| {
+        OutPoint::new_builder()
+            .tx_hash(submit_tx_hash.pack())
+            .index(index.pack())
+            .build()
+    };
+    let record_out_point = query::withdrawal_cell_out_point(&rollup_context, &record, &submit_tx);
+    let duplicate_out_point =
+        query::withdrawal_cell_out_point(&rollup_context, &duplicate, &submit_tx);
+    assert_eq!(
+        record_out_point.as_ref().map(|o| o.as_slice()),
+        Some(out_point(1).as_slice())
+    );
+    assert_eq!(
+        duplicate_out_point.as_ref().map(|o| o.as_slice()),
+        Some(out_point(3).as_slice())
+    );
+
+    // only the duplicate is unlocked, its identical cell doesn't hide it
+    let live_cell = CellInfo {
+        out_point: out_point(1),
+        output: cell,
+        data: Default::default(),
+    };
+    let status = query::withdrawal_status_on_layer1(&record, Some(&live_cell));
+    assert_eq!(status, WithdrawalStatus::Finalized);
+    let status = query::withdrawal_status_on_layer1(&duplicate, None);
+    assert_eq!(status, WithdrawalStatus::Unlocked);
+
+    // no cell for an index out of the submit transaction
+    let missing = WithdrawalRecord {
+        withdrawal: withdrawal.clone(),
+        status: WithdrawalStatus::Finalized,
+        block: record.block,
+        index: Some(2),
+        submit_tx_hash: Some(submit_tx_hash),
+    };
+    assert!(query::withdrawal_cell_out_point(&rollup_context, &missing, &submit_tx).is_none());
+
+    // not finalized withdrawals are never unlocked
+    let pending = WithdrawalRecord {
+        withdrawal,
+        status: WithdrawalStatus::Pending,
+        block: None,
+        index: None,
+        submit_tx_hash: None,
+    };
+    assert!(query::withdrawal_cell_out_point(&rollup_context, &pending, &submit_tx).is_none());
+    let status = query::withdrawal_status_on_layer1(&pending, None);
+    assert_eq!(status, WithdrawalStatus::Pending);
+}
@@ -0,0 +1,156 @@
+use anyhow::Result;
+use gw_config::StoreConfig;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use crate::dump_state::{build_state_dump, open_store, read_state_dump, AccountDump, StateDump};
+
+/// Compare states of two dump files or db dirs, the block number and store
+/// config apply to db dirs. Print per-account differences and return the
+/// difference count.
+pub fn diff_state(
+    from: &Path,
+    to: &Path,
+    block_number: Option<u64>,
+    store_config: &StoreConfig,
+) -> Result<usize> {
+    let from = load_state(from, block_number, store_config)?;
+    let to = load_state(to, block_number, store_config)?;
+    let diffs = diff_state_dumps(&from, &to);
+    for diff in diffs.iter() {
+        println!("{}", diff);
+    }
+    if diffs.is_empty() {
+        println!("no difference");
+    }
+    Ok(diffs.len())
+}
+
+fn load_state(
+    path: &Path,
+    block_number: Option<u64>,
+    store_config: &StoreConfig,
+) -> Result<StateDump> {
+    if path.is_dir() {
+        let store = open_store(&StoreConfig {
+            path: path.to_path_buf(),
+            ..store_config.clone()
+        })?;
+        build_state_dump(&store, block_number)
+    } else {
+        read_state_dump(path)
+    }
+}
+
+pub fn diff_state_dumps(from: &StateDump, to: &StateDump) -> Vec<String> {
+    let mut diffs = Vec::new();
+    if from.block_number != to.block_number {
+        diffs.push(format!(
+            "block number: {} => {}",
+            from.block_number.value(),
+            to.block_number.value()
+        ));
+    }
+    if from.block_hash != to.block_hash {
+        diffs.push(format!(
+            "block hash: {:#x} => {:#x}",
+            from.block_hash, to.block_hash
+        ));
+    }
+    if from.account_root != to.account_root {
+        diffs.push(format!(
+            "account root: {:#x} => {:#x}",
+            from.account_root, to.account_root
+        ));
+    }
+    if from.account_count != to.account_count {
+        diffs.push(format!(
+            "account count: {} => {}",
+            from.account_count.value(),
+            to.account_count.value()
+        ));
+    }
+
+    let from_accounts: BTreeMap<u32, &AccountDump> =
+        from.accounts.iter().map(|a| (a.id.value(), a)).collect();
+    let to_accounts: BTreeMap<u32, &AccountDump> =
+        to.accounts.iter().map(|a| (a.id.value(), a)).collect();
+    let ids: BTreeSet<u32> = from_accounts
+        .keys()
+        .chain(to_accounts.keys())
+        .copied()
+        .collect();
+    for id in ids {
+        match (from_accounts.get(&id), to_accounts.get(&id)) {
+            (Some(from), Some(to)) => diff_account(id, from, to, &mut diffs),
+            (Some(_), None) => diffs.push(format!("account {}: only in from", id)),
+            (None, Some(_)) => diffs.push(format!("account {}: only in to", id)),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    let keys: BTreeSet<_> = from.kv.keys().chain(to.kv.keys()).collect();
+    for key in keys {
+        let from_value = from.kv.get(key).cloned().unwrap_or_default();
+        let to_value = to.kv.get(key).cloned().unwrap_or_default();
+        if from_value != to_value {
+            diffs.push(format!(
+                "kv {:#x}: {:#x} => {:#x}",
+                key, from_value, to_value
+            ));
+        }
+    }
+
+    diffs
+}
+
+fn diff_account(id: u32, from: &AccountDump, to: &AccountDump, diffs: &mut Vec<String>) {
+    if from.script_hash != to.script_hash {
+        diffs.push(format!(
+            "account {}: script hash {:#x} => {:#x}",
+            id, from.script_hash, to.script_hash
+        ));
+    }
+    if from.script != to.script {
+        diffs.push(format!(
+            "account {}: script {:?} => {:?}",
+            id, from.script, to.script
+        ));
+    }
+    if from.nonce != to.nonce {
+        diffs.push(format!(
+            "account {}: nonce {} => {}",
+            id,
+            from.nonce.value(),
+            to.nonce.value()
+        ));
+    }
+
+    let from_balances: BTreeMap<u32, u128> = from
+        .balances
+        .iter()
+        .map(|b| (b.sudt_id.value(), b.balance.value()))
+        .collect();
+    let to_balances: BTreeMap<u32, u128> = to
+        .balances
+        .iter()
+        .map(|b| (b.sudt_id.value(), b.balance.value()))
+        .collect();
+    let sudt_ids: BTreeSet<u32> = from_balances
+        .keys()
+        .chain(to_balances.keys())
+        .copied()
+        .collect();
+    for sudt_id in sudt_ids {
+        let from_balance = from_balances.get(&sudt_id).copied().unwrap_or(0);
+        let to_balance = to_balances.get(&sudt_id).copied().unwrap_or(0);
+        if from_balance != to_balance {
+            diffs.push(format!(
+                "account {}: sudt {} balance {} => {}",
+                id, sudt_id, from_balance, to_balance
+            ));
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use anyhow::{anyhow, bail, Result};
+use ckb_fixed_hash::H256;
+use gw_common::{
+    builtins::CKB_SUDT_ACCOUNT_ID,
+    h256_ext::H256Ext,
+    state::{build_account_key, build_sudt_key, to_short_address, State, SUDT_KEY_FLAG_BALANCE},
+    H256 as StateH256,
+};
+use gw_config::StoreConfig;
+use gw_db::{
+    schema::{COLUMNS, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_BLOCK_STATE_REVERSE_RECORD},
+    IteratorMode, RocksDB,
+};
+use gw_jsonrpc_types::{
+    blockchain::Script,
+    ckb_jsonrpc_types::{Uint128, Uint32, Uint64},
+};
+use gw_store::{
+    state::state_db::StateContext, traits::KVStore, transaction::StoreTransaction, Store,
+};
+use gw_traits::CodeStore;
+use gw_types::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StateDump {
+    pub block_number: Uint64,
+    pub block_hash: H256,
+    pub account_root: H256,
+    pub account_count: Uint32,
+    pub accounts: Vec<AccountDump>,
+    /// Raw state key value pairs, zero values are omitted
+    pub kv: BTreeMap<H256, H256>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AccountDump {
+    pub id: Uint32,
+    pub script_hash: H256,
+    pub script: Option<Script>,
+    pub nonce: Uint32,
+    /// Non-zero sUDT balances
+    pub balances: Vec<SUDTBalance>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SUDTBalance {
+    pub sudt_id: Uint32,
+    pub balance: Uint128,
+}
+
+pub fn dump_state(store_config: &StoreConfig, block_number: Option<u64>) -> Result<StateDump> {
+    let store = open_store(store_config)?;
+    build_state_dump(&store, block_number)
+}
+
+/// Open the db of a stopped node with the node's store config, the db must
+/// be opened with the same options and column profiles it's created with
+pub fn open_store(store_config: &StoreConfig) -> Result<Store> {
+    if !store_config.path.is_dir() {
+        bail!("store path {} not found", store_config.path.display());
+    }
+    Ok(Store::new(RocksDB::open(store_config, COLUMNS)))
+}
+
+/// Dump accounts and raw state at the block, default to the tip block
+pub fn build_state_dump(store: &Store, block_number: Option<u64>) -> Result<StateDump> {
+    let db = store.begin_transaction();
+    let tip_block_number: u64 = db.get_tip_block()?.raw().number().unpack();
+    let block_number = block_number.unwrap_or(tip_block_number);
+    if block_number > tip_block_number {
+        bail!(
+            "block #{} is above tip block #{}",
+            block_number,
+            tip_block_number
+        );
+    }
+    let block_hash = db
+        .get_block_hash_by_number(block_number)?
+        .ok_or_else(|| anyhow!("block #{} not found", block_number))?;
+
+    // state records may not exist at tip of an imported snapshot
+    let context = if block_number == tip_block_number {
+        StateContext::ReadOnly
+    } else {
+        StateContext::ReadOnlyHistory(block_number)
+    };
+    let tree = db.state_tree(context)?;
+    let account_count = tree.get_account_count()?;
+
+    let mut accounts = Vec::with_capacity(account_count as usize);
+    for id in 0..account_count {
+        let script_hash = tree.get_script_hash(id)?;
+        accounts.push(AccountDump {
+            id: id.into(),
+            script_hash: to_json_h256(&script_hash),
+            script: tree.get_script(&script_hash).map(Into::into),
+            nonce: tree.get_nonce(id)?.into(),
+            balances: Vec::new(),
+        });
+    }
+
+    let kv = if block_number == tip_block_number {
+        dump_tip_kv(&db)
+    } else {
+        dump_history_kv(&db, block_number)
+    };
+
+    // sUDT accounts share the script code hash of CKB sUDT
+    let sudt_ids: Vec<u32> = match accounts.get(CKB_SUDT_ACCOUNT_ID as usize) {
+        Some(AccountDump {
+            script: Some(ckb_sudt),
+            ..
+        }) => accounts
+            .iter()
+            .filter(|account| match account.script {
+                Some(ref script) => {
+                    script.code_hash == ckb_sudt.code_hash && script.hash_type == ckb_sudt.hash_type
+                }
+                None => false,
+            })
+            .map(|account| account.id.value())
+            .collect(),
+        _ => Vec::new(),
+    };
+    // balance keys are hashed, look them up in the dumped non-zero state
+    // instead of reading the state of every account and sUDT pair
+    for account in accounts.iter_mut() {
+        let script_hash: StateH256 = account.script_hash.0.into();
+        let sudt_key = build_sudt_key(SUDT_KEY_FLAG_BALANCE, to_short_address(&script_hash));
+        for sudt_id in sudt_ids.iter() {
+            let balance_key = to_json_h256(&build_account_key(*sudt_id, &sudt_key));
+            if let Some(balance) = kv.get(&balance_key) {
+                let balance: StateH256 = balance.0.into();
+                account.balances.push(SUDTBalance {
+                    sudt_id: (*sudt_id).into(),
+                    balance: balance.to_u128().into(),
+                });
+            }
+        }
+    }
+
+    Ok(StateDump {
+        block_number: block_number.into(),
+        block_hash: to_json_h256(&block_hash),
+        account_root: to_json_h256(&tree.calculate_root()?),
+        account_count: account_count.into(),
+        accounts,
+        kv,
+    })
+}
+
+pub fn read_state_dump(path: &Path) -> Result<StateDump> {
+    let content = fs::read(path)?;
+    Ok(serde_json::from_slice(&content)?)
+}
+
+fn dump_tip_kv(db: &StoreTransaction) -> BTreeMap<H256, H256> {
+    db.get_iter(COLUMN_ACCOUNT_SMT_LEAF, IteratorMode::Start)
+        .filter_map(|(key, value)| {
+            let value = H256::from_slice(&value).ok()?;
+            if value == H256::default() {
+                return None;
+            }
+            Some((H256::from_slice(&key).ok()?, value))
+        })
+        .collect()
+}
+
+fn dump_history_kv(db: &StoreTransaction, block_number: u64) -> BTreeMap<H256, H256> {
+    // reverse record key: state key(32 bytes) | block number(8 bytes)
+    let mut kv = BTreeMap::new();
+    let mut last_key: Option<StateH256> = None;
+    for (key, _) in db.get_iter(COLUMN_BLOCK_STATE_REVERSE_RECORD, IteratorMode::Start) {
+        let mut state_key = [0u8; 32];
+        state_key.copy_from_slice(&key[..32]);
+        let state_key: StateH256 = state_key.into();
+        if last_key.as_ref() == Some(&state_key) {
+            continue;
+        }
+        last_key = Some(state_key);
+        if let Some(value) = db.get_history_state(block_number, &state_key) {
+            if !value.is_zero() {
+                kv.insert(to_json_h256(&state_key), to_json_h256(&value));
+            }
+        }
+    }
+    kv
+}
+
+fn to_json_h256(hash: &StateH256) -> H256 {
+    H256::from_slice(hash.as_slice()).expect("h256")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_state_dump, open_store, SUDTBalance};
+    use gw_common::{
+        builtins::CKB_SUDT_ACCOUNT_ID,
+        merkle_utils::calculate_state_checkpoint,
+        state::{to_short_address, State},
+        H256,
+    };
+    use gw_config::StoreConfig;
+    use gw_db::schema::COLUMN_BLOCK;
+    use gw_store::{state::state_db::StateContext, traits::KVStore, Store};
+    use gw_traits::CodeStore;
+    use gw_types::{
+        core::ScriptHashType,
+        packed::{AccountMerkleState, L2Block, RawL2Block, Script, SubmitTransactions},
+        prelude::*,
+    };
+
+    #[test]
+    fn test_open_store_with_node_config() {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("test_open_store")
+            .tempdir()
+            .unwrap();
+        let missing = StoreConfig {
+            path: tmp_dir.path().join("missing"),
+            ..Default::default()
+        };
+        assert!(open_store(&missing).is_err());
+
+        let mut config = StoreConfig {
+            path: tmp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config
+            .options
+            .insert("disable_auto_compactions".to_owned(), "true".to_owned());
+        open_store(&config).unwrap();
+    }
+
+    #[test]
+    fn test_dump_sudt_balances() {
+        let store = Store::open_memory();
+        let db = store.begin_transaction();
+        db.set_block_smt_root(H256::zero()).unwrap();
+        let mut tree = db.state_tree(StateContext::AttachBlock(0)).unwrap();
+
+        let sudt_code_hash = [1u8; 32];
+        let mut create_account = |code_hash: [u8; 32], args: Vec<u8>| {
+            let script = Script::new_builder()
+                .code_hash(code_hash.pack())
+                .hash_type(ScriptHashType::Type.into())
+                .args(args.pack())
+                .build();
+            let script_hash: H256 = script.hash().into();
+            tree.insert_script(script_hash, script);
+            tree.create_account(script_hash).unwrap();
+            script_hash
+        };
+        create_account([0u8; 32], Vec::new());
+        create_account(sudt_code_hash, vec![1]);
+        create_account(sudt_code_hash, vec![2]);
+        let user = create_account([2u8; 32], Vec::new());
+        let empty_user = create_account([2u8; 32], vec![1]);
+        let sudt_id = 2;
+        tree.mint_sudt(CKB_SUDT_ACCOUNT_ID, to_short_address(&user), 100)
+            .unwrap();
+        tree.mint_sudt(sudt_id, to_short_address(&user), 200)
+            .unwrap();
+        tree.mint_sudt(sudt_id, to_short_address(&empty_user), 300)
+            .unwrap();
+        tree.burn_sudt(sudt_id, to_short_address(&empty_user), 300)
+            .unwrap();
+
+        let post_account = AccountMerkleState::new_builder()
+            .merkle_root(tree.calculate_root().unwrap().pack())
+            .count(tree.get_account_count().unwrap().pack())
+            .build();
+        let prev_txs_state_checkpoint = calculate_state_checkpoint(&H256::zero(), 0);
+        let genesis = L2Block::new_builder()
+            .raw(
+                RawL2Block::new_builder()
+                    .post_account(post_account)
+                    .submit_transactions(
+                        SubmitTransactions::new_builder()
+                            .prev_state_checkpoint(prev_txs_state_checkpoint.pack())
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build();
+        db.insert_raw(COLUMN_BLOCK, &genesis.hash(), genesis.as_slice())
+            .unwrap();
+        db.attach_block(genesis).unwrap();
+        db.commit().unwrap();
+
+        let dump = build_state_dump(&store, None).unwrap();
+        assert_eq!(dump.account_count.value(), 5);
+        let balances: Vec<Vec<SUDTBalance>> = dump
+            .accounts
+            .into_iter()
+            .map(|account| account.balances)
+            .collect();
+        assert_eq!(
+            balances[3],
+            vec![
+                SUDTBalance {
+                    sudt_id: CKB_SUDT_ACCOUNT_ID.into(),
+                    balance: 100u128.into(),
+                },
+                SUDTBalance {
+                    sudt_id: sudt_id.into(),
+                    balance: 200u128.into(),
+                },
+            ]
+        );
+        // zero balances are omitted
+        assert!(balances[4].is_empty());
+        assert!(balances[0].is_empty());
+    }
+}
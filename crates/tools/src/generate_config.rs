@@ -232,6 +232,7 @@ pub fn generate_node_config(args: GenerateNodeConfigArgs) -> Result<Config> {
                 .meta_contract_validator
                 .script_type_hash
                 .clone(),
+            activation: None,
         },
         BackendConfig {
             backend_type: BackendType::Sudt,
@@ -241,6 +242,7 @@ pub fn generate_node_config(args: GenerateNodeConfigArgs) -> Result<Config> {
                 .l2_sudt_validator
                 .script_type_hash
                 .clone(),
+            activation: None,
         },
         BackendConfig {
             backend_type: BackendType::Polyjuice,
@@ -250,6 +252,7 @@ pub fn generate_node_config(args: GenerateNodeConfigArgs) -> Result<Config> {
                 .polyjuice_validator
                 .script_type_hash
                 .clone(),
+            activation: None,
         },
     ];
 
@@ -258,6 +261,9 @@ pub fn generate_node_config(args: GenerateNodeConfigArgs) -> Result<Config> {
         options: HashMap::new(),
         options_file: None,
         cache_size: None,
+        prune: None,
+        secondary_path: None,
+        column_profiles: HashMap::new(),
     };
     let genesis_committed_info = L2BlockCommittedInfo {
         block_hash,
@@ -268,6 +274,8 @@ pub fn generate_node_config(args: GenerateNodeConfigArgs) -> Result<Config> {
         genesis_committed_info,
         rollup_type_script,
         skipped_invalid_block_list: Default::default(),
+        tx_access_list: false,
+        vm_version_schedule: Default::default(),
     };
     let rpc_client: RPCClientConfig = RPCClientConfig {
         indexer_url,
@@ -335,6 +343,7 @@ pub fn generate_node_config(args: GenerateNodeConfigArgs) -> Result<Config> {
         mem_pool: Default::default(),
         db_block_validator: Default::default(),
         store,
+        parallel_execution: Default::default(),
     };
 
     Ok(config)
@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+use ckb_fixed_hash::H256;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::{Uint32, Uint64},
+    godwoken::AccountMerkleState,
+};
+use gw_mem_pool::restore_manager::{decode_mem_block_file, RestoreManager};
+use gw_types::{packed, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemBlockSummary {
+    pub block_producer_id: Uint32,
+    pub number: Uint64,
+    pub timestamp: Uint64,
+    pub prev_merkle_state: AccountMerkleState,
+    pub post_merkle_state: AccountMerkleState,
+    pub txs: Vec<H256>,
+    pub withdrawals: Vec<H256>,
+    pub deposits_count: Uint32,
+    pub state_checkpoints: Vec<H256>,
+    pub touched_keys_count: Uint32,
+}
+
+impl From<packed::MemBlock> for MemBlockSummary {
+    fn from(mem_block: packed::MemBlock) -> Self {
+        let block_info = mem_block.block_info();
+        let to_hashes = |hashes: packed::Byte32Vec| -> Vec<H256> {
+            hashes
+                .into_iter()
+                .map(|hash| H256::from_slice(hash.as_slice()).expect("h256"))
+                .collect()
+        };
+        let block_producer_id: u32 = mem_block.block_producer_id().unpack();
+        let number: u64 = block_info.number().unpack();
+        let timestamp: u64 = block_info.timestamp().unpack();
+        MemBlockSummary {
+            block_producer_id: block_producer_id.into(),
+            number: number.into(),
+            timestamp: timestamp.into(),
+            prev_merkle_state: mem_block.prev_merkle_state().into(),
+            post_merkle_state: mem_block.post_merkle_state().into(),
+            txs: to_hashes(mem_block.txs()),
+            withdrawals: to_hashes(mem_block.withdrawals()),
+            deposits_count: (mem_block.deposits().len() as u32).into(),
+            state_checkpoints: to_hashes(mem_block.state_checkpoints()),
+            touched_keys_count: (mem_block.touched_keys().len() as u32).into(),
+        }
+    }
+}
+
+/// Print mem block files saved in the restore path
+pub fn list_mem_blocks(restore_path: &Path) -> Result<()> {
+    if !restore_path.is_dir() {
+        return Err(anyhow!("restore path {} not found", restore_path.display()));
+    }
+    let restore_manager = RestoreManager::build(&restore_path)?;
+    for saved in restore_manager.list()? {
+        let status = match fs::read(&saved.path)
+            .map_err(Into::into)
+            .and_then(|content| decode_mem_block_file(&content))
+        {
+            Ok(mem_block) => {
+                let number: u64 = mem_block.block_info().number().unpack();
+                format!(
+                    "block #{} txs {} withdrawals {} deposits {}",
+                    number,
+                    mem_block.txs().len(),
+                    mem_block.withdrawals().len(),
+                    mem_block.deposits().len()
+                )
+            }
+            Err(err) => format!("invalid: {}", err),
+        };
+        println!("{} {} bytes {}", saved.timestamp, saved.size, status);
+    }
+    Ok(())
+}
+
+/// Decode a saved mem block file, or the output of `dump-mem-block`
+pub fn inspect_mem_block(path: &Path) -> Result<MemBlockSummary> {
+    let content = fs::read(path)?;
+    let mem_block = match decode_mem_block_file(&content) {
+        Ok(mem_block) => mem_block,
+        Err(err) => match packed::MemBlock::from_slice(&content) {
+            Ok(mem_block) => {
+                log::info!("decode {} as a raw mem block", path.display());
+                mem_block
+            }
+            Err(_) => return Err(anyhow!("decode {}: {}", path.display(), err)),
+        },
+    };
+    Ok(mem_block.into())
+}
@@ -6,14 +6,19 @@ mod create_creator_account;
 mod deploy_genesis;
 mod deploy_scripts;
 mod deposit_ckb;
+mod diff_state;
 mod dump_mem_block;
+mod dump_state;
 mod dump_tx;
 mod generate_config;
 mod get_balance;
 pub mod godwoken_rpc;
 mod hasher;
+mod inspect_mem_block;
 mod polyjuice;
 mod prepare_scripts;
+mod profile_tx;
+mod replay;
 mod setup;
 mod stat;
 mod sudt;
@@ -32,6 +37,7 @@ use deploy_genesis::DeployRollupCellArgs;
 use dump_tx::ChallengeBlock;
 use generate_config::GenerateNodeConfigArgs;
 use godwoken_rpc::GodwokenRpcClient;
+use gw_config::StoreConfig;
 use gw_jsonrpc_types::godwoken::ChallengeTargetType;
 use gw_rpc_client::indexer_client::CKBIndexerClient;
 use std::{
@@ -43,7 +49,7 @@ use types::{
     BuildScriptsResult, PoAConfig, RollupDeploymentResult, ScriptsDeploymentResult,
     UserRollupConfig,
 };
-use utils::{cli_args, transaction::read_config};
+use utils::{cli_args, generator::parse_replace_backend, transaction::read_config};
 
 use crate::{setup::SetupArgs, sudt::account::build_l1_sudt_type_script};
 
@@ -81,6 +87,20 @@ fn run_cli() -> Result<()> {
         .takes_value(true)
         .required(true)
         .help("The config.toml file path");
+    let arg_node_config_path = Arg::with_name("config-path")
+        .long("config-path")
+        .takes_value(true)
+        .help("The node config.toml file path, db dirs are opened with its store config");
+    let arg_replay_db = Arg::with_name("db")
+        .long("db")
+        .takes_value(true)
+        .help("The db dir, default to the store path of the config");
+    let arg_replace_backend = Arg::with_name("replace-backend")
+        .long("replace-backend")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Replace the generator of a backend, <validator script type hash>=<generator path>");
     let arg_godwoken_rpc_url = Arg::with_name("godwoken-rpc-url")
         .short("g")
         .long("godwoken-rpc-url")
@@ -744,6 +764,35 @@ fn run_cli() -> Result<()> {
                         .help("output file"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("list-mem-blocks")
+                .about("List mem block files saved in the mem pool restore path")
+                .arg(
+                    Arg::with_name("restore-path")
+                        .long("restore-path")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The mem pool restore path"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("inspect-mem-block")
+                .about("Decode a saved mem block file or a dumped mem block")
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The mem block file"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("output file, print to stdout if unset"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("dump-cancel-challenge-tx")
                 .about("Dump offchain cancel challenge tx")
@@ -807,6 +856,106 @@ fn run_cli() -> Result<()> {
                         .default_value("0")
                         .help("Query cells with min capacity(shannon)"),
                 )
+        )
+        .subcommand(
+            SubCommand::with_name("dump-state")
+                .about("Dump accounts and raw state at a block from the db of a stopped node")
+                .arg(arg_node_config_path.clone())
+                .arg(
+                    Arg::with_name("store-path")
+                        .long("store-path")
+                        .takes_value(true)
+                        .required_unless("config-path")
+                        .help("The db dir, default to the store path of the node config"),
+                )
+                .arg(
+                    Arg::with_name("block")
+                        .short("b")
+                        .long("block")
+                        .takes_value(true)
+                        .help("Block number, default to the tip block"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .required(true)
+                        .help("output file"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff-state")
+                .about("Compare states of two state dumps or db dirs")
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .takes_value(true)
+                        .required(true)
+                        .help("State dump file or db dir"),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .takes_value(true)
+                        .required(true)
+                        .help("State dump file or db dir"),
+                )
+                .arg(
+                    Arg::with_name("block")
+                        .short("b")
+                        .long("block")
+                        .takes_value(true)
+                        .help("Block number of db dirs, default to the tip block"),
+                )
+                .arg(arg_node_config_path),
+        )
+        .subcommand(
+            SubCommand::with_name("profile-tx")
+                .about("Profile cycles of a committed tx in the db of a stopped node, output folded stacks")
+                .arg(arg_config_path.clone())
+                .arg(
+                    Arg::with_name("tx-hash")
+                        .long("tx-hash")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The L2 tx hash"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .takes_value(true)
+                        .help("Folded stacks output file, default to stdout"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("replay-tx")
+                .about("Re-execute a committed tx in the db of a stopped node and compare with its receipt")
+                .arg(arg_config_path.clone())
+                .arg(arg_replay_db.clone())
+                .arg(
+                    Arg::with_name("tx")
+                        .long("tx")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The L2 tx hash"),
+                )
+                .arg(arg_replace_backend.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("replay-block")
+                .about("Re-execute txs of a committed block in the db of a stopped node and compare with their receipts")
+                .arg(arg_config_path.clone())
+                .arg(arg_replay_db)
+                .arg(
+                    Arg::with_name("block")
+                        .short("b")
+                        .long("block")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Block number"),
+                )
+                .arg(arg_replace_backend),
         );
 
     let matches = app.clone().get_matches();
@@ -1335,6 +1484,28 @@ fn run_cli() -> Result<()> {
                 std::process::exit(-1);
             }
         }
+        ("list-mem-blocks", Some(m)) => {
+            let restore_path = Path::new(m.value_of("restore-path").unwrap());
+
+            if let Err(err) = inspect_mem_block::list_mem_blocks(restore_path) {
+                log::error!("List mem blocks: {}", err);
+                std::process::exit(-1);
+            }
+        }
+        ("inspect-mem-block", Some(m)) => {
+            let file = Path::new(m.value_of("file").unwrap());
+
+            match inspect_mem_block::inspect_mem_block(file) {
+                Ok(summary) => match m.value_of("output") {
+                    Some(output) => output_json_file(&summary, Path::new(output)),
+                    None => println!("{}", serde_json::to_string_pretty(&summary)?),
+                },
+                Err(err) => {
+                    log::error!("Inspect mem block: {}", err);
+                    std::process::exit(-1);
+                }
+            }
+        }
         ("dump-cancel-challenge-tx", Some(m)) => {
             let godwoken_rpc_url = m.value_of("godwoken-rpc-url").unwrap();
             let block = ChallengeBlock::from_str(m.value_of("block").unwrap()).unwrap();
@@ -1421,6 +1592,97 @@ fn run_cli() -> Result<()> {
                 );
             }
         }
+        ("dump-state", Some(m)) => {
+            let store_config = read_store_config(m)?;
+            let block: Option<u64> = m.value_of("block").map(str::parse).transpose()?;
+            let output = Path::new(m.value_of("output").unwrap());
+
+            match dump_state::dump_state(&store_config, block) {
+                Ok(dump) => output_json_file(&dump, output),
+                Err(err) => {
+                    log::error!("Dump state: {}", err);
+                    std::process::exit(-1);
+                }
+            }
+        }
+        ("diff-state", Some(m)) => {
+            let from = Path::new(m.value_of("from").unwrap());
+            let to = Path::new(m.value_of("to").unwrap());
+            let block: Option<u64> = m.value_of("block").map(str::parse).transpose()?;
+            let store_config = read_store_config(m)?;
+
+            if let Err(err) = diff_state::diff_state(from, to, block, &store_config) {
+                log::error!("Diff state: {}", err);
+                std::process::exit(-1);
+            }
+        }
+        ("profile-tx", Some(m)) => {
+            let config_path = Path::new(m.value_of("config-path").unwrap());
+            let tx_hash: ckb_fixed_hash::H256 = m
+                .value_of("tx-hash")
+                .unwrap()
+                .trim_start_matches("0x")
+                .parse()?;
+            let output = m.value_of("output").map(Path::new);
+
+            let config = read_config(config_path)?;
+            match profile_tx::profile_tx(&config, tx_hash.0.into()) {
+                Ok((run_result, profile)) => {
+                    let folded = profile.to_folded();
+                    match output {
+                        Some(output) => {
+                            std::fs::write(output, folded.as_bytes())?;
+                            log::info!(
+                                "used cycles: {}, write folded stacks to {:?}",
+                                run_result.used_cycles,
+                                output
+                            );
+                        }
+                        None => print!("{}", folded),
+                    }
+                }
+                Err(err) => {
+                    log::error!("Profile tx: {}", err);
+                    std::process::exit(-1);
+                }
+            }
+        }
+        ("replay-tx", Some(m)) | ("replay-block", Some(m)) => {
+            let config_path = Path::new(m.value_of("config-path").unwrap());
+            let config = read_config(config_path)?;
+            let backends = m
+                .values_of("replace-backend")
+                .into_iter()
+                .flatten()
+                .map(parse_replace_backend)
+                .collect::<Result<HashMap<_, _>>>()?;
+            let store_path = m
+                .value_of("db")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| config.store.path.clone());
+
+            let replayed = match m.value_of("tx") {
+                Some(tx_hash) => {
+                    let tx_hash: ckb_fixed_hash::H256 = tx_hash.trim_start_matches("0x").parse()?;
+                    replay::replay_tx(&config, &store_path, tx_hash.0.into(), &backends)
+                }
+                None => {
+                    let block: u64 = m.value_of("block").unwrap().parse()?;
+                    replay::replay_block(&config, &store_path, block, &backends)
+                }
+            };
+            match replayed {
+                Ok(0) => {}
+                Ok(diffs) => {
+                    log::error!("Replay: {} differences", diffs);
+                    std::process::exit(-1);
+                }
+                Err(err) => {
+                    log::error!("Replay: {}", err);
+                    std::process::exit(-1);
+                }
+            }
+        }
         _ => {
             app.print_help().expect("print help");
         }
@@ -1439,3 +1701,16 @@ where
     std::fs::write(output_path, output_content.as_bytes()).expect("generate json file");
     println!("Generate file {:?}", output_path);
 }
+
+/// Store config of the node config if `config-path` is given, `store-path`
+/// overrides the db dir
+fn read_store_config(m: &clap::ArgMatches) -> Result<StoreConfig> {
+    let mut store_config = match m.value_of("config-path") {
+        Some(config_path) => read_config(config_path)?.store,
+        None => StoreConfig::default(),
+    };
+    if let Some(store_path) = m.value_of("store-path") {
+        store_config.path = PathBuf::from(store_path);
+    }
+    Ok(store_config)
+}
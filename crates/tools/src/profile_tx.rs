@@ -0,0 +1,53 @@
+use anyhow::{anyhow, Result};
+use gw_common::H256;
+use gw_config::Config;
+use gw_generator::{profile::CycleProfile, Generator};
+use gw_store::{chain_view::ChainView, Store};
+use gw_types::{offchain::RunResult, prelude::*};
+
+use crate::{dump_state::open_store, utils::generator::build_generator};
+
+/// Re-execute a committed tx in the db of a stopped node and profile its
+/// cycles, nothing is written to the db.
+pub fn profile_tx(config: &Config, tx_hash: H256) -> Result<(RunResult, CycleProfile)> {
+    let store = open_store(&config.store)?;
+    let generator = build_generator(config, &store.begin_transaction())?;
+    profile_block_tx(&store, &generator, tx_hash)
+}
+
+fn profile_block_tx(
+    store: &Store,
+    generator: &Generator,
+    tx_hash: H256,
+) -> Result<(RunResult, CycleProfile)> {
+    let db = store.begin_transaction();
+    let tx_info = db
+        .get_transaction_info(&tx_hash)?
+        .ok_or_else(|| anyhow!("tx {} not found", hex::encode(tx_hash.as_slice())))?;
+    let block_number: u64 = tx_info.block_number().unpack();
+    db.check_history_available(block_number.saturating_sub(1))?;
+    let tx_index = {
+        let mut index = [0u8; 4];
+        index.copy_from_slice(&tx_info.key().as_slice()[32..]);
+        u32::from_be_bytes(index)
+    };
+
+    let block_hash = db
+        .get_block_hash_by_number(block_number)?
+        .ok_or_else(|| anyhow!("block #{} not found", block_number))?;
+    let block = db
+        .get_block(&block_hash)?
+        .ok_or_else(|| anyhow!("block #{} not found", block_number))?;
+    let deposit_requests = db
+        .get_block_deposit_requests(&block_hash)?
+        .unwrap_or_default();
+    let chain_view = ChainView::new(&db, block.raw().parent_block_hash().unpack());
+    let profiled = generator.profile_block_transaction(
+        &db,
+        &chain_view,
+        &block,
+        &deposit_requests,
+        tx_index,
+    )?;
+    Ok(profiled)
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use gw_common::H256;
+use gw_config::{Config, StoreConfig};
+use gw_generator::{error::TransactionError, generator::ReplayedBlock, Generator};
+use gw_store::{chain_view::ChainView, transaction::StoreTransaction};
+use gw_types::{
+    offchain::RunResult,
+    packed::{L2Block, TransactionKey, TxReceipt},
+    prelude::*,
+};
+
+use crate::{
+    dump_state::open_store,
+    utils::generator::{build_generator, replace_backends},
+};
+
+/// Re-execute a committed tx in the db of a stopped node, compare the result
+/// with the stored receipt. Print differences and return the difference count.
+///
+/// With replaced backends, the tx is also re-executed with the stored
+/// backends to compare results not stored in receipts, like the return data.
+pub fn replay_tx(
+    config: &Config,
+    store_path: &Path,
+    tx_hash: H256,
+    replaced_backends: &HashMap<ckb_fixed_hash::H256, PathBuf>,
+) -> Result<usize> {
+    let store = open_store(&StoreConfig {
+        path: store_path.to_path_buf(),
+        ..config.store.clone()
+    })?;
+    let db = store.begin_transaction();
+    let tx_info = db
+        .get_transaction_info(&tx_hash)?
+        .ok_or_else(|| anyhow!("tx {} not found", hex::encode(tx_hash.as_slice())))?;
+    let block_number: u64 = tx_info.block_number().unpack();
+    let tx_index = {
+        let mut index = [0u8; 4];
+        index.copy_from_slice(&tx_info.key().as_slice()[32..]);
+        u32::from_be_bytes(index) as usize
+    };
+
+    let block = get_block(&db, block_number)?;
+    let (stored, replayed) = replay(&db, config, replaced_backends, &block, tx_index + 1)?;
+    // txs before the replayed tx are only reported if they failed
+    let skip = replayed.txs.len().saturating_sub(1);
+    let mut diffs = diff_replaced(&block, stored.as_ref(), &replayed, skip);
+    diffs.extend(diff_replayed(&db, &block, replayed.txs, skip)?);
+    Ok(print_diffs(diffs))
+}
+
+/// Re-execute all txs of a committed block in the db of a stopped node,
+/// compare results with the stored receipts and the state written by the
+/// block. Print differences and return the difference count.
+///
+/// With replaced backends, the block is also re-executed with the stored
+/// backends to compare results not stored in receipts, like the return data.
+pub fn replay_block(
+    config: &Config,
+    store_path: &Path,
+    block_number: u64,
+    replaced_backends: &HashMap<ckb_fixed_hash::H256, PathBuf>,
+) -> Result<usize> {
+    let store = open_store(&StoreConfig {
+        path: store_path.to_path_buf(),
+        ..config.store.clone()
+    })?;
+    let db = store.begin_transaction();
+
+    let block = get_block(&db, block_number)?;
+    let tx_count = block.transactions().len();
+    let (stored, replayed) = replay(&db, config, replaced_backends, &block, tx_count)?;
+    let mut diffs = diff_replaced(&block, stored.as_ref(), &replayed, 0);
+    let replayed_all = replayed.txs.len() == tx_count && replayed.txs.iter().all(Result::is_ok);
+    let ReplayedBlock {
+        txs,
+        write_values,
+        account_count,
+    } = replayed;
+    diffs.extend(diff_replayed(&db, &block, txs, 0)?);
+    if replayed_all {
+        let post_account = block.raw().post_account();
+        let expected_count: u32 = post_account.count().unpack();
+        if expected_count != account_count {
+            diffs.push(format!(
+                "block post account count: {} => {}",
+                expected_count, account_count
+            ));
+        }
+        diffs.extend(diff_write_values(
+            &db.get_block_state_record(block_number),
+            &write_values,
+        ));
+    }
+    Ok(print_diffs(diffs))
+}
+
+type ReplayedTx = Result<(RunResult, u32), TransactionError>;
+
+fn get_block(db: &StoreTransaction, block_number: u64) -> Result<L2Block> {
+    let block_hash = db
+        .get_block_hash_by_number(block_number)?
+        .ok_or_else(|| anyhow!("block #{} not found", block_number))?;
+    db.get_block(&block_hash)?
+        .ok_or_else(|| anyhow!("block #{} not found", block_number))
+}
+
+/// Replay with the replaced backends, and with the stored backends if any
+/// backend is replaced
+fn replay(
+    db: &StoreTransaction,
+    config: &Config,
+    replaced_backends: &HashMap<ckb_fixed_hash::H256, PathBuf>,
+    block: &L2Block,
+    tx_count: usize,
+) -> Result<(Option<ReplayedBlock>, ReplayedBlock)> {
+    let block_number: u64 = block.raw().number().unpack();
+    db.check_history_available(block_number.saturating_sub(1))?;
+    let deposit_requests = db
+        .get_block_deposit_requests(&block.hash().into())?
+        .unwrap_or_default();
+    let chain_view = ChainView::new(db, block.raw().parent_block_hash().unpack());
+    let replay_with = |generator: &Generator| -> Result<ReplayedBlock> {
+        let replayed = generator.replay_block_transactions(
+            db,
+            &chain_view,
+            block,
+            &deposit_requests,
+            tx_count,
+        )?;
+        Ok(replayed)
+    };
+
+    let stored = if replaced_backends.is_empty() {
+        None
+    } else {
+        Some(replay_with(&build_generator(config, db)?)?)
+    };
+    let mut replaced_config = config.clone();
+    replace_backends(&mut replaced_config, replaced_backends)?;
+    let replayed = replay_with(&build_generator(&replaced_config, db)?)?;
+    Ok((stored, replayed))
+}
+
+/// Compare txs from `skip` replayed with the stored backends and the replaced
+/// backends
+fn diff_replaced(
+    block: &L2Block,
+    stored: Option<&ReplayedBlock>,
+    replaced: &ReplayedBlock,
+    skip: usize,
+) -> Vec<String> {
+    let stored = match stored {
+        Some(stored) => stored,
+        None => return Vec::new(),
+    };
+    let mut diffs = Vec::new();
+    if stored.txs.len() != replaced.txs.len() {
+        diffs.push(format!(
+            "replayed tx count with replaced backends: {} => {}",
+            stored.txs.len(),
+            replaced.txs.len()
+        ));
+    }
+    let txs = stored.txs.iter().zip(replaced.txs.iter());
+    for (index, (stored_tx, replaced_tx)) in txs.enumerate().skip(skip) {
+        let tx_hash: [u8; 32] = block.transactions().get(index).expect("replayed tx").hash();
+        let tx_diffs = match (stored_tx, replaced_tx) {
+            (Ok((stored, _)), Ok((replaced, _))) => diff_run_result(stored, replaced),
+            (Err(stored), Err(replaced)) if stored == replaced => Vec::new(),
+            (stored, replaced) => vec![format!(
+                "execute result: {} => {}",
+                display_replayed_tx(stored),
+                display_replayed_tx(replaced)
+            )],
+        };
+        diffs.extend(tx_diffs.into_iter().map(|diff| {
+            format!(
+                "tx #{} 0x{} with replaced backends: {}",
+                index,
+                hex::encode(tx_hash),
+                diff
+            )
+        }));
+    }
+    diffs
+}
+
+fn display_replayed_tx(replayed_tx: &ReplayedTx) -> String {
+    match replayed_tx {
+        Ok((run_result, _)) => format!("exit code {}", run_result.exit_code),
+        Err(err) => format!("error {}", err),
+    }
+}
+
+/// Compare results of the same tx, including ones not stored in receipts
+fn diff_run_result(expected: &RunResult, actual: &RunResult) -> Vec<String> {
+    let mut diffs = Vec::new();
+    if expected.exit_code != actual.exit_code {
+        diffs.push(format!(
+            "exit code: {} => {}",
+            expected.exit_code, actual.exit_code
+        ));
+    }
+    if expected.return_data != actual.return_data {
+        diffs.push(format!(
+            "return data: 0x{} => 0x{}",
+            hex::encode(&expected.return_data),
+            hex::encode(&actual.return_data)
+        ));
+    }
+    let logs = |run_result: &RunResult| -> Vec<Vec<u8>> {
+        let logs = run_result.logs.iter();
+        logs.map(|log| log.as_slice().to_vec()).collect()
+    };
+    if logs(expected) != logs(actual) {
+        diffs.push(format!(
+            "logs: {} => {}",
+            expected.logs.len(),
+            actual.logs.len()
+        ));
+    }
+    let expected_write_values: Vec<_> = expected.write_values.clone().into_iter().collect();
+    diffs.extend(diff_write_values(
+        &expected_write_values,
+        &actual.write_values,
+    ));
+    diffs
+}
+
+/// Compare written state key values, keys are sorted to print differences in
+/// a stable order
+fn diff_write_values(expected: &[(H256, H256)], actual: &HashMap<H256, H256>) -> Vec<String> {
+    let mut diffs = Vec::new();
+    let mut keys: Vec<H256> = expected
+        .iter()
+        .map(|(key, _)| *key)
+        .chain(actual.keys().copied())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    keys.sort_unstable_by_key(|key| key.as_slice().to_vec());
+    let expected: HashMap<H256, H256> = expected.iter().cloned().collect();
+    let display = |value: Option<&H256>| match value {
+        Some(value) => format!("0x{}", hex::encode(value.as_slice())),
+        None => "none".to_string(),
+    };
+    for key in keys {
+        let (expected_value, value) = (expected.get(&key), actual.get(&key));
+        if expected_value != value {
+            diffs.push(format!(
+                "write 0x{}: {} => {}",
+                hex::encode(key.as_slice()),
+                display(expected_value),
+                display(value)
+            ));
+        }
+    }
+    diffs
+}
+
+/// Compare replayed txs from `skip` with the stored receipts
+fn diff_replayed(
+    db: &StoreTransaction,
+    block: &L2Block,
+    replayed: Vec<ReplayedTx>,
+    skip: usize,
+) -> Result<Vec<String>> {
+    let mut diffs = Vec::new();
+    for (index, replayed_tx) in replayed.into_iter().enumerate().skip(skip) {
+        let tx_hash: [u8; 32] = block.transactions().get(index).expect("replayed tx").hash();
+        let tx_hash = hex::encode(tx_hash);
+        let (run_result, account_count) = match replayed_tx {
+            Ok(replayed_tx) => replayed_tx,
+            Err(err) => {
+                diffs.push(format!(
+                    "tx #{} 0x{}: execute error: {}",
+                    index, tx_hash, err
+                ));
+                continue;
+            }
+        };
+        println!(
+            "tx #{} 0x{}: exit code: {}, used cycles: {}, writes: {}, logs: {}, return data: 0x{}",
+            index,
+            tx_hash,
+            run_result.exit_code,
+            run_result.used_cycles,
+            run_result.write_values.len(),
+            run_result.logs.len(),
+            hex::encode(&run_result.return_data)
+        );
+
+        let tx_key = TransactionKey::build_transaction_key(block.hash().pack(), index as u32);
+        let receipt = db
+            .get_transaction_receipt_by_key(&tx_key)?
+            .ok_or_else(|| anyhow!("receipt of tx 0x{} not found", tx_hash))?;
+        diffs.extend(
+            diff_receipt(&receipt, &run_result, account_count)
+                .into_iter()
+                .map(|diff| format!("tx #{} 0x{}: {}", index, tx_hash, diff)),
+        );
+    }
+    Ok(diffs)
+}
+
+/// Receipts don't store return data and write set, and the replayed state
+/// has no merkle root, only the account count of the post state is compared.
+fn diff_receipt(receipt: &TxReceipt, run_result: &RunResult, account_count: u32) -> Vec<String> {
+    let mut diffs = Vec::new();
+    if run_result.exit_code != 0 {
+        diffs.push(format!("exit code: 0 => {}", run_result.exit_code));
+    }
+    let expected_count: u32 = receipt.post_state().count().unpack();
+    if expected_count != account_count {
+        diffs.push(format!(
+            "post state count: {} => {}",
+            expected_count, account_count
+        ));
+    }
+
+    let expected_logs: Vec<_> = receipt.logs().into_iter().collect();
+    if expected_logs.len() != run_result.logs.len() {
+        diffs.push(format!(
+            "log count: {} => {}",
+            expected_logs.len(),
+            run_result.logs.len()
+        ));
+    }
+    for (index, (expected, log)) in expected_logs.iter().zip(run_result.logs.iter()).enumerate() {
+        if expected.as_slice() != log.as_slice() {
+            diffs.push(format!("log #{}: {} => {}", index, expected, log));
+        }
+    }
+
+    let expected_read_data: HashSet<[u8; 32]> = receipt
+        .read_data_hashes()
+        .into_iter()
+        .map(|hash| hash.unpack())
+        .collect();
+    let read_data: HashSet<[u8; 32]> = run_result
+        .read_data
+        .keys()
+        .map(|hash| (*hash).into())
+        .collect();
+    for hash in expected_read_data.difference(&read_data) {
+        diffs.push(format!("read data 0x{} => none", hex::encode(hash)));
+    }
+    for hash in read_data.difference(&expected_read_data) {
+        diffs.push(format!("read data none => 0x{}", hex::encode(hash)));
+    }
+    diffs
+}
+
+fn print_diffs(diffs: Vec<String>) -> usize {
+    for diff in diffs.iter() {
+        println!("{}", diff);
+    }
+    if diffs.is_empty() {
+        println!("no difference");
+    }
+    diffs.len()
+}
@@ -0,0 +1,67 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, bail, Result};
+use ckb_fixed_hash::H256;
+use gw_config::Config;
+use gw_generator::Generator;
+use gw_store::transaction::StoreTransaction;
+use gw_types::offchain::RollupContext;
+
+/// Build the generator of a node config with VM version activations of the
+/// db, same as the node does
+pub fn build_generator(config: &Config, db: &StoreTransaction) -> Result<Generator> {
+    let rollup_context = RollupContext {
+        rollup_config: config.genesis.rollup_config.clone().into(),
+        rollup_script_hash: {
+            let rollup_script_hash: [u8; 32] = config.genesis.rollup_type_hash.clone().into();
+            rollup_script_hash.into()
+        },
+    };
+    let generator = Generator::from_config(config, rollup_context)?;
+    generator
+        .vm_version_schedule()
+        .restore_activations(db.get_vm_version_activations()?)?;
+    Ok(generator)
+}
+
+/// Replace generator binaries of backends by validator script type hash, all
+/// activations of a backend are replaced.
+pub fn replace_backends(config: &mut Config, generators: &HashMap<H256, PathBuf>) -> Result<()> {
+    let mut replaced = HashSet::with_capacity(generators.len());
+    for backend in config.backends.iter_mut() {
+        let generator_path = match generators.get(&backend.validator_script_type_hash) {
+            Some(path) => path,
+            None => continue,
+        };
+        log::info!(
+            "replace generator of backend {:#x} with binary from {:?}",
+            backend.validator_script_type_hash,
+            generator_path
+        );
+        backend.generator_path = generator_path.clone();
+        replaced.insert(backend.validator_script_type_hash.clone());
+    }
+
+    let missing: Vec<_> = generators
+        .keys()
+        .filter(|code_hash| !replaced.contains(*code_hash))
+        .collect();
+    if !missing.is_empty() {
+        bail!("{:?} backends not found in config", missing);
+    }
+    Ok(())
+}
+
+/// Parse `<validator script type hash>=<generator path>`
+pub fn parse_replace_backend(arg: &str) -> Result<(H256, PathBuf)> {
+    let mut parts = arg.splitn(2, '=');
+    let code_hash = parts.next().unwrap_or_default();
+    let generator_path = parts
+        .next()
+        .ok_or_else(|| anyhow!("invalid replace backend {}, expect <code hash>=<path>", arg))?;
+    let code_hash: H256 = code_hash.trim_start_matches("0x").parse()?;
+    Ok((code_hash, PathBuf::from(generator_path)))
+}
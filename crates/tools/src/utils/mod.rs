@@ -1,3 +1,4 @@
 pub mod cli_args;
+pub mod generator;
 pub mod message;
 pub mod transaction;
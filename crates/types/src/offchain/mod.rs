@@ -5,6 +5,7 @@ mod pool;
 mod rollup_context;
 mod rpc;
 mod run_result;
+mod state_override;
 
 pub use error_receipt::*;
 pub use extension::global_state_from_slice;
@@ -13,3 +14,4 @@ pub use pool::*;
 pub use rollup_context::*;
 pub use rpc::*;
 pub use run_result::*;
+pub use state_override::*;
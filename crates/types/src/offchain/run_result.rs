@@ -29,6 +29,26 @@ pub struct RunResult {
     pub exit_code: i8,
 }
 
+/// State keys touched by a transaction, sorted by bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxAccessList {
+    pub read_keys: Vec<H256>,
+    pub write_keys: Vec<H256>,
+}
+
+impl From<&RunResult> for TxAccessList {
+    fn from(run_result: &RunResult) -> Self {
+        let mut read_keys: Vec<H256> = run_result.read_values.keys().cloned().collect();
+        read_keys.sort_unstable_by(|a, b| a.as_slice().cmp(b.as_slice()));
+        let mut write_keys: Vec<H256> = run_result.write_values.keys().cloned().collect();
+        write_keys.sort_unstable_by(|a, b| a.as_slice().cmp(b.as_slice()));
+        TxAccessList {
+            read_keys,
+            write_keys,
+        }
+    }
+}
+
 impl CellOutput {
     pub fn occupied_capacity(&self, data_capacity: usize) -> ckb_types::core::CapacityResult<u64> {
         let output = ckb_types::packed::CellOutput::new_unchecked(self.as_bytes());
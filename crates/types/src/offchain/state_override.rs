@@ -0,0 +1,24 @@
+use crate::bytes::Bytes;
+use crate::packed::Script;
+use sparse_merkle_tree::H256;
+
+/// Temporary state overrides of a dry run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateOverride {
+    pub accounts: Vec<AccountOverride>,
+    /// Data to inject, indexed by data hash
+    pub data: Vec<Bytes>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountOverride {
+    /// Account id, an account is created if it equals the account count
+    pub id: u32,
+    /// Replace the account script, required to create an account
+    pub script: Option<Script>,
+    pub nonce: Option<u32>,
+    /// sUDT id and balance pairs
+    pub balances: Vec<(u32, u128)>,
+    /// Account storage key value pairs
+    pub storage: Vec<(H256, H256)>,
+}